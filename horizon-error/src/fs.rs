@@ -0,0 +1,60 @@
+ij_core_workaround!();
+
+crate::define_error_module! {
+    /// Errors of the fs module, the ones not listed are `Unknown`
+    pub enum FsErrorCode: 2 {
+        PathNotFound = 1, "path does not exist",
+        PathAlreadyExists = 2, "path already exists",
+        TargetLocked = 7, "target is in use",
+        DirectoryNotEmpty = 8, "directory is not empty",
+        DirectoryStatusLocked = 13, "directory is in use",
+        UsableSpaceNotEnough = 30, "not enough free space",
+
+        TargetNotFound = 1002, "target not found",
+
+        SdCardNoDevice = 2001, "SD card is not inserted",
+
+        NotImplemented = 3001, "operation is not implemented",
+        OutOfRange = 3005, "offset is out of range",
+
+        TooLongPath = 6003, "path is too long",
+        InvalidCharacter = 6004, "path contains an invalid character",
+        PermissionDenied = 6400, "permission denied",
+    }
+}
+
+#[cfg(feature = "error-in-core")]
+impl core::error::Error for FsErrorCode {}
+
+#[cfg(test)]
+mod test {
+    use crate::{ErrorCode, FsErrorCode};
+
+    #[test]
+    fn try_as() {
+        assert_eq!(
+            ErrorCode::from_parts(2, 1).try_as::<FsErrorCode>(),
+            Some(FsErrorCode::PathNotFound)
+        );
+        assert_eq!(ErrorCode::from_parts(1, 1).try_as::<FsErrorCode>(), None);
+        // unknown descriptions don't panic
+        assert_eq!(
+            ErrorCode::from_parts(2, 5).try_as::<FsErrorCode>(),
+            Some(FsErrorCode::Unknown(5))
+        );
+    }
+
+    #[cfg(feature = "error-messages")]
+    #[test]
+    fn message() {
+        assert_eq!(
+            FsErrorCode::PathNotFound.message(),
+            Some("path does not exist")
+        );
+        assert_eq!(
+            FsErrorCode::SdCardNoDevice.message(),
+            Some("SD card is not inserted")
+        );
+        assert_eq!(FsErrorCode::Unknown(5).message(), None);
+    }
+}
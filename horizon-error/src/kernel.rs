@@ -1,32 +1,9 @@
 ij_core_workaround!();
 
-use crate::ErrorCodeModule;
-
-// TODO: refine and export as a macro for defining an error code module
-macro_rules! back_to_enum {
-    ($(#[$meta:meta])* $vis:vis enum $name:ident {
-        $($(#[$vmeta:meta])* $vname:ident $(= $val:expr)?,)*
-    }) => {
-        $(#[$meta])*
-        $vis enum $name {
-            $($(#[$vmeta])* $vname $(= $val)?,)*
-        }
-
-        impl core::convert::TryFrom<u32> for $name {
-            type Error = ();
-
-            fn try_from(v: u32) -> Result<Self, Self::Error> {
-                match v {
-                    $(x if x == $name::$vname as u32 => Ok($name::$vname),)*
-                    _ => Err(()),
-                }
-            }
-        }
-    }
-}
+use crate::{ErrorCode, ErrorCodeModule};
 
 back_to_enum! {
-    #[derive(Debug)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
     #[repr(u32)]
     pub enum KernelErrorCode {
         OutOfSessions = 7,
@@ -95,3 +72,90 @@ impl ErrorCodeModule for KernelErrorCode {
         KernelErrorCode::try_from(desc).expect("Unknown kernel error code")
     }
 }
+
+/// Same as the [Display](core::fmt::Display) of the corresponding [ErrorCode]
+impl core::fmt::Display for KernelErrorCode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(&ErrorCode::from_parts(Self::MODULE, *self as u32), f)
+    }
+}
+
+#[cfg(feature = "error-in-core")]
+impl core::error::Error for KernelErrorCode {}
+
+/// Classification of the well-known kernel errors
+///
+/// These compare the raw module and description instead of going through
+///  [ErrorCode::try_as], as it panics on descriptions not known to [KernelErrorCode]
+impl ErrorCode {
+    #[inline(always)]
+    const fn is_kernel(&self, code: KernelErrorCode) -> bool {
+        self.get_module() == KernelErrorCode::MODULE && self.get_description() == code as u32
+    }
+
+    /// The wait timed out (including a zero timeout that would have blocked)
+    #[inline(always)]
+    pub const fn is_timeout(&self) -> bool {
+        self.is_kernel(KernelErrorCode::TimedOut)
+    }
+
+    /// The wait was cancelled via `svc::cancel_synchronization`
+    #[inline(always)]
+    pub const fn is_cancelled(&self) -> bool {
+        self.is_kernel(KernelErrorCode::Cancelled)
+    }
+
+    #[inline(always)]
+    pub const fn is_out_of_memory(&self) -> bool {
+        self.is_kernel(KernelErrorCode::OutOfMemory)
+    }
+
+    /// The handle table of the process is full
+    #[inline(always)]
+    pub const fn is_out_of_handles(&self) -> bool {
+        self.is_kernel(KernelErrorCode::OutOfHandles)
+    }
+
+    /// The other side of the IPC session was closed
+    #[inline(always)]
+    pub const fn is_session_closed(&self) -> bool {
+        self.is_kernel(KernelErrorCode::SessionClosed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ErrorCode;
+
+    #[test]
+    fn classifiers() {
+        // 2001-0117
+        assert!(ErrorCode::new(0xea01).is_timeout());
+        // 2001-0118
+        assert!(ErrorCode::new(0xec01).is_cancelled());
+        // 2001-0104
+        assert!(ErrorCode::new(0xd001).is_out_of_memory());
+        // 2001-0105
+        assert!(ErrorCode::new(0xd201).is_out_of_handles());
+        // 2001-0123
+        assert!(ErrorCode::new(0xf601).is_session_closed());
+    }
+
+    #[test]
+    fn classifiers_check_module() {
+        // same description, but from the fs module
+        let code = ErrorCode::from_parts(2, 117);
+        assert!(!code.is_timeout());
+
+        assert!(!ErrorCode::from_parts(1, 118).is_timeout());
+        assert!(!ErrorCode::default().is_timeout());
+    }
+
+    #[test]
+    fn classifiers_unknown_kernel_desc() {
+        // not a known KernelErrorCode, must not panic
+        let code = ErrorCode::from_parts(1, 1000);
+        assert!(!code.is_timeout());
+        assert!(!code.is_session_closed());
+    }
+}
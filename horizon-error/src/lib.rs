@@ -25,10 +25,35 @@ macro_rules! ij_core_workaround {
 
 ij_core_workaround!();
 
+// TODO: refine and export as a macro for defining an error code module
+macro_rules! back_to_enum {
+    ($(#[$meta:meta])* $vis:vis enum $name:ident {
+        $($(#[$vmeta:meta])* $vname:ident $(= $val:expr)?,)*
+    }) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($(#[$vmeta])* $vname $(= $val)?,)*
+        }
+
+        impl core::convert::TryFrom<u32> for $name {
+            type Error = ();
+
+            fn try_from(v: u32) -> Result<Self, Self::Error> {
+                match v {
+                    $(x if x == $name::$vname as u32 => Ok($name::$vname),)*
+                    _ => Err(()),
+                }
+            }
+        }
+    };
+}
+
+mod fs;
 mod kernel;
 
-use core::fmt::{Debug, Formatter};
+use core::fmt::{Debug, Display, Formatter};
 
+pub use fs::FsErrorCode;
 pub use kernel::KernelErrorCode;
 
 const SUCCESS_VALUE: u32 = 0;
@@ -139,6 +164,19 @@ impl ErrorCode {
             Err(self)
         }
     }
+
+    /// Maps the `code` error to `None`, propagating any other error
+    ///
+    /// For operations where a specific error just means that something is absent,
+    ///  like `PathNotFound` when checking whether a file exists.
+    #[inline(always)]
+    pub fn or_none_if<T>(result: Result<T>, code: ErrorCode) -> Result<Option<T>> {
+        match result {
+            Ok(v) => Ok(Some(v)),
+            Err(e) if e == code => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
 }
 
 impl Debug for ErrorCode {
@@ -150,6 +188,53 @@ impl Debug for ErrorCode {
     }
 }
 
+/// Names of the well-known modules, used in the [Display] impl of [ErrorCode]
+fn module_name(module: u32) -> Option<&'static str> {
+    Some(match module {
+        1 => "kernel",
+        2 => "fs",
+        3 => "os",
+        5 => "ncm",
+        8 => "lr",
+        9 => "ldr",
+        10 => "sf",
+        11 => "hipc",
+        15 => "dmnt",
+        16 => "pm",
+        21 => "sm",
+        22 => "ro",
+        26 => "spl",
+        _ => return None,
+    })
+}
+
+/// Formats as `2001-0117 (kernel: TimedOut)`, falling back to the bare code when the module
+///  or the description is not known
+///
+/// With the `error-messages` feature, fs errors get their description: `2002-0001 (fs: path does not exist)`
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(self, f)?;
+
+        if self.get_module() == KernelErrorCode::MODULE {
+            if let Ok(code) = KernelErrorCode::try_from(self.get_description()) {
+                return write!(f, " (kernel: {:?})", code);
+            }
+        }
+        #[cfg(feature = "error-messages")]
+        if let Some(message) = self.try_as::<FsErrorCode>().and_then(|code| code.message()) {
+            return write!(f, " (fs: {})", message);
+        }
+        match module_name(self.get_module()) {
+            Some(name) => write!(f, " ({})", name),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "error-in-core")]
+impl core::error::Error for ErrorCode {}
+
 pub trait ErrorCodeModule: Debug {
     const MODULE: u32;
 
@@ -157,6 +242,227 @@ pub trait ErrorCodeModule: Debug {
     fn from_desc(desc: u32) -> Self;
 }
 
-// TODO: macro for defining the ErrorCodeModule's
+/// Defines an enum of the error descriptions of a module, implementing [ErrorCodeModule]
+///
+/// The descriptions not listed map to the `Unknown` arm instead of panicking in [ErrorCode::try_as],
+///  so it's fine to list only the errors of interest. `Debug` prints the name of the arm.
+///
+/// ```
+/// use horizon_error::{define_error_module, ErrorCode};
+///
+/// define_error_module! {
+///     /// Errors of the fs module
+///     pub enum FsError: 2 {
+///         PathNotFound = 1,
+///         PathAlreadyExists = 2,
+///         TargetLocked = 7,
+///     }
+/// }
+///
+/// assert_eq!(
+///     ErrorCode::from_parts(2, 1).try_as::<FsError>(),
+///     Some(FsError::PathNotFound)
+/// );
+/// assert_eq!(
+///     ErrorCode::from_parts(2, 3).try_as::<FsError>(),
+///     Some(FsError::Unknown(3))
+/// );
+/// assert_eq!(FsError::TargetLocked.code(), ErrorCode::from_parts(2, 7));
+/// assert_eq!(format!("{:?}", FsError::TargetLocked), "TargetLocked");
+/// ```
+///
+/// The arms can also carry a description (`PathNotFound = 1, "path does not exist",`),
+///  exposed as `message()` with the `error-messages` feature of this crate.
+#[macro_export]
+macro_rules! define_error_module {
+    ($(#[$meta:meta])* $vis:vis enum $name:ident: $module:literal {
+        $($(#[$vmeta:meta])* $vname:ident = $desc:expr,)*
+    }) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        $vis enum $name {
+            $($(#[$vmeta])* $vname,)*
+            /// A description not listed in the definition
+            Unknown(u32),
+        }
+
+        impl $name {
+            /// The description part of the error code
+            pub const fn desc(&self) -> u32 {
+                match self {
+                    $($name::$vname => $desc,)*
+                    $name::Unknown(desc) => *desc,
+                }
+            }
+
+            pub const fn code(&self) -> $crate::ErrorCode {
+                $crate::ErrorCode::from_parts(
+                    <Self as $crate::ErrorCodeModule>::MODULE,
+                    self.desc(),
+                )
+            }
+        }
+
+        impl $crate::ErrorCodeModule for $name {
+            const MODULE: u32 = $module;
+
+            fn from_desc(desc: u32) -> Self {
+                match desc {
+                    $(x if x == $desc => $name::$vname,)*
+                    desc => $name::Unknown(desc),
+                }
+            }
+        }
+
+        impl ::core::convert::From<$name> for $crate::ErrorCode {
+            fn from(code: $name) -> Self {
+                code.code()
+            }
+        }
+
+        /// Same as the [Display](core::fmt::Display) of the corresponding [ErrorCode]($crate::ErrorCode)
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                ::core::fmt::Display::fmt(&self.code(), f)
+            }
+        }
+    };
+    ($(#[$meta:meta])* $vis:vis enum $name:ident: $module:literal {
+        $($(#[$vmeta:meta])* $vname:ident = $desc:expr, $message:literal,)*
+    }) => {
+        $crate::define_error_module! {
+            $(#[$meta])*
+            $vis enum $name: $module {
+                $($(#[$vmeta])* $vname = $desc,)*
+            }
+        }
+
+        $crate::__if_error_messages! {
+            impl $name {
+                /// Human-readable description of the error, `None` for the `Unknown` arm
+                pub fn message(&self) -> ::core::option::Option<&'static str> {
+                    match self {
+                        $($name::$vname => ::core::option::Option::Some($message),)*
+                        $name::Unknown(_) => ::core::option::Option::None,
+                    }
+                }
+            }
+        }
+    };
+}
+
+// Expands to the items only with the `error-messages` feature of this crate,
+//  as a cfg in the expansion of define_error_module would check the features of the calling crate
+#[cfg(feature = "error-messages")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_error_messages {
+    ($($item:item)*) => {
+        $($item)*
+    };
+}
+
+#[cfg(not(feature = "error-messages"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_error_messages {
+    ($($item:item)*) => {};
+}
 
 pub type Result<T> = core::result::Result<T, ErrorCode>;
+
+#[cfg(test)]
+mod test {
+    use crate::ErrorCode;
+
+    extern crate std;
+    use std::string::ToString;
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            ErrorCode::from_parts(1, 117).to_string(),
+            "2001-0117 (kernel: TimedOut)"
+        );
+        // unknown kernel description
+        assert_eq!(
+            ErrorCode::from_parts(1, 1000).to_string(),
+            "2001-1000 (kernel)"
+        );
+        // the description is added with error-messages, see `display_message`
+        #[cfg(not(feature = "error-messages"))]
+        assert_eq!(ErrorCode::from_parts(2, 1).to_string(), "2002-0001 (fs)");
+        assert_eq!(ErrorCode::from_parts(400, 1).to_string(), "2400-0001");
+    }
+
+    #[cfg(feature = "error-messages")]
+    #[test]
+    fn display_message() {
+        assert_eq!(
+            ErrorCode::from_parts(2, 1).to_string(),
+            "2002-0001 (fs: path does not exist)"
+        );
+        // unknown fs description
+        assert_eq!(ErrorCode::from_parts(2, 5).to_string(), "2002-0005 (fs)");
+    }
+
+    #[test]
+    fn or_none_if() {
+        let not_found = ErrorCode::from_parts(2, 1);
+        let locked = ErrorCode::from_parts(2, 7);
+
+        assert_eq!(ErrorCode::or_none_if(Ok(42), not_found), Ok(Some(42)));
+        assert_eq!(
+            ErrorCode::or_none_if::<u32>(Err(not_found), not_found),
+            Ok(None)
+        );
+        assert_eq!(
+            ErrorCode::or_none_if::<u32>(Err(locked), not_found),
+            Err(locked)
+        );
+    }
+
+    #[cfg(feature = "error-in-core")]
+    #[test]
+    fn dyn_error() {
+        use std::boxed::Box;
+
+        fn fails() -> Result<(), Box<dyn core::error::Error>> {
+            Err(ErrorCode::from_parts(1, 117))?;
+            Ok(())
+        }
+
+        let err = fails().unwrap_err();
+        assert_eq!(err.to_string(), "2001-0117 (kernel: TimedOut)");
+        assert!(err.source().is_none());
+    }
+
+    crate::define_error_module! {
+        enum TestError: 345 {
+            First = 1,
+            Second = 1001,
+        }
+    }
+
+    #[test]
+    fn defined_module() {
+        use crate::ErrorCodeModule;
+
+        assert_eq!(TestError::MODULE, 345);
+        for code in [TestError::First, TestError::Second] {
+            assert_eq!(ErrorCode::from(code).try_as::<TestError>(), Some(code));
+        }
+        assert_eq!(TestError::Second.code(), ErrorCode::from_parts(345, 1001));
+
+        // unknown descriptions don't panic
+        assert_eq!(
+            ErrorCode::from_parts(345, 2).try_as::<TestError>(),
+            Some(TestError::Unknown(2))
+        );
+        assert_eq!(TestError::Unknown(2).desc(), 2);
+        assert_eq!(ErrorCode::from_parts(1, 1).try_as::<TestError>(), None);
+
+        assert_eq!(std::format!("{:?}", TestError::Second), "Second");
+        assert_eq!(TestError::First.to_string(), "2345-0001");
+    }
+}
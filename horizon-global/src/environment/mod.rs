@@ -34,6 +34,19 @@ pub struct Environment {
     pub environment_type: EnvironmentType,
     pub main_thread_handle: u32,
     pub hos_version: HorizonVersion,
+    /// Handle of the current process, if the loader passed it
+    pub process_handle: Option<u32>,
+    /// Null-terminated command line passed by the loader, null if none
+    pub argv: *const u8,
+    /// Buffers for the path and argv of the NRO the loader should run after us, null if not supported
+    pub next_load_path: *mut u8,
+    pub next_load_argv: *mut u8,
+    /// Result of the previous NRO the loader ran
+    pub last_load_result: Option<u32>,
+    /// Bitmask of syscalls 0x00..=0xbf the process is allowed to use (as a hint, can have false positives)
+    pub syscall_available_hints: Option<[u64; 3]>,
+    pub applet_type: Option<u32>,
+    pub random_seed: Option<[u64; 2]>,
 }
 
 #[cfg(feature = "impl")]
@@ -46,3 +59,53 @@ extern "Rust" {
 pub fn get() -> Environment {
     unsafe { __horizon_global_environment_get() }
 }
+
+/// Iterator over the command line arguments passed by the homebrew loader, see [args]
+#[derive(Clone, Debug)]
+pub struct Args {
+    rest: &'static [u8],
+}
+
+impl Iterator for Args {
+    type Item = &'static [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.rest.iter().position(|c| !c.is_ascii_whitespace())?;
+        let rest = &self.rest[start..];
+
+        // same rules as libnx: a token starting with a quote ends at the next quote,
+        //  otherwise at the next whitespace
+        let (arg, rest) = if let Some(quoted) = rest.strip_prefix(b"\"") {
+            match quoted.iter().position(|&c| c == b'"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, &quoted[quoted.len()..]),
+            }
+        } else {
+            let end = rest
+                .iter()
+                .position(|c| c.is_ascii_whitespace())
+                .unwrap_or(rest.len());
+            rest.split_at(end)
+        };
+
+        self.rest = rest;
+        Some(arg)
+    }
+}
+
+/// Gets the command line arguments passed by the homebrew loader
+///
+/// The first argument is usually the path to the NRO. There are no arguments when running as an NSO
+///  or when the loader didn't pass any.
+pub fn args() -> Args {
+    let argv = get().argv;
+
+    let rest = if argv.is_null() {
+        &[]
+    } else {
+        // SAFETY: the loader passes a NUL-terminated string that stays valid for the whole process lifetime
+        unsafe { core::ffi::CStr::from_ptr(argv as *const _) }.to_bytes()
+    };
+
+    Args { rest }
+}
@@ -3,6 +3,10 @@ ij_core_workaround!();
 use crate::core::mem::MaybeUninit;
 use crate::core::ptr::NonNull;
 use core::alloc::Layout;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use horizon_ipc::buffer::get_ipc_buffer_ptr;
+use horizon_svc::BreakReason;
+use horizon_sync::lock_owner::LockOwner;
 use horizon_sync::mutex::Mutex;
 
 mod buddy;
@@ -12,6 +16,68 @@ const BUDDY_LEVELS: usize = 21;
 
 static mut BUDDY_ALLOCATOR: MaybeUninit<Mutex<buddy::Heap<BUDDY_LEVELS>>> = MaybeUninit::uninit();
 
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// The thread holding the allocator lock right now
+///
+/// Used to detect reentrant allocations (for example from a logging call inside the allocator),
+///  which would otherwise deadlock on the mutex
+static LOCK_OWNER: LockOwner = LockOwner::new();
+
+/// The `fn(Layout)` registered with [super::on_alloc_failure], null if there is none
+static ALLOC_FAILURE_HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Thread-local region address is unique per thread, so we use it as a thread id
+///
+/// It costs a single register read, unlike getting the real thread id through a syscall
+#[inline]
+fn current_thread_id() -> u64 {
+    unsafe { get_ipc_buffer_ptr() as u64 }
+}
+
+/// Notify an attached debugger about a misuse of the heap, only in debug builds
+///
+/// The process continues to run, the caller is expected to fail the operation gracefully
+///  (which it does in all builds)
+#[cold]
+fn report_misuse(message: &str) {
+    if cfg!(debug_assertions) {
+        let _ = unsafe {
+            horizon_svc::r#break(
+                BreakReason::ASSERT | BreakReason::NOTIFICATION_ONLY_FLAG,
+                message.as_ptr(),
+                message.len(),
+            )
+        };
+    }
+}
+
+/// Run `f` with the allocator locked
+///
+/// Returns `None` instead of deadlocking or touching uninitialized memory when the heap is used
+///  before [init] or reentrantly from the same thread
+#[inline]
+fn with_allocator<R>(f: impl FnOnce(&mut buddy::Heap<BUDDY_LEVELS>) -> R) -> Option<R> {
+    if !INITIALIZED.load(Ordering::Acquire) {
+        report_misuse("horizon-global: heap used before initialization");
+        return None;
+    }
+
+    let this_thread = current_thread_id();
+    if LOCK_OWNER.is(this_thread) {
+        report_misuse("horizon-global: reentrant heap usage");
+        return None;
+    }
+
+    let mut allocator = unsafe { BUDDY_ALLOCATOR.assume_init_ref() }.lock();
+    LOCK_OWNER.set(this_thread);
+
+    let res = f(&mut allocator);
+
+    LOCK_OWNER.clear();
+    Some(res)
+}
+
 /// Initialize the heap
 ///
 /// # Safety
@@ -23,11 +89,12 @@ pub unsafe fn init(heap_start: *mut u8, heap_size: usize) {
     let heap = buddy::Heap::new(NonNull::new_unchecked(heap_start), heap_size).unwrap();
 
     BUDDY_ALLOCATOR.write(Mutex::new(heap));
+    INITIALIZED.store(true, Ordering::Release);
 }
 
 /// Allocate memory
 ///
-/// Returns `null` on error
+/// Returns `null` on error, including the allocation before [init] or from inside the allocator itself
 ///
 /// # Safety
 ///
@@ -38,14 +105,35 @@ pub unsafe fn init(heap_start: *mut u8, heap_size: usize) {
 pub fn __horizon_global_heap_allocate(size: usize, alignment: usize) -> *mut u8 {
     let layout = unsafe { Layout::from_size_align_unchecked(size, alignment) };
 
-    let mut allocator = unsafe { BUDDY_ALLOCATOR.assume_init_ref() }.lock();
+    // the hook is called after the lock is released, so it doesn't trip the reentrancy check
+    with_allocator(|allocator| allocator.allocate(layout)).map_or(core::ptr::null_mut(), |res| {
+        handle_alloc_result(res, layout)
+    })
+}
 
-    allocator.allocate(layout).unwrap_or_else(|_e| {
-        // TODO: log or put a breakpoint here?
+#[inline]
+fn handle_alloc_result(res: Result<*mut u8, buddy::AllocationError>, layout: Layout) -> *mut u8 {
+    res.unwrap_or_else(|_| {
+        alloc_failure(layout);
         core::ptr::null_mut()
     })
 }
 
+#[cold]
+fn alloc_failure(layout: Layout) {
+    let hook = ALLOC_FAILURE_HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // SAFETY: only `fn(Layout)` pointers are stored in ALLOC_FAILURE_HOOK
+        let hook: fn(Layout) = unsafe { core::mem::transmute(hook) };
+        hook(layout);
+    }
+}
+
+#[no_mangle]
+pub fn __horizon_global_heap_set_alloc_failure_hook(hook: fn(Layout)) {
+    ALLOC_FAILURE_HOOK.store(hook as *mut (), Ordering::Release);
+}
+
 /// Deallocate memory
 ///
 /// Ignores errors
@@ -61,7 +149,70 @@ pub fn __horizon_global_heap_allocate(size: usize, alignment: usize) -> *mut u8
 pub fn __horizon_global_heap_deallocate(ptr: *mut u8, size: usize, alignment: usize) {
     let layout = unsafe { Layout::from_size_align_unchecked(size, alignment) };
 
-    let mut allocator = unsafe { BUDDY_ALLOCATOR.assume_init_ref() }.lock();
+    // if the heap is misused the memory is leaked
+    let _ = with_allocator(|allocator| unsafe { allocator.deallocate(ptr, layout) });
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::{
+        __horizon_global_heap_allocate, __horizon_global_heap_deallocate,
+        __horizon_global_heap_set_alloc_failure_hook, buddy, handle_alloc_result, init,
+        with_allocator,
+    };
+    use core::alloc::Layout;
+    use core::ptr::NonNull;
+    use std::sync::Mutex;
+
+    static FAILED_LAYOUT: Mutex<Option<Layout>> = Mutex::new(None);
+
+    fn record_failure(layout: Layout) {
+        *FAILED_LAYOUT.lock().unwrap() = Some(layout);
+    }
+
+    #[test]
+    fn alloc_failure_hook() {
+        let memory_layout = Layout::from_size_align(0x1000, 0x1000).unwrap();
+        let memory = unsafe { std::alloc::alloc(memory_layout) };
+        let mut heap: buddy::Heap<4> =
+            unsafe { buddy::Heap::new(NonNull::new(memory).unwrap(), 0x1000) }.unwrap();
+
+        __horizon_global_heap_set_alloc_failure_hook(record_failure);
+
+        let whole = Layout::from_size_align(0x1000, 8).unwrap();
+        let res = handle_alloc_result(heap.allocate(whole), whole);
+        assert!(!res.is_null());
+        assert_eq!(*FAILED_LAYOUT.lock().unwrap(), None);
+
+        // the heap is exhausted now
+        let small = Layout::from_size_align(0x10, 8).unwrap();
+        let res = handle_alloc_result(heap.allocate(small), small);
+        assert!(res.is_null());
+        assert_eq!(*FAILED_LAYOUT.lock().unwrap(), Some(small));
+
+        unsafe { std::alloc::dealloc(memory, memory_layout) };
+    }
+
+    #[test]
+    fn reentrant_allocation() {
+        // the smallest heap where the minimum block can still hold the free list header
+        const HEAP_SIZE: usize = 8 << (super::BUDDY_LEVELS - 1);
+
+        // the global heap stays initialized until the end of the process, so the memory is leaked
+        let memory =
+            unsafe { std::alloc::alloc(Layout::from_size_align(HEAP_SIZE, 0x1000).unwrap()) };
+        unsafe { init(memory, HEAP_SIZE) };
+
+        // an allocation made while this thread holds the allocator lock, like one made from a
+        //  logging call inside the allocator, must fail instead of deadlocking
+        let nested = with_allocator(|_| __horizon_global_heap_allocate(0x10, 8)).unwrap();
+        assert!(nested.is_null());
 
-    unsafe { allocator.deallocate(ptr, layout) }
+        // the lock owner is cleared on the way out
+        let ptr = __horizon_global_heap_allocate(0x10, 8);
+        assert!(!ptr.is_null());
+        __horizon_global_heap_deallocate(ptr, 0x10, 8);
+    }
 }
@@ -1,6 +1,6 @@
 //! This uses a buddy memory allocator to coarsely distribute memory between dlmalloc (or any other heap) and some other alloc that may be used
 //!
-//! uwin use-case: get some memory pages to feed it to svc::map_process_code_memory to remap them to arbitrary address inside alias region
+//! uwin use-case: get some memory pages to feed it to [map_process_code_memory](horizon_svc::map_process_code_memory) to remap them to arbitrary address inside alias region
 
 ij_core_workaround!();
 
@@ -15,6 +15,19 @@ pub use r#impl::init;
 extern "Rust" {
     fn __horizon_global_heap_allocate(size: usize, alignment: usize) -> *mut u8;
     fn __horizon_global_heap_deallocate(ptr: *mut u8, size: usize, alignment: usize);
+    fn __horizon_global_heap_set_alloc_failure_hook(hook: fn(Layout));
+}
+
+/// Register a function to be called when an allocation fails because the heap is exhausted
+///
+/// The hook gets the layout of the failed allocation and is called right before the allocator
+///  returns null, so it can log it, dump heap stats or break into the debugger. It replaces
+///  the previously registered hook, by default nothing is called.
+///
+/// The hook is called without holding the allocator lock, but it should avoid allocating:
+///  an allocation failing inside of it calls it again.
+pub fn on_alloc_failure(hook: fn(Layout)) {
+    unsafe { __horizon_global_heap_set_alloc_failure_hook(hook) }
 }
 
 /// Allocate a block of memory large enough to contain `layout.size`,
@@ -25,7 +38,7 @@ extern "Rust" {
 /// All allocated memory must be passed to `deallocate` with the same
 /// `layout` parameter, or else horrible things will happen.
 pub fn allocate(layout: Layout) -> Result<*mut u8, ()> {
-    // SAFETY: TODO
+    // SAFETY: the implementation returns null when used before init or reentrantly
     let res = unsafe { __horizon_global_heap_allocate(layout.size(), layout.align()) };
     if res == core::ptr::null_mut() {
         Err(())
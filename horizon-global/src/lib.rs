@@ -2,6 +2,7 @@
 // we do very black magic here. Maybe there is a better way...
 #![cfg_attr(feature = "std", feature(rustc_private, horizon_nx_platform))]
 #![deny(rust_2018_idioms)]
+#![feature(thread_local)]
 #![cfg_attr(feature = "rustc-dep-of-std", feature(no_core), no_core)]
 
 #[cfg(all(feature = "std", feature = "rustc-dep-of-std"))]
@@ -58,12 +59,13 @@ macro_rules! abi_version {
         }
     };
 }
-abi_version!(2);
+abi_version!(3);
 
 pub mod environment;
 pub mod heap;
 pub mod mounts;
 pub mod services;
+pub mod thread_local;
 cfg_if! {
     if #[cfg(feature = "impl")] {
         pub mod virtual_memory;
@@ -1,7 +1,57 @@
 //! Stores sessions for various services that should be global per process and are used by libstd
+//!
+//! When running as an NRO the runtime pre-registers the sessions handed over by the homebrew loader
+//!  (`OverrideService` config entries) with `replace`, so they take precedence over the ones
+//!  `get_or_connect` would open.
 
 // TODO: implement domain handles
 
+ij_core_workaround!();
+
+use core::time::Duration;
+use horizon_error::{ErrorCode, ErrorCodeModule, KernelErrorCode, Result};
+use horizon_ipc::handle_storage::SharedHandle;
+
+/// How often [wait_for] retries connecting to the port
+pub const NAMED_PORT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Connects to a named port (like `sm:`), waiting for it to be created
+///
+/// Useful early in the boot, before the port owner got to create it. The kernel doesn't signal
+///  anything when a named port is created, so this polls `connect_to_named_port` every
+///  [NAMED_PORT_POLL_INTERVAL]. To wait for a service registered in sm use
+///  `IUserInterface::wait_for_service` of `horizon-ipcdef`, which can block in sm instead.
+///
+/// Fails with `TimedOut` if the port did not appear in time. `name` is at most 11 bytes long.
+pub fn wait_for(name: &str, timeout: Option<Duration>) -> Result<SharedHandle> {
+    let mut port_name = [0u8; 12];
+    assert!(name.len() < port_name.len(), "named port name too long");
+    port_name[..name.len()].copy_from_slice(name.as_bytes());
+
+    let not_found =
+        ErrorCode::from_parts(KernelErrorCode::MODULE, KernelErrorCode::NotFound as u32);
+
+    let mut waited = Duration::ZERO;
+    loop {
+        // SAFETY: the name is zero-terminated
+        match unsafe { horizon_svc::connect_to_named_port(&port_name[..=name.len()]) } {
+            Ok(handle) => return Ok(SharedHandle::new(handle)),
+            Err(e) if e == not_found => {}
+            Err(e) => return Err(e),
+        }
+
+        if timeout.map_or(false, |timeout| waited >= timeout) {
+            return Err(ErrorCode::from_parts(
+                KernelErrorCode::MODULE,
+                KernelErrorCode::TimedOut as u32,
+            ));
+        }
+
+        horizon_svc::sleep_thread(NAMED_PORT_POLL_INTERVAL);
+        waited += NAMED_PORT_POLL_INTERVAL;
+    }
+}
+
 macro_rules! normal_service {
     ($name:ident) => {
         pub mod $name {
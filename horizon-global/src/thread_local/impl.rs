@@ -0,0 +1,103 @@
+ij_core_workaround!();
+
+use crate::thread_local::{Dtor, RegisterError, DTOR_COUNT, DTOR_ROUNDS};
+
+type Dtors = [Option<(*mut u8, Dtor)>; DTOR_COUNT];
+
+#[thread_local]
+static mut DTORS: Dtors = [None; DTOR_COUNT];
+
+/// Run `f` on the destructor list of the current thread
+fn with_dtors<R>(f: impl FnOnce(&mut Dtors) -> R) -> R {
+    // SAFETY: only the current thread can access the list, and no destructors are called while it's borrowed
+    unsafe { f(&mut *core::ptr::addr_of_mut!(DTORS)) }
+}
+
+#[no_mangle]
+pub fn __horizon_global_thread_local_register_dtor(
+    data: *mut u8,
+    dtor: Dtor,
+) -> Result<(), RegisterError> {
+    with_dtors(|dtors| {
+        // the list is filled from the start, so the first free entry is after all the registered ones
+        if let Some(entry) = dtors.iter_mut().find(|e| e.is_none()) {
+            *entry = Some((data, dtor));
+            Ok(())
+        } else {
+            Err(RegisterError::TooManyDtors)
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe fn __horizon_global_thread_local_run_dtors() {
+    for _ in 0..DTOR_ROUNDS {
+        // take the whole list, the destructors may register new ones
+        let dtors = with_dtors(|dtors| core::mem::replace(dtors, [None; DTOR_COUNT]));
+        if dtors.iter().all(|e| e.is_none()) {
+            return;
+        }
+
+        for &(data, dtor) in dtors.iter().rev().flatten() {
+            dtor(data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::{
+        __horizon_global_thread_local_register_dtor, __horizon_global_thread_local_run_dtors,
+    };
+    use crate::thread_local::DTOR_ROUNDS;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    static RUN: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+    unsafe extern "C" fn record(data: *mut u8) {
+        RUN.lock().unwrap().push(data as usize);
+    }
+
+    unsafe extern "C" fn reregister(data: *mut u8) {
+        record(data);
+        __horizon_global_thread_local_register_dtor(data, reregister).unwrap();
+    }
+
+    #[test]
+    fn run_on_teardown() {
+        __horizon_global_thread_local_register_dtor(1 as _, record).unwrap();
+        __horizon_global_thread_local_register_dtor(2 as _, record).unwrap();
+        // a destructor that keeps registering itself shouldn't hang the teardown
+        __horizon_global_thread_local_register_dtor(3 as _, reregister).unwrap();
+
+        unsafe { __horizon_global_thread_local_run_dtors() };
+
+        let mut expected = std::vec![3, 2, 1];
+        expected.extend(core::iter::repeat_n(3, DTOR_ROUNDS - 1));
+        assert_eq!(*RUN.lock().unwrap(), expected);
+    }
+
+    static COUNTED: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe extern "C" fn count(_: *mut u8) {
+        COUNTED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn per_thread_lists() {
+        __horizon_global_thread_local_register_dtor(core::ptr::null_mut(), count).unwrap();
+
+        // another thread exiting doesn't run the destructors of this one
+        std::thread::spawn(|| unsafe { __horizon_global_thread_local_run_dtors() })
+            .join()
+            .unwrap();
+        assert_eq!(COUNTED.load(Ordering::SeqCst), 0);
+
+        unsafe { __horizon_global_thread_local_run_dtors() };
+        assert_eq!(COUNTED.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,69 @@
+//! Implements a storage for destructors of thread-local variables
+//!
+//! Each thread has its own list of destructors, kept in its TLS block. Horizon doesn't run any code when
+//!  a thread exits, so they are run by the runtime: horizon-rt calls [run_dtors] from `__horizon_rt_exit`
+//!  for the main thread and from `__horizon_rt_exit_thread` for the others, which the code creating
+//!  the thread must call when the thread is done. A thread exiting with a bare `svc::exit_thread`
+//!  leaks its thread-locals.
+//!
+//! The destructors run in rounds: a round runs the ones registered so far in the reverse order of registration,
+//!  and the ones they register run in the next round. See [DTOR_ROUNDS] for the limit.
+
+ij_core_workaround!();
+
+#[cfg(feature = "impl")]
+mod r#impl;
+
+/// Maximum number of destructors that can be registered at the same time
+pub const DTOR_COUNT: usize = 64;
+
+/// Maximum number of times [run_dtors] goes over the registered destructors
+///
+/// Destructors can access other thread-locals, registering more destructors.
+///  Those are run on the next round, the ones still registered after the last one are leaked.
+pub const DTOR_ROUNDS: usize = 4;
+
+/// A destructor of a thread-local variable, called with the pointer it was registered with
+pub type Dtor = unsafe extern "C" fn(*mut u8);
+
+/// Represents an error that occurred while registering a destructor
+#[derive(Debug)]
+#[repr(C)]
+pub enum RegisterError {
+    /// There are already [DTOR_COUNT] destructors registered
+    TooManyDtors,
+}
+
+extern "Rust" {
+    fn __horizon_global_thread_local_register_dtor(
+        data: *mut u8,
+        dtor: Dtor,
+    ) -> Result<(), RegisterError>;
+    fn __horizon_global_thread_local_run_dtors();
+}
+
+/// Register a destructor to be called with `data` when the current thread exits
+///
+/// The destructors are run in the reverse order of registration.
+///
+/// # Errors
+///
+/// See [RegisterError]
+///
+/// # Safety
+///
+/// `dtor` must be safe to call with `data` at any point until the thread exits
+pub unsafe fn register_dtor(data: *mut u8, dtor: Dtor) -> Result<(), RegisterError> {
+    __horizon_global_thread_local_register_dtor(data, dtor)
+}
+
+/// Run the destructors registered for the current thread
+///
+/// This is called by horizon-rt when a thread exits, so usually you don't call this
+///
+/// # Safety
+///
+/// Must be called only when the current thread is exiting, the thread-locals can't be used after it
+pub unsafe fn run_dtors() {
+    __horizon_global_thread_local_run_dtors()
+}
@@ -0,0 +1,169 @@
+//! Sending IPC requests without blocking the thread, for apps running their own async executor
+//!
+//! The request is sent with `send_async_request_with_user_buffer`: the kernel gives back an event
+//!  that gets signaled once the server has replied. Waiting for the event is up to the app,
+//!  which plugs its executor in by implementing [IpcExecutor].
+//!
+//! The codegen can emit `async fn <command>_async` variants of the commands built on top of this
+//!  (see the `--gen-async` option), available with the `async-ipc` feature of `horizon-ipcdef`.
+//!
+//! Apps that don't have an executor can use the `<command>_send` variants instead (see the `--gen-send` option):
+//!  they return a [PendingResponse], whose event the app waits for however it likes.
+
+use alloc::boxed::Box;
+use core::future::Future;
+use horizon_error::Result;
+use horizon_svc as svc;
+use horizon_svc::RawHandle;
+
+/// The app-supplied part of async IPC: waiting for the completion event of a request
+pub trait IpcExecutor {
+    type Wait<'a>: Future<Output = Result<()>> + 'a
+    where
+        Self: 'a;
+
+    /// Returns a future that completes when `event` gets signaled
+    ///
+    /// The event is owned (and closed) by the caller, the executor should not close or reset it
+    fn wait_for_event(&self, event: RawHandle) -> Self::Wait<'_>;
+}
+
+/// Size of the message buffer of an [AsyncRequest]
+pub const ASYNC_REQUEST_BUFFER_SIZE: usize = 0x1000;
+
+#[repr(C, align(0x1000))]
+struct AlignedBuffer([u8; ASYNC_REQUEST_BUFFER_SIZE]);
+
+/// Message buffer for a single async request, plays the role of the TLS IPC buffer
+///
+/// The kernel writes the reply to the buffer whenever the server gets to it, so dropping the request
+///  while it is in flight (e.g. when the future awaiting [AsyncRequest::send] is cancelled)
+///  blocks the thread until the reply arrives.
+pub struct AsyncRequest {
+    buffer: AlignedBuffer,
+    /// Completion event of the request that has been sent, but not waited for yet
+    pending_event: Option<RawHandle>,
+}
+
+impl AsyncRequest {
+    pub const fn new() -> Self {
+        Self {
+            buffer: AlignedBuffer([0; ASYNC_REQUEST_BUFFER_SIZE]),
+            pending_event: None,
+        }
+    }
+
+    /// Allocates the request on the heap, so that it can be moved around while in flight
+    pub fn new_boxed() -> Box<Self> {
+        Box::new(Self::new())
+    }
+
+    /// Pointer to the message buffer, the request should be written here and the response read from here
+    ///
+    /// Don't hold on to it across [AsyncRequest::send], get a new one instead
+    #[inline]
+    pub fn buffer_ptr(&mut self) -> *mut u8 {
+        self.buffer.0.as_mut_ptr()
+    }
+
+    /// Sends the request in the buffer to `session` and waits for the reply with `executor`
+    pub async fn send<E: IpcExecutor + ?Sized>(
+        &mut self,
+        executor: &E,
+        session: RawHandle,
+    ) -> Result<()> {
+        // SAFETY: the future is pinned while awaited, and we don't let the buffer go away while the request
+        //  is in flight, see Drop
+        let event = unsafe { self.start(session) }?;
+
+        executor.wait_for_event(event).await?;
+
+        self.finish();
+
+        Ok(())
+    }
+
+    /// Sends the request in the buffer to `session`, returning the event signaled when the reply arrives
+    ///
+    /// # Safety
+    ///
+    /// The kernel writes the reply to the buffer wherever it was at this point,
+    ///  so the request must not move until [finish](Self::finish) is called
+    unsafe fn start(&mut self, session: RawHandle) -> Result<RawHandle> {
+        debug_assert!(
+            self.pending_event.is_none(),
+            "another request is still in flight"
+        );
+
+        let event = svc::send_async_request_with_user_buffer(&mut self.buffer.0, session)?;
+        self.pending_event = Some(event);
+
+        Ok(event)
+    }
+
+    /// Marks the request as done, once the event returned by [start](Self::start) has been signaled
+    fn finish(&mut self) {
+        if let Some(event) = self.pending_event.take() {
+            let _ = svc::close_handle(event);
+        }
+    }
+}
+
+impl Default for AsyncRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A request sent by a `<command>_send` variant of a command, whose reply hasn't been read yet
+///
+/// The app waits for [event](Self::event) however it likes (e.g. along with other handles in its own
+///  `wait_synchronization` loop) and then calls [receive](Self::receive), no [IpcExecutor] is needed.
+///  Dropping it without receiving blocks until the reply arrives, same as dropping an in-flight [AsyncRequest].
+pub struct PendingResponse<T> {
+    request: Box<AsyncRequest>,
+    event: RawHandle,
+    decode: fn(*mut u8) -> Result<T>,
+}
+
+impl<T> PendingResponse<T> {
+    /// Sends the request written to `request` to `session`, the reply is decoded with `decode` once received
+    pub fn send(
+        mut request: Box<AsyncRequest>,
+        session: RawHandle,
+        decode: fn(*mut u8) -> Result<T>,
+    ) -> Result<Self> {
+        // SAFETY: the request is on the heap, so moving the box around doesn't move the buffer
+        let event = unsafe { request.start(session) }?;
+
+        Ok(Self {
+            request,
+            event,
+            decode,
+        })
+    }
+
+    /// The event signaled when the reply arrives, it's owned by the pending response and shouldn't be closed
+    #[inline]
+    pub fn event(&self) -> RawHandle {
+        self.event
+    }
+
+    /// Waits for the reply (returning right away if it has already arrived) and decodes it
+    pub fn receive(mut self) -> Result<T> {
+        svc::wait_synchronization(&[self.event], None)?;
+        self.request.finish();
+
+        (self.decode)(self.request.buffer_ptr())
+    }
+}
+
+impl Drop for AsyncRequest {
+    fn drop(&mut self) {
+        if let Some(event) = self.pending_event.take() {
+            // the only thing we can do is to wait for the reply to land in the buffer before freeing it
+            let _ = svc::wait_synchronization(&[event], None);
+            let _ = svc::close_handle(event);
+        }
+    }
+}
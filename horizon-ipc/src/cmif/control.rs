@@ -1,12 +1,212 @@
+//! CMIF control requests, handled by the IPC framework of the server for any session
+//!
+//! Closing an object inside a domain is not here, as it's a domain request, not a control one (see [super::domain])
+
 use crate::buffer::get_ipc_buffer_ptr;
 use crate::cmif::CommandType;
 use crate::raw::cmif::{CmifInHeader, CmifOutHeader};
-use crate::raw::hipc::HipcHeader;
+use crate::raw::hipc::{HipcHeader, HipcSpecialHeader};
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use horizon_error::{ErrorCode, Result};
 use horizon_svc::RawHandle;
 
-pub fn clone_object(_handle: RawHandle) -> RawHandle {
-    todo!()
+#[repr(C, packed)]
+struct ControlRequest<T> {
+    hipc: HipcHeader,
+    pre_padding: [u8; 8],
+    cmif: CmifInHeader,
+    raw_data: T,
+    post_padding: [u8; 8],
+}
+
+#[repr(C, packed)]
+struct ControlResponse<T> {
+    hipc: HipcHeader,
+    pre_padding: [u8; 8],
+    cmif: CmifOutHeader,
+    raw_data: T,
+    post_padding: [u8; 8],
+}
+
+#[repr(C, packed)]
+struct ControlHandleResponse {
+    hipc: HipcHeader,
+    special_header: HipcSpecialHeader,
+    handle: RawHandle,
+    cmif: CmifOutHeader,
+    post_padding: [u8; 16],
+}
+
+/// Offset of the result in a response that has no handles, used when the server failed
+///  the request that should have returned a handle
+const NO_HANDLE_RESULT_OFFSET: isize = 24;
+
+fn write_control_request<In>(command_id: u32, data_in: In) -> *mut u8 {
+    // the raw data must be word-sized, all control requests take either nothing or an u32
+    debug_assert_eq!(::core::mem::size_of::<In>() % 4, 0);
+    let num_data_words = 8 + ::core::mem::size_of::<In>() as u32 / 4;
+
+    let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+    unsafe {
+        ::core::ptr::write(
+            ipc_buffer_ptr as *mut _,
+            ControlRequest {
+                hipc: HipcHeader::new(
+                    CommandType::Control,
+                    0,
+                    0,
+                    0,
+                    0,
+                    num_data_words,
+                    0,
+                    0,
+                    false,
+                ),
+                pre_padding: Default::default(),
+                cmif: CmifInHeader {
+                    magic: CmifInHeader::MAGIC,
+                    version: 0,
+                    command_id,
+                    token: 0,
+                },
+                raw_data: data_in,
+                post_padding: Default::default(),
+            },
+        )
+    };
+
+    ipc_buffer_ptr
+}
+
+fn send_control_request<In, Out>(handle: RawHandle, command_id: u32, data_in: In) -> Result<Out> {
+    let ipc_buffer_ptr = write_control_request(command_id, data_in);
+
+    horizon_svc::send_sync_request(handle)?;
+
+    read_control_response(ipc_buffer_ptr)
+}
+
+fn read_control_response<Out>(ipc_buffer_ptr: *const u8) -> Result<Out> {
+    let ControlResponse {
+        hipc,
+        cmif,
+        raw_data,
+        ..
+    } = unsafe { ::core::ptr::read(ipc_buffer_ptr as *const ControlResponse<Out>) };
+    if cmif.result.is_failure() {
+        return Err(cmif.result);
+    }
+    debug_assert_eq!(hipc.has_special_header(), 0);
+    debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+
+    Ok(raw_data)
+}
+
+fn send_control_request_with_handle<In>(
+    handle: RawHandle,
+    command_id: u32,
+    data_in: In,
+) -> Result<RawHandle> {
+    let ipc_buffer_ptr = write_control_request(command_id, data_in);
+
+    horizon_svc::send_sync_request(handle)?;
+
+    let ControlHandleResponse {
+        hipc,
+        special_header,
+        handle,
+        cmif,
+        ..
+    } = unsafe { ::core::ptr::read(ipc_buffer_ptr as *const _) };
+    if hipc.has_special_header() == 0 {
+        // no handle -> the layout is different, see the comment in the codegen
+        return Err(unsafe {
+            ::core::ptr::read(ipc_buffer_ptr.offset(NO_HANDLE_RESULT_OFFSET) as *const ErrorCode)
+        });
+    }
+    if cmif.result.is_failure() {
+        return Err(cmif.result);
+    }
+    debug_assert_eq!(special_header.num_move_handles(), 1);
+    debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+
+    Ok(handle)
+}
+
+/// Converts the session into a domain, returning the object id the session object got in it
+pub fn convert_current_object_to_domain(handle: RawHandle) -> Result<u32> {
+    send_control_request(handle, 0, ())
+}
+
+/// Creates a new session to the object with the specified id inside of the domain
+pub fn copy_from_current_domain(handle: RawHandle, object_id: u32) -> Result<RawHandle> {
+    send_control_request_with_handle(handle, 1, object_id)
+}
+
+/// Creates a new session to the same object
+pub fn clone_current_object(handle: RawHandle) -> Result<RawHandle> {
+    send_control_request_with_handle(handle, 2, ())
+}
+
+/// Gets the size of the pointer buffer the server allocated for the session
+///
+/// The size limits the total size of the pointer buffers that can be sent in one request
+pub fn query_pointer_buffer_size(handle: RawHandle) -> Result<u16> {
+    send_control_request(handle, 3, ())
+}
+
+/// Same as [clone_current_object], but allows to pass a tag (unused by servers as far as we know)
+pub fn clone_current_object_ex(handle: RawHandle, tag: u32) -> Result<RawHandle> {
+    send_control_request_with_handle(handle, 4, tag)
+}
+
+const POINTER_BUFFER_SIZE_CACHE_SIZE: usize = 16;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_CACHE_ENTRY: AtomicU64 = AtomicU64::new(0);
+
+/// Recently queried pointer buffer sizes, each entry is `handle << 32 | size`, 0 meaning an empty entry
+static POINTER_BUFFER_SIZE_CACHE: [AtomicU64; POINTER_BUFFER_SIZE_CACHE_SIZE] =
+    [EMPTY_CACHE_ENTRY; POINTER_BUFFER_SIZE_CACHE_SIZE];
+/// The entry to be replaced next
+static POINTER_BUFFER_SIZE_CACHE_NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// Gets the pointer buffer size of the session, querying the server only the first time
+///
+/// Used to decide whether an auto-select buffer fits into the pointer buffer or has to be sent
+///  as a map alias. If the server fails the query, it's assumed to have no pointer buffer (size 0).
+///
+/// The cache is keyed by the handle value and is invalidated by [close_object].
+pub fn pointer_buffer_size(handle: RawHandle) -> usize {
+    for entry in POINTER_BUFFER_SIZE_CACHE.iter() {
+        let value = entry.load(Ordering::Relaxed);
+        if (value >> 32) as u32 == handle.0 {
+            return (value & 0xffff) as usize;
+        }
+    }
+
+    let size = query_pointer_buffer_size(handle).unwrap_or(0);
+
+    // a race here can at worst put the same handle twice into the cache, which is harmless
+    let index = POINTER_BUFFER_SIZE_CACHE_NEXT.fetch_add(1, Ordering::Relaxed)
+        % POINTER_BUFFER_SIZE_CACHE_SIZE;
+    POINTER_BUFFER_SIZE_CACHE[index].store((handle.0 as u64) << 32 | size as u64, Ordering::Relaxed);
+
+    size as usize
+}
+
+fn invalidate_pointer_buffer_size(handle: RawHandle) {
+    for entry in POINTER_BUFFER_SIZE_CACHE.iter() {
+        let value = entry.load(Ordering::Relaxed);
+        if (value >> 32) as u32 == handle.0 {
+            let _ = entry.compare_exchange(value, 0, Ordering::Relaxed, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Creates a new session to the same object, the counterpart of [close_object]
+pub fn clone_object(handle: RawHandle) -> Result<RawHandle> {
+    clone_current_object(handle)
 }
 
 #[allow(dead_code)]
@@ -62,7 +262,39 @@ fn send_close_request(handle: RawHandle) -> Result<()> {
 
 #[allow(unreachable_code)]
 pub fn close_object(handle: RawHandle) {
+    invalidate_pointer_buffer_size(handle);
     send_close_request(handle).unwrap();
 
     horizon_svc::close_handle(handle).unwrap();
 }
+
+#[cfg(test)]
+mod test {
+    use super::{read_control_response, write_control_request};
+    use crate::buffer::get_ipc_buffer_mut;
+    use crate::raw::cmif::{CmifInHeader, CmifOutHeader};
+    use horizon_error::ErrorCode;
+
+    #[test]
+    fn query_pointer_buffer_size() {
+        let ipc_buffer_ptr = write_control_request(3, ());
+        let buffer = unsafe { get_ipc_buffer_mut() };
+
+        // type 5 (control), 8 raw data words
+        assert_eq!(buffer[0x00..0x08], [5, 0, 0, 0, 8, 0, 0, 0]);
+        assert_eq!(buffer[0x10..0x14], CmifInHeader::MAGIC.to_le_bytes());
+        // version, command id, token
+        assert_eq!(buffer[0x14..0x20], [0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+
+        // the server replies with the size as an u16 after the header
+        buffer[0x00..0x08].copy_from_slice(&[0, 0, 0, 0, 8, 0, 0, 0]);
+        buffer[0x10..0x14].copy_from_slice(&CmifOutHeader::MAGIC.to_le_bytes());
+        buffer[0x14..0x20].fill(0);
+        buffer[0x20..0x22].copy_from_slice(&0x500u16.to_le_bytes());
+        assert_eq!(read_control_response::<u16>(ipc_buffer_ptr), Ok(0x500));
+
+        let error = ErrorCode::from_parts(10, 221);
+        buffer[0x18..0x1c].copy_from_slice(&error.repr().to_le_bytes());
+        assert_eq!(read_control_response::<u16>(ipc_buffer_ptr), Err(error));
+    }
+}
@@ -0,0 +1,396 @@
+//! CMIF domains: many objects multiplexed over a single session, addressed by object ids
+//!
+//! A session is converted with [DomainHandle::convert], and the objects in the domain are [DomainObject]s
+//!  borrowing it. Only raw-data requests are supported, the codegen can't make domain requests yet
+
+use crate::buffer::get_ipc_buffer_ptr;
+use crate::cmif::control::convert_current_object_to_domain;
+use crate::cmif::{CommandType, SessionHandle, SessionHandleRef};
+use crate::handle_storage::{HandleStorage, RefHandle};
+use crate::raw::cmif::{CmifDomainInHeader, CmifDomainOutHeader, CmifInHeader, CmifOutHeader};
+use crate::raw::hipc::HipcHeader;
+use core::ops::Deref;
+use horizon_error::{ErrorCode, Result};
+use horizon_svc::RawHandle;
+
+/// Type of the request inside of a domain message
+#[repr(u8)]
+#[derive(Copy, Clone)]
+pub enum DomainRequestType {
+    Invalid = 0,
+    SendMessage = 1,
+    Close = 2,
+}
+
+/// Sends the domain message closing the object with the specified id
+pub fn send_domain_close_request(domain: RawHandle, object_id: u32) {
+    #[repr(C, packed)]
+    struct Request {
+        hipc: HipcHeader,
+        pre_padding: [u8; 8],
+        domain: CmifDomainInHeader,
+        post_padding: [u8; 8],
+    }
+    // Compiler time request size check
+    let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+
+    let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+    unsafe {
+        ::core::ptr::write(
+            ipc_buffer_ptr as *mut _,
+            Request {
+                hipc: HipcHeader::new(CommandType::Request, 0, 0, 0, 0, 8, 0, 0, false),
+                pre_padding: Default::default(),
+                domain: CmifDomainInHeader {
+                    type_: DomainRequestType::Close as u8,
+                    num_in_objects: 0,
+                    data_size: 0,
+                    object_id,
+                    padding: 0,
+                    token: 0,
+                },
+                post_padding: Default::default(),
+            },
+        )
+    };
+    // the reply carries nothing of interest, and there is nothing to do if closing failed anyway
+    let _ = horizon_svc::send_sync_request(domain);
+}
+
+/// A session converted to a domain, the objects in it are [DomainObject]s borrowing it
+///
+/// Each [DomainObject] closes itself when dropped, and the borrow makes sure that it happens before
+///  the session is closed. The protocol closes one object per message, so when all of the objects
+///  are going away it's cheaper to [leak](DomainObject::leak) them and drop the domain:
+///  closing the session destroys all the objects still in it at once.
+pub struct DomainHandle(SessionHandle);
+
+impl DomainHandle {
+    /// Converts the session into a domain, returns it along with the id the session object got
+    ///
+    /// The session object can be wrapped into a [DomainObject] to close it with the others.
+    ///  The conversion costs a round-trip to the server, and each domain message carries an additional
+    ///  16-byte header, so it only pays off for objects handing out many sub-objects (like `am` does).
+    ///  If the server refuses the conversion, the session is returned along with the error,
+    ///  so that it can be used as is.
+    pub fn convert(
+        session: SessionHandle,
+    ) -> core::result::Result<(Self, u32), (ErrorCode, SessionHandle)> {
+        let result = convert_current_object_to_domain(*session.get());
+        match result {
+            Ok(object_id) => Ok((Self(session), object_id)),
+            Err(e) => Err((e, session)),
+        }
+    }
+
+    #[inline]
+    pub fn as_ref(&self) -> DomainHandleRef<'_> {
+        DomainHandleRef(self.0.as_ref())
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> SessionHandle {
+        self.0
+    }
+}
+
+impl Deref for DomainHandle {
+    type Target = SessionHandle;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// A borrowed [DomainHandle]
+#[derive(Copy, Clone)]
+pub struct DomainHandleRef<'a>(SessionHandleRef<'a>);
+
+impl DomainHandleRef<'_> {
+    /// Create a new DomainHandleRef from a raw handle
+    ///
+    /// It's caller's responsibility to ensure that the session is a domain and that the lifetime of the handle is correct
+    #[inline]
+    pub fn new(handle: RawHandle) -> Self {
+        Self(SessionHandle::new(RefHandle::new(handle)))
+    }
+}
+
+impl<'a> Deref for DomainHandleRef<'a> {
+    type Target = SessionHandleRef<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// An object living in a domain, closed with a domain message when dropped
+pub struct DomainObject<'a> {
+    domain: DomainHandleRef<'a>,
+    object_id: u32,
+}
+
+impl<'a> DomainObject<'a> {
+    /// Takes the ownership of the object with the id `object_id`, it must be alive in the domain
+    #[inline]
+    pub fn new(domain: DomainHandleRef<'a>, object_id: u32) -> Self {
+        Self { domain, object_id }
+    }
+
+    #[inline]
+    pub fn get_domain(&self) -> DomainHandleRef<'a> {
+        self.domain
+    }
+
+    #[inline]
+    pub fn object_id(&self) -> u32 {
+        self.object_id
+    }
+
+    #[inline]
+    pub fn framing(&self) -> Framing {
+        Framing::Domain {
+            object_id: self.object_id,
+        }
+    }
+
+    /// Sends a request with raw data only to the object
+    pub fn send_request<In, Out>(&self, command_id: u32, data_in: In) -> Result<Out> {
+        let framing = self.framing();
+
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        unsafe { write_request(ipc_buffer_ptr, framing, command_id, data_in) };
+
+        horizon_svc::send_sync_request(self.domain.inner())?;
+
+        unsafe { read_response(ipc_buffer_ptr, framing) }
+    }
+
+    /// Stops managing the object without closing it, returns its id
+    #[inline]
+    pub fn leak(self) -> u32 {
+        let object_id = self.object_id;
+        ::core::mem::forget(self);
+        object_id
+    }
+}
+
+impl Drop for DomainObject<'_> {
+    fn drop(&mut self) {
+        send_domain_close_request(self.domain.inner(), self.object_id)
+    }
+}
+
+/// How the requests to an object are framed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Framing {
+    /// The object is the session itself
+    Session,
+    /// The object lives in the domain the session was converted to
+    Domain { object_id: u32 },
+}
+
+#[repr(C, packed)]
+struct SessionRequest<T> {
+    hipc: HipcHeader,
+    pre_padding: [u8; 8],
+    cmif: CmifInHeader,
+    raw_data: T,
+    post_padding: [u8; 8],
+}
+
+#[repr(C, packed)]
+struct DomainRequest<T> {
+    hipc: HipcHeader,
+    pre_padding: [u8; 8],
+    domain: CmifDomainInHeader,
+    cmif: CmifInHeader,
+    raw_data: T,
+    post_padding: [u8; 8],
+}
+
+#[repr(C, packed)]
+struct SessionResponse<T> {
+    hipc: HipcHeader,
+    pre_padding: [u8; 8],
+    cmif: CmifOutHeader,
+    raw_data: T,
+    post_padding: [u8; 8],
+}
+
+#[repr(C, packed)]
+struct DomainResponse<T> {
+    hipc: HipcHeader,
+    pre_padding: [u8; 8],
+    domain: CmifDomainOutHeader,
+    cmif: CmifOutHeader,
+    raw_data: T,
+    post_padding: [u8; 8],
+}
+
+/// Writes a request with only raw data to the message buffer
+///
+/// # Safety
+///
+/// `buffer` must be valid for writes of the whole request
+unsafe fn write_request<In>(buffer: *mut u8, framing: Framing, command_id: u32, data_in: In) {
+    // the raw data must be word-sized
+    debug_assert_eq!(::core::mem::size_of::<In>() % 4, 0);
+
+    let cmif = CmifInHeader {
+        magic: CmifInHeader::MAGIC,
+        version: 1,
+        command_id,
+        token: 0,
+    };
+    let hipc = |num_data_words: usize| {
+        HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            num_data_words as u32,
+            0,
+            0,
+            false,
+        )
+    };
+
+    match framing {
+        Framing::Session => ::core::ptr::write(
+            buffer as *mut _,
+            SessionRequest {
+                hipc: hipc(::core::mem::size_of::<SessionRequest<In>>() / 4 - 2),
+                pre_padding: Default::default(),
+                cmif,
+                raw_data: data_in,
+                post_padding: Default::default(),
+            },
+        ),
+        Framing::Domain { object_id } => ::core::ptr::write(
+            buffer as *mut _,
+            DomainRequest {
+                hipc: hipc(::core::mem::size_of::<DomainRequest<In>>() / 4 - 2),
+                pre_padding: Default::default(),
+                domain: CmifDomainInHeader {
+                    type_: DomainRequestType::SendMessage as u8,
+                    num_in_objects: 0,
+                    data_size: (::core::mem::size_of::<CmifInHeader>()
+                        + ::core::mem::size_of::<In>()) as u16,
+                    object_id,
+                    padding: 0,
+                    token: 0,
+                },
+                cmif,
+                raw_data: data_in,
+                post_padding: Default::default(),
+            },
+        ),
+    }
+}
+
+/// Reads the response to a request written by [write_request]
+///
+/// # Safety
+///
+/// `buffer` must contain a response
+unsafe fn read_response<Out>(buffer: *const u8, framing: Framing) -> Result<Out> {
+    let (cmif, raw_data) = match framing {
+        Framing::Session => {
+            let SessionResponse { cmif, raw_data, .. } =
+                ::core::ptr::read(buffer as *const SessionResponse<Out>);
+            (cmif, raw_data)
+        }
+        Framing::Domain { .. } => {
+            let DomainResponse { cmif, raw_data, .. } =
+                ::core::ptr::read(buffer as *const DomainResponse<Out>);
+            (cmif, raw_data)
+        }
+    };
+
+    if cmif.result.is_failure() {
+        return Err(cmif.result);
+    }
+    debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+
+    Ok(raw_data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        read_response, write_request, DomainHandle, DomainHandleRef, DomainObject, Framing,
+    };
+    use crate::cmif::SessionHandle;
+    use crate::handle_storage::{HandleStorage, OwnedHandle};
+    use horizon_svc::RawHandle;
+
+    fn word(buffer: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(buffer[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn session_framing() {
+        let mut buffer = [0u8; 0x100];
+        unsafe { write_request(buffer.as_mut_ptr(), Framing::Session, 3, 0x1234u32) };
+
+        // 8 + 16 + 4 + 8 bytes of data
+        assert_eq!(word(&buffer, 4) & 0x3ff, 9);
+        assert_eq!(word(&buffer, 0x10), 0x49434653);
+        assert_eq!(word(&buffer, 0x18), 3);
+        assert_eq!(word(&buffer, 0x20), 0x1234);
+
+        let mut response = [0u8; 0x100];
+        response[0x10..0x14].copy_from_slice(&0x4F434653u32.to_le_bytes());
+        response[0x20..0x24].copy_from_slice(&0x5678u32.to_le_bytes());
+        let out: u32 = unsafe { read_response(response.as_ptr(), Framing::Session) }.unwrap();
+        assert_eq!(out, 0x5678);
+    }
+
+    #[test]
+    fn domain_framing() {
+        let framing = Framing::Domain { object_id: 5 };
+
+        let mut buffer = [0u8; 0x100];
+        unsafe { write_request(buffer.as_mut_ptr(), framing, 3, 0x1234u32) };
+
+        // 8 + 16 + 16 + 4 + 8 bytes of data
+        assert_eq!(word(&buffer, 4) & 0x3ff, 13);
+        // SendMessage, no objects, CMIF header + raw data
+        assert_eq!(word(&buffer, 0x10), 1 | (16 + 4) << 16);
+        assert_eq!(word(&buffer, 0x14), 5);
+        assert_eq!(word(&buffer, 0x20), 0x49434653);
+        assert_eq!(word(&buffer, 0x28), 3);
+        assert_eq!(word(&buffer, 0x30), 0x1234);
+
+        let mut response = [0u8; 0x100];
+        response[0x20..0x24].copy_from_slice(&0x4F434653u32.to_le_bytes());
+        response[0x30..0x34].copy_from_slice(&0x5678u32.to_le_bytes());
+        let out: u32 = unsafe { read_response(response.as_ptr(), framing) }.unwrap();
+        assert_eq!(out, 0x5678);
+    }
+
+    #[test]
+    fn domain_object() {
+        let object = DomainObject::new(DomainHandleRef::new(RawHandle(0x1234)), 5);
+
+        assert_eq!(object.framing(), Framing::Domain { object_id: 5 });
+        assert_eq!(object.get_domain().inner(), RawHandle(0x1234));
+        // doesn't send the close request, there is no server to send it to
+        assert_eq!(object.leak(), 5);
+    }
+
+    #[test]
+    fn failed_conversion() {
+        // the handle is made up, so the conversion fails and the session is given back
+        let session = SessionHandle::new(OwnedHandle::new(RawHandle(0x1234)));
+        let session = match DomainHandle::convert(session) {
+            Ok(_) => panic!("converted a made-up session"),
+            Err((_, session)) => session,
+        };
+
+        assert_eq!(*session.get(), RawHandle(0x1234));
+        core::mem::forget(session);
+    }
+}
@@ -1,4 +1,18 @@
+use crate::handle_storage::{HandleStorage, OwnedHandle, RefHandle};
+use core::fmt::{Display, Formatter};
+use core::marker::PhantomData;
+use core::ops::Deref;
+use horizon_error::ErrorCode;
+
 pub mod control;
+pub mod domain;
+pub mod raw_command;
+
+/// Returned by the generated clients when the server responds with a value invalid for the declared type,
+///  like an enum value not matching any arm
+///
+/// Uses the libnx homebrew module (345), same as `server::HANDLER_PANICKED`
+pub const INVALID_RESPONSE_VALUE: ErrorCode = ErrorCode::from_parts(345, 1001);
 
 #[repr(u16)]
 #[derive(Copy, Clone)]
@@ -13,56 +27,60 @@ pub enum CommandType {
     ControlWithContext = 7,
 }
 
-// /// A handle to an IPC object that must be a domain object
-// pub struct DomainHandle(SessionHandle);
-//
-// impl Deref for DomainHandle {
-//     type Target = SessionHandle;
-//
-//     fn deref(&self) -> &Self::Target {
-//         &self.0
-//     }
-// }
-//
-// #[derive(Copy, Clone)]
-// pub struct DomainHandleRef<'a>(SessionHandleRef<'a>);
-//
-// impl<'a> Deref for DomainHandleRef<'a> {
-//     type Target = SessionHandleRef<'a>;
-//
-//     fn deref(&self) -> &Self::Target {
-//         &self.0
-//     }
-// }
-//
-// pub struct DomainObject<'a> {
-//     domain: DomainHandleRef<'a>,
-//     object_id: u32,
-// }
-//
-// impl<'a> DomainObject<'a> {
-//     pub fn get_domain(&self) -> DomainHandleRef<'a> {
-//         self.domain
-//     }
-// }
-//
-// impl<'a> Drop for DomainObject<'a> {
-//     fn drop(&mut self) {
-//         todo!("Implement when there will be a CMIF IPC definitions for sending the close requests")
-//     }
-// }
-//
-// pub struct DomainObjectRef<'a> {
-//     domain: DomainHandleRef<'a>,
-//     object_id: u32,
-//     phantom: PhantomData<&'a ()>,
-// }
-//
-// // TODO: will we actually have functions that are agnostic to the kind of object we are using?
-// /// A way to refer to an IPC object
-// pub enum ObjectReference<'a> {
-//     /// Direct reference to an object (a session handle)
-//     SessionObject(SessionHandleRef<'a>),
-//     /// Reference to an object inside a domain (a domain session handle and an object id)
-//     DomainObject(DomainObjectRef<'a>),
-// }
+/// Marks a [SessionHandle] as a session to a CMIF server
+///
+/// Never constructed, only used as a phantom
+pub enum Cmif {}
+
+/// A session to a CMIF server, with the handle held in a [HandleStorage]
+///
+/// A handle storage can hold any handle, while this is a session the requests can be sent on.
+///  Derefs to the storage, so the handle is borrowed with `session.get()`.
+///  The generated interface types (like `horizon_ipcdef::sm::IUserInterface<S>`) wrap one.
+#[derive(Copy, Clone)]
+#[repr(transparent)]
+pub struct SessionHandle<S: HandleStorage = OwnedHandle> {
+    storage: S,
+    _protocol: PhantomData<Cmif>,
+}
+
+/// A [SessionHandle] borrowing the handle of another one, see [SessionHandle::as_ref]
+pub type SessionHandleRef<'a> = SessionHandle<RefHandle<'a>>;
+
+impl<S: HandleStorage> SessionHandle<S> {
+    /// Wraps a storage holding a session to a CMIF server
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            _protocol: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl SessionHandle {
+    #[inline]
+    pub fn as_ref(&self) -> SessionHandleRef<'_> {
+        SessionHandle::new(self.storage.as_ref())
+    }
+}
+
+impl<S: HandleStorage> Deref for SessionHandle<S> {
+    type Target = S;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.storage
+    }
+}
+
+impl<S: HandleStorage + Display> Display for SessionHandle<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(&self.storage, f)
+    }
+}
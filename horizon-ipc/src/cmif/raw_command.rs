@@ -0,0 +1,235 @@
+//! Sending commands with a payload built by hand, skipping the typed marshalling
+//!
+//! This is a tool for debugging and experimenting with commands (e.g. to exercise an undocumented field),
+//!  the generated clients expose it as the `<command>_raw` methods behind the `raw-ipc` feature.
+//!  Only the raw data is sent: the request has no buffers, handles or process id.
+//!  Objects in a domain are addressed with [Framing::Domain].
+
+use crate::buffer::{get_ipc_buffer, get_ipc_buffer_mut};
+use crate::cmif::domain::{DomainRequestType, Framing};
+use crate::cmif::CommandType;
+use crate::raw::cmif::{CmifDomainInHeader, CmifDomainOutHeader, CmifInHeader, CmifOutHeader};
+use crate::raw::hipc::{HipcHeader, HipcSpecialHeader};
+use crate::server::INVALID_HEADER_SIZE;
+use core::mem::size_of;
+use horizon_error::Result;
+use horizon_svc::RawHandle;
+
+/// Writes a request carrying `raw_in` as the raw data into `message`, which should be laid out like the IPC buffer
+///
+/// Panics if the request doesn't fit into `message`
+pub fn write_request(message: &mut [u8], framing: Framing, command_id: u32, raw_in: &[u8]) {
+    let domain_header_size = match framing {
+        Framing::Session => 0,
+        Framing::Domain { .. } => size_of::<CmifDomainInHeader>(),
+    };
+    // the padding aligning the cmif header to 16 bytes is included in the raw data
+    let num_data_words =
+        (16 + domain_header_size + size_of::<CmifInHeader>() + raw_in.len()).div_ceil(4);
+    assert!(
+        size_of::<HipcHeader>() + num_data_words * 4 <= message.len(),
+        "Request is too large, would not fit into the IPC command buffer"
+    );
+
+    let cmif = CmifInHeader {
+        magic: CmifInHeader::MAGIC,
+        version: 1,
+        command_id,
+        token: 0,
+    };
+    let hipc = HipcHeader::new(
+        CommandType::Request,
+        0,
+        0,
+        0,
+        0,
+        num_data_words as u32,
+        0,
+        0,
+        false,
+    );
+
+    let message = message.as_mut_ptr();
+    let cmif_offset = 0x10 + domain_header_size;
+    // SAFETY: the size of the message is checked above
+    unsafe {
+        ::core::ptr::write_unaligned(message as *mut HipcHeader, hipc);
+        if let Framing::Domain { object_id } = framing {
+            ::core::ptr::write_unaligned(
+                message.add(0x10) as *mut CmifDomainInHeader,
+                CmifDomainInHeader {
+                    type_: DomainRequestType::SendMessage as u8,
+                    num_in_objects: 0,
+                    data_size: (size_of::<CmifInHeader>() + raw_in.len()) as u16,
+                    object_id,
+                    padding: 0,
+                    token: 0,
+                },
+            );
+        }
+        ::core::ptr::write_unaligned(message.add(cmif_offset) as *mut CmifInHeader, cmif);
+        ::core::ptr::copy_nonoverlapping(
+            raw_in.as_ptr(),
+            message.add(cmif_offset + size_of::<CmifInHeader>()),
+            raw_in.len(),
+        );
+    }
+}
+
+/// Reads the response in `message`, copying as much of its raw data as fits into `out`
+///
+/// Returns the size of the raw data, which can be larger than the output of the command, as the server pads it.
+///  Handles sent in the response are not closed, neither are the objects the server put into the domain.
+pub fn read_response(message: &[u8], framing: Framing, out: &mut [u8]) -> Result<usize> {
+    fn read<T>(message: &[u8], offset: usize) -> Result<T> {
+        if offset + size_of::<T>() > message.len() {
+            return Err(INVALID_HEADER_SIZE);
+        }
+        Ok(unsafe { ::core::ptr::read_unaligned(message.as_ptr().add(offset) as *const T) })
+    }
+
+    let hipc: HipcHeader = read(message, 0)?;
+
+    // skip everything up to the raw data
+    let mut offset = size_of::<HipcHeader>();
+    if hipc.has_special_header() != 0 {
+        let special: HipcSpecialHeader = read(message, offset)?;
+        offset += size_of::<HipcSpecialHeader>();
+        if special.send_pid() != 0 {
+            offset += size_of::<u64>();
+        }
+        offset +=
+            size_of::<u32>() * (special.num_copy_handles() + special.num_move_handles()) as usize;
+    }
+    // the request had no buffers, so the response has no descriptors
+
+    let raw_data_end = offset + 4 * hipc.num_data_words() as usize;
+    // the cmif header is aligned to 16 bytes, the padding is included in the raw data
+    let cmif_offset = match framing {
+        Framing::Session => (offset + 15) & !15,
+        Framing::Domain { .. } => ((offset + 15) & !15) + size_of::<CmifDomainOutHeader>(),
+    };
+    let data_offset = cmif_offset + size_of::<CmifOutHeader>();
+    if data_offset > raw_data_end || raw_data_end > message.len() {
+        return Err(INVALID_HEADER_SIZE);
+    }
+
+    let cmif: CmifOutHeader = read(message, cmif_offset)?;
+    cmif.result.into_result(())?;
+
+    let data = &message[data_offset..raw_data_end];
+    let copied = data.len().min(out.len());
+    out[..copied].copy_from_slice(&data[..copied]);
+
+    Ok(data.len())
+}
+
+/// Sends the command `command_id` with `raw_in` as the raw data, see [read_response] for the output
+///
+/// # Safety
+///
+/// The server gets whatever is in `raw_in`, it's up to the caller to make it a valid request for the command
+pub unsafe fn send_request(
+    session: RawHandle,
+    framing: Framing,
+    command_id: u32,
+    raw_in: &[u8],
+    out: &mut [u8],
+) -> Result<usize> {
+    write_request(get_ipc_buffer_mut(), framing, command_id, raw_in);
+    horizon_svc::send_sync_request(session)?;
+    read_response(get_ipc_buffer(), framing, out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_response, write_request};
+    use crate::cmif::domain::Framing;
+    use crate::raw::cmif::{CmifInHeader, CmifOutHeader};
+    use crate::raw::hipc::HipcHeader;
+    use crate::server::IncomingRequest;
+    use horizon_error::ErrorCode;
+
+    #[test]
+    fn raw_request() {
+        let mut message = [0; 0x100];
+        write_request(&mut message, Framing::Session, 7, &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        // decoded the same way the server does it
+        let request = IncomingRequest::parse(&message).unwrap();
+        assert_eq!(request.command_id(), 7);
+        assert_eq!(&request.data()[..8], &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert!(message[0x10..0x14] == CmifInHeader::MAGIC.to_le_bytes());
+    }
+
+    #[test]
+    fn raw_domain_request() {
+        let mut message = [0; 0x100];
+        let framing = Framing::Domain { object_id: 5 };
+        write_request(&mut message, framing, 7, &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        // 16 bytes of padding + domain header + cmif header + data
+        assert_eq!(message[4], 14);
+        // SendMessage with no objects, the size of the cmif header and the data, then the object id
+        assert_eq!(message[0x10..0x18], [1, 0, 24, 0, 5, 0, 0, 0]);
+        assert!(message[0x20..0x24] == CmifInHeader::MAGIC.to_le_bytes());
+        assert_eq!(message[0x28..0x2c], 7u32.to_le_bytes());
+        assert_eq!(message[0x30..0x38], [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    fn response(framing: Framing, result: ErrorCode, data: &[u8]) -> [u8; 0x100] {
+        let mut message = [0; 0x100];
+        let cmif_offset = match framing {
+            Framing::Session => 0x10,
+            Framing::Domain { .. } => 0x20,
+        };
+        let num_data_words =
+            (cmif_offset - 8 + core::mem::size_of::<CmifOutHeader>() + data.len()) / 4;
+        unsafe {
+            core::ptr::write_unaligned(
+                message.as_mut_ptr() as *mut HipcHeader,
+                HipcHeader::new(
+                    crate::cmif::CommandType::Invalid,
+                    0,
+                    0,
+                    0,
+                    0,
+                    num_data_words as u32,
+                    0,
+                    0,
+                    false,
+                ),
+            );
+            core::ptr::write_unaligned(
+                message.as_mut_ptr().add(cmif_offset) as *mut CmifOutHeader,
+                CmifOutHeader {
+                    magic: CmifOutHeader::MAGIC,
+                    version: 0,
+                    result,
+                    token: 0,
+                },
+            );
+        }
+        let data_offset = cmif_offset + 0x10;
+        message[data_offset..data_offset + data.len()].copy_from_slice(data);
+        message
+    }
+
+    #[test]
+    fn raw_response() {
+        for framing in [Framing::Session, Framing::Domain { object_id: 5 }] {
+            let data = [9, 8, 7, 6, 5, 4, 3, 2];
+            let message = response(framing, ErrorCode::from_parts(0, 0), &data);
+
+            let mut out = [0; 4];
+            assert_eq!(read_response(&message, framing, &mut out), Ok(8));
+            assert_eq!(out, [9, 8, 7, 6]);
+
+            let failure = ErrorCode::from_parts(2, 1);
+            assert_eq!(
+                read_response(&response(framing, failure, &[]), framing, &mut out),
+                Err(failure)
+            );
+        }
+    }
+}
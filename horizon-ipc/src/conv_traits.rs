@@ -183,26 +183,153 @@ macro_rules! from_bytes_impl_transmute {
 pub(crate) use as_bytes_impl_transmute;
 pub(crate) use from_bytes_impl_transmute;
 
-as_bytes_impl_transmute!(u8);
-as_bytes_impl_transmute!(u16);
-as_bytes_impl_transmute!(u32);
-as_bytes_impl_transmute!(u64);
+as_bytes_impl_transmute!(());
+from_bytes_impl_transmute!(());
 
-as_bytes_impl_transmute!(i8);
-as_bytes_impl_transmute!(i16);
-as_bytes_impl_transmute!(i32);
-as_bytes_impl_transmute!(i64);
+/// Plain data, written to and read from the raw data byte-for-byte
+///
+/// This is what lets a `#[repr(C)]` struct be written with a single `writer.write(&value)`,
+///  with the same layout a hand-written `#[repr(C)]` message struct would have.
+///
+/// Usually implemented with [unsafe_impl_raw_data](crate::unsafe_impl_raw_data), which spells out the requirements.
+///
+/// # Safety
+///
+/// Same as for [unsafe_impl_raw_data](crate::unsafe_impl_raw_data): no implicit padding, any bit pattern is valid.
+pub unsafe trait RawData: Copy {}
+
+/// Implements [RawData] for the listed types
+///
+/// # Safety
+///
+/// The types must be `#[repr(C)]` or `#[repr(transparent)]` without any implicit padding (explicit padding fields are fine),
+///  and any bit pattern must be a valid value: no bools, enums, references or such inside.
+#[macro_export]
+macro_rules! unsafe_impl_raw_data {
+    ($($t:ty),* $(,)?) => {
+        $(
+            unsafe impl $crate::conv_traits::RawData for $t {}
+        )*
+    };
+}
 
-as_bytes_impl_transmute!(());
+crate::unsafe_impl_raw_data!(u8, u16, u32, u64, i8, i16, i32, i64, horizon_svc::RawHandle);
 
-from_bytes_impl_transmute!(u8);
-from_bytes_impl_transmute!(u16);
-from_bytes_impl_transmute!(u32);
-from_bytes_impl_transmute!(u64);
+// an array has no padding between the elements, the size of a type is always a multiple of its alignment
+unsafe impl<T: RawData, const N: usize> RawData for [T; N] {}
 
-from_bytes_impl_transmute!(i8);
-from_bytes_impl_transmute!(i16);
-from_bytes_impl_transmute!(i32);
-from_bytes_impl_transmute!(i64);
+impl<T: RawData> WriteAsBytes for T {
+    #[inline]
+    fn write_as_bytes(&self, dest: &mut (impl Writer + ?Sized)) {
+        // SAFETY: RawData types have no padding, so all the bytes are initialized
+        let bytes = unsafe {
+            core::slice::from_raw_parts(self as *const T as *const u8, core::mem::size_of::<T>())
+        };
 
-from_bytes_impl_transmute!(());
+        dest.write_bytes(bytes)
+    }
+}
+
+impl<'d, T: RawData> ReadFromBytes<'d> for T {
+    #[inline]
+    fn read_from_bytes(src: &mut (impl Reader<'d> + ?Sized)) -> Self {
+        let bytes = src.read_bytes(core::mem::size_of::<T>());
+        assert_eq!(bytes.len(), core::mem::size_of::<T>());
+
+        // SAFETY: the size is checked above, and any bit pattern is a valid RawData value
+        unsafe { core::ptr::read_unaligned(bytes.as_ptr() as *const T) }
+    }
+}
+
+// not RawData, as only 0 and 1 are valid
+impl WriteAsBytes for bool {
+    #[inline]
+    fn write_as_bytes(&self, dest: &mut (impl Writer + ?Sized)) {
+        dest.write(&(*self as u8))
+    }
+}
+
+impl<'d> ReadFromBytes<'d> for bool {
+    #[inline]
+    fn read_from_bytes(src: &mut (impl Reader<'d> + ?Sized)) -> Self {
+        src.read::<u8>() != 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Reader, SliceReader, SliceWriter, Writer};
+    use horizon_svc::RawHandle;
+
+    /// Same as the generated `horizon_ipcdef::fssrv::FsSaveDataCreationInfo`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(C)]
+    struct FsSaveDataCreationInfo {
+        save_data_size: i64,
+        journal_size: i64,
+        available_size: u64,
+        owner_id: u64,
+        flags: u32,
+        save_data_space_id: u8,
+        unk: u8,
+        padding: [u8; 26],
+    }
+
+    crate::unsafe_impl_raw_data!(FsSaveDataCreationInfo);
+
+    #[test]
+    fn struct_matches_manual_layout() {
+        let info = FsSaveDataCreationInfo {
+            save_data_size: 0x1000,
+            journal_size: -2,
+            available_size: 0x0102030405060708,
+            owner_id: 0x0100000000001000,
+            flags: 0xaabbccdd,
+            save_data_space_id: 1,
+            unk: 0xff,
+            padding: [0x5a; 26],
+        };
+
+        let mut written = [0u8; 64];
+        let mut writer = SliceWriter::new(&mut written);
+        writer.write(&info);
+        assert_eq!(writer.pos(), 64);
+
+        let manual: [u8; 64] = unsafe { core::mem::transmute(info) };
+        assert_eq!(written, manual);
+
+        let mut fields = [0u8; 64];
+        let mut writer = SliceWriter::new(&mut fields);
+        writer.write(&info.save_data_size);
+        writer.write(&info.journal_size);
+        writer.write(&info.available_size);
+        writer.write(&info.owner_id);
+        writer.write(&info.flags);
+        writer.write(&info.save_data_space_id);
+        writer.write(&info.unk);
+        writer.write(&info.padding);
+        assert_eq!(written, fields);
+        assert_eq!(written[..8], 0x1000i64.to_le_bytes());
+
+        let mut reader = SliceReader::new(&written);
+        assert_eq!(reader.read::<FsSaveDataCreationInfo>(), info);
+    }
+
+    #[test]
+    fn primitives() {
+        let mut written = [0u8; 9];
+        let mut writer = SliceWriter::new(&mut written);
+        writer.write(&true);
+        writer.write(&RawHandle(0x12345678));
+        writer.write(&[1u8, 2, 3]);
+        writer.write(&false);
+
+        assert_eq!(written, [1, 0x78, 0x56, 0x34, 0x12, 1, 2, 3, 0]);
+
+        let mut reader = SliceReader::new(&written);
+        assert!(reader.read::<bool>());
+        assert_eq!(reader.read::<RawHandle>(), RawHandle(0x12345678));
+        assert_eq!(reader.read::<[u8; 3]>(), [1, 2, 3]);
+        assert!(!reader.read::<bool>());
+    }
+}
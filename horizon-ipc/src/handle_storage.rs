@@ -1,3 +1,9 @@
+//! Ways to hold a session handle, deciding how it's shared between the clients and when it's closed
+//!
+//! The storages can hold any handle. A storage known to hold a session to a CMIF server is wrapped
+//!  in a [SessionHandle](crate::cmif::SessionHandle), and the generated interface types (like
+//!  `horizon_ipcdef::sm::IUserInterface<S>`) wrap one to tell the sessions of different interfaces apart.
+
 use crate::cmif::control::{clone_object, close_object};
 use alloc::boxed::Box;
 use core::fmt::{Debug, Display, Formatter};
@@ -5,6 +11,7 @@ use core::marker::PhantomData;
 use core::ops::Deref;
 use core::ptr::NonNull;
 use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use horizon_error::Result;
 use horizon_svc::RawHandle;
 
 /// A type mostly used to represent a handle borrow
@@ -226,6 +233,108 @@ impl Drop for SharedHandle {
     }
 }
 
+struct SharedHandleWithInner<F: FnOnce(RawHandle)> {
+    refcount: AtomicUsize,
+    on_drop: F,
+}
+
+/// A reference-counted handle running a cleanup closure when the last reference is dropped
+///
+/// This is for the kernel objects that need some teardown before their handle can be closed,
+///  like shared memory and transfer memory, that have to be unmapped first.
+///  On the final drop the closure is run first, and then the handle is closed.
+///
+/// Unlike [SharedHandle], the handle is closed with just `close_handle`, as it's usually not a session.
+///  When it does hold the session of an interface, the closure can send the CMIF close request itself.
+pub struct SharedHandleWith<F: FnOnce(RawHandle)> {
+    inner: NonNull<SharedHandleWithInner<F>>,
+    handle: RawHandle,
+}
+
+impl<F: FnOnce(RawHandle)> SharedHandleWith<F> {
+    /// Takes the ownership of `handle`, `on_drop` gets it right before it's closed
+    pub fn new(handle: RawHandle, on_drop: F) -> Self {
+        let inner = Box::new(SharedHandleWithInner {
+            refcount: AtomicUsize::new(1),
+            on_drop,
+        });
+
+        Self {
+            inner: NonNull::new(Box::into_raw(inner)).unwrap(),
+            handle,
+        }
+    }
+
+    #[inline]
+    pub fn handle(&self) -> RawHandle {
+        self.handle
+    }
+}
+
+// The closure is only ever called by the thread dropping the last reference
+unsafe impl<F: FnOnce(RawHandle) + Send> Send for SharedHandleWith<F> {}
+unsafe impl<F: FnOnce(RawHandle) + Send> Sync for SharedHandleWith<F> {}
+
+impl<F: FnOnce(RawHandle)> Debug for SharedHandleWith<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "SharedHandleWith({})", self)
+    }
+}
+
+impl<F: FnOnce(RawHandle)> Display for SharedHandleWith<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "0x{:x}", self.handle.0)
+    }
+}
+
+impl<F: FnOnce(RawHandle)> HandleStorage for SharedHandleWith<F> {
+    #[inline]
+    fn get(&self) -> HandleRef<'_, Self> {
+        HandleRef {
+            handle: self.handle,
+            index: 0,
+            storage: self,
+        }
+    }
+
+    #[inline]
+    fn give_back(&self, _: &HandleRef<'_, Self>) {}
+}
+
+impl<F: FnOnce(RawHandle)> SharedHandleWith<F> {
+    /// Drops this reference, on the last one runs the closure and then `close`
+    ///
+    /// The object must not be used (or dropped) after that
+    fn release(&mut self, close: impl FnOnce(RawHandle)) {
+        let inner = unsafe { self.inner.as_ref() };
+        if inner.refcount.fetch_sub(1, Ordering::SeqCst) != 1 {
+            return;
+        }
+        core::sync::atomic::fence(Ordering::SeqCst);
+        let inner = unsafe { Box::from_raw(self.inner.as_ptr()) };
+        (inner.on_drop)(self.handle);
+        close(self.handle);
+    }
+}
+
+impl<F: FnOnce(RawHandle)> Clone for SharedHandleWith<F> {
+    fn clone(&self) -> Self {
+        let inner = unsafe { self.inner.as_ref() };
+        inner.refcount.fetch_add(1, Ordering::SeqCst);
+
+        Self {
+            inner: self.inner,
+            handle: self.handle,
+        }
+    }
+}
+
+impl<F: FnOnce(RawHandle)> Drop for SharedHandleWith<F> {
+    fn drop(&mut self) {
+        self.release(|handle| horizon_svc::close_handle(handle).unwrap());
+    }
+}
+
 struct PooledHandleInner<const POOL_SIZE: usize> {
     refcount: AtomicUsize,
     used_mask: AtomicU32,
@@ -240,10 +349,25 @@ pub struct PooledHandle<const POOL_SIZE: usize = 16> {
 
 impl<const POOL_SIZE: usize> PooledHandle<POOL_SIZE> {
     pub fn new(handle: RawHandle) -> Self {
+        Self::try_new(handle).expect("Cloning an IPC object for the pool failed")
+    }
+
+    /// Fills the pool with clones of the session, failing if the server refuses to clone it
+    ///
+    /// On failure the clones made so far are closed, while `handle` is left open.
+    pub fn try_new(handle: RawHandle) -> Result<Self> {
         let mut handles = [RawHandle(0); POOL_SIZE];
         handles[0] = handle;
         for i in 1..POOL_SIZE {
-            handles[i] = clone_object(handle);
+            match clone_object(handle) {
+                Ok(clone) => handles[i] = clone,
+                Err(e) => {
+                    for &clone in &handles[1..i] {
+                        close_object(clone);
+                    }
+                    return Err(e);
+                }
+            }
         }
 
         let inner = Box::new(PooledHandleInner {
@@ -252,9 +376,9 @@ impl<const POOL_SIZE: usize> PooledHandle<POOL_SIZE> {
             handles,
         });
 
-        Self {
+        Ok(Self {
             inner: NonNull::new(Box::into_raw(inner)).unwrap(),
-        }
+        })
     }
 }
 
@@ -349,3 +473,45 @@ impl<const POOL_SIZE: usize> Drop for PooledHandle<POOL_SIZE> {
         unsafe { Box::from_raw(self.inner.as_ptr()) };
     }
 }
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::{HandleStorage, SharedHandleWith};
+    use core::mem::ManuallyDrop;
+    use horizon_svc::RawHandle;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn shared_handle_with_cleanup() {
+        let cleanups = Arc::new(AtomicUsize::new(0));
+
+        let handle = SharedHandleWith::new(RawHandle(0x1234), {
+            let cleanups = cleanups.clone();
+            move |handle| {
+                assert_eq!(handle, RawHandle(0x1234));
+                cleanups.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        let clone = handle.clone();
+        assert_eq!(clone.handle(), RawHandle(0x1234));
+        assert_eq!(*clone.get(), RawHandle(0x1234));
+
+        drop(handle);
+        assert_eq!(cleanups.load(Ordering::SeqCst), 0);
+
+        // the handle is made up, so the last reference is released without closing it for real
+        let closed = AtomicBool::new(false);
+        let mut clone = ManuallyDrop::new(clone);
+        clone.release(|handle| {
+            assert_eq!(handle, RawHandle(0x1234));
+            // the cleanup runs before the close
+            assert_eq!(cleanups.load(Ordering::SeqCst), 1);
+            closed.store(true, Ordering::SeqCst);
+        });
+        assert!(closed.load(Ordering::SeqCst));
+        assert_eq!(cleanups.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,16 +1,24 @@
 //! Provides low-level primitives used to implement IPC clients for horizon sysmodules
 
 #![no_std]
+#![feature(thread_local)]
 #![deny(rust_2018_idioms)]
 
 #[cfg(not(feature = "rustc-dep-of-std"))]
 extern crate alloc;
 
+#[cfg(feature = "unwind")]
+extern crate std;
+
 pub use horizon_svc::RawHandle;
 
+#[cfg(feature = "async-ipc")]
+pub mod async_ipc;
 pub mod buffer;
 pub mod cmif;
 pub mod conv_traits;
 pub mod handle_storage;
 pub mod hipc;
 pub mod raw;
+pub mod scratch;
+pub mod server;
@@ -1,5 +1,5 @@
 use super::hipc::{
-    HipcHeader, HipcInPointerBufferDescriptor, HipcMapAliasBufferDescriptor,
+    __BindgenBitfieldUnit, HipcHeader, HipcInPointerBufferDescriptor, HipcMapAliasBufferDescriptor,
     HipcOutPointerBufferDescriptor, HipcSpecialHeader,
 };
 use crate::cmif::CommandType;
@@ -19,8 +19,10 @@ from_bytes_impl_transmute!(HipcOutPointerBufferDescriptor);
 from_bytes_impl_transmute!(HipcMapAliasBufferDescriptor);
 
 impl HipcHeader {
+    /// Builds the header, this is a `const fn` so the generated code can build the headers of
+    ///  commands with no buffers and handles at compile time
     #[inline]
-    pub fn new(
+    pub const fn new(
         type_: CommandType,
         num_in_pointers: u32,
         num_in_map_aliases: u32,
@@ -31,20 +33,20 @@ impl HipcHeader {
         recv_list_offset: u32,
         has_special_header: bool,
     ) -> Self {
-        // TODO: make bitfield construction const
+        // the bindgen bitfield setters are not const, so the fields are packed by hand
+        //  with the same layout (the target is little endian)
+        let bits = (type_ as u64 & 0xffff)
+            | (num_in_pointers as u64 & 0xf) << 16
+            | (num_in_map_aliases as u64 & 0xf) << 20
+            | (num_out_map_aliases as u64 & 0xf) << 24
+            | (num_inout_map_aliases as u64 & 0xf) << 28
+            | (num_data_words as u64 & 0x3ff) << 32
+            | (out_pointer_mode as u64 & 0xf) << 42
+            | (recv_list_offset as u64 & 0x7ff) << 52
+            | (has_special_header as u64) << 63;
+
         Self {
-            _bitfield_1: HipcHeader::new_bitfield_1(
-                type_ as u16,
-                num_in_pointers,
-                num_in_map_aliases,
-                num_out_map_aliases,
-                num_inout_map_aliases,
-                num_data_words,
-                out_pointer_mode,
-                0,
-                recv_list_offset,
-                has_special_header as _,
-            ),
+            _bitfield_1: __BindgenBitfieldUnit::new(bits.to_le_bytes()),
         }
     }
 }
@@ -117,3 +119,18 @@ impl HipcMapAliasBufferDescriptor {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::HipcHeader;
+    use crate::cmif::CommandType;
+
+    #[test]
+    fn const_header_matches_bindgen() {
+        const HEADER: HipcHeader =
+            HipcHeader::new(CommandType::Request, 1, 2, 3, 0, 10, 4, 0x123, true);
+
+        let expected = HipcHeader::new_bitfield_1(4, 1, 2, 3, 0, 10, 4, 0, 0x123, 1);
+        assert_eq!(HEADER._bitfield_1, expected);
+    }
+}
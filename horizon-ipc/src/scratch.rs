@@ -0,0 +1,79 @@
+//! A per-thread scratch buffer for small transient IO buffers
+//!
+//! Commands taking map-alias buffers need the memory to stay valid only for the duration of the call,
+//!  so instead of allocating it each time the caller can borrow the buffer of the current thread
+//!  with [with_scratch]. It lives in the thread's TLS block, so it doesn't need the heap either.
+
+use core::cell::{Cell, UnsafeCell};
+
+/// Size of the scratch buffer, one page
+pub const SCRATCH_BUFFER_SIZE: usize = 0x1000;
+
+#[repr(C, align(0x1000))]
+struct ScratchBuffer([u8; SCRATCH_BUFFER_SIZE]);
+
+#[thread_local]
+static SCRATCH_BUFFER: UnsafeCell<ScratchBuffer> =
+    UnsafeCell::new(ScratchBuffer([0; SCRATCH_BUFFER_SIZE]));
+
+#[thread_local]
+static SCRATCH_BUFFER_IN_USE: Cell<bool> = Cell::new(false);
+
+/// Releases the scratch buffer, even when the closure unwinds
+struct InUseGuard;
+
+impl Drop for InUseGuard {
+    fn drop(&mut self) {
+        SCRATCH_BUFFER_IN_USE.set(false);
+    }
+}
+
+/// Calls `f` with the page-aligned scratch buffer of the current thread ([SCRATCH_BUFFER_SIZE] bytes)
+///
+/// The buffer is reused between calls, so it holds whatever the previous user left there.
+///  It can be borrowed only once at a time: calling `with_scratch` from inside `f` panics.
+pub fn with_scratch<R>(f: impl FnOnce(&mut [u8]) -> R) -> R {
+    if SCRATCH_BUFFER_IN_USE.replace(true) {
+        panic!("The scratch buffer is already in use by this thread");
+    }
+    let _guard = InUseGuard;
+
+    // SAFETY: the buffer is thread-local and the flag above ensures there are no other references to it
+    let buffer = unsafe { &mut (*SCRATCH_BUFFER.get()).0 };
+    f(buffer)
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::{with_scratch, SCRATCH_BUFFER_SIZE};
+
+    #[test]
+    fn scratch_per_thread() {
+        let first = with_scratch(|buf| {
+            assert_eq!(buf.len(), SCRATCH_BUFFER_SIZE);
+            assert_eq!(buf.as_ptr() as usize % 0x1000, 0);
+            buf[0] = 0x42;
+            buf.as_ptr() as usize
+        });
+
+        // the buffer is released after the first use, and the same one is handed out again
+        let second = with_scratch(|buf| {
+            assert_eq!(buf[0], 0x42);
+            buf.as_ptr() as usize
+        });
+        assert_eq!(first, second);
+
+        let other_thread = std::thread::spawn(|| with_scratch(|buf| buf.as_ptr() as usize))
+            .join()
+            .unwrap();
+        assert_ne!(first, other_thread);
+    }
+
+    #[test]
+    #[should_panic(expected = "already in use")]
+    fn nested_scratch() {
+        with_scratch(|_| with_scratch(|_| ()));
+    }
+}
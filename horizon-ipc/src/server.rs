@@ -0,0 +1,531 @@
+//! Helpers for implementing IPC servers
+//!
+//! # Panics in handlers
+//!
+//! Horizon sysmodules are usually built with `panic = "abort"`, so a panicking command handler
+//! takes the whole server down with it. Handlers should report failures through the returned
+//! [ErrorCode] instead of panicking.
+//!
+//! When the `unwind` feature is enabled (which requires `std` and a panic strategy that unwinds),
+//! [dispatch] catches the panic and turns it into a [HANDLER_PANICKED] error, so that the
+//! dispatch loop can send an error response and keep serving other sessions.
+//!
+//! # Session handles
+//!
+//! Objects created on behalf of a session (events, shared memory, sub-sessions) should be
+//! registered in the [SessionHandles] of that session. When the client closes the session
+//! (either with the CMIF `Close` command or by closing its handle, in which case
+//! `reply_and_receive` fails with `SessionClosed`), [close_session] closes all of them, so
+//! long-running servers don't leak handles. [Sessions] does that as part of the dispatch loop.
+//!
+//! # Generated dispatchers
+//!
+//! With `--gen-server`, the codegen emits an `<Interface>Server` trait for each interface, with
+//!  a provided `dispatch` method decoding an [IncomingRequest], calling the right handler and
+//!  writing the response with [write_response]. Only the raw data of the requests is decoded for now.
+
+use crate::buffer::{get_ipc_buffer, get_ipc_buffer_ptr};
+use crate::cmif::CommandType;
+use crate::raw::cmif::{CmifInHeader, CmifOutHeader};
+use crate::raw::hipc::{HipcHeader, HipcSpecialHeader};
+use alloc::vec::Vec;
+use core::mem::size_of;
+use core::time::Duration;
+use horizon_error::{ErrorCode, ErrorCodeModule, KernelErrorCode, Result};
+use horizon_svc::RawHandle;
+
+/// Result returned to the client when a command handler panicked
+///
+/// Uses the libnx homebrew module (345) with a description not used by libnx itself
+pub const HANDLER_PANICKED: ErrorCode = ErrorCode::from_parts(345, 1000);
+
+/// `sf::cmif::ResultInvalidHeaderSize`, the request is too short for what it claims to contain
+pub const INVALID_HEADER_SIZE: ErrorCode = ErrorCode::from_parts(10, 202);
+/// `sf::cmif::ResultInvalidInHeader`, the request doesn't have a CMIF header
+pub const INVALID_IN_HEADER: ErrorCode = ErrorCode::from_parts(10, 211);
+/// `sf::cmif::ResultUnknownCommandId`, the server doesn't implement the command
+pub const UNKNOWN_COMMAND_ID: ErrorCode = ErrorCode::from_parts(10, 221);
+
+/// Size of the thread-local IPC buffer, no message is larger than that
+const IPC_BUFFER_SIZE: usize = 0x100;
+
+/// Runs a command handler, converting a panic inside of it into [HANDLER_PANICKED]
+///
+/// Without the `unwind` feature this just calls the handler, see the [module-level docs](self)
+#[inline]
+pub fn dispatch<T, F>(handler: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T>,
+{
+    #[cfg(feature = "unwind")]
+    {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(handler))
+            .unwrap_or(Err(HANDLER_PANICKED))
+    }
+
+    #[cfg(not(feature = "unwind"))]
+    {
+        handler()
+    }
+}
+
+/// Writes a response carrying only a failure result into the thread-local IPC buffer
+///
+/// This is what the server should reply with when [dispatch] returns an error
+pub fn write_error_response(result: ErrorCode) {
+    debug_assert!(result.is_failure());
+
+    #[repr(C, packed)]
+    struct Response {
+        hipc: HipcHeader,
+        pre_padding: [u8; 8],
+        cmif: CmifOutHeader,
+        post_padding: [u8; 8],
+    }
+    // Compiler time response size check
+    let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+
+    let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+    unsafe {
+        ::core::ptr::write(
+            ipc_buffer_ptr as *mut _,
+            Response {
+                // the command type is ignored by the kernel in responses
+                hipc: HipcHeader::new(CommandType::Invalid, 0, 0, 0, 0, 8, 0, 0, false),
+                pre_padding: Default::default(),
+                cmif: CmifOutHeader {
+                    magic: CmifOutHeader::MAGIC,
+                    version: 0,
+                    result,
+                    token: 0,
+                },
+                post_padding: Default::default(),
+            },
+        )
+    };
+}
+
+/// A CMIF request, only the command id and the raw data are decoded
+///
+/// The raw data is copied out of the IPC buffer, so the response can be written while the request is alive
+pub struct IncomingRequest {
+    command_id: u32,
+    data: [u8; IPC_BUFFER_SIZE],
+    data_size: usize,
+}
+
+impl IncomingRequest {
+    /// Decodes the request received into the thread-local IPC buffer
+    pub fn read() -> Result<Self> {
+        Self::parse(unsafe { get_ipc_buffer() })
+    }
+
+    /// Decodes the request in `message`, which should be laid out like the IPC buffer
+    pub fn parse(message: &[u8]) -> Result<Self> {
+        fn read<T>(message: &[u8], offset: usize) -> Result<T> {
+            if offset + size_of::<T>() > message.len() {
+                return Err(INVALID_HEADER_SIZE);
+            }
+            Ok(unsafe { ::core::ptr::read_unaligned(message.as_ptr().add(offset) as *const T) })
+        }
+
+        let hipc: HipcHeader = read(message, 0)?;
+
+        // skip everything up to the raw data
+        let mut offset = size_of::<HipcHeader>();
+        if hipc.has_special_header() != 0 {
+            let special: HipcSpecialHeader = read(message, offset)?;
+            offset += size_of::<HipcSpecialHeader>();
+            if special.send_pid() != 0 {
+                offset += size_of::<u64>();
+            }
+            offset += size_of::<u32>()
+                * (special.num_copy_handles() + special.num_move_handles()) as usize;
+        }
+        // pointer descriptors are 8 bytes, map alias ones are 12
+        offset += 8 * hipc.num_in_pointers() as usize;
+        offset += 12
+            * (hipc.num_in_map_aliases()
+                + hipc.num_out_map_aliases()
+                + hipc.num_inout_map_aliases()) as usize;
+
+        let raw_data_end = offset + 4 * hipc.num_data_words() as usize;
+        // the cmif header is aligned to 16 bytes, the padding is included in the raw data
+        let cmif_offset = (offset + 15) & !15;
+        let data_offset = cmif_offset + size_of::<CmifInHeader>();
+        if data_offset > raw_data_end || raw_data_end > message.len() {
+            return Err(INVALID_HEADER_SIZE);
+        }
+
+        let cmif: CmifInHeader = read(message, cmif_offset)?;
+        if cmif.magic != CmifInHeader::MAGIC {
+            return Err(INVALID_IN_HEADER);
+        }
+
+        let data_size = raw_data_end - data_offset;
+        // can only happen when parsing a message larger than the IPC buffer
+        if data_size > IPC_BUFFER_SIZE {
+            return Err(INVALID_HEADER_SIZE);
+        }
+        let mut data = [0; IPC_BUFFER_SIZE];
+        data[..data_size].copy_from_slice(&message[data_offset..raw_data_end]);
+
+        Ok(Self {
+            command_id: cmif.command_id,
+            data,
+            data_size,
+        })
+    }
+
+    #[inline]
+    pub fn command_id(&self) -> u32 {
+        self.command_id
+    }
+
+    /// The raw data following the CMIF header
+    ///
+    /// Clients pad the raw data, so it can be longer than the arguments of the command
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.data_size]
+    }
+
+    /// Reads the arguments of the command from the raw data
+    ///
+    /// # Safety
+    ///
+    /// Any bit pattern should be a valid `T`, the client can send anything
+    pub unsafe fn read_data<T>(&self) -> Result<T> {
+        if size_of::<T>() > self.data_size {
+            return Err(INVALID_HEADER_SIZE);
+        }
+        Ok(::core::ptr::read_unaligned(self.data.as_ptr() as *const T))
+    }
+}
+
+/// Writes a successful response carrying `data` as the raw data into the thread-local IPC buffer
+pub fn write_response<T>(data: &T) {
+    let size = size_of::<T>();
+    // the padding aligning the cmif header to 16 bytes is included in the raw data
+    let num_data_words = (8 + size_of::<CmifOutHeader>() + size).div_ceil(4);
+    debug_assert!(size_of::<HipcHeader>() + num_data_words * 4 <= IPC_BUFFER_SIZE);
+
+    let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+    unsafe {
+        ::core::ptr::write(
+            ipc_buffer_ptr as *mut HipcHeader,
+            // the command type is ignored by the kernel in responses
+            HipcHeader::new(
+                CommandType::Invalid,
+                0,
+                0,
+                0,
+                0,
+                num_data_words as u32,
+                0,
+                0,
+                false,
+            ),
+        );
+        ::core::ptr::write(
+            ipc_buffer_ptr.add(0x10) as *mut CmifOutHeader,
+            CmifOutHeader {
+                magic: CmifOutHeader::MAGIC,
+                version: 0,
+                // zero is success
+                result: ErrorCode::from_parts(0, 0),
+                token: 0,
+            },
+        );
+        ::core::ptr::copy_nonoverlapping(
+            data as *const T as *const u8,
+            ipc_buffer_ptr.add(0x10 + size_of::<CmifOutHeader>()),
+            size,
+        );
+    }
+}
+
+/// Handles owned by the server on behalf of a single session
+///
+/// All the tracked handles are closed when this is dropped
+#[derive(Debug, Default)]
+pub struct SessionHandles {
+    handles: Vec<RawHandle>,
+}
+
+impl SessionHandles {
+    pub const fn new() -> Self {
+        Self {
+            handles: Vec::new(),
+        }
+    }
+
+    pub fn track(&mut self, handle: RawHandle) {
+        self.handles.push(handle);
+    }
+
+    /// Stops tracking a handle that is about to be moved to the client in a reply
+    ///
+    /// The kernel removes a moved handle from our handle table, so it must not be closed by us afterwards.
+    /// Returns `None` if the handle was not tracked.
+    pub fn take_for_reply(&mut self, handle: RawHandle) -> Option<RawHandle> {
+        let index = self.handles.iter().position(|&h| h == handle)?;
+        Some(self.handles.swap_remove(index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Closes all the tracked handles
+    pub fn close_all(&mut self) {
+        self.close_all_with(horizon_svc::close_handle)
+    }
+
+    fn close_all_with(&mut self, mut close: impl FnMut(RawHandle) -> Result<()>) {
+        for handle in self.handles.drain(..) {
+            // nothing useful we can do if it fails
+            let _ = close(handle);
+        }
+    }
+}
+
+impl Drop for SessionHandles {
+    fn drop(&mut self) {
+        self.close_all()
+    }
+}
+
+/// Checks whether the request received into the thread-local IPC buffer is a CMIF `Close`
+///
+/// The close request should not be replied to, the server should call [close_session] instead
+pub fn is_close_request() -> bool {
+    let hipc = unsafe { ::core::ptr::read(get_ipc_buffer_ptr() as *const HipcHeader) };
+
+    hipc.type_() == CommandType::Close as u16
+}
+
+/// Closes the server side of the session along with all the handles it owned
+pub fn close_session(session: RawHandle, handles: SessionHandles) -> Result<()> {
+    close_session_with(session, handles, horizon_svc::close_handle)
+}
+
+fn close_session_with(
+    session: RawHandle,
+    mut handles: SessionHandles,
+    mut close: impl FnMut(RawHandle) -> Result<()>,
+) -> Result<()> {
+    handles.close_all_with(&mut close);
+    close(session)
+}
+
+/// The sessions accepted on a server port, along with the handles owned on behalf of each of them
+///
+/// This is the skeleton of a dispatch loop:
+///
+/// ```ignore
+/// let mut sessions = Sessions::new(port);
+/// loop {
+///     let session = sessions.receive()?;
+///     let request = IncomingRequest::read()?;
+///     // handle the request, tracking the handles created for the session in `sessions.handles(session)`
+///     sessions.reply(session)?;
+/// }
+/// ```
+///
+/// The requests are received from one session at a time, so a `SessionClosed` error always refers to
+///  a known session, which is then closed with [close_session]. Up to [MAX_WAIT_HANDLES](horizon_svc::MAX_WAIT_HANDLES)
+///  minus one sessions can be served, as the port is waited on too.
+pub struct Sessions {
+    port: RawHandle,
+    sessions: Vec<(RawHandle, SessionHandles)>,
+}
+
+impl Sessions {
+    pub const fn new(port: RawHandle) -> Self {
+        Self {
+            port,
+            sessions: Vec::new(),
+        }
+    }
+
+    /// The handles owned on behalf of `session`, `None` if it's not one of the served sessions
+    pub fn handles(&mut self, session: RawHandle) -> Option<&mut SessionHandles> {
+        self.sessions
+            .iter_mut()
+            .find(|(s, _)| *s == session)
+            .map(|(_, handles)| handles)
+    }
+
+    /// Waits for a request on any of the sessions and receives it into the thread-local IPC buffer
+    ///
+    /// New sessions are accepted on the port along the way. Sessions closed by the client
+    ///  (either with the CMIF `Close` command or by closing the handle) are closed here.
+    ///  Returns the session that has received the request.
+    pub fn receive(&mut self) -> Result<RawHandle> {
+        loop {
+            let mut wait_handles = Vec::with_capacity(self.sessions.len() + 1);
+            wait_handles.push(self.port);
+            wait_handles.extend(self.sessions.iter().map(|&(session, _)| session));
+
+            match horizon_svc::wait_synchronization(&wait_handles, None)? {
+                0 => {
+                    let session = horizon_svc::accept_session(self.port)?;
+                    self.sessions.push((session, SessionHandles::new()));
+                }
+                index => {
+                    let session = wait_handles[index];
+                    let res =
+                        horizon_svc::reply_and_receive(&[session], None, Some(Duration::ZERO));
+                    let received = self.check_closed(session, res, horizon_svc::close_handle)?;
+                    if received.is_none() {
+                        // the client has closed its handle
+                        continue;
+                    }
+                    if is_close_request() {
+                        self.close(session)?;
+                        continue;
+                    }
+                    return Ok(session);
+                }
+            }
+        }
+    }
+
+    /// Sends the response in the thread-local IPC buffer to `session`
+    ///
+    /// If the client has closed the session in the meantime, it's closed here instead.
+    pub fn reply(&mut self, session: RawHandle) -> Result<()> {
+        // nothing to receive, so a successful reply ends with a timeout
+        let res = match horizon_svc::reply_and_receive(&[], Some(session), Some(Duration::ZERO)) {
+            Err(e) if e.is_timeout() => Ok(()),
+            res => res.map(|_| ()),
+        };
+        self.check_closed(session, res, horizon_svc::close_handle)?;
+        Ok(())
+    }
+
+    /// Closes `session` along with all the handles it owned
+    ///
+    /// Fails with `InvalidHandle` if it's not one of the served sessions
+    pub fn close(&mut self, session: RawHandle) -> Result<()> {
+        self.close_with(session, horizon_svc::close_handle)
+    }
+
+    fn close_with(
+        &mut self,
+        session: RawHandle,
+        close: impl FnMut(RawHandle) -> Result<()>,
+    ) -> Result<()> {
+        let index = self
+            .sessions
+            .iter()
+            .position(|&(s, _)| s == session)
+            .ok_or(ErrorCode::from_parts(
+                KernelErrorCode::MODULE,
+                KernelErrorCode::InvalidHandle as u32,
+            ))?;
+        let (session, handles) = self.sessions.swap_remove(index);
+        close_session_with(session, handles, close)
+    }
+
+    /// Closes `session` if `res` is `SessionClosed`, returning `None` then
+    fn check_closed<T>(
+        &mut self,
+        session: RawHandle,
+        res: Result<T>,
+        close: impl FnMut(RawHandle) -> Result<()>,
+    ) -> Result<Option<T>> {
+        match res {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.is_session_closed() => {
+                self.close_with(session, close)?;
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::{IncomingRequest, SessionHandles, Sessions, INVALID_HEADER_SIZE};
+    use crate::cmif::domain::Framing;
+    use crate::cmif::raw_command::write_request;
+    use horizon_error::{ErrorCode, ErrorCodeModule, KernelErrorCode};
+    use horizon_svc::RawHandle;
+    use std::vec::Vec;
+
+    #[cfg(feature = "unwind")]
+    #[test]
+    fn panicking_handler() {
+        let res = super::dispatch(|| -> horizon_error::Result<()> { panic!("handler panicked") });
+
+        assert_eq!(res, Err(super::HANDLER_PANICKED));
+    }
+
+    #[test]
+    fn oversized_request() {
+        // a message is never larger than the IPC buffer, but parse can be given any slice
+        let mut message = [0; 0x200];
+        write_request(&mut message, Framing::Session, 7, &[0xab; 0x180]);
+
+        assert_eq!(
+            IncomingRequest::parse(&message).err(),
+            Some(INVALID_HEADER_SIZE)
+        );
+    }
+
+    #[test]
+    fn closed_session_closes_its_handles() {
+        let session_closed = ErrorCode::from_parts(
+            KernelErrorCode::MODULE,
+            KernelErrorCode::SessionClosed as u32,
+        );
+
+        // the handles are made up, so they are closed with the closure recording them
+        let mut closed = Vec::new();
+        let mut record = |handle| {
+            closed.push(handle);
+            Ok(())
+        };
+
+        let mut sessions = Sessions::new(RawHandle(0x10));
+        for session in [0x20, 0x30] {
+            let mut handles = SessionHandles::new();
+            handles.track(RawHandle(session + 1));
+            handles.track(RawHandle(session + 2));
+            sessions.sessions.push((RawHandle(session), handles));
+        }
+
+        // other errors don't close anything
+        let timed_out =
+            ErrorCode::from_parts(KernelErrorCode::MODULE, KernelErrorCode::TimedOut as u32);
+        assert_eq!(
+            sessions.check_closed::<()>(RawHandle(0x20), Err(timed_out), &mut record),
+            Err(timed_out)
+        );
+        assert_eq!(
+            sessions.check_closed(RawHandle(0x20), Ok(0), &mut record),
+            Ok(Some(0))
+        );
+
+        assert_eq!(
+            sessions.check_closed::<()>(RawHandle(0x20), Err(session_closed), &mut record),
+            Ok(None)
+        );
+        assert!(sessions.handles(RawHandle(0x20)).is_none());
+        assert_eq!(sessions.handles(RawHandle(0x30)).unwrap().len(), 2);
+
+        sessions.close_with(RawHandle(0x30), &mut record).unwrap();
+        assert!(sessions.sessions.is_empty());
+
+        assert_eq!(closed, [0x21, 0x22, 0x20, 0x31, 0x32, 0x30].map(RawHandle));
+    }
+}
@@ -1,24 +1,82 @@
-use crate::swipc::codegen::{gen_ipc_file, TokenStorage};
-use crate::swipc::diagnostics::{diagnostics_from_parse_error, DiagnosticResultExt};
-use crate::swipc::model::{IpcFile, TypecheckedIpcFile};
+use crate::ninupdates::ipc_parse::IpcFile as IpcDump;
+use crate::swipc::codegen::features::{ModuleFeatures, FEATURES_FILE_NAME};
+use crate::swipc::codegen::{gen_ipc_file, CodegenOptions, TokenStorage};
+use crate::swipc::crosscheck::crosscheck;
+use crate::swipc::diagnostics::{
+    diagnostics_from_parse_error, DiagnosticExt, DiagnosticResultExt, Span,
+};
+use crate::swipc::diff::diff;
+use crate::swipc::model::{
+    IpcFile, TypecheckOptions, TypecheckedIpcFile, Version, DEFAULT_POINTER_BUFFER_WARN_SIZE,
+};
 use crate::swipc::parser::IpcFileParser;
 use anyhow::{anyhow, Context};
+use codespan_reporting::diagnostic::{Diagnostic, Severity};
 use codespan_reporting::term::termcolor::ColorChoice;
-use std::collections::BTreeMap;
+use itertools::Itertools;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ffi::OsStr;
 use std::fs::ReadDir;
 use std::ops::Range;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 #[derive(clap::Args, Debug)]
 pub struct Args {
+    /// Warn about structs larger than this (in bytes) sent via in-pointer buffers without `sf::LargeData`
+    #[clap(long, global = true, default_value_t = DEFAULT_POINTER_BUFFER_WARN_SIZE)]
+    pointer_buffer_warn_size: u64,
     #[clap(subcommand)]
     command: Command,
 }
 
 #[derive(clap::Subcommand, Debug)]
 enum Command {
-    GenIpcdef {},
+    GenIpcdef {
+        /// Generate an `<Interface>Api` trait for each interface
+        #[clap(long)]
+        gen_traits: bool,
+        /// Generate `async fn` variants of the commands (used with the `async-ipc` feature)
+        #[clap(long)]
+        gen_async: bool,
+        /// Generate `fn <command>_send` variants of the commands, returning without waiting for the reply (used with the `async-ipc` feature)
+        #[clap(long)]
+        gen_send: bool,
+        /// Derive `bytemuck` traits for the plain data structs (used with the `bytemuck` feature)
+        #[clap(long)]
+        gen_bytemuck: bool,
+        /// Put each sysmodule behind a feature, writing the feature list to `features.toml` in the generated directory
+        #[clap(long)]
+        gate_modules: bool,
+        /// Generate an `<Interface>Server` trait for each interface, with a dispatcher for the implementations
+        #[clap(long)]
+        gen_server: bool,
+        /// Generate `unsafe fn <command>_raw` variants of the commands taking a pre-built payload (used with the `raw-ipc` feature)
+        #[clap(long)]
+        gen_raw: bool,
+        /// Generate `fn <command>_in_place` variants of the commands returning a large struct, filling a caller-provided `MaybeUninit`
+        #[clap(long)]
+        gen_in_place: bool,
+        /// Make the commands `#[track_caller]` wrappers logging the caller location on error (used with the `track-ipc-errors` feature)
+        #[clap(long)]
+        gen_track_caller: bool,
+        /// Skip the commands added in a later firmware version than this one, like `4.0.0`
+        #[clap(long)]
+        target_version: Option<Version>,
+    },
+    /// Check the definitions against a server-side IPC dump (`swipcgen_server_modern.info` from ninupdates)
+    Crosscheck {
+        /// Path to the dump file
+        dump: PathBuf,
+    },
+    /// Compare two versions of the definitions, listing the added, removed and changed interfaces, commands and structs
+    Diff {
+        /// The defs directory of the old version
+        #[clap(long)]
+        old: PathBuf,
+        /// The defs directory of the new version
+        #[clap(long)]
+        new: PathBuf,
+    },
 }
 
 struct Paths {
@@ -194,11 +252,10 @@ impl<'a> codespan_reporting::files::Files<'a> for SourceFiles {
     }
 }
 
-fn collect_source_files(defs_directory: &Path) -> anyhow::Result<SourceFiles> {
+/// Collects the names of all the source files in the defs directory (relative to it)
+fn collect_source_files(defs_directory: &Path) -> anyhow::Result<Vec<String>> {
     let mut filenames = Vec::new();
 
-    let mut files = SourceFiles { files: Vec::new() };
-
     for entry in walkdir::WalkDir::new(defs_directory) {
         let entry = entry.context("Walking the defs directory")?;
 
@@ -215,34 +272,179 @@ fn collect_source_files(defs_directory: &Path) -> anyhow::Result<SourceFiles> {
         }
     }
 
-    for filename in filenames {
-        let content = std::fs::read_to_string(defs_directory.join(&filename))
-            .with_context(|| format!("Reading source file `{}`", filename))?;
+    Ok(filenames)
+}
 
-        files.add(filename, content);
+/// Resolves a path in the `include` directive relative to the directory of the including file
+///
+/// This is done purely lexically, so that the resulting name can be used as a key identifying the file
+fn resolve_include_path(including_file: &str, path: &str) -> String {
+    let base = Path::new(including_file)
+        .parent()
+        .unwrap_or_else(|| Path::new(""));
+
+    let mut res = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(res.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                res.pop();
+            }
+            c => res.push(c),
+        }
     }
 
-    Ok(files)
+    res.to_str()
+        .expect("Please use UTF-8 filenames")
+        .to_string()
 }
 
-/// Parse & typecheck a collection of files in a single pass as a one merged file
-fn parse_files(files: &SourceFiles) -> crate::swipc::diagnostics::Result<TypecheckedIpcFile> {
-    let mut res_file = IpcFile::new();
+/// Parses files, following the `include` directives, and merges them into one [IpcFile]
+///
+/// Every file is parsed only once, even if it is included multiple times (or is also a root file),
+/// so that its definitions are not duplicated
+struct IncludeResolver<F> {
+    read_file: F,
+    files: SourceFiles,
+    /// Names of files that were already parsed
+    parsed: BTreeSet<String>,
+    /// Chain of files that are being parsed right now, used to detect include cycles
+    stack: Vec<String>,
+    result: IpcFile,
+    diagnostics: crate::swipc::diagnostics::Result<()>,
+}
 
-    let mut res = Ok(());
+impl<F: FnMut(&str) -> std::io::Result<String>> IncludeResolver<F> {
+    fn new(read_file: F) -> Self {
+        Self {
+            read_file,
+            files: SourceFiles { files: Vec::new() },
+            parsed: BTreeSet::new(),
+            stack: Vec::new(),
+            result: IpcFile::new(),
+            diagnostics: Ok(()),
+        }
+    }
 
-    for (id, file) in files.iter() {
-        match IpcFileParser::new().parse(id, &file.content) {
-            Ok(f) => res_file.merge_with(f),
-            Err(e) => res.extend(diagnostics_from_parse_error(id, &file.content, e)),
+    fn parse_file(&mut self, name: String, included_from: Option<Span>) {
+        if let Some(pos) = self.stack.iter().position(|f| f == &name) {
+            let cycle = self.stack[pos..]
+                .iter()
+                .chain(std::iter::once(&name))
+                .map(|f| format!("`{}`", f))
+                .join(" -> ");
+
+            let diagnostic = Diagnostic::error()
+                .with_message(format!("Include cycle detected: {}", cycle));
+            self.diagnostics.push(match included_from {
+                Some(location) => diagnostic.with_primary_label(location),
+                None => diagnostic,
+            });
+            return;
+        }
+
+        if !self.parsed.insert(name.clone()) {
+            return;
+        }
+
+        let content = match (self.read_file)(&name) {
+            Ok(content) => content,
+            Err(e) => {
+                let diagnostic = Diagnostic::error()
+                    .with_message(format!("Could not read source file `{}`: {}", name, e));
+                self.diagnostics.push(match included_from {
+                    Some(location) => diagnostic.with_primary_label(location),
+                    None => diagnostic,
+                });
+                return;
+            }
+        };
+
+        let id = self.files.add(name.clone(), content);
+        let content = &self.files.get(id).unwrap().content;
+
+        let file = IpcFileParser::new()
+            .parse(id, content)
+            .map_err(|e| diagnostics_from_parse_error(id, content, e));
+
+        match file {
+            Ok(mut file) => {
+                let includes = std::mem::take(&mut file.includes);
+                self.result.merge_with(file);
+
+                self.stack.push(name.clone());
+                for include in includes {
+                    let path = resolve_include_path(&name, &include.path);
+                    self.parse_file(path, Some(include.location));
+                }
+                self.stack.pop();
+            }
+            Err(e) => self.diagnostics.extend(e),
         }
     }
+}
 
-    if let Err(e) = res {
-        return Err(e);
+/// Parse & typecheck a collection of files (and the files they include) in a single pass as a one merged file
+///
+/// Returns the loaded source files along with the result, so that the diagnostics can be displayed
+fn parse_files(
+    root_files: Vec<String>,
+    options: &TypecheckOptions,
+    read_file: impl FnMut(&str) -> std::io::Result<String>,
+) -> (
+    SourceFiles,
+    crate::swipc::diagnostics::Result<TypecheckedIpcFile>,
+) {
+    let mut resolver = IncludeResolver::new(read_file);
+
+    for name in root_files {
+        resolver.parse_file(name, None);
     }
 
-    res_file.typecheck()
+    let IncludeResolver {
+        files,
+        result,
+        diagnostics,
+        ..
+    } = resolver;
+
+    let res = diagnostics.and_then(|_| result.typecheck_with_options(options));
+
+    (files, res)
+}
+
+/// Parses and typechecks all the definitions, displaying the diagnostics on failure
+fn parse_defs(
+    paths: &Paths,
+    options: &TypecheckOptions,
+) -> anyhow::Result<(SourceFiles, TypecheckedIpcFile)> {
+    parse_defs_directory(&paths.defs_directory, options)
+}
+
+/// Parses and typechecks all the definitions in `defs_directory`, displaying the diagnostics on failure
+fn parse_defs_directory(
+    defs_directory: &Path,
+    options: &TypecheckOptions,
+) -> anyhow::Result<(SourceFiles, TypecheckedIpcFile)> {
+    let root_files = collect_source_files(defs_directory).context("Collecting source files")?;
+
+    let (source_files, file) = parse_files(root_files, options, |name| {
+        std::fs::read_to_string(defs_directory.join(name))
+    });
+
+    match file {
+        Ok(f) => {
+            display_diagnostics(&source_files, f.warnings().clone());
+            Ok((source_files, f))
+        }
+        Err(diags) => {
+            display_diagnostics(&source_files, diags);
+
+            Err(anyhow!("Compilation failed"))
+        }
+    }
 }
 
 fn display_diagnostics(files: &SourceFiles, diagnostics: crate::swipc::diagnostics::Error) {
@@ -288,35 +490,208 @@ fn write_files(gen_directory: &Path, files: &BTreeMap<String, String>) -> anyhow
 }
 
 pub fn run(args: Args) -> anyhow::Result<()> {
+    let typecheck_options = TypecheckOptions {
+        pointer_buffer_warn_size: args.pointer_buffer_warn_size,
+    };
+
     match args.command {
-        Command::GenIpcdef {} => {
+        Command::GenIpcdef {
+            gen_traits,
+            gen_async,
+            gen_send,
+            gen_bytemuck,
+            gate_modules,
+            gen_server,
+            gen_raw,
+            gen_in_place,
+            gen_track_caller,
+            target_version,
+        } => {
             let paths = get_paths().context("Getting workspace paths")?;
 
-            let source_files =
-                collect_source_files(&paths.defs_directory).context("Collecting source files")?;
-
-            let file = match parse_files(&source_files) {
-                Ok(f) => f,
-                Err(diags) => {
-                    display_diagnostics(&source_files, diags);
-
-                    // TODO: do not fail if we only have warnings
-                    // this mandates some changes to the diagnostics types...
-                    // Maybe add a DiagResult that has Result and Diagnostics attached?
-                    return Err(anyhow!("Compilation failed"));
-                }
-            };
+            let (_, file) = parse_defs(&paths, &typecheck_options)?;
 
             let mut tok = TokenStorage::new();
-            gen_ipc_file(&mut tok, file.context(), &file);
+            let options = CodegenOptions {
+                gen_traits,
+                gen_async,
+                gen_send,
+                gen_bytemuck,
+                gate_modules,
+                gen_server,
+                gen_raw,
+                gen_in_place,
+                gen_track_caller,
+                target_version,
+            };
+            gen_ipc_file(&mut tok, file.context(), &options, &file);
 
-            let files = tok
+            let mut files = tok
                 .to_file_string()
                 .context("Formatting the generated source code")?;
 
+            if gate_modules {
+                files.insert(
+                    FEATURES_FILE_NAME.to_string(),
+                    ModuleFeatures::collect(&file).to_manifest_fragment(),
+                );
+            }
+
             write_files(&paths.gen_directory, &files).context("Writing output files")?;
 
             Ok(())
         }
+        Command::Crosscheck { dump } => {
+            let paths = get_paths().context("Getting workspace paths")?;
+
+            let (source_files, file) = parse_defs(&paths, &typecheck_options)?;
+
+            let dump = std::fs::read_to_string(&dump)
+                .with_context(|| format!("Reading the dump `{}`", dump.display()))?;
+            let dump = IpcDump::parse(&dump).context("Parsing the dump")?;
+
+            let diagnostics = crosscheck(&file, &dump);
+            let has_errors = diagnostics.iter().any(|d| d.severity == Severity::Error);
+
+            display_diagnostics(&source_files, diagnostics);
+
+            if has_errors {
+                return Err(anyhow!("The definitions do not match the dump"));
+            }
+
+            Ok(())
+        }
+        Command::Diff { old, new } => {
+            let (_, old_file) = parse_defs_directory(&old, &typecheck_options)
+                .with_context(|| format!("Parsing the old definitions `{}`", old.display()))?;
+            let (_, new_file) = parse_defs_directory(&new, &typecheck_options)
+                .with_context(|| format!("Parsing the new definitions `{}`", new.display()))?;
+
+            for change in diff(&old_file, &new_file) {
+                println!("{}", change);
+            }
+
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::swipc::cli::{parse_files, resolve_include_path};
+    use crate::swipc::model::TypecheckOptions;
+    use codespan_reporting::files::Files;
+    use std::collections::BTreeMap;
+
+    fn read_from<'a>(
+        files: &'a BTreeMap<&str, &str>,
+    ) -> impl FnMut(&str) -> std::io::Result<String> + 'a {
+        |name| {
+            files
+                .get(name)
+                .map(|c| c.to_string())
+                .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+        }
+    }
+
+    #[test]
+    fn include_path() {
+        assert_eq!(resolve_include_path("a.id", "b.id"), "b.id");
+        assert_eq!(resolve_include_path("fs/a.id", "./b.id"), "fs/b.id");
+        assert_eq!(resolve_include_path("fs/a.id", "../sm/b.id"), "sm/b.id");
+        assert_eq!(resolve_include_path("a.id", "../b.id"), "../b.id");
+    }
+
+    #[test]
+    fn two_file_include() {
+        let files = BTreeMap::from([
+            (
+                "fs/fs.id",
+                r#"
+                    include "../common/types.id";
+
+                    type fs::Value = common::Value;
+                "#,
+            ),
+            (
+                "common/types.id",
+                r#"
+                    type common::Value = u32;
+                "#,
+            ),
+        ]);
+
+        // the included file is also a root file, it should not be parsed twice
+        let (source_files, res) = parse_files(
+            vec!["fs/fs.id".to_string(), "common/types.id".to_string()],
+            &TypecheckOptions::default(),
+            read_from(&files),
+        );
+
+        let file = res.unwrap();
+
+        assert_eq!(file.iter_items().count(), 2);
+        assert_eq!(source_files.files.len(), 2);
+    }
+
+    #[test]
+    fn include_error_location() {
+        let files = BTreeMap::from([
+            (
+                "main.id",
+                r#"
+                    include "other.id";
+                "#,
+            ),
+            (
+                "other.id",
+                r#"
+                    type other::Value = undefined_type;
+                "#,
+            ),
+        ]);
+
+        let (source_files, res) = parse_files(
+            vec!["main.id".to_string()],
+            &TypecheckOptions::default(),
+            read_from(&files),
+        );
+
+        let diagnostics = res.unwrap_err();
+        let label = &diagnostics[0].labels[0];
+
+        assert_eq!(source_files.name(label.file_id).unwrap(), "other.id");
+    }
+
+    #[test]
+    fn include_cycle() {
+        let files = BTreeMap::from([
+            (
+                "a.id",
+                r#"
+                    include "b.id";
+                "#,
+            ),
+            (
+                "b.id",
+                r#"
+                    include "a.id";
+                "#,
+            ),
+        ]);
+
+        let (_, res) = parse_files(
+            vec!["a.id".to_string()],
+            &TypecheckOptions::default(),
+            read_from(&files),
+        );
+
+        let diagnostics = res.unwrap_err();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Include cycle detected: `a.id` -> `b.id` -> `a.id`"
+        );
     }
 }
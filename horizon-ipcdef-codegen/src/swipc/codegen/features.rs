@@ -0,0 +1,169 @@
+//! Gating the generated top-level modules (one per sysmodule, like `fssrv` or `sm`) behind cargo features
+//!
+//! Each module gets a feature named after it, enabling the features of the modules it uses types from.
+//!  The feature list is emitted as a fragment to be pasted into the `[features]` section of the manifest.
+
+use crate::swipc::model::{IpcFileItem, NamespacedIdent, NominalType, TypecheckedIpcFile, Value};
+use arcstr::ArcStr;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Name of the feature enabling all the modules
+pub const ALL_FEATURE: &str = "all";
+
+/// Name of the file the feature list is written to, relative to the generated directory
+pub const FEATURES_FILE_NAME: &str = "features.toml";
+
+/// The feature gating the item, items in the root namespace are not gated
+fn feature_of(name: &NamespacedIdent) -> Option<&ArcStr> {
+    name.iter_namespaces().next()
+}
+
+fn nominal_type_name(ty: &NominalType) -> Option<&NamespacedIdent> {
+    match ty {
+        NominalType::TypeName { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
+fn item_name(item: &IpcFileItem) -> &NamespacedIdent {
+    match item {
+        IpcFileItem::TypeAlias(a) => &a.name,
+        IpcFileItem::StructDef(s) => &s.name,
+        IpcFileItem::EnumDef(e) => &e.name,
+        IpcFileItem::BitflagsDef(b) => &b.name,
+        IpcFileItem::InterfaceDef(i) => &i.name,
+    }
+}
+
+/// Names of the types and interfaces the item refers to
+fn referenced_names(item: &IpcFileItem) -> Vec<&NamespacedIdent> {
+    match item {
+        IpcFileItem::TypeAlias(a) => nominal_type_name(&a.referenced_type).into_iter().collect(),
+        IpcFileItem::StructDef(s) => s
+            .fields
+            .iter()
+            .filter_map(|f| nominal_type_name(&f.ty))
+            .collect(),
+        IpcFileItem::EnumDef(_) | IpcFileItem::BitflagsDef(_) => Vec::new(),
+        IpcFileItem::InterfaceDef(i) => i
+            .commands
+            .iter()
+            .flat_map(|c| c.arguments.iter())
+            .filter_map(|(_, value)| match value.as_ref() {
+                Value::In(ty) | Value::Out(ty) | Value::InArray(ty, _) | Value::OutArray(ty, _) => {
+                    nominal_type_name(ty)
+                }
+                Value::InObject(name, _)
+                | Value::OutObject(Some(name), _)
+                | Value::OptionalOutObject(name, _) => Some(name),
+                _ => None,
+            })
+            .collect(),
+    }
+}
+
+/// Module features along with the features they depend on
+#[derive(Debug, Default, PartialEq)]
+pub struct ModuleFeatures {
+    features: BTreeMap<ArcStr, BTreeSet<ArcStr>>,
+}
+
+impl ModuleFeatures {
+    pub fn collect(file: &TypecheckedIpcFile) -> Self {
+        let mut features = BTreeMap::<ArcStr, BTreeSet<ArcStr>>::new();
+
+        for item in file.iter_items() {
+            let feature = match feature_of(item_name(item)) {
+                Some(feature) => feature,
+                None => continue,
+            };
+
+            let dependencies = features.entry(feature.clone()).or_default();
+            for name in referenced_names(item) {
+                match feature_of(name) {
+                    Some(dependency) if dependency != feature => {
+                        dependencies.insert(dependency.clone());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Self { features }
+    }
+
+    /// Renders the features in the cargo manifest syntax
+    pub fn to_manifest_fragment(&self) -> String {
+        let quoted_list = |features: &mut dyn Iterator<Item = &ArcStr>| {
+            features
+                .map(|f| format!("\"{}\"", f))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut res = String::new();
+        res.push_str("# Generated by horizon-ipcdef-codegen, paste into the [features] section of the manifest\n");
+        writeln!(
+            res,
+            "{} = [{}]",
+            ALL_FEATURE,
+            quoted_list(&mut self.features.keys())
+        )
+        .unwrap();
+        for (feature, dependencies) in self.features.iter() {
+            writeln!(
+                res,
+                "{} = [{}]",
+                feature,
+                quoted_list(&mut dependencies.iter())
+            )
+            .unwrap();
+        }
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::swipc::codegen::features::ModuleFeatures;
+    use crate::swipc::model::TypecheckedIpcFile;
+    use crate::swipc::tests::{parse_typechecked_ipc_file, unwrap_parse};
+    use indoc::indoc;
+
+    #[test]
+    fn feature_dependencies() {
+        let s = r#"
+            struct ncm::ProgramId {
+                u64 value;
+            }
+            struct fssrv::Struct {
+                ncm::ProgramId program_id;
+            }
+            interface fssrv::IFileSystemProxy {
+                [0] OpenFileSystem(sf::Out<sf::SharedPointer<fssrv::IFileSystem>> fs);
+            }
+            interface fssrv::IFileSystem {
+            }
+            interface ldr::IProcessManagerInterface {
+                [0] Hello(ncm::ProgramId program_id);
+            }
+        "#;
+
+        let file: TypecheckedIpcFile = unwrap_parse(s, parse_typechecked_ipc_file);
+
+        let features = ModuleFeatures::collect(&file);
+
+        assert_eq!(
+            features.to_manifest_fragment(),
+            indoc! {r#"
+                # Generated by horizon-ipcdef-codegen, paste into the [features] section of the manifest
+                all = ["fssrv", "ldr", "ncm"]
+                fssrv = ["ncm"]
+                ldr = ["ncm"]
+                ncm = []
+            "#}
+        );
+    }
+}
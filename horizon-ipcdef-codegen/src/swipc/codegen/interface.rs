@@ -1,10 +1,11 @@
-use crate::swipc::codegen::types::make_nominal_type;
-use crate::swipc::codegen::{import_in, make_ident, TokenStorage};
+use crate::swipc::codegen::types::{is_pod_type, make_nominal_type};
+use crate::swipc::codegen::{import_in, make_ident, CodegenOptions, TokenStorage};
 use crate::swipc::diagnostics::Span;
-use crate::swipc::layout::FieldsLayoutItem;
+use crate::swipc::layout::{FieldsLayoutItem, TypeLayout};
 use crate::swipc::model::{
     BufferExtraAttrs, BufferTransferMode, CodegenContext, Command, Direction, HandleTransferType,
-    IntType, Interface, Namespace, NamespacedIdent, NominalType, Struct, StructField, Value,
+    IntType, Interface, Namespace, NamespacedIdent, NominalType, Struct, StructField,
+    StructuralType, Value, Version,
 };
 use crate::swipc::util::PaddingHelper;
 use arcstr::ArcStr;
@@ -47,6 +48,12 @@ fn imp_pooled_handle() -> Tokens {
     quote!($imp)
 }
 
+fn imp_session_handle() -> Tokens {
+    let imp = rust::import("horizon_ipc::cmif", "SessionHandle");
+
+    quote!($imp)
+}
+
 fn imp_error_code() -> Tokens {
     let imp = rust::import("horizon_error", "ErrorCode");
 
@@ -125,12 +132,48 @@ fn imp_get_ipc_buffer_ptr() -> Tokens {
     quote!($imp)
 }
 
+fn imp_pointer_buffer_size() -> Tokens {
+    let imp = rust::import("horizon_ipc::cmif::control", "pointer_buffer_size");
+
+    quote!($imp)
+}
+
 fn imp_command_type() -> Tokens {
     let imp = rust::import("horizon_ipc::cmif", "CommandType");
 
     quote!($imp)
 }
 
+fn imp_invalid_response_value() -> Tokens {
+    let imp = rust::import("horizon_ipc::cmif", "INVALID_RESPONSE_VALUE");
+
+    quote!($imp)
+}
+
+fn imp_incoming_request() -> Tokens {
+    let imp = rust::import("horizon_ipc::server", "IncomingRequest");
+
+    quote!($imp)
+}
+
+fn imp_dispatch() -> Tokens {
+    let imp = rust::import("horizon_ipc::server", "dispatch");
+
+    quote!($imp)
+}
+
+fn imp_write_response() -> Tokens {
+    let imp = rust::import("horizon_ipc::server", "write_response");
+
+    quote!($imp)
+}
+
+fn imp_unknown_command_id() -> Tokens {
+    let imp = rust::import("horizon_ipc::server", "UNKNOWN_COMMAND_ID");
+
+    quote!($imp)
+}
+
 #[derive(Clone)]
 enum BufferSource {
     /// We have a byte slice in scope that should be converted to a buffer
@@ -152,6 +195,19 @@ struct Buffer {
     extra_attrs: BufferExtraAttrs,
 }
 
+impl Buffer {
+    /// The local telling whether an auto-select buffer is sent via the pointer buffer, see [make_auto_select_choices]
+    fn in_pointer_buffer_var(&self) -> String {
+        let name = match &self.source {
+            BufferSource::ByteSlice(name)
+            | BufferSource::TypedUninitVariable(name)
+            | BufferSource::TypedReference(name)
+            | BufferSource::TypedSlice(name) => name,
+        };
+        format!("{}_in_pointer_buffer", name)
+    }
+}
+
 enum RawDataInSource {
     Local,
     PidPlaceholder,
@@ -165,7 +221,10 @@ struct RawDataIn {
 
 struct RawDataOut {
     name: ArcStr,
+    /// The type on the wire, the underlying int for enums
     ty: NominalType,
+    /// The enum type the value is decoded to, the server can send an invalid value so it's not read as is
+    enum_ty: Option<NominalType>,
 }
 
 struct HandleIn {
@@ -177,6 +236,8 @@ struct HandleOut {
     name: ArcStr,
     transfer_type: HandleTransferType,
     transform: HandleTransformType,
+    /// The server is allowed to not send this handle, it's then returned as `None`
+    optional: bool,
 }
 
 enum HandleTransformType {
@@ -188,6 +249,8 @@ struct CommandInterfaceInfo {
     args: Vec<(ArcStr, Tokens)>,
     results: Vec<(ArcStr, Tokens)>,
     uninit_vars: Vec<(ArcStr, Tokens)>,
+    /// Element types of the typed array buffers, with their layout in the SwIPC definition
+    array_elements: Vec<(Tokens, TypeLayout)>,
 }
 
 struct CommandWireFormatInfo {
@@ -210,6 +273,10 @@ impl CommandWireFormatInfo {
         !self.handles_out.is_empty()
     }
 
+    pub fn has_optional_handle_out(&self) -> bool {
+        self.handles_out.iter().any(|h| h.optional)
+    }
+
     fn get_buffers(&self, mut filter: impl FnMut(&Buffer) -> bool) -> Vec<Buffer> {
         self.buffers
             .iter()
@@ -218,15 +285,25 @@ impl CommandWireFormatInfo {
             .collect::<Vec<_>>()
     }
 
+    /// Includes the auto-select buffers, as those are sent with both descriptors
     pub fn in_pointer_buffers(&self) -> Vec<Buffer> {
         self.get_buffers(|b| {
-            b.direction == Direction::In && b.transfer_mode == BufferTransferMode::Pointer
+            b.direction == Direction::In
+                && matches!(
+                    b.transfer_mode,
+                    BufferTransferMode::Pointer | BufferTransferMode::AutoSelect
+                )
         })
     }
 
+    /// Includes the auto-select buffers, as those are sent with both descriptors
     pub fn out_pointer_buffers(&self) -> Vec<Buffer> {
         self.get_buffers(|b| {
-            b.direction == Direction::Out && b.transfer_mode == BufferTransferMode::Pointer
+            b.direction == Direction::Out
+                && matches!(
+                    b.transfer_mode,
+                    BufferTransferMode::Pointer | BufferTransferMode::AutoSelect
+                )
         })
     }
 
@@ -237,18 +314,39 @@ impl CommandWireFormatInfo {
             .count()
     }
 
+    /// Includes the auto-select buffers, as those are sent with both descriptors
     pub fn in_map_alias_buffers(&self) -> Vec<Buffer> {
         self.get_buffers(|b| {
-            b.direction == Direction::In && b.transfer_mode == BufferTransferMode::MapAlias
+            b.direction == Direction::In
+                && matches!(
+                    b.transfer_mode,
+                    BufferTransferMode::MapAlias | BufferTransferMode::AutoSelect
+                )
         })
     }
 
+    /// Includes the auto-select buffers, as those are sent with both descriptors
     pub fn out_map_alias_buffers(&self) -> Vec<Buffer> {
         self.get_buffers(|b| {
-            b.direction == Direction::Out && b.transfer_mode == BufferTransferMode::MapAlias
+            b.direction == Direction::Out
+                && matches!(
+                    b.transfer_mode,
+                    BufferTransferMode::MapAlias | BufferTransferMode::AutoSelect
+                )
         })
     }
 
+    /// The command has no buffers and no handles or pid to send, so its request headers are constant
+    pub fn is_static(&self) -> bool {
+        self.buffers.is_empty() && !self.has_in_special_header()
+    }
+
+    pub fn has_auto_select_buffers(&self) -> bool {
+        self.buffers
+            .iter()
+            .any(|b| b.transfer_mode == BufferTransferMode::AutoSelect)
+    }
+
     pub fn in_copy_handles(&self) -> usize {
         self.handles_in
             .iter()
@@ -303,6 +401,7 @@ fn collect_command_info(
     let mut args = Vec::new();
     let mut results = Vec::new();
     let mut uninit_vars = Vec::new();
+    let mut array_elements = Vec::new();
 
     let mut buffers = Vec::new();
     let mut raw_data_in = Vec::new();
@@ -375,9 +474,17 @@ fn collect_command_info(
                         fixed_size: true,
                     });
                 } else {
-                    raw_data_out.push(RawDataOut {
-                        name: name.clone(),
-                        ty: ty.clone(),
+                    raw_data_out.push(match struct_ty {
+                        StructuralType::Enum(e) => RawDataOut {
+                            name: name.clone(),
+                            ty: NominalType::Int(e.base_type),
+                            enum_ty: Some(ty.clone()),
+                        },
+                        _ => RawDataOut {
+                            name: name.clone(),
+                            ty: ty.clone(),
+                            enum_ty: None,
+                        },
                     })
                 }
                 results.push((
@@ -407,6 +514,7 @@ fn collect_command_info(
                         name: name.clone(),
                         transfer_type: HandleTransferType::Move,
                         transform: HandleTransformType::Interface(interface_name.clone()),
+                        optional: false,
                     });
 
                     results.push((
@@ -419,6 +527,31 @@ fn collect_command_info(
                     todo!("Handling return of unknown object type")
                 }
             }
+            Value::OptionalOutObject(interface_name, _) => {
+                if is_domain {
+                    todo!("Domains not implemented")
+                }
+
+                let interface = ctx.resolve_interface(interface_name);
+
+                if interface.is_domain {
+                    todo!("Domains not implemented")
+                }
+
+                handles_out.push(HandleOut {
+                    name: name.clone(),
+                    transfer_type: HandleTransferType::Move,
+                    transform: HandleTransformType::Interface(interface_name.clone()),
+                    optional: true,
+                });
+
+                results.push((
+                    name,
+                    quote! {
+                        Option<$(make_interface_reference(namespace, interface_name))>
+                    },
+                ))
+            }
             &Value::InHandle(transfer_type) => {
                 // TODO: we probably want to distinguish between Move and Copy handles here
                 // by taking in owning or referencing handle types
@@ -439,6 +572,7 @@ fn collect_command_info(
                     name: name.clone(),
                     transfer_type,
                     transform: HandleTransformType::Owned,
+                    optional: false,
                 });
 
                 results.push((
@@ -448,6 +582,21 @@ fn collect_command_info(
                     },
                 ));
             }
+            &Value::OptionalOutHandle(transfer_type) => {
+                handles_out.push(HandleOut {
+                    name: name.clone(),
+                    transfer_type,
+                    transform: HandleTransformType::Owned,
+                    optional: true,
+                });
+
+                results.push((
+                    name,
+                    quote! {
+                        Option<$(imp_owned_handle())>
+                    },
+                ));
+            }
             Value::InArray(ty, transfer_mode) => {
                 let struct_ty = ctx.resolve_type(ty);
 
@@ -460,10 +609,13 @@ fn collect_command_info(
                     extra_attrs: BufferExtraAttrs::None,
                 });
 
+                let ty_tok = make_nominal_type(namespace, ty);
+                array_elements.push((ty_tok.clone(), struct_ty.layout(ctx)));
+
                 args.push((
                     name,
                     quote! {
-                        &[$(make_nominal_type(namespace, ty))]
+                        &[$ty_tok]
                     },
                 ));
             }
@@ -479,10 +631,13 @@ fn collect_command_info(
                     extra_attrs: BufferExtraAttrs::None,
                 });
 
+                let ty_tok = make_nominal_type(namespace, ty);
+                array_elements.push((ty_tok.clone(), struct_ty.layout(ctx)));
+
                 args.push((
                     name,
                     quote! {
-                        &mut [$(make_nominal_type(namespace, ty))]
+                        &mut [$ty_tok]
                     },
                 ));
             }
@@ -529,12 +684,20 @@ fn collect_command_info(
     assert!(buffers.len() <= 8, "Methods must take in <= 8 Buffers");
     assert!(handles_in.len() <= 8, "Methods must take in <= 8 Handles");
     assert!(handles_out.len() <= 8, "Methods must output <= 8 Handles");
+    // when the handle is not sent, the response has no special header and the layout shifts,
+    //  so we can only handle it when there is nothing else to read from the response
+    assert!(
+        !handles_out.iter().any(|h| h.optional)
+            || (handles_out.len() == 1 && results.len() == 1 && raw_data_out.is_empty()),
+        "Optional output handles are supported only as the sole output of a command"
+    );
 
     (
         CommandInterfaceInfo {
             args,
             results,
             uninit_vars,
+            array_elements,
         },
         CommandWireFormatInfo {
             is_domain,
@@ -549,6 +712,34 @@ fn collect_command_info(
     )
 }
 
+/// The parts of the wire format of a command that are listed in the server-side dumps,
+///  see [crosscheck](crate::swipc::crosscheck)
+pub struct CommandWireSummary {
+    pub in_bytes: u64,
+    pub out_bytes: u64,
+    /// Directions of the buffers, in the order they are declared
+    pub buffer_directions: Vec<Direction>,
+}
+
+pub fn command_wire_summary(
+    ctx: &CodegenContext,
+    interface: &Interface,
+    command: &Command,
+) -> CommandWireSummary {
+    let (_, w_info) = collect_command_info(
+        interface.name.namespace(),
+        ctx,
+        interface.is_domain,
+        command,
+    );
+
+    CommandWireSummary {
+        in_bytes: w_info.in_raw_data_struct().layout(ctx).size(),
+        out_bytes: w_info.out_raw_data_struct().layout(ctx).size(),
+        buffer_directions: w_info.buffers.iter().map(|b| b.direction.clone()).collect(),
+    }
+}
+
 fn raw_data_struct(items: impl Iterator<Item = (ArcStr, NominalType)>) -> Struct {
     let s = Struct::try_new(
         NamespacedIdent::new(Arc::new(Vec::new()), arcstr::literal!("RawData")),
@@ -813,6 +1004,36 @@ fn response_sizes(ctx: &CodegenContext, w_info: &CommandWireFormatInfo) -> Respo
     }
 }
 
+/// Makes the consts with the handle counts put into the special header of the `message` struct,
+///  and checks at compile time that its handle fields take exactly the space for them
+///
+/// `next_field` is the field following the handles
+fn make_handle_counts(
+    message: &str,
+    handle_fields: &[String],
+    next_field: &str,
+    num_copy_handles: usize,
+    num_move_handles: usize,
+) -> Tokens {
+    let prefix = message.to_case(Case::UpperSnake);
+    let num_copy_name = format!("{}_NUM_COPY_HANDLES", prefix);
+    let num_move_name = format!("{}_NUM_MOVE_HANDLES", prefix);
+
+    quote! {
+        const $(&num_copy_name): u32 = $num_copy_handles;
+        const $(&num_move_name): u32 = $num_move_handles;
+        $(if let Some(first_field) = handle_fields.first() {
+            _comment_!($(quoted(format!("Compiler time {} handle count check", message.to_lowercase()))));
+            const _: () = if ::core::mem::offset_of!($message, $next_field)
+                - ::core::mem::offset_of!($message, $first_field)
+                != 4 * ($(&num_copy_name) + $(&num_move_name)) as usize
+            {
+                panic!("Handle fields don't match the special header")
+            };
+        })
+    }
+}
+
 fn make_request_struct(
     namespace: &Namespace,
     ctx: &CodegenContext,
@@ -852,8 +1073,19 @@ fn make_request_struct(
 
     let raw_data_word_padding = (4 - raw_data_size % 4) % 4;
 
+    // the first field after the handles
+    let next_field = if !in_pointer_buffers.is_empty() {
+        "in_pointer_desc_0"
+    } else if !in_map_aliases.is_empty() {
+        "in_map_alias_desc_0"
+    } else if !out_map_aliases.is_empty() {
+        "out_map_alias_desc_0"
+    } else {
+        "pre_padding"
+    };
+
     let r: Tokens = quote! {
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: $(imp_hipc_header()),
             $(if w_info.has_in_special_header() {
@@ -895,6 +1127,16 @@ fn make_request_struct(
 
         _comment_!("Compiler time request size check");
         let _ = ::core::mem::transmute::<Request, [u8; $(request_size)]>;
+
+        $(if w_info.has_in_special_header() {
+            $(make_handle_counts(
+                "Request",
+                &handles_in.iter().map(|h| format!("handle_{}", h.name)).collect::<Vec<_>>(),
+                &next_field,
+                w_info.in_copy_handles(),
+                w_info.in_move_handles(),
+            ))
+        })
     };
 
     r
@@ -933,8 +1175,15 @@ fn make_response_struct(
 
     let raw_data_word_padding = (4 - (raw_data_size % 4)) % 4;
 
+    // the first field after the handles
+    let next_field = if !out_pointer_buffers.is_empty() {
+        "in_pointer_desc_0"
+    } else {
+        "pre_padding"
+    };
+
     let r: Tokens = quote! {
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: $(imp_hipc_header()),
             $(if w_info.has_out_special_header() {
@@ -956,6 +1205,16 @@ fn make_response_struct(
 
         _comment_!("Compiler time request size check");
         let _ = ::core::mem::transmute::<Response, [u8; $response_size]>;
+
+        $(if w_info.has_out_special_header() {
+            $(make_handle_counts(
+                "Response",
+                &handles_out.iter().map(|h| format!("handle_{}", h.name)).collect::<Vec<_>>(),
+                next_field,
+                w_info.out_copy_handles(),
+                w_info.out_move_handles(),
+            ))
+        })
     };
 
     r
@@ -1030,14 +1289,28 @@ fn make_error_return(ctx: &CodegenContext, w_info: &CommandWireFormatInfo) -> To
                     return Err(cmif.result)
                 }
             } else {
-                return Err(
-                    unsafe {
+                $(if w_info.has_optional_handle_out() {
+                    let result = unsafe {
                         ::core::ptr::read(
                             ipc_buffer_ptr.offset($cmif_alternative_result_offset)
                                 as *const $(imp_error_code())
                         )
+                    };
+                    if result.is_failure() {
+                        return Err(result)
                     }
-                )
+                    _comment_!("the server did not return the optional handle");
+                    return Ok(None)
+                } else {
+                    return Err(
+                        unsafe {
+                            ::core::ptr::read(
+                                ipc_buffer_ptr.offset($cmif_alternative_result_offset)
+                                    as *const $(imp_error_code())
+                            )
+                        }
+                    )
+                })
             }
         })
     } as Tokens)
@@ -1055,9 +1328,6 @@ fn make_check_response(ctx: &CodegenContext, w_info: &CommandWireFormatInfo) ->
 
     let has_special_header = w_info.has_out_special_header();
 
-    let num_copy_handles = w_info.out_copy_handles();
-    let num_move_handles = w_info.out_move_handles();
-
     (quote! {
         debug_assert_eq!(hipc.num_in_pointers(), $num_in_pointers);
         debug_assert_eq!(hipc.num_in_map_aliases(), 0);
@@ -1072,19 +1342,19 @@ fn make_check_response(ctx: &CodegenContext, w_info: &CommandWireFormatInfo) ->
 
         $(if has_special_header {
             debug_assert_eq!(special_header.send_pid(), 0);
-            debug_assert_eq!(special_header.num_copy_handles(), $num_copy_handles);
-            debug_assert_eq!(special_header.num_move_handles(), $num_move_handles);
+            debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+            debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
         })
 
         debug_assert_eq!(cmif.magic, $(imp_cmif_out_header())::MAGIC);
     } as Tokens)
 }
 
-fn make_buffer_size(buffer: &Buffer) -> Tokens {
+fn make_buffer_len(buffer: &Buffer) -> Tokens {
     (match &buffer.source {
         BufferSource::TypedUninitVariable(name) => {
             quote! {
-                ::core::mem::size_of_val(&$(name.as_str())) as u16
+                ::core::mem::size_of_val(&$(name.as_str()))
             }
         }
 
@@ -1092,12 +1362,37 @@ fn make_buffer_size(buffer: &Buffer) -> Tokens {
         | BufferSource::TypedSlice(name)
         | BufferSource::ByteSlice(name) => {
             quote! {
-                ::core::mem::size_of_val($(name.as_str())) as u16
+                ::core::mem::size_of_val($(name.as_str()))
             }
         }
     }) as Tokens
 }
 
+fn make_buffer_size(buffer: &Buffer) -> Tokens {
+    quote! {
+        $(make_buffer_len(buffer)) as u16
+    }
+}
+
+/// Decides which of the auto-select buffers are sent via the pointer buffer of the session
+///
+/// The buffers share the pointer buffer, so each one is checked against the space left by the ones before it.
+fn make_auto_select_choices(w_info: &CommandWireFormatInfo) -> Tokens {
+    let buffers = w_info.get_buffers(|b| b.transfer_mode == BufferTransferMode::AutoSelect);
+
+    quote! {
+        let $(if buffers.len() > 1 => mut) pointer_buffer_space = $(imp_pointer_buffer_size())(*self.handle.get());
+        $(for (i, b) in buffers.iter().enumerate() {
+            let $(b.in_pointer_buffer_var()) = $(make_buffer_len(b)) <= pointer_buffer_space;
+            $(if i + 1 < buffers.len() {
+                if $(b.in_pointer_buffer_var()) {
+                    pointer_buffer_space -= $(make_buffer_len(b));
+                }
+            })
+        })
+    }
+}
+
 enum DescriptorType {
     MapAlias,
     InPointer,
@@ -1147,29 +1442,47 @@ fn make_buffer_desc(ty: DescriptorType, index: usize, buffer: &Buffer) -> Tokens
         })
     };
 
+    // auto-select buffers are sent with both a pointer and a map alias descriptor,
+    //  only one of them is filled in depending on whether the buffer fits into the pointer buffer
+    //  of the session (decided at the start of the command, see `make_auto_select_choices`)
+    let is_auto_select = buffer.transfer_mode == BufferTransferMode::AutoSelect;
+    let in_pointer_buffer = buffer.in_pointer_buffer_var();
+
     (quote! {
         $(match ty {
             DescriptorType::MapAlias => {
-                $alias_desc::new(
-                    $extra_attrs,
-                    $addr,
-                    $size
-                )
+                $(if is_auto_select {
+                    if $(in_pointer_buffer.as_str()) {
+                        $(alias_desc.clone())::new($(extra_attrs.clone()), 0, 0)
+                    } else {
+                        $alias_desc::new($extra_attrs, $addr, $size)
+                    }
+                } else {
+                    $alias_desc::new(
+                        $extra_attrs,
+                        $addr,
+                        $size
+                    )
+                })
             }
             DescriptorType::InPointer => {
-                $(if buffer.transfer_mode == BufferTransferMode::AutoSelect {
-                    // TODO: use pointer transfer mode if enough space in pointer buffer
-                    // need to decide that at runtime though
-                    $ptr_in_desc::new($index, 0, 0)
+                $(if is_auto_select {
+                    if $(in_pointer_buffer.as_str()) {
+                        $(ptr_in_desc.clone())::new($index, $(addr.clone()), $(size.clone()))
+                    } else {
+                        $ptr_in_desc::new($index, 0, 0)
+                    }
                 } else {
                     $ptr_in_desc::new($index, $addr, $size)
                 })
             }
             DescriptorType::OutPointer => {
-                $(if buffer.transfer_mode == BufferTransferMode::AutoSelect {
-                    // TODO: use pointer transfer mode if enough space in pointer buffer
-                    // need to decide that at runtime though
-                    $ptr_out_desc::new(0, 0)
+                $(if is_auto_select {
+                    if $(in_pointer_buffer.as_str()) {
+                        $(ptr_out_desc.clone())::new($(addr.clone()), $(size.clone()))
+                    } else {
+                        $ptr_out_desc::new(0, 0)
+                    }
                 } else {
                     $ptr_out_desc::new($addr, $size)
                 })
@@ -1178,21 +1491,13 @@ fn make_buffer_desc(ty: DescriptorType, index: usize, buffer: &Buffer) -> Tokens
     } as Tokens)
 }
 
-fn make_request(ctx: &CodegenContext, w_info: &CommandWireFormatInfo) -> Tokens {
-    let &CommandWireFormatInfo {
-        should_pass_pid,
-        command_id,
-        ref handles_in,
-        ..
-    } = w_info;
-
+/// The HIPC and CMIF headers of the request, these depend only on the command signature
+fn make_request_headers(ctx: &CodegenContext, w_info: &CommandWireFormatInfo) -> (Tokens, Tokens) {
     let in_pointer_buffers = w_info.in_pointer_buffers();
     let out_pointer_buffers = w_info.out_pointer_buffers();
     let in_map_aliases = w_info.in_map_alias_buffers();
     let out_map_aliases = w_info.out_map_alias_buffers();
 
-    let out_pointer_sizes_count = w_info.out_pointer_sizes_count();
-
     // switchbrew:
     // > If it has value 0, the C descriptor functionality is disabled.
     // > If it has value 1, there is an "inlined" C buffer after the raw data.
@@ -1215,23 +1520,59 @@ fn make_request(ctx: &CodegenContext, w_info: &CommandWireFormatInfo) -> Tokens
         2 + out_pointer_buffers.len()
     };
 
+    let hipc = quote! {
+        $(imp_hipc_header())::new(
+            $(imp_command_type())::Request,
+            $(in_pointer_buffers.len()),
+            $(in_map_aliases.len()),
+            $(out_map_aliases.len()),
+            0, // num_inout_map_aliases
+            $(sizes.data_size / 4), // num_data_words
+            $(out_pointer_mode),
+            0, // recv_list_offset
+            $(if w_info.has_in_special_header() {
+                true
+            } else {
+                false
+            }),
+        )
+    };
+    let cmif = quote! {
+        $(imp_cmif_in_header()) {
+            magic: $(imp_cmif_in_header())::MAGIC,
+            version: 1,
+            command_id: $(w_info.command_id),
+            token: 0,
+        }
+    };
+
+    (hipc, cmif)
+}
+
+fn make_request(ctx: &CodegenContext, w_info: &CommandWireFormatInfo) -> Tokens {
+    let &CommandWireFormatInfo {
+        should_pass_pid,
+        ref handles_in,
+        ..
+    } = w_info;
+
+    let in_pointer_buffers = w_info.in_pointer_buffers();
+    let out_pointer_buffers = w_info.out_pointer_buffers();
+    let in_map_aliases = w_info.in_map_alias_buffers();
+    let out_map_aliases = w_info.out_map_alias_buffers();
+
+    let out_pointer_sizes_count = w_info.out_pointer_sizes_count();
+
+    // the headers of static commands are consts defined in the command body
+    let (hipc, cmif) = if w_info.is_static() {
+        (quote!(HIPC_HEADER), quote!(CMIF_HEADER))
+    } else {
+        make_request_headers(ctx, w_info)
+    };
+
     let r: Tokens = quote! {
         Request {
-            hipc: $(imp_hipc_header())::new(
-                $(imp_command_type())::Request,
-                $(in_pointer_buffers.len()),
-                $(in_map_aliases.len()),
-                $(out_map_aliases.len()),
-                0, // num_inout_map_aliases
-                $(sizes.data_size / 4), // num_data_words
-                $(out_pointer_mode),
-                0, // recv_list_offset
-                $(if w_info.has_in_special_header() {
-                    true
-                } else {
-                    false
-                }),
-            ),
+            hipc: $hipc,
             $(if w_info.has_in_special_header() {
                 special_header: $(imp_hipc_special_header())::new(
                     $(if should_pass_pid {
@@ -1239,8 +1580,8 @@ fn make_request(ctx: &CodegenContext, w_info: &CommandWireFormatInfo) -> Tokens
                     } else {
                         false
                     }),
-                    $(w_info.in_copy_handles()),
-                    $(w_info.in_move_handles()),
+                    REQUEST_NUM_COPY_HANDLES,
+                    REQUEST_NUM_MOVE_HANDLES,
                 ),
                 $(if should_pass_pid => pid_placeholder: 0,)
                 $(for h in handles_in {
@@ -1261,19 +1602,22 @@ fn make_request(ctx: &CodegenContext, w_info: &CommandWireFormatInfo) -> Tokens
             })
 
             pre_padding: Default::default(),
-            cmif: $(imp_cmif_in_header()) {
-                magic: $(imp_cmif_in_header())::MAGIC,
-                version: 1,
-                command_id: $command_id,
-                token: 0,
-            },
+            cmif: $cmif,
             raw_data: data_in,
             raw_data_word_padding: Default::default(),
             post_padding: Default::default(),
 
             $(for (i, b) in out_pointer_buffers.iter().enumerate() {
                 $(if !b.fixed_size {
-                    $(format!("out_pointer_size_{}", i)): $(make_buffer_size(b)),
+                    $(format!("out_pointer_size_{}", i)): $(if b.transfer_mode == BufferTransferMode::AutoSelect {
+                        if $(b.in_pointer_buffer_var()) {
+                            $(make_buffer_size(b))
+                        } else {
+                            0
+                        }
+                    } else {
+                        $(make_buffer_size(b))
+                    }),
                 })
             })
             $(if out_pointer_sizes_count % 2 != 0 {
@@ -1290,6 +1634,19 @@ fn make_request(ctx: &CodegenContext, w_info: &CommandWireFormatInfo) -> Tokens
     r
 }
 
+/// Which variant of the command the body is made for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommandFlavor {
+    /// The plain command, returning the results by value
+    Sync,
+    /// The `_async` variant, see [make_async_command]
+    Async,
+    /// The `_in_place` variant, see [make_in_place_command]
+    InPlace,
+    /// The `_send` variant, see [make_send_command]
+    Send,
+}
+
 fn make_command_body(
     namespace: &Namespace,
     ctx: &CodegenContext,
@@ -1297,7 +1654,12 @@ fn make_command_body(
     command: &Command,
     i_info: &CommandInterfaceInfo,
     w_info: &CommandWireFormatInfo,
+    flavor: CommandFlavor,
 ) -> Tokens {
+    let is_async = flavor == CommandFlavor::Async;
+    let in_place = flavor == CommandFlavor::InPlace;
+    let is_send = flavor == CommandFlavor::Send;
+
     let fq_command_name = format!(
         "{}::{}::{}",
         namespace.join("::"),
@@ -1305,10 +1667,14 @@ fn make_command_body(
         command.name
     );
     let fq_command_name = &fq_command_name;
+    // the ipc hooks dump only this much of the IPC buffer
+    let request_size = request_sizes(ctx, w_info).request_size;
+    let response_size = response_sizes(ctx, w_info).response_size;
 
     let CommandInterfaceInfo {
         uninit_vars,
         results,
+        array_elements,
         ..
     } = i_info;
     let CommandWireFormatInfo {
@@ -1322,6 +1688,42 @@ fn make_command_body(
         should_pass_pid: _,
     } = w_info;
 
+    let (static_hipc_header, static_cmif_header) = make_request_headers(ctx, w_info);
+
+    // for the `_send` variant this becomes the decoding closure, so it can use only the items defined in the body
+    let read_response: Tokens = quote! {
+        $(if in_place {
+            // only the headers are read, the raw data is copied to its place at the end
+            // SAFETY: The pointer should be valid
+            let (hipc, cmif) = unsafe {
+                (
+                    ::core::ptr::read_unaligned(ipc_buffer_ptr as *const $(imp_hipc_header())),
+                    ::core::ptr::read_unaligned(
+                        ipc_buffer_ptr.add(::core::mem::offset_of!(Response, cmif))
+                            as *const $(imp_cmif_out_header())
+                    ),
+                )
+            };
+        } else {
+            // SAFETY: The pointer should be valid
+            let $(make_response_pattern(ctx, w_info))
+                = unsafe {
+                    ::core::ptr::read(
+                    ipc_buffer_ptr as *const _,
+                    )
+                };
+        })
+
+        $(make_error_return(ctx, w_info))
+        $(make_check_response(ctx, w_info))
+
+        $(if in_place {
+            $(make_in_place_return(results))
+        } else {
+            $(make_results_return(namespace, uninit_vars, results, raw_data_out, handles_out))
+        })
+    };
+
     let r: Tokens = quote! {
         // defines a data_in variable
         $(make_raw_data_in(namespace, ctx, &raw_data_in))
@@ -1334,9 +1736,42 @@ fn make_command_body(
             let $(name.as_str()) = $(imp_maybe_uninit())::<$ty>::uninit();
         })
 
-        let ipc_buffer_ptr = unsafe {
-            $(imp_get_ipc_buffer_ptr())()
-        };
+        // the server reads and writes the array elements with the layout from the definition,
+        //  so the rust type must have the same stride and can't require a stricter alignment
+        $(for (ty, layout) in array_elements {
+            _comment_!("Compiler time array element layout check");
+            let _ = ::core::mem::transmute::<$ty, [u8; $(layout.size())]>;
+            const _: () = if ::core::mem::align_of::<$ty>() > $(layout.alignment()) {
+                panic!("Array element is over-aligned")
+            };
+        })
+
+        $(if w_info.has_auto_select_buffers() {
+            // needs to be done before we start writing the request, as it might do IPC itself
+            $(make_auto_select_choices(w_info))
+        })
+
+        $(if is_async {
+            // the TLS buffer can't be held across an await, so the message goes to a buffer of our own
+            let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+            let ipc_buffer_ptr = async_request.buffer_ptr();
+        } else {
+            $(if is_send {
+                // the reply lands in the buffer after we have returned, so it has to stay in place
+                let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+                let ipc_buffer_ptr = async_request.buffer_ptr();
+            } else {
+                let ipc_buffer_ptr = unsafe {
+                    $(imp_get_ipc_buffer_ptr())()
+                };
+            })
+        })
+
+        $(if w_info.is_static() {
+            // nothing in the headers depends on the arguments, so they are built at compile time
+            const HIPC_HEADER: $(imp_hipc_header()) = $(&static_hipc_header);
+            const CMIF_HEADER: $(imp_cmif_in_header()) = $(&static_cmif_header);
+        })
 
         // SAFETY: The pointer should be valid
         unsafe {
@@ -1346,23 +1781,70 @@ fn make_command_body(
             )
         };
 
-        {
-            let handle = self.handle.get();
-            crate::pre_ipc_hook($(quoted(fq_command_name)), *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook($(quoted(fq_command_name)), *handle);
-        }
+        $(if is_async {
+            // the ipc hooks look at the TLS buffer, so they are not called here
+            {
+                let handle = self.handle.get();
+                async_request.send(executor, *handle).await?;
+            }
+            let ipc_buffer_ptr = async_request.buffer_ptr();
+            $(&read_response)
+        } else {
+            $(if is_send {
+                // the ipc hooks are not called here either, same as for the `_async` variant
+                let handle = self.handle.get();
+                horizon_ipc::async_ipc::PendingResponse::send(async_request, *handle, |ipc_buffer_ptr| {
+                    $(&read_response)
+                })
+            } else {
+                {
+                    let handle = self.handle.get();
+                    crate::pre_ipc_hook($(quoted(fq_command_name)), *handle, $request_size);
+                    horizon_svc::send_sync_request(*handle)?;
+                    crate::post_ipc_hook($(quoted(fq_command_name)), *handle, $response_size);
+                }
+                $(&read_response)
+            })
+        })
+    };
 
-        // SAFETY: The pointer should be valid
-        let $(make_response_pattern(ctx, w_info))
-            = unsafe {
-                ::core::ptr::read(
-                ipc_buffer_ptr as *const _,
-                )
-            };
+    r
+}
 
-        $(make_error_return(ctx, w_info))
-        $(make_check_response(ctx, w_info))
+/// Copies the raw data of the response to the place given by the caller, see [in_place_result]
+fn make_in_place_return(results: &[(ArcStr, Tokens)]) -> Tokens {
+    let [(name, _)] = results else {
+        unreachable!("BUG: in-place command with multiple results")
+    };
+
+    quote! {
+        // SAFETY: The pointer should be valid, the raw data was written by the server
+        unsafe {
+            ::core::ptr::copy_nonoverlapping(
+                ipc_buffer_ptr.add(::core::mem::offset_of!(Response, raw_data)),
+                $(name.as_str()).as_mut_ptr() as *mut u8,
+                ::core::mem::size_of_val($(name.as_str())),
+            )
+        };
+
+        Ok(unsafe { $(name.as_str()).assume_init_mut() })
+    }
+}
+
+fn make_results_return(
+    namespace: &Namespace,
+    uninit_vars: &[(ArcStr, Tokens)],
+    results: &[(ArcStr, Tokens)],
+    raw_data_out: &[RawDataOut],
+    handles_out: &[HandleOut],
+) -> Tokens {
+    quote! {
+        $(for d in raw_data_out {
+            $(if let Some(enum_ty) = &d.enum_ty {
+                let $(d.name.as_str()) = $(make_nominal_type(namespace, enum_ty))::try_from($(d.name.as_str()))
+                    .map_err(|_| $(imp_invalid_response_value()))?;
+            })
+        })
 
         $(for (name, _) in uninit_vars {
             let $(name.as_str()) = unsafe { $(name.as_str()).assume_init() };
@@ -1375,11 +1857,14 @@ fn make_command_body(
                 },
                 HandleTransformType::Interface(interface) => {
                     let $(h.name.as_str()) =
-                        $(make_interface_reference(namespace, interface)) {
-                            handle: $(imp_owned_handle())::new($(h.name.as_str()))
-                        };
+                        $(make_interface_reference(namespace, interface))::new(
+                            $(imp_owned_handle())::new($(h.name.as_str()))
+                        );
                 }
             })
+            $(if h.optional {
+                let $(h.name.as_str()) = Some($(h.name.as_str()));
+            })
         })
 
         Ok(
@@ -1397,44 +1882,463 @@ fn make_command_body(
                 })
             })
         )
+    }
+}
+
+/// Out structs at least this large get an `_in_place` variant of the command, see [in_place_result]
+const IN_PLACE_MIN_SIZE: u64 = 0x40;
+
+/// Finds the result the `_in_place` variant of the command fills in place
+///
+/// Only the commands returning nothing but a single large struct have it:
+///  the struct is copied from the IPC buffer to the caller's `MaybeUninit`, instead of going through the stack.
+fn in_place_result<'a>(
+    ctx: &CodegenContext,
+    i_info: &'a CommandInterfaceInfo,
+    w_info: &CommandWireFormatInfo,
+) -> Option<&'a (ArcStr, Tokens)> {
+    let [result] = i_info.results.as_slice() else {
+        return None;
+    };
+    let [data] = w_info.raw_data_out.as_slice() else {
+        return None;
     };
+    if data.name != result.0 || !w_info.handles_out.is_empty() {
+        return None;
+    }
 
-    r
+    match ctx.resolve_type(&data.ty) {
+        StructuralType::Struct(s) if s.layout(ctx).size() >= IN_PLACE_MIN_SIZE => Some(result),
+        _ => None,
+    }
 }
 
-fn make_command(
+/// Makes the type wrapped in `Result` returned by the command
+fn make_return_type(i_info: &CommandInterfaceInfo) -> Tokens {
+    if let [(_, res)] = i_info.results.as_slice() {
+        quote!($res)
+    } else {
+        // TODO: doing this we lose names. This is not __that__ bad, but kinda meh...
+        quote! {
+            (
+                $(for (_, ty) in i_info.results.iter() join (,) => $ty)
+            )
+        }
+    }
+}
+
+/// Makes the `fn name(&self, args...) -> Result<...>` part of the command, shared by the interface struct and trait
+fn make_command_signature(command: &Command, i_info: &CommandInterfaceInfo) -> Tokens {
+    // we expect command names in PascalCase, but convert them to snake_case when converting to rust
+    make_command_signature_named(&command.name.to_case(Case::Snake), i_info)
+}
+
+fn make_command_signature_named(name: &str, i_info: &CommandInterfaceInfo) -> Tokens {
+    let return_type = make_return_type(i_info);
+
+    quote! {
+        fn $name(
+            &self,
+            $(for (name, ty) in &i_info.args join (,) => $(name.as_str()): $ty)
+        ) -> $(imp_result())<$return_type>
+    }
+}
+
+fn make_must_use(w_info: &CommandWireFormatInfo) -> Tokens {
+    // dropping a freshly received handle (or an object owning it) is most likely a bug
+    let must_use = !w_info.handles_out.is_empty();
+
+    quote! {
+        $(if must_use {
+            #[must_use = "the returned object owns a kernel handle"]
+        })
+    }
+}
+
+/// Makes the doc note about the firmware versions the command exists in, if it's limited by `@version`
+fn make_version_doc(command: &Command) -> Tokens {
+    let req = &command.version_req;
+    let note = if req.max.is_none() {
+        "The command doesn't exist on older firmware"
+    } else {
+        "The command doesn't exist on other firmware versions"
+    };
+
+    quote! {
+        $(if !req.is_any() {
+            #[doc = $(quoted(format!(" [{}] {}", req, note)))]
+        })
+    }
+}
+
+fn make_command(
     namespace: &Namespace,
     ctx: &CodegenContext,
     interface: &Interface,
     command: &Command,
     is_domain: bool,
+    track_caller: bool,
 ) -> Tokens {
     let (i_info, w_info) = collect_command_info(namespace, ctx, is_domain, command);
 
-    let return_type = if let [(_, res)] = i_info.results.as_slice() {
-        quote!($res) as Tokens
-    } else {
-        // TODO: doing this we lose names. This is not __that__ bad, but kinda meh...
-        quote! {
-            (
-                $(for (_, ty) in i_info.results.iter() join (,) => $ty)
-            )
+    if track_caller {
+        return make_tracked_command(namespace, ctx, interface, command, &i_info, &w_info);
+    }
+
+    quote! {
+        $(make_version_doc(command))
+        $(make_must_use(&w_info))
+        pub $(make_command_signature(command, &i_info)) {
+            $(make_command_body(namespace, ctx, interface, command, &i_info, &w_info, CommandFlavor::Sync))
         }
-    };
+    }
+}
 
-    // we expect command names in PascalCase, but convert them to snake_case when converting to rust
+/// Makes the command as a `#[track_caller]` wrapper reporting the errors with the location of its caller
+///
+/// The actual implementation goes to the private `fn <command>_untracked`
+fn make_tracked_command(
+    namespace: &Namespace,
+    ctx: &CodegenContext,
+    interface: &Interface,
+    command: &Command,
+    i_info: &CommandInterfaceInfo,
+    w_info: &CommandWireFormatInfo,
+) -> Tokens {
     let name = command.name.to_case(Case::Snake);
+    let untracked_name = format!("{}_untracked", name);
+    let fq_command_name = format!(
+        "{}::{}::{}",
+        namespace.join("::"),
+        interface.name.ident(),
+        command.name
+    );
+
     quote! {
-        pub fn $name(
+        $(make_version_doc(command))
+        $(make_must_use(w_info))
+        #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+        pub $(make_command_signature(command, i_info)) {
+            let res = self.$(&untracked_name)($(for (name, _) in &i_info.args join (, ) => $(name.as_str())));
+            if let Err(e) = &res {
+                crate::ipc_error_hook($(quoted(fq_command_name)), ::core::panic::Location::caller(), *e);
+            }
+            res
+        }
+        _blank_!();
+        #[inline(always)]
+        $(make_command_signature_named(&untracked_name, i_info)) {
+            $(make_command_body(namespace, ctx, interface, command, i_info, w_info, CommandFlavor::Sync))
+        }
+    }
+}
+
+/// Makes the `async fn <command>_async` variant of the command, waiting for the reply with an `IpcExecutor`
+fn make_async_command(
+    namespace: &Namespace,
+    ctx: &CodegenContext,
+    interface: &Interface,
+    command: &Command,
+    is_domain: bool,
+) -> Tokens {
+    let (i_info, w_info) = collect_command_info(namespace, ctx, is_domain, command);
+
+    let return_type = make_return_type(&i_info);
+    let name = format!("{}_async", command.name.to_case(Case::Snake));
+
+    quote! {
+        #[cfg(feature = "async-ipc")]
+        pub async fn $name<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
             &self,
+            executor: &E,
             $(for (name, ty) in &i_info.args join (,) => $(name.as_str()): $ty)
         ) -> $(imp_result())<$return_type> {
-            $(make_command_body(namespace, ctx, interface, command, &i_info, &w_info))
+            $(make_command_body(namespace, ctx, interface, command, &i_info, &w_info, CommandFlavor::Async))
         }
     }
 }
 
-pub fn gen_interface(tok: &mut TokenStorage, ctx: &CodegenContext, i: &Interface) {
+/// Makes the `fn <command>_send` variant of the command, returning a `PendingResponse` instead of waiting for the reply
+///
+/// Returns nothing for commands with buffers: the memory they point to would have to outlive the pending response
+fn make_send_command(
+    namespace: &Namespace,
+    ctx: &CodegenContext,
+    interface: &Interface,
+    command: &Command,
+    is_domain: bool,
+) -> Option<Tokens> {
+    let (i_info, w_info) = collect_command_info(namespace, ctx, is_domain, command);
+    if !w_info.buffers.is_empty() {
+        return None;
+    }
+
+    let return_type = make_return_type(&i_info);
+    let name = format!("{}_send", command.name.to_case(Case::Snake));
+
+    Some(quote! {
+        #[cfg(feature = "async-ipc")]
+        pub fn $name(
+            &self,
+            $(for (name, ty) in &i_info.args join (,) => $(name.as_str()): $ty)
+        ) -> $(imp_result())<horizon_ipc::async_ipc::PendingResponse<$return_type>> {
+            $(make_command_body(namespace, ctx, interface, command, &i_info, &w_info, CommandFlavor::Send))
+        }
+    })
+}
+
+/// Makes the `fn <command>_in_place` variant of the command, returning the large out struct in a caller-provided place
+///
+/// Returns nothing for commands without a suitable result, see [in_place_result]
+fn make_in_place_command(
+    namespace: &Namespace,
+    ctx: &CodegenContext,
+    interface: &Interface,
+    command: &Command,
+    is_domain: bool,
+) -> Option<Tokens> {
+    let (i_info, w_info) = collect_command_info(namespace, ctx, is_domain, command);
+    let (result_name, result_ty) = in_place_result(ctx, &i_info, &w_info)?;
+
+    let name = format!("{}_in_place", command.name.to_case(Case::Snake));
+
+    Some(quote! {
+        pub fn $name<'o>(
+            &self,
+            $(for (name, ty) in &i_info.args => $(name.as_str()): $ty,)
+            $(result_name.as_str()): &'o mut $(imp_maybe_uninit())<$result_ty>,
+        ) -> $(imp_result())<&'o mut $result_ty> {
+            $(make_command_body(namespace, ctx, interface, command, &i_info, &w_info, CommandFlavor::InPlace))
+        }
+    })
+}
+
+/// Makes the `unsafe fn <command>_raw` variant of the command, sending a payload built by the caller
+///
+/// Domain interfaces are not generated, so the requests are always framed for a plain session.
+/// The paths are spelled out, so that there are no unused imports without the feature
+fn make_raw_command(command: &Command) -> Tokens {
+    let name = format!("{}_raw", command.name.to_case(Case::Snake));
+
+    quote! {
+        #[doc = " Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`"]
+        #[doc = ""]
+        #[doc = " # Safety"]
+        #[doc = ""]
+        #[doc = " `raw_in` must be a valid payload for the command"]
+        #[cfg(feature = "raw-ipc")]
+        pub unsafe fn $name(&self, raw_in: &[u8], out: &mut [u8]) -> $(imp_result())<usize> {
+            horizon_ipc::cmif::raw_command::send_request(
+                *self.handle.get(),
+                horizon_ipc::cmif::domain::Framing::Session,
+                $(command.id),
+                raw_in,
+                out,
+            )
+        }
+    }
+}
+
+/// Makes the `<COMMAND>_REQUEST_SIZE` and `<COMMAND>_RESPONSE_SIZE` consts,
+/// useful to size the buffers for `send_sync_request_with_user_buffer`
+fn make_command_size_consts(
+    namespace: &Namespace,
+    ctx: &CodegenContext,
+    command: &Command,
+    is_domain: bool,
+) -> Tokens {
+    let (_, w_info) = collect_command_info(namespace, ctx, is_domain, command);
+
+    let name = command.name.to_case(Case::UpperSnake);
+
+    quote! {
+        pub const $(format!("{}_REQUEST_SIZE", name)): usize = $(request_sizes(ctx, &w_info).request_size);
+        pub const $(format!("{}_RESPONSE_SIZE", name)): usize = $(response_sizes(ctx, &w_info).response_size);
+    }
+}
+
+fn make_trait(namespace: &Namespace, ctx: &CodegenContext, i: &Interface) -> Tokens {
+    let name = make_ident(i.name.ident());
+    let name = &name;
+    let trait_name = format!("{}Api", i.name.ident());
+    let trait_name = &trait_name;
+
+    let commands = i
+        .commands
+        .iter()
+        .map(|command| {
+            let (i_info, w_info) = collect_command_info(namespace, ctx, i.is_domain, command);
+            (command, i_info, w_info)
+        })
+        .collect::<Vec<_>>();
+
+    quote! {
+        pub trait $trait_name {
+            $(for (command, i_info, w_info) in commands.iter() join (_blank_!();) {
+                $(make_must_use(w_info))
+                $(make_command_signature(command, i_info));
+            })
+        }
+
+        impl<S: $(imp_handle_storage())> $trait_name for $name<S> {
+            $(for (command, i_info, _) in commands.iter() join (_blank_!();) {
+                #[inline]
+                $(make_command_signature(command, i_info)) {
+                    Self::$(command.name.to_case(Case::Snake))(
+                        self,
+                        $(for (name, _) in &i_info.args join (,) => $(name.as_str()))
+                    )
+                }
+            })
+        }
+        _blank_!();
+    }
+}
+
+/// Whether the server dispatcher can handle the command
+///
+/// Only the raw data is decoded by the dispatcher for now, so commands passing buffers, handles,
+///  objects or the client pid are not supported. The inputs are read straight from the request,
+///  so they must not have invalid bit patterns (no bools or enums).
+fn is_server_supported(ctx: &CodegenContext, w_info: &CommandWireFormatInfo) -> bool {
+    w_info.buffers.is_empty()
+        && w_info.handles_in.is_empty()
+        && w_info.handles_out.is_empty()
+        && !w_info.should_pass_pid
+        && w_info.raw_data_in.iter().all(|d| is_pod_type(ctx, &d.ty))
+}
+
+/// Makes the match arm decoding the request, calling the handler and writing the response
+fn make_server_dispatch_arm(
+    namespace: &Namespace,
+    ctx: &CodegenContext,
+    command: &Command,
+    w_info: &CommandWireFormatInfo,
+) -> Tokens {
+    let name = command.name.to_case(Case::Snake);
+
+    let args = match w_info.raw_data_in.as_slice() {
+        [] => quote!(),
+        [data] => quote! {
+            let $(data.name.as_str()) = unsafe {
+                request.read_data::<$(make_nominal_type(namespace, &data.ty))>()
+            }?;
+        },
+        _ => quote! {
+            $(make_raw_data_struct(namespace, ctx, Direction::In, &w_info.in_raw_data_struct()))
+
+            let data_in = unsafe { request.read_data::<In>() }?;
+            $(for data in w_info.raw_data_in.iter() {
+                let $(data.name.as_str()) = data_in.$(data.name.as_str());
+            })
+        },
+    };
+
+    let response = match w_info.raw_data_out.as_slice() {
+        [] => quote! {
+            $(imp_dispatch())(|| self.$(&name)($(for d in w_info.raw_data_in.iter() join (,) => $(d.name.as_str()))))?;
+            $(imp_write_response())(&());
+        },
+        [data] => quote! {
+            let $(data.name.as_str()) = $(imp_dispatch())(|| self.$(&name)($(for d in w_info.raw_data_in.iter() join (,) => $(d.name.as_str()))))?;
+            $(imp_write_response())(&$(data.name.as_str()));
+        },
+        _ => {
+            let s = w_info.out_raw_data_struct();
+            let mut padding_helper = PaddingHelper::new();
+
+            quote! {
+                $(make_raw_data_struct(namespace, ctx, Direction::Out, &s))
+
+                let ($(for d in w_info.raw_data_out.iter() join (,) => $(d.name.as_str()))) =
+                    $(imp_dispatch())(|| self.$(&name)($(for d in w_info.raw_data_in.iter() join (,) => $(d.name.as_str()))))?;
+                $(imp_write_response())(&Out {
+                    $(for d in w_info.raw_data_out.iter() {
+                        $(if d.enum_ty.is_some() {
+                            $(d.name.as_str()): $(d.name.as_str()) as $(make_nominal_type(namespace, &d.ty)),
+                        } else {
+                            $(d.name.as_str()),
+                        })
+                    })
+                    $(for _ in s.paddings(ctx) {
+                        $(padding_helper.next_padding_name()): Default::default(),
+                    })
+                });
+            }
+        }
+    };
+
+    quote! {
+        $(w_info.command_id) => {
+            $args
+            $response
+            Ok(())
+        }
+    }
+}
+
+/// Makes the `<Interface>Server` trait, implemented by the server and dispatching the requests to its methods
+fn make_server_trait(namespace: &Namespace, ctx: &CodegenContext, i: &Interface) -> Tokens {
+    let trait_name = format!("{}Server", i.name.ident());
+
+    let commands = i
+        .commands
+        .iter()
+        .map(|command| {
+            let (i_info, w_info) = collect_command_info(namespace, ctx, i.is_domain, command);
+            (command, i_info, w_info)
+        })
+        .filter(|(_, _, w_info)| is_server_supported(ctx, w_info))
+        .collect::<Vec<_>>();
+
+    quote! {
+        // the unsupported commands are not in the trait, they are answered with UNKNOWN_COMMAND_ID
+        pub trait $trait_name {
+            $(for (command, i_info, _) in commands.iter() {
+                fn $(command.name.to_case(Case::Snake))(
+                    &mut self,
+                    $(for (name, ty) in &i_info.args join (,) => $(name.as_str()): $ty)
+                ) -> $(imp_result())<$(make_return_type(i_info))>;
+                _blank_!();
+            })
+
+            // on failure nothing is written, the caller is expected to send an error response
+            fn dispatch(&mut self, request: &$(imp_incoming_request())) -> $(imp_result())<()> {
+                match request.command_id() {
+                    $(for (command, _, w_info) in commands.iter() {
+                        $(make_server_dispatch_arm(namespace, ctx, command, w_info))
+                    })
+                    _ => Err($(imp_unknown_command_id())),
+                }
+            }
+        }
+        _blank_!();
+    }
+}
+
+/// Drops the commands added in a later version than `target`, see [CodegenOptions::target_version]
+fn commands_for_target(i: &Interface, target: Version) -> Interface {
+    let mut i = i.clone();
+    i.commands
+        .retain(|c| c.version_req.min.map_or(true, |min| min <= target));
+    i
+}
+
+pub fn gen_interface(
+    tok: &mut TokenStorage,
+    ctx: &CodegenContext,
+    options: &CodegenOptions,
+    i: &Interface,
+) {
+    let filtered;
+    let i = match options.target_version {
+        Some(target) => {
+            filtered = commands_for_target(i, target);
+            &filtered
+        }
+        None => i,
+    };
+
     let name = make_ident(i.name.ident());
     let name = &name;
     let namespace = i.name.namespace();
@@ -1446,38 +2350,99 @@ pub fn gen_interface(tok: &mut TokenStorage, ctx: &CodegenContext, i: &Interface
     tok.push(
         namespace.clone(),
         quote! {
+            // the handle is the only field, so the interface object has the same layout as the handle itself
+            #[repr(transparent)]
             pub struct $name<S: $(imp_handle_storage()) = $(imp_owned_handle())> {
                 // the generated interface object owns the session handle!
-                pub(crate) handle: S,
+                pub(crate) handle: $(imp_session_handle())<S>,
             }
 
             impl<S: $(imp_handle_storage())> $name<S> {
                 pub fn new(handle: S) -> Self {
-                    Self { handle }
+                    Self {
+                        handle: $(imp_session_handle())::new(handle)
+                    }
                 }
 
                 pub fn into_inner(self) -> S {
-                    self.handle
+                    self.handle.into_inner()
+                }
+
+                pub fn with_storage<S2: $(imp_handle_storage())>(
+                    self,
+                    f: impl FnOnce(S) -> S2
+                ) -> $name<S2> {
+                    $name::new(f(self.into_inner()))
                 }
 
                 $(for command in i.commands.iter() join (_blank_!();) {
-                    $(make_command(namespace, ctx, i, command, i.is_domain))
+                    $(make_command(namespace, ctx, i, command, i.is_domain, options.gen_track_caller))
+                })
+
+                $(if options.gen_async {
+                    _blank_!();
+                    $(for command in i.commands.iter() join (_blank_!();) {
+                        $(make_async_command(namespace, ctx, i, command, i.is_domain))
+                    })
+                })
+
+                $(if options.gen_send {
+                    $(for command in i.commands.iter() {
+                        $(if let Some(command) = make_send_command(namespace, ctx, i, command, i.is_domain) {
+                            _blank_!();
+                            $command
+                        })
+                    })
+                })
+
+                $(if options.gen_in_place {
+                    $(for command in i.commands.iter() {
+                        $(if let Some(command) = make_in_place_command(namespace, ctx, i, command, i.is_domain) {
+                            _blank_!();
+                            $command
+                        })
+                    })
+                })
+
+                $(if options.gen_raw {
+                    _blank_!();
+                    $(for command in i.commands.iter() join (_blank_!();) {
+                        $(make_raw_command(command))
+                    })
                 })
             }
 
             impl $name<$(imp_owned_handle())> {
+                $(for command in i.commands.iter() {
+                    $(make_command_size_consts(namespace, ctx, command, i.is_domain))
+                })
+
                 pub fn as_ref(&self) -> $name<$(imp_ref_handle())<'_>> {
                     $name {
                         handle: self.handle.as_ref()
                     }
                 }
                 pub fn into_shared(self) -> $name<$(imp_shared_handle())> {
-                    $name {
-                        handle: $(imp_shared_handle())::new(self.handle.leak())
-                    }
+                    $name::new($(imp_shared_handle())::new(self.into_inner().leak()))
                 }
             }
 
+            impl<S: $(imp_handle_storage())> From<S> for $name<S> {
+                fn from(handle: S) -> Self {
+                    Self::new(handle)
+                }
+            }
+
+            // compares the session handles, not the objects behind them: two sessions opened
+            // separately to the same server object are not equal
+            impl<S: $(imp_handle_storage())> PartialEq for $name<S> {
+                fn eq(&self, other: &Self) -> bool {
+                    *self.handle.get() == *other.handle.get()
+                }
+            }
+
+            impl<S: $(imp_handle_storage())> Eq for $name<S> {}
+
             impl ::core::fmt::Debug for $name {
                 fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
                     write!(f, $[str]($[const](name)$[const]("({})")), self.handle)
@@ -1486,17 +2451,695 @@ pub fn gen_interface(tok: &mut TokenStorage, ctx: &CodegenContext, i: &Interface
             _blank_!();
         },
     );
+
+    if options.gen_traits {
+        tok.push(namespace.clone(), make_trait(namespace, ctx, i));
+    }
+
+    if options.gen_server {
+        tok.push(namespace.clone(), make_server_trait(namespace, ctx, i));
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::swipc::codegen::interface::gen_interface;
-    use crate::swipc::codegen::TokenStorage;
-    use crate::swipc::model::{IpcFileItem, TypecheckedIpcFile};
+    use crate::swipc::codegen::{CodegenOptions, TokenStorage};
+    use crate::swipc::model::{IpcFileItem, TypecheckedIpcFile, Version};
     use crate::swipc::tests::{parse_typechecked_ipc_file, unwrap_parse};
     use indoc::indoc;
     use itertools::Itertools;
 
+    fn gen_single_interface(s: &str) -> String {
+        gen_single_interface_with(s, &CodegenOptions::default())
+    }
+
+    fn gen_single_interface_with(s: &str, options: &CodegenOptions) -> String {
+        let file: TypecheckedIpcFile = unwrap_parse(s, parse_typechecked_ipc_file);
+
+        let mut ts = TokenStorage::new();
+
+        for item in file.iter_items() {
+            if let IpcFileItem::InterfaceDef(i) = item {
+                gen_interface(&mut ts, file.context(), options, i);
+            }
+        }
+
+        let (_, res) = ts
+            .to_file_string()
+            .unwrap()
+            .into_iter()
+            .exactly_one()
+            .unwrap();
+
+        println!("{}", res);
+
+        res
+    }
+
+    #[test]
+    fn must_use_on_handle_results() {
+        let res = gen_single_interface(
+            r#"
+            interface IHelloInterface {
+                [0] OpenHello(sf::Out<sf::SharedPointer<IHelloInterface>> hello);
+                [1] GetHandle(sf::OutMoveHandle handle);
+                [2] DoNothing();
+            }
+        "#,
+        );
+
+        let must_use = "#[must_use = \"the returned object owns a kernel handle\"]";
+
+        assert!(res.contains(&format!("{}\n    pub fn open_hello(", must_use)));
+        assert!(res.contains(&format!("{}\n    pub fn get_handle(", must_use)));
+        assert!(!res.contains(&format!("{}\n    pub fn do_nothing(", must_use)));
+    }
+
+    #[test]
+    fn service_manager_commands() {
+        let res = gen_single_interface(
+            r#"
+            struct ServiceName {
+                sf::Bytes<8> name;
+            }
+
+            interface IUserInterface is "sm:" {
+                [0] Initialize(sf::ClientProcessId);
+                [1] GetService(ServiceName name, sf::OutMoveHandle session_handle);
+                [2] RegisterService(ServiceName name, u32 max_sessions, b8 is_light, sf::OutMoveHandle port_handle);
+                [3] UnregisterService(ServiceName name);
+            }
+        "#,
+        );
+
+        assert!(!res.contains("todo!"));
+        // the pid is sent in the special header
+        assert!(
+            res.contains("special_header: HipcSpecialHeader::new(\n                        true,")
+        );
+        assert!(res.contains("pid_placeholder: 0,"));
+        // the handles are returned, not written through an out parameter
+        assert!(
+            res.contains("pub fn get_service(&self, name: ServiceName) -> Result<OwnedHandle> {")
+        );
+        assert!(res.contains("pub fn unregister_service(&self, name: ServiceName) -> Result<()> {"));
+        assert!(res.contains("handle_port_handle: RawHandle,"));
+    }
+
+    #[test]
+    fn with_storage() {
+        let res = gen_single_interface(
+            r#"
+            interface IHelloInterface {
+                [0] DoNothing();
+            }
+        "#,
+        );
+
+        assert!(res.contains(concat!(
+            "    pub fn with_storage<S2: HandleStorage>(\n",
+            "        self,\n",
+            "        f: impl FnOnce(S) -> S2,\n",
+            "    ) -> IHelloInterface<S2> {\n",
+            "        IHelloInterface::new(f(self.into_inner()))\n",
+            "    }\n",
+        )));
+        // the storage is wrapped in a cmif session
+        assert!(res.contains("    pub(crate) handle: SessionHandle<S>,\n"));
+        assert!(res.contains("        self.handle.into_inner()\n"));
+    }
+
+    #[test]
+    fn interface_trait() {
+        let interface = r#"
+            interface IHelloInterface {
+                [0] HelloCommand(u32 input, sf::Out<u32> output);
+                [1] OpenHello(sf::Out<sf::SharedPointer<IHelloInterface>> hello);
+            }
+        "#;
+
+        let res = gen_single_interface(interface);
+        assert!(!res.contains("IHelloInterfaceApi"));
+
+        let res = gen_single_interface_with(
+            interface,
+            &CodegenOptions {
+                gen_traits: true,
+                ..Default::default()
+            },
+        );
+
+        assert!(res.contains(indoc! {r#"
+            pub trait IHelloInterfaceApi {
+                fn hello_command(&self, input: u32) -> Result<u32>;
+
+                #[must_use = "the returned object owns a kernel handle"]
+                fn open_hello(&self) -> Result<IHelloInterface>;
+            }
+            impl<S: HandleStorage> IHelloInterfaceApi for IHelloInterface<S> {
+                #[inline]
+                fn hello_command(&self, input: u32) -> Result<u32> {
+                    Self::hello_command(self, input)
+                }
+
+                #[inline]
+                fn open_hello(&self) -> Result<IHelloInterface> {
+                    Self::open_hello(self)
+                }
+            }
+        "#}));
+    }
+
+    #[test]
+    fn server_trait() {
+        let res = gen_single_interface_with(
+            r#"
+            interface IHelloInterface {
+                [0] Add(u32 a, u64 b, sf::Out<u64> sum);
+                [1] Split(u64 value, sf::Out<u32> low, sf::Out<u32> high);
+                [2] OpenHello(sf::Out<sf::SharedPointer<IHelloInterface>> hello);
+            }
+        "#,
+            &CodegenOptions {
+                gen_server: true,
+                ..Default::default()
+            },
+        );
+
+        assert!(res.contains(indoc! {r#"
+            pub trait IHelloInterfaceServer {
+                fn add(&mut self, a: u32, b: u64) -> Result<u64>;
+
+                fn split(&mut self, value: u64) -> Result<(u32, u32)>;
+
+                fn dispatch(&mut self, request: &IncomingRequest) -> Result<()> {
+                    match request.command_id() {
+                        0 => {
+                            #[repr(C, packed)]
+                            struct In {
+                                pub a: u32,
+                                pub _padding_0: [u8; 4],
+                                pub b: u64,
+                            }
+                            let _ = ::core::mem::transmute::<In, [u8; 16]>;
+                            let data_in = unsafe { request.read_data::<In>() }?;
+                            let a = data_in.a;
+                            let b = data_in.b;
+                            let sum = dispatch(|| self.add(a, b))?;
+                            write_response(&sum);
+                            Ok(())
+                        }
+                        1 => {
+                            let value = unsafe { request.read_data::<u64>() }?;
+                            #[repr(C, packed)]
+                            struct Out {
+                                pub low: u32,
+                                pub high: u32,
+                            }
+                            let _ = ::core::mem::transmute::<Out, [u8; 8]>;
+                            let (low, high) = dispatch(|| self.split(value))?;
+                            write_response(&Out { low, high });
+                            Ok(())
+                        }
+                        _ => Err(UNKNOWN_COMMAND_ID),
+                    }
+                }
+            }
+        "#}));
+        assert!(res.contains(
+            "use horizon_ipc::server::{IncomingRequest, UNKNOWN_COMMAND_ID, dispatch, write_response};"
+        ));
+    }
+
+    #[test]
+    fn enum_return() {
+        let res = gen_single_interface(
+            r#"
+            enum EntryType : u8 {
+                Directory = 0,
+                File = 1,
+            }
+            interface IHelloInterface {
+                [0] GetEntryType(sf::Out<EntryType> out);
+            }
+        "#,
+        );
+
+        assert!(res.contains("pub fn get_entry_type(&self) -> Result<EntryType> {"));
+        // the raw value is received as the underlying int...
+        assert!(res.contains("raw_data: u8,"));
+        // ...and decoded after checking the result
+        assert!(res.contains(concat!(
+            "        let out = EntryType::try_from(out).map_err(|_| INVALID_RESPONSE_VALUE)?;\n",
+            "        Ok(out)\n",
+        )));
+    }
+
+    #[test]
+    fn async_commands() {
+        let interface = r#"
+            interface IHelloInterface {
+                [0] HelloCommand(u32 input, sf::Out<u32> output);
+            }
+        "#;
+
+        let res = gen_single_interface(interface);
+        assert!(!res.contains("hello_command_async"));
+
+        let res = gen_single_interface_with(
+            interface,
+            &CodegenOptions {
+                gen_async: true,
+                ..Default::default()
+            },
+        );
+
+        assert!(res.contains(concat!(
+            "    #[cfg(feature = \"async-ipc\")]\n",
+            "    pub async fn hello_command_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(\n",
+            "        &self,\n",
+            "        executor: &E,\n",
+            "        input: u32,\n",
+            "    ) -> Result<u32> {\n",
+        )));
+        assert!(res.contains("async_request.send(executor, *handle).await?;"));
+    }
+
+    #[test]
+    fn send_commands() {
+        let interface = r#"
+            interface IHelloInterface {
+                [0] HelloCommand(u32 input, sf::Out<u32> output);
+                [1] BufferCommand(sf::InBuffer buffer);
+            }
+        "#;
+
+        let res = gen_single_interface(interface);
+        assert!(!res.contains("hello_command_send"));
+
+        let res = gen_single_interface_with(
+            interface,
+            &CodegenOptions {
+                gen_send: true,
+                ..Default::default()
+            },
+        );
+
+        assert!(res.contains(concat!(
+            "    #[cfg(feature = \"async-ipc\")]\n",
+            "    pub fn hello_command_send(\n",
+            "        &self,\n",
+            "        input: u32,\n",
+            "    ) -> Result<horizon_ipc::async_ipc::PendingResponse<u32>> {\n",
+        )));
+        assert!(res.contains("horizon_ipc::async_ipc::AsyncRequest::new_boxed();"));
+        // the response is decoded by the closure, with the same struct as the plain command
+        assert!(res.contains(concat!(
+            "        horizon_ipc::async_ipc::PendingResponse::send(\n",
+            "            async_request,\n",
+            "            *handle,\n",
+            "            |ipc_buffer_ptr| {\n",
+        )));
+        // the buffer would have to outlive the call
+        assert!(!res.contains("buffer_command_send"));
+    }
+
+    #[test]
+    fn versioned_commands() {
+        let interface = r#"
+            interface IHelloInterface {
+                [0] HelloCommand();
+                @version(4.0.0+)
+                [1] NewCommand();
+                @version(1.0.0-3.0.2)
+                [2] OldCommand();
+            }
+        "#;
+
+        let res = gen_single_interface(interface);
+        assert!(res.contains(concat!(
+            "    /// [4.0.0+] The command doesn't exist on older firmware\n",
+            "    pub fn new_command(&self) -> Result<()> {\n",
+        )));
+        assert!(res.contains(concat!(
+            "    /// [1.0.0-3.0.2] The command doesn't exist on other firmware versions\n",
+            "    pub fn old_command(&self) -> Result<()> {\n",
+        )));
+
+        let res = gen_single_interface_with(
+            interface,
+            &CodegenOptions {
+                target_version: Some(Version::new(3, 0, 0)),
+                ..Default::default()
+            },
+        );
+        assert!(res.contains("pub fn hello_command("));
+        assert!(res.contains("pub fn old_command("));
+        assert!(!res.contains("new_command"));
+    }
+
+    #[test]
+    fn size_consts() {
+        let res = gen_single_interface(
+            r#"
+            interface IHelloInterface {
+                [0] HelloCommand(u32 input, sf::Out<u64> output, sf::InBuffer buffer);
+            }
+        "#,
+        );
+
+        let find_size = |pattern: &str| -> usize {
+            let start = res.find(pattern).unwrap() + pattern.len();
+            let len = res[start..].find(|c: char| !c.is_ascii_digit()).unwrap();
+            res[start..start + len].parse().unwrap()
+        };
+
+        assert_eq!(
+            find_size("pub const HELLO_COMMAND_REQUEST_SIZE: usize = "),
+            find_size("transmute::<Request, [u8; ")
+        );
+        assert_eq!(
+            find_size("pub const HELLO_COMMAND_RESPONSE_SIZE: usize = "),
+            find_size("transmute::<Response, [u8; ")
+        );
+    }
+
+    #[test]
+    fn auto_select_buffer() {
+        let res = gen_single_interface(
+            r#"
+            interface IHelloInterface {
+                [0] Write(sf::InAutoSelectBuffer data);
+            }
+        "#,
+        );
+        // the formatting of the conditions depends on the line width, so compare ignoring it
+        let res = res.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        assert!(res.contains(concat!(
+            "let pointer_buffer_space = pointer_buffer_size(*self.handle.get()); ",
+            "let data_in_pointer_buffer = ::core::mem::size_of_val(data) <= pointer_buffer_space;"
+        )));
+        // the buffer is sent via the pointer descriptor when it fits...
+        assert!(res.contains(concat!(
+            "in_pointer_desc_0: if data_in_pointer_buffer { ",
+            "HipcInPointerBufferDescriptor::new( 0, data.as_ptr() as usize, ::core::mem::size_of_val(data), ) ",
+            "} else { HipcInPointerBufferDescriptor::new(0, 0, 0) },"
+        )));
+        // ...and via the map alias otherwise
+        assert!(res.contains(concat!(
+            "in_map_alias_desc_0: if data_in_pointer_buffer { ",
+            "HipcMapAliasBufferDescriptor::new( MapAliasBufferMode::Normal, 0, 0, ) ",
+            "} else { HipcMapAliasBufferDescriptor::new( MapAliasBufferMode::Normal, ",
+            "data.as_ptr() as usize, ::core::mem::size_of_val(data), ) },"
+        )));
+    }
+
+    #[test]
+    fn auto_select_buffers_share_pointer_buffer() {
+        let res = gen_single_interface(
+            r#"
+            interface IHelloInterface {
+                [0] Transfer(sf::InAutoSelectBuffer input, sf::OutAutoSelectBuffer output);
+            }
+        "#,
+        );
+        let res = res.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        // the second buffer only gets the space the first one has left
+        assert!(res.contains(concat!(
+            "let mut pointer_buffer_space = pointer_buffer_size(*self.handle.get()); ",
+            "let input_in_pointer_buffer = ::core::mem::size_of_val(input) <= pointer_buffer_space; ",
+            "if input_in_pointer_buffer { pointer_buffer_space -= ::core::mem::size_of_val(input); } ",
+            "let output_in_pointer_buffer = ::core::mem::size_of_val(output) <= pointer_buffer_space;"
+        )));
+        assert!(res.contains("in_pointer_desc_0: if input_in_pointer_buffer {"));
+        assert!(res.contains("out_pointer_size_0: if output_in_pointer_buffer {"));
+        assert!(res.contains("out_pointer_desc_0: if output_in_pointer_buffer {"));
+    }
+
+    #[test]
+    fn optional_out_handle() {
+        let res = gen_single_interface(
+            r#"
+            interface IHelloInterface {
+                [0] OpenHello(sf::Out<sf::Optional<sf::SharedPointer<IHelloInterface>>> hello);
+            }
+        "#,
+        );
+
+        assert!(res.contains(indoc! {r#"
+                pub fn open_hello(&self) -> Result<Option<IHelloInterface>> {
+        "#}));
+        assert!(res.contains("return Ok(None);"));
+        assert!(res.contains("let hello = Some(hello);"));
+    }
+
+    #[test]
+    fn from_handle_storage() {
+        let res = gen_single_interface(
+            r#"
+            interface IHelloInterface {
+                [0] DoNothing();
+            }
+        "#,
+        );
+
+        assert!(res.contains(indoc! {r#"
+            impl<S: HandleStorage> From<S> for IHelloInterface<S> {
+                fn from(handle: S) -> Self {
+                    Self::new(handle)
+                }
+            }
+        "#}));
+    }
+
+    #[test]
+    fn handle_identity_eq() {
+        let res = gen_single_interface(
+            r#"
+            interface IHelloInterface {
+                [0] DoNothing();
+            }
+        "#,
+        );
+
+        assert!(res.contains(indoc! {r#"
+            impl<S: HandleStorage> PartialEq for IHelloInterface<S> {
+                fn eq(&self, other: &Self) -> bool {
+                    *self.handle.get() == *other.handle.get()
+                }
+            }
+        "#}));
+    }
+
+    #[test]
+    fn static_request_headers() {
+        let res = gen_single_interface(
+            r#"
+            interface IHelloInterface {
+                [3] Flush();
+                [4] Write(sf::InBuffer data);
+            }
+        "#,
+        );
+
+        // the formatting of the header depends on the line width, so compare ignoring it
+        let res = res.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        assert!(res.contains(concat!(
+            "const HIPC_HEADER: HipcHeader = HipcHeader::new( ",
+            "CommandType::Request, 0, 0, 0, 0, 8, 0, 0, false, ); ",
+            "const CMIF_HEADER: CmifInHeader = CmifInHeader { ",
+            "magic: CmifInHeader::MAGIC, version: 1, command_id: 3, token: 0, };"
+        )));
+        assert!(res.contains("hipc: HIPC_HEADER,"));
+        // the command with a buffer builds the headers at runtime
+        assert_eq!(res.matches("const HIPC_HEADER").count(), 1);
+    }
+
+    #[test]
+    fn typed_out_array() {
+        let res = gen_single_interface(
+            r#"
+            struct Entry {
+                u64 id;
+                u32 kind;
+            }
+            interface IHelloInterface {
+                [0] Read(sf::Out<i64> count, sf::OutArray<Entry> entries);
+            }
+        "#,
+        );
+
+        let res = res.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        assert!(res.contains("pub fn read(&self, entries: &mut [Entry]) -> Result<i64> {"));
+        assert!(res.contains(concat!(
+            "let _ = ::core::mem::transmute::<Entry, [u8; 16]>; ",
+            "const _: () = if ::core::mem::align_of::<Entry>() > 8 {"
+        )));
+    }
+
+    #[test]
+    fn raw_commands() {
+        let res = gen_single_interface_with(
+            r#"
+            interface IHelloInterface {
+                [8] OpenFile(u32 mode, sf::Out<u64> size);
+            }
+        "#,
+            &CodegenOptions {
+                gen_raw: true,
+                ..Default::default()
+            },
+        );
+
+        assert!(res.contains(concat!(
+            "    #[cfg(feature = \"raw-ipc\")]\n",
+            "    pub unsafe fn open_file_raw(&self, raw_in: &[u8], out: &mut [u8]) -> Result<usize> {\n",
+            "        horizon_ipc::cmif::raw_command::send_request(\n",
+            "            *self.handle.get(),\n",
+            "            horizon_ipc::cmif::domain::Framing::Session,\n",
+            "            8,\n",
+            "            raw_in,\n",
+            "            out,\n",
+            "        )\n",
+            "    }\n",
+        )));
+
+        // not generated by default
+        assert!(!gen_single_interface(
+            r#"
+            interface IHelloInterface {
+                [8] OpenFile(u32 mode, sf::Out<u64> size);
+            }
+        "#
+        )
+        .contains("open_file_raw"));
+    }
+
+    #[test]
+    fn in_place_commands() {
+        let s = r#"
+            struct RangeInfo {
+                u32 aes_ctr_key_type;
+                u32 speed_emulation_type;
+                sf::Bytes<0x38> reserved;
+            }
+            interface IHelloInterface {
+                [5] OperateRange(u32 op_id, i64 offset, sf::Out<RangeInfo> info);
+                [6] GetSize(sf::Out<i64> size);
+            }
+        "#;
+        let res = gen_single_interface_with(
+            s,
+            &CodegenOptions {
+                gen_in_place: true,
+                ..Default::default()
+            },
+        );
+
+        let res = res.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        assert!(res.contains(concat!(
+            "pub fn operate_range_in_place<'o>( &self, op_id: u32, offset: i64, ",
+            "info: &'o mut MaybeUninit<RangeInfo>, ) -> Result<&'o mut RangeInfo> {"
+        )));
+        assert!(res.contains(concat!(
+            "::core::ptr::copy_nonoverlapping( ipc_buffer_ptr.add(::core::mem::offset_of!(Response, raw_data)), ",
+            "info.as_mut_ptr() as *mut u8, ::core::mem::size_of_val(info), ) }; ",
+            "Ok(unsafe { info.assume_init_mut() })"
+        )));
+        // the small results are returned by value only
+        assert!(!res.contains("get_size_in_place"));
+
+        // the copying form is the default
+        assert!(!gen_single_interface(s).contains("operate_range_in_place"));
+    }
+
+    #[test]
+    fn track_caller_commands() {
+        let s = r#"
+            interface IHelloInterface {
+                [8] OpenFile(u32 mode, sf::Out<u64> size);
+            }
+        "#;
+        let res = gen_single_interface_with(
+            s,
+            &CodegenOptions {
+                gen_track_caller: true,
+                ..Default::default()
+            },
+        );
+
+        let res = res.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        // the location is taken in the public method, which is the one called by the user code
+        assert!(res.contains(concat!(
+            "#[cfg_attr(feature = \"track-ipc-errors\", track_caller)] ",
+            "pub fn open_file(&self, mode: u32) -> Result<u64> { ",
+            "let res = self.open_file_untracked(mode); ",
+            "if let Err(e) = &res { ",
+            "crate::ipc_error_hook( \"::IHelloInterface::OpenFile\", ::core::panic::Location::caller(), *e, ); ",
+            "} res }"
+        )));
+        assert!(res.contains(
+            "#[inline(always)] fn open_file_untracked(&self, mode: u32) -> Result<u64> {"
+        ));
+        // the hooks get the sizes of the request and the response, to dump only that much of the IPC buffer
+        assert!(res.contains("crate::pre_ipc_hook(\"::IHelloInterface::OpenFile\", *handle, 44);"));
+        assert!(res.contains("crate::post_ipc_hook(\"::IHelloInterface::OpenFile\", *handle, 48);"));
+
+        // the commands are not wrapped by default
+        assert!(!gen_single_interface(s).contains("_untracked"));
+    }
+
+    #[test]
+    fn handle_count_check() {
+        let res = gen_single_interface(
+            r#"
+            interface IHelloInterface {
+                [0] SendHandles(sf::MoveHandle first, sf::MoveHandle second);
+            }
+        "#,
+        );
+
+        let res = res.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        assert!(res.contains(concat!(
+            "const REQUEST_NUM_COPY_HANDLES: u32 = 0; ",
+            "const REQUEST_NUM_MOVE_HANDLES: u32 = 2; ",
+            "// Compiler time request handle count check ",
+            "const _: () = if ::core::mem::offset_of!(Request, pre_padding) ",
+            "- ::core::mem::offset_of!(Request, handle_first) ",
+            "!= 4 * (REQUEST_NUM_COPY_HANDLES + REQUEST_NUM_MOVE_HANDLES) as usize {"
+        )));
+        // the special header is built from the checked counts
+        assert!(res.contains(concat!(
+            "special_header: HipcSpecialHeader::new( false, ",
+            "REQUEST_NUM_COPY_HANDLES, REQUEST_NUM_MOVE_HANDLES, ), ",
+            "handle_first: first, handle_second: second,"
+        )));
+    }
+
+    #[test]
+    fn repr_transparent() {
+        let res = gen_single_interface(
+            r#"
+            interface IHelloInterface {
+                [0] DoNothing();
+            }
+        "#,
+        );
+
+        assert!(res.contains(indoc! {r#"
+            #[repr(transparent)]
+            pub struct IHelloInterface<S: HandleStorage = OwnedHandle> {
+        "#}));
+    }
+
     #[ignore] // TODO: update when the codegen results for commands will be more or less stable
     #[test]
     fn simple_interface() {
@@ -1518,7 +3161,7 @@ mod tests {
 
         let mut ts = TokenStorage::new();
 
-        gen_interface(&mut ts, file.context(), i);
+        gen_interface(&mut ts, file.context(), &CodegenOptions::default(), i);
 
         let (_, res) = ts
             .to_file_string()
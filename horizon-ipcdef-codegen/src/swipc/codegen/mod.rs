@@ -1,7 +1,7 @@
 use crate::swipc::codegen::interface::gen_interface;
 use crate::swipc::codegen::types::{gen_bitflags, gen_enum, gen_struct, gen_type_alias};
 use crate::swipc::model::{
-    CodegenContext, IpcFileItem, Namespace, NamespacedIdent, TypecheckedIpcFile,
+    CodegenContext, IpcFileItem, Namespace, NamespacedIdent, TypecheckedIpcFile, Version,
 };
 use anyhow::Context;
 use arcstr::ArcStr;
@@ -9,6 +9,7 @@ use genco::fmt::Indentation;
 use genco::lang::rust::Tokens;
 use genco::lang::{rust, Rust};
 use genco::quote;
+use genco::tokens::quoted;
 use indoc::indoc;
 use itertools::Itertools;
 use rust_format::{Formatter, PostProcess};
@@ -16,6 +17,7 @@ use sequence_trie::SequenceTrie;
 use std::collections::BTreeMap;
 use std::sync::Arc;
 
+pub mod features;
 pub mod interface;
 pub mod types;
 
@@ -99,12 +101,15 @@ fn filename_for_namespace(namespace: &Namespace, is_directory: bool) -> String {
 
 pub struct TokenStorage {
     storage: BTreeMap<Arc<Vec<ArcStr>>, Tokens>,
+    /// Put the top-level modules behind features named after them, see [features]
+    gate_root_modules: bool,
 }
 
 impl TokenStorage {
     pub fn new() -> Self {
         let mut res = Self {
             storage: BTreeMap::new(),
+            gate_root_modules: false,
         };
 
         // add an dummy root file to generate at least a `mod.rs` on empty input
@@ -113,6 +118,10 @@ impl TokenStorage {
         res
     }
 
+    pub fn gate_root_modules(&mut self) {
+        self.gate_root_modules = true;
+    }
+
     pub fn push(&mut self, namespace: Namespace, tokens: Tokens) {
         self.storage.entry(namespace).or_default().append(tokens);
     }
@@ -153,8 +162,13 @@ impl TokenStorage {
                     .sorted()
                     .collect::<Vec<_>>();
 
+                let gate_modules = ns.is_empty() && self.gate_root_modules;
+
                 let tok = quote! {
                     $(for module in child_modules.iter() {
+                        $(if gate_modules {
+                            #[cfg(feature = $(quoted(module.as_str())))]
+                        })
                         pub mod $(module.as_str());
                     })
 
@@ -222,25 +236,107 @@ fn make_formatter() -> impl rust_format::Formatter {
     rust_format::PrettyPlease::from_config(config)
 }
 
-pub fn gen_ipc_file(tok: &mut TokenStorage, ctx: &CodegenContext, f: &TypecheckedIpcFile) {
+/// Knobs changing the shape of the generated code
+#[derive(Debug, Clone, Default)]
+pub struct CodegenOptions {
+    /// Generate an `<Interface>Api` trait for each interface, implemented by the interface struct
+    ///
+    /// Allows writing code generic over the trait, replacing the real client with a mock in tests
+    pub gen_traits: bool,
+    /// Generate `async fn <command>_async` variants of the commands, behind the `async-ipc` feature
+    ///
+    /// They wait for the reply through an app-supplied `horizon_ipc::async_ipc::IpcExecutor`
+    pub gen_async: bool,
+    /// Generate `fn <command>_send` variants of the commands, behind the `async-ipc` feature
+    ///
+    /// They return a `horizon_ipc::async_ipc::PendingResponse` right after sending the request,
+    ///  whose `receive` reads the reply once it arrives
+    pub gen_send: bool,
+    /// Derive `bytemuck::Pod` and `bytemuck::Zeroable` for the structs that allow it, behind the `bytemuck` feature
+    pub gen_bytemuck: bool,
+    /// Put each top-level module behind a feature named after it, see [features]
+    pub gate_modules: bool,
+    /// Generate an `<Interface>Server` trait for each interface, dispatching the requests to its methods
+    ///
+    /// Only the commands with nothing but raw data are supported, see `horizon_ipc::server`
+    pub gen_server: bool,
+    /// Generate `unsafe fn <command>_raw` variants of the commands, behind the `raw-ipc` feature
+    ///
+    /// They send the raw data given by the caller as is, see `horizon_ipc::cmif::raw_command`
+    pub gen_raw: bool,
+    /// Generate `fn <command>_in_place` variants of the commands returning a large struct
+    ///
+    /// They fill a caller-provided `MaybeUninit` straight from the IPC buffer, instead of returning the struct by value
+    pub gen_in_place: bool,
+    /// Make the commands `#[track_caller]` wrappers logging the location of the call on error, behind the `track-ipc-errors` feature
+    ///
+    /// The implementation of the command is moved to a private `fn <command>_untracked`
+    pub gen_track_caller: bool,
+    /// Skip the commands added in a later firmware version than this one (with `@version`)
+    ///
+    /// The commands limited to some versions get a doc note regardless of this option
+    pub target_version: Option<Version>,
+}
+
+pub fn gen_ipc_file(
+    tok: &mut TokenStorage,
+    ctx: &CodegenContext,
+    options: &CodegenOptions,
+    f: &TypecheckedIpcFile,
+) {
+    if options.gate_modules {
+        tok.gate_root_modules();
+    }
+
     for item in f.iter_items() {
         match item {
             IpcFileItem::TypeAlias(a) => gen_type_alias(tok, ctx, a),
-            IpcFileItem::StructDef(s) => gen_struct(tok, ctx, s),
+            IpcFileItem::StructDef(s) => gen_struct(tok, ctx, options, s),
             IpcFileItem::EnumDef(e) => gen_enum(tok, ctx, e),
             IpcFileItem::BitflagsDef(b) => gen_bitflags(tok, ctx, b),
-            IpcFileItem::InterfaceDef(i) => gen_interface(tok, ctx, i),
+            IpcFileItem::InterfaceDef(i) => gen_interface(tok, ctx, options, i),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::swipc::codegen::{gen_ipc_file, TokenStorage};
+    use crate::swipc::codegen::{gen_ipc_file, CodegenOptions, TokenStorage};
     use crate::swipc::model::TypecheckedIpcFile;
     use crate::swipc::tests::{parse_typechecked_ipc_file, unwrap_parse};
     use indoc::indoc;
 
+    #[test]
+    fn gated_modules() {
+        let s = r#"
+            enum ns_1::Enum1 : u32 {
+                Arm0 = 0,
+            }
+            type ns_2::nested::HelloAlias = ns_1::Enum1;
+        "#;
+
+        let file: TypecheckedIpcFile = unwrap_parse(s, parse_typechecked_ipc_file);
+
+        let mut ts = TokenStorage::new();
+
+        let options = CodegenOptions {
+            gate_modules: true,
+            ..Default::default()
+        };
+        gen_ipc_file(&mut ts, file.context(), &options, &file);
+
+        let files = ts.to_file_string().unwrap();
+
+        assert!(files["mod.rs"].ends_with(indoc! {r#"
+            #[cfg(feature = "ns_1")]
+            pub mod ns_1;
+            #[cfg(feature = "ns_2")]
+            pub mod ns_2;
+        "#}));
+        // only the top-level modules are gated
+        assert!(!files["ns_2/mod.rs"].contains("cfg"));
+    }
+
     #[test]
     fn multifile() {
         let s = r#"
@@ -259,7 +355,7 @@ mod tests {
 
         let mut ts = TokenStorage::new();
 
-        gen_ipc_file(&mut ts, file.context(), &file);
+        gen_ipc_file(&mut ts, file.context(), &CodegenOptions::default(), &file);
 
         let files = ts.to_file_string().unwrap();
 
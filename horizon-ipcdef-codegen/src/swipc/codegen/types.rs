@@ -1,7 +1,7 @@
 use crate::swipc::model::StructuralType;
 use crate::swipc::util::PaddingHelper;
 use crate::swipc::{
-    codegen::{import_in, make_ident, TokenStorage, Tokens},
+    codegen::{import_in, make_ident, CodegenOptions, TokenStorage, Tokens},
     layout::FieldsLayoutItem,
     model::{Bitflags, CodegenContext, Enum, IntType, Namespace, NominalType, Struct, TypeAlias},
 };
@@ -72,7 +72,32 @@ fn make_manual_struct_default(ctx: &CodegenContext, s: &Struct) -> Tokens {
     //
 }
 
-pub fn gen_struct(tok: &mut TokenStorage, ctx: &CodegenContext, s: &Struct) {
+/// Whether any bit pattern is a valid value of the struct, so it can derive `bytemuck::Pod`
+///
+/// Bools, enums and bitflags (even in nested structs) have invalid bit patterns.
+///  The padding is made of explicit fields, so it's not a problem.
+fn is_pod(ctx: &CodegenContext, s: &Struct) -> bool {
+    s.fields.iter().all(|f| is_pod_type(ctx, &f.ty))
+}
+
+/// Whether any bit pattern is a valid value of the type, see [is_pod]
+pub fn is_pod_type(ctx: &CodegenContext, ty: &NominalType) -> bool {
+    match ty.codegen_resolve(ctx) {
+        StructuralType::Int(_) | StructuralType::F32 | StructuralType::Bytes { .. } => true,
+        StructuralType::Struct(s) => is_pod(ctx, &s),
+        StructuralType::Bool
+        | StructuralType::Enum(_)
+        | StructuralType::Bitflags(_)
+        | StructuralType::Unknown { .. } => false,
+    }
+}
+
+pub fn gen_struct(
+    tok: &mut TokenStorage,
+    ctx: &CodegenContext,
+    options: &CodegenOptions,
+    s: &Struct,
+) {
     let name = make_ident(s.name.ident());
     let name = &name;
     let namespace = s.name.namespace();
@@ -108,6 +133,9 @@ pub fn gen_struct(tok: &mut TokenStorage, ctx: &CodegenContext, s: &Struct) {
                     , Default
                 })
             )]
+            $(if options.gen_bytemuck && is_pod(ctx, s) {
+                #[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+            })
             #[repr(C)] // not packed, but we insert all necessary padding manually
             pub struct $name {
                 $(for f in s.fields_layout(ctx).items.iter() {
@@ -145,13 +173,26 @@ pub fn gen_enum(tok: &mut TokenStorage, _ctx: &CodegenContext, e: &Enum) {
         namespace.clone(),
         quote! {
             #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
-            #[repr($base_type)]
-            pub enum $name {
+            #[repr($(&base_type))]
+            pub enum $(&name) {
                 $(for arm in e.arms.iter() {
                     $(if arm.value == 0 => #[default])
                     $(make_ident(&arm.name)) = $(arm.value),
                 })
             }
+            // used to decode the values received over IPC, which are not guaranteed to be valid
+            impl ::core::convert::TryFrom<$(&base_type)> for $(&name) {
+                type Error = $(&base_type);
+
+                fn try_from(value: $(&base_type)) -> ::core::result::Result<Self, $(&base_type)> {
+                    match value {
+                        $(for arm in e.arms.iter() {
+                            $(arm.value) => Ok(Self::$(make_ident(&arm.name))),
+                        })
+                        _ => Err(value),
+                    }
+                }
+            }
         },
     );
 }
@@ -200,7 +241,7 @@ pub fn gen_type_alias(tok: &mut TokenStorage, _ctx: &CodegenContext, a: &TypeAli
 #[cfg(test)]
 mod tests {
     use crate::swipc::codegen::types::{gen_bitflags, gen_enum, gen_struct, gen_type_alias};
-    use crate::swipc::codegen::{import_in, TokenStorage};
+    use crate::swipc::codegen::{import_in, CodegenOptions, TokenStorage};
     use crate::swipc::model::{IpcFileItem, NamespacedIdent, TypecheckedIpcFile};
     use crate::swipc::tests::{parse_typechecked_ipc_file, unwrap_parse};
     use indoc::indoc;
@@ -261,7 +302,7 @@ mod tests {
 
         let mut ts = TokenStorage::new();
 
-        gen_struct(&mut ts, file.context(), s);
+        gen_struct(&mut ts, file.context(), &CodegenOptions::default(), s);
 
         let (_, res) = ts
             .to_file_string()
@@ -297,6 +338,52 @@ mod tests {
         )
     }
 
+    #[test]
+    fn bytemuck_struct() {
+        let s = r#"
+            struct PodStruct {
+                u32 aaaa;
+                u8 bbbb;
+            }
+            struct NonPodStruct {
+                PodStruct inner;
+                bool flag;
+            }
+        "#;
+
+        let file: TypecheckedIpcFile = unwrap_parse(s, parse_typechecked_ipc_file);
+
+        let options = CodegenOptions {
+            gen_bytemuck: true,
+            ..Default::default()
+        };
+
+        let mut ts = TokenStorage::new();
+
+        for item in file.iter_items() {
+            match item {
+                IpcFileItem::StructDef(s) => gen_struct(&mut ts, file.context(), &options, s),
+                _ => unreachable!(),
+            }
+        }
+
+        let (_, res) = ts
+            .to_file_string()
+            .unwrap()
+            .into_iter()
+            .exactly_one()
+            .unwrap();
+
+        println!("{}", res);
+
+        assert!(res.contains(indoc! {"
+            #[cfg_attr(feature = \"bytemuck\", derive(bytemuck::Pod, bytemuck::Zeroable))]
+            #[repr(C)]
+            pub struct PodStruct {
+        "}));
+        assert_eq!(res.matches("bytemuck::Pod").count(), 1);
+    }
+
     #[test]
     fn simple_enum() {
         let s = r#"
@@ -0,0 +1,200 @@
+//! Cross-checks the hand-written SwIPC definitions against the server-side IPC dumps
+//!
+//! The dumps (`swipcgen_server_modern.info` on ninupdates, parsed by [ipc_parse](crate::ninupdates::ipc_parse))
+//!  are extracted from the firmware and list the raw data sizes and the buffers of every command,
+//!  so they can catch transcription errors in the definitions.
+//!
+//! Interfaces in the dumps are named by their mangled C++ type names (or just vtable addresses),
+//!  only the former can be matched with the definitions.
+
+use crate::ninupdates::ipc_parse::{IpcFile as IpcDump, IpcInterface as IpcDumpInterface};
+use crate::swipc::codegen::interface::command_wire_summary;
+use crate::swipc::diagnostics::DiagnosticExt;
+use crate::swipc::model::{
+    CodegenContext, Direction, Interface, IpcFileItem, NamespacedIdent, TypecheckedIpcFile,
+};
+use codespan_reporting::diagnostic::Diagnostic;
+
+/// Buffer attribute bits telling the direction of the buffer
+const BUFFER_ATTRIBUTE_IN: u32 = 1;
+const BUFFER_ATTRIBUTE_OUT: u32 = 2;
+
+/// Makes the part of the mangled name identifying the interface, like `4gpio8IManagerE` for `gpio::IManager`
+///
+/// The `nn::` prefix dropped in the definitions is usually mangled as a substitution, so it's not included
+fn mangled_name_fragment(name: &NamespacedIdent) -> String {
+    let mut res = String::new();
+    for part in name.iter_namespaces().chain(std::iter::once(name.ident())) {
+        res.push_str(&format!("{}{}", part.len(), part));
+    }
+    res.push('E');
+    res
+}
+
+fn find_dump_interface<'a>(
+    dump: &'a IpcDump,
+    interface: &Interface,
+) -> Option<&'a IpcDumpInterface> {
+    let fragment = mangled_name_fragment(&interface.name);
+
+    dump.interfaces
+        .iter()
+        .find(|i| i.raw_name.contains(&fragment))
+}
+
+fn buffer_direction(attributes: u32) -> Option<Direction> {
+    match attributes & (BUFFER_ATTRIBUTE_IN | BUFFER_ATTRIBUTE_OUT) {
+        BUFFER_ATTRIBUTE_IN => Some(Direction::In),
+        BUFFER_ATTRIBUTE_OUT => Some(Direction::Out),
+        _ => None,
+    }
+}
+
+fn crosscheck_interface(
+    ctx: &CodegenContext,
+    interface: &Interface,
+    dump: &IpcDumpInterface,
+) -> Vec<Diagnostic<usize>> {
+    let mut diagnostics = Vec::new();
+
+    for command in interface.commands.iter() {
+        let name = format!("{}::{}", interface.name, command.name);
+
+        let method = match dump.methods.get(&command.id) {
+            Some(method) => method,
+            None => {
+                diagnostics.push(
+                    Diagnostic::warning()
+                        .with_message(format!(
+                            "Command `{}` (id {}) is not present in the dump",
+                            name, command.id
+                        ))
+                        .with_primary_label(command.location),
+                );
+                continue;
+            }
+        };
+
+        let summary = command_wire_summary(ctx, interface, command);
+
+        let mut mismatch = |what: &str, expected: String, actual: String| {
+            diagnostics.push(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "Command `{}` {} mismatch: the dump has {}, the definition has {}",
+                        name, what, expected, actual
+                    ))
+                    .with_primary_label(command.location),
+            )
+        };
+
+        if method.in_bytes as u64 != summary.in_bytes {
+            mismatch(
+                "inbytes",
+                method.in_bytes.to_string(),
+                summary.in_bytes.to_string(),
+            );
+        }
+        if method.out_bytes as u64 != summary.out_bytes {
+            mismatch(
+                "outbytes",
+                method.out_bytes.to_string(),
+                summary.out_bytes.to_string(),
+            );
+        }
+
+        let dump_directions = method
+            .buffers
+            .iter()
+            .map(|&b| buffer_direction(b))
+            .collect::<Vec<_>>();
+        let directions = summary
+            .buffer_directions
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        if dump_directions != directions {
+            mismatch(
+                "buffers",
+                format!("{:?}", dump_directions),
+                format!("{:?}", directions),
+            );
+        }
+    }
+
+    diagnostics
+}
+
+/// Checks all the interfaces of the file that can be found in the dump
+pub fn crosscheck(file: &TypecheckedIpcFile, dump: &IpcDump) -> Vec<Diagnostic<usize>> {
+    file.iter_items()
+        .filter_map(|item| match item {
+            IpcFileItem::InterfaceDef(i) => Some(i),
+            _ => None,
+        })
+        .filter_map(|interface| {
+            find_dump_interface(dump, interface)
+                .map(|dump| crosscheck_interface(file.context(), interface, dump))
+        })
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ninupdates::ipc_parse::IpcFile;
+    use crate::swipc::crosscheck::crosscheck;
+    use crate::swipc::model::TypecheckedIpcFile;
+    use crate::swipc::tests::{parse_typechecked_ipc_file, unwrap_parse};
+    use codespan_reporting::diagnostic::Severity;
+
+    const DEFS: &str = r#"
+        interface gpio::IPadSession {
+            [0] SetDirection(u32 direction);
+            [1] GetDirection(sf::Out<u32> direction);
+            [3] Read(sf::OutBuffer data, sf::Out<u64> read);
+        }
+    "#;
+
+    fn dump(set_direction_inbytes: u32) -> IpcFile {
+        IpcFile::parse(&format!(
+            r#"
+'bus': {{
+  'N2nn2sf6detail38ObjectImplFactoryWithStatefulAllocatorINS0_13InterfaceInfoINS_4gpio11IPadSessionEE4_tABINS6_5_tO2NINS4_6server14PadSessionImplEE4typeES5_E4typeENS0_24StatefulAllocationPolicyINS0_16ExpHeapAllocatorEEEE6ObjectE': {{
+      0:     {{"vt":  0x20, "lr": 0x7100028928, "inbytes":     {}, "outbytes":     0}},
+      1:     {{"vt":  0x28, "lr": 0x7100028A90, "inbytes":     0, "outbytes":     4}},
+      3:     {{"vt":  0x38, "lr": 0x7100041874, "inbytes":     0, "outbytes":     8, "buffers": [6]}},
+  }},
+  '0x710000E81C': {{
+      0:     {{"vt":  0x20, "lr": 0x710000E9F8, "inbytes":     4, "outbytes":     0}},
+  }},
+}},
+"#,
+            set_direction_inbytes
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn matching_dump() {
+        let file: TypecheckedIpcFile = unwrap_parse(DEFS, parse_typechecked_ipc_file);
+
+        let diagnostics = crosscheck(&file, &dump(4));
+
+        assert!(diagnostics.is_empty(), "{:#?}", diagnostics);
+    }
+
+    #[test]
+    fn wrong_inbytes() {
+        let file: TypecheckedIpcFile = unwrap_parse(DEFS, parse_typechecked_ipc_file);
+
+        let diagnostics = crosscheck(&file, &dump(8));
+
+        assert_eq!(diagnostics.len(), 1, "{:#?}", diagnostics);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(
+            diagnostics[0].message,
+            "Command `gpio::IPadSession::SetDirection` inbytes mismatch: the dump has 8, the definition has 4"
+        );
+    }
+}
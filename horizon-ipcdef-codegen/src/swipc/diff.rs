@@ -0,0 +1,379 @@
+//! Compares two versions of the definitions, listing the changes that affect the generated code
+//!
+//! The comparison is done on the typechecked model, so formatting changes, moved definitions and such
+//!  are not reported. It's meant to be used when bumping the definitions to a new firmware version,
+//!  to see which commands changed the signature and which structs changed the layout (and the size in particular).
+
+use crate::swipc::codegen::interface::command_wire_summary;
+use crate::swipc::diagnostics::Span;
+use crate::swipc::model::{
+    CodegenContext, Command, Interface, IpcFileItem, Struct, TypecheckedIpcFile, Value,
+};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A single difference between the two versions of the definitions
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// What has changed, like "interface `sm::IUserInterface`"
+    pub item: String,
+    /// Details of the change, empty for added and removed items
+    pub details: Vec<String>,
+}
+
+impl Change {
+    fn added(item: String) -> Self {
+        Self {
+            kind: ChangeKind::Added,
+            item,
+            details: Vec::new(),
+        }
+    }
+
+    fn removed(item: String) -> Self {
+        Self {
+            kind: ChangeKind::Removed,
+            item,
+            details: Vec::new(),
+        }
+    }
+
+    fn changed(item: String, details: Vec<String>) -> Self {
+        Self {
+            kind: ChangeKind::Changed,
+            item,
+            details,
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            ChangeKind::Added => "+",
+            ChangeKind::Removed => "-",
+            ChangeKind::Changed => "~",
+        };
+        write!(f, "{} {}", kind, self.item)?;
+        for detail in self.details.iter() {
+            write!(f, "\n    {}", detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// The definitions of one version, indexed by name
+struct Definitions<'a> {
+    ctx: &'a CodegenContext,
+    interfaces: BTreeMap<String, &'a Interface>,
+    structs: BTreeMap<String, &'a Struct>,
+}
+
+impl<'a> Definitions<'a> {
+    fn new(file: &'a TypecheckedIpcFile) -> Self {
+        let mut interfaces = BTreeMap::new();
+        let mut structs = BTreeMap::new();
+
+        for item in file.iter_items() {
+            match item {
+                IpcFileItem::InterfaceDef(i) => {
+                    interfaces.insert(i.name.to_string(), i.as_ref());
+                }
+                IpcFileItem::StructDef(s) => {
+                    structs.insert(s.name.to_string(), s.as_ref());
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            ctx: file.context(),
+            interfaces,
+            structs,
+        }
+    }
+}
+
+/// Items present in only one or both of the maps
+struct MapDiff<'a, K, V> {
+    added: Vec<(&'a K, V)>,
+    removed: Vec<(&'a K, V)>,
+    common: Vec<(&'a K, V, V)>,
+}
+
+fn compare_maps<'a, K: Ord, V: Copy>(
+    old: &'a BTreeMap<K, V>,
+    new: &'a BTreeMap<K, V>,
+) -> MapDiff<'a, K, V> {
+    let mut res = MapDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        common: Vec::new(),
+    };
+
+    for (key, &old_value) in old.iter() {
+        match new.get(key) {
+            Some(&new_value) => res.common.push((key, old_value, new_value)),
+            None => res.removed.push((key, old_value)),
+        }
+    }
+    for (key, &new_value) in new.iter() {
+        if !old.contains_key(key) {
+            res.added.push((key, new_value));
+        }
+    }
+
+    res
+}
+
+/// Drops the source locations from the value, as they are different in the two versions even if nothing has changed
+fn without_location(value: &Value) -> Value {
+    match value {
+        Value::InObject(name, _) => Value::InObject(name.clone(), Span::default()),
+        Value::OutObject(name, _) => Value::OutObject(name.clone(), Span::default()),
+        Value::OptionalOutObject(name, _) => {
+            Value::OptionalOutObject(name.clone(), Span::default())
+        }
+        v => v.clone(),
+    }
+}
+
+fn same_arguments(old: &Command, new: &Command) -> bool {
+    old.arguments.len() == new.arguments.len()
+        && old.arguments.iter().zip(new.arguments.iter()).all(
+            |((old_name, old_value), (new_name, new_value))| {
+                old_name == new_name && without_location(old_value) == without_location(new_value)
+            },
+        )
+}
+
+fn compare_commands(
+    old: &Definitions,
+    new: &Definitions,
+    old_interface: &Interface,
+    new_interface: &Interface,
+) -> Vec<String> {
+    fn by_id(interface: &Interface) -> BTreeMap<u32, &Command> {
+        interface.commands.iter().map(|c| (c.id, c)).collect()
+    }
+    let old_commands = by_id(old_interface);
+    let new_commands = by_id(new_interface);
+
+    let commands = compare_maps(&old_commands, &new_commands);
+
+    let mut details = Vec::new();
+    for (id, command) in commands.removed {
+        details.push(format!("- command [{}] {}", id, command.name));
+    }
+    for (id, command) in commands.added {
+        details.push(format!("+ command [{}] {}", id, command.name));
+    }
+    for (id, old_command, new_command) in commands.common {
+        if old_command.name != new_command.name {
+            details.push(format!(
+                "~ command [{}] renamed from {} to {}",
+                id, old_command.name, new_command.name
+            ));
+        }
+        if !same_arguments(old_command, new_command) {
+            let old_summary = command_wire_summary(old.ctx, old_interface, old_command);
+            let new_summary = command_wire_summary(new.ctx, new_interface, new_command);
+
+            let mut detail = format!("~ command [{}] {} signature changed", id, new_command.name);
+            if old_summary.in_bytes != new_summary.in_bytes {
+                detail.push_str(&format!(
+                    ", inbytes {} -> {}",
+                    old_summary.in_bytes, new_summary.in_bytes
+                ));
+            }
+            if old_summary.out_bytes != new_summary.out_bytes {
+                detail.push_str(&format!(
+                    ", outbytes {} -> {}",
+                    old_summary.out_bytes, new_summary.out_bytes
+                ));
+            }
+            if old_summary.buffer_directions != new_summary.buffer_directions {
+                detail.push_str(&format!(
+                    ", buffers {:?} -> {:?}",
+                    old_summary.buffer_directions, new_summary.buffer_directions
+                ));
+            }
+            details.push(detail);
+        }
+    }
+
+    details
+}
+
+fn compare_interfaces(old: &Definitions, new: &Definitions, changes: &mut Vec<Change>) {
+    let interfaces = compare_maps(&old.interfaces, &new.interfaces);
+
+    for (name, _) in interfaces.removed {
+        changes.push(Change::removed(format!("interface `{}`", name)));
+    }
+    for (name, _) in interfaces.added {
+        changes.push(Change::added(format!("interface `{}`", name)));
+    }
+
+    for (name, old_interface, new_interface) in interfaces.common {
+        let mut details = Vec::new();
+
+        if old_interface.is_domain != new_interface.is_domain {
+            details.push(format!(
+                "~ is_domain {} -> {}",
+                old_interface.is_domain, new_interface.is_domain
+            ));
+        }
+        if old_interface.sm_names != new_interface.sm_names {
+            details.push(format!(
+                "~ service names {:?} -> {:?}",
+                old_interface.sm_names, new_interface.sm_names
+            ));
+        }
+        details.extend(compare_commands(old, new, old_interface, new_interface));
+
+        if !details.is_empty() {
+            changes.push(Change::changed(format!("interface `{}`", name), details));
+        }
+    }
+}
+
+fn compare_structs(old: &Definitions, new: &Definitions, changes: &mut Vec<Change>) {
+    let structs = compare_maps(&old.structs, &new.structs);
+
+    for (name, _) in structs.removed {
+        changes.push(Change::removed(format!("struct `{}`", name)));
+    }
+    for (name, _) in structs.added {
+        changes.push(Change::added(format!("struct `{}`", name)));
+    }
+
+    for (name, old_struct, new_struct) in structs.common {
+        let mut details = Vec::new();
+
+        // the layout can change even if the struct itself didn't, when a type it uses has changed
+        let old_layout = old_struct.layout(old.ctx);
+        let new_layout = new_struct.layout(new.ctx);
+        if old_layout.size() != new_layout.size() {
+            details.push(format!(
+                "~ size 0x{:x} -> 0x{:x}",
+                old_layout.size(),
+                new_layout.size()
+            ));
+        }
+        if old_layout.alignment() != new_layout.alignment() {
+            details.push(format!(
+                "~ alignment 0x{:x} -> 0x{:x}",
+                old_layout.alignment(),
+                new_layout.alignment()
+            ));
+        }
+        if old_struct.fields != new_struct.fields {
+            details.push("~ fields changed".to_string());
+        }
+        if old_struct.is_large_data != new_struct.is_large_data
+            || old_struct.preferred_transfer_mode != new_struct.preferred_transfer_mode
+        {
+            details.push("~ markers changed".to_string());
+        }
+
+        if !details.is_empty() {
+            changes.push(Change::changed(format!("struct `{}`", name), details));
+        }
+    }
+}
+
+/// Lists the interfaces and structs that were added, removed or changed between the two versions
+pub fn diff(old: &TypecheckedIpcFile, new: &TypecheckedIpcFile) -> Vec<Change> {
+    let old = Definitions::new(old);
+    let new = Definitions::new(new);
+
+    let mut changes = Vec::new();
+    compare_interfaces(&old, &new, &mut changes);
+    compare_structs(&old, &new, &mut changes);
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::swipc::diff::{diff, Change, ChangeKind};
+    use crate::swipc::model::TypecheckedIpcFile;
+    use crate::swipc::tests::{parse_typechecked_ipc_file, unwrap_parse};
+
+    const OLD: &str = r#"
+        struct fs::Info {
+            u32 a;
+        }
+
+        interface fs::IFile {
+            [0] Read(u64 offset, sf::OutBuffer data, sf::Out<u64> read);
+            [1] GetSize(sf::Out<u64> size);
+        }
+    "#;
+
+    const NEW: &str = r#"
+        struct fs::Info {
+            u32 a;
+            u32 b;
+        }
+
+        interface fs::IFile {
+            [0] Read(u32 option, u64 offset, sf::OutBuffer data, sf::Out<u64> read);
+            [1] GetSize(sf::Out<u64> size);
+            [2] Flush();
+        }
+
+        interface fs::IDirectory {
+        }
+    "#;
+
+    #[test]
+    fn unchanged() {
+        let old: TypecheckedIpcFile = unwrap_parse(OLD, parse_typechecked_ipc_file);
+        let new: TypecheckedIpcFile = unwrap_parse(OLD, parse_typechecked_ipc_file);
+
+        assert_eq!(diff(&old, &new), vec![]);
+    }
+
+    #[test]
+    fn command_signature_change() {
+        let old: TypecheckedIpcFile = unwrap_parse(OLD, parse_typechecked_ipc_file);
+        let new: TypecheckedIpcFile = unwrap_parse(NEW, parse_typechecked_ipc_file);
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                Change {
+                    kind: ChangeKind::Added,
+                    item: "interface `fs::IDirectory`".to_string(),
+                    details: vec![],
+                },
+                Change {
+                    kind: ChangeKind::Changed,
+                    item: "interface `fs::IFile`".to_string(),
+                    details: vec![
+                        "+ command [2] Flush".to_string(),
+                        "~ command [0] Read signature changed, inbytes 8 -> 16".to_string(),
+                    ],
+                },
+                Change {
+                    kind: ChangeKind::Changed,
+                    item: "struct `fs::Info`".to_string(),
+                    details: vec![
+                        "~ size 0x4 -> 0x8".to_string(),
+                        "~ fields changed".to_string()
+                    ],
+                },
+            ]
+        );
+    }
+}
@@ -16,7 +16,9 @@ use lalrpop_util::lalrpop_mod;
 
 pub mod cli;
 pub mod codegen;
+pub mod crosscheck;
 pub mod diagnostics;
+pub mod diff;
 pub mod layout;
 pub mod model;
 mod typecheck;
@@ -33,11 +35,12 @@ lalrpop_mod!(
 mod tests {
     use crate::swipc::diagnostics::{diagnostics_and_files_from_parse_error, Span};
     use crate::swipc::model::{
-        BufferTransferMode, IntType, Interface, NamespacedIdent, NominalType, Struct, StructField,
-        TypeAlias, TypecheckedIpcFile,
+        BufferTransferMode, HandleTransferType, IntType, Interface, NamespacedIdent, NominalType,
+        Struct, StructField, TypeAlias, TypecheckOptions, TypecheckedIpcFile, Value, Version,
+        VersionReq,
     };
     use crate::swipc::parser;
-    use codespan_reporting::diagnostic::Diagnostic;
+    use codespan_reporting::diagnostic::{Diagnostic, Severity};
     use codespan_reporting::term::termcolor::Buffer;
     use lalrpop_util::lexer::Token;
     use std::default::Default;
@@ -249,11 +252,82 @@ interface sm::detail::IUserInterface is "sm:" {
         assert_eq!(interface.sm_names, vec!["sm:"]);
     }
 
+    #[test]
+    fn optional_out_handles() {
+        let s = r#"
+interface IHelloInterface {
+    [0] OpenObject(sf::Out<sf::Optional<sf::SharedPointer<IObject>>> object);
+    [1] GetEvent(sf::OutOptionalCopyHandle event);
+}
+        "#;
+        let interface: Interface = unwrap_parse(s, parse_interface);
+
+        let (name, value) = &interface.commands[0].arguments[0];
+        assert_eq!(name.as_deref(), Some("object"));
+        assert!(matches!(
+            value.as_ref(),
+            Value::OptionalOutObject(iface, _) if iface == &NamespacedIdent::parse("IObject").unwrap()
+        ));
+
+        let (name, value) = &interface.commands[1].arguments[0];
+        assert_eq!(name.as_deref(), Some("event"));
+        assert_eq!(
+            value.as_ref(),
+            &Value::OptionalOutHandle(HandleTransferType::Copy)
+        );
+    }
+
+    #[test]
+    fn versioned_commands() {
+        let s = r#"
+interface IHelloInterface {
+    [0] Hello();
+    @version(4.0.0+)
+    [1] HelloNew(u32 value);
+    @undocumented
+    @version(1.0.0-3.0.2)
+    [2] HelloOld();
+}
+        "#;
+        let interface: Interface = unwrap_parse(s, parse_interface);
+
+        assert_eq!(interface.commands[0].version_req, VersionReq::default());
+        assert_eq!(
+            interface.commands[1].version_req,
+            VersionReq {
+                min: Some(Version::new(4, 0, 0)),
+                max: None,
+            }
+        );
+        assert_eq!(
+            interface.commands[2].version_req,
+            VersionReq {
+                min: Some(Version::new(1, 0, 0)),
+                max: Some(Version::new(3, 0, 2)),
+            }
+        );
+
+        assert!(interface.commands[2]
+            .version_req
+            .contains(Version::new(3, 0, 2)));
+        assert!(!interface.commands[2]
+            .version_req
+            .contains(Version::new(4, 0, 0)));
+        assert_eq!(interface.commands[1].version_req.to_string(), "4.0.0+");
+    }
+
+    /// Parses and typechecks the file, treating the warnings as errors so that they can be matched too
     pub fn parse_typechecked_ipc_file(s: &str) -> Result<TypecheckedIpcFile, ParseError> {
-        parser::IpcFileParser::new()
+        let file = parser::IpcFileParser::new()
             .parse(0, s)?
             .typecheck()
-            .map_err(|error| ParseError::User { error })
+            .map_err(|error| ParseError::User { error })?;
+        if !file.warnings().is_empty() {
+            return Err(ParseError::User {
+                error: file.warnings().clone(),
+            });
+        }
+        Ok(file)
     }
 
     #[test]
@@ -331,6 +405,22 @@ enum Test : u8 {
         );
     }
 
+    #[test]
+    fn enum_overflow_suggestion() {
+        let s = r#"
+enum Test : u8 {
+    Ok1 = 0,
+    Large = 256,
+    Larger = 300,
+}
+        "#;
+        unwrap_err_parse(
+            s,
+            parse_typechecked_ipc_file,
+            "The values require at least u16",
+        );
+    }
+
     #[test]
     fn enum_duplicate_val_file() {
         let s = r#"
@@ -610,4 +700,58 @@ interface PascalName {
 
         println!("{:#?}", file);
     }
+
+    #[test]
+    fn oversized_pointer_buffer_struct() {
+        let s = r#"
+struct BigStruct {
+    sf::Bytes<0x300> data;
+}
+interface IBig {
+    [0] Send(sf::InPointerArray<BigStruct> data);
+}
+        "#;
+        // it's only a warning, so the file is still usable
+        let file = unwrap_parse(s, |s| {
+            parser::IpcFileParser::new()
+                .parse(0, s)?
+                .typecheck()
+                .map_err(|error| ParseError::User { error })
+        });
+        let warnings = file
+            .warnings()
+            .iter()
+            .map(|w| (w.severity, w.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            [(
+                Severity::Warning,
+                "Struct `BigStruct` (0x300 bytes) is sent via an in-pointer buffer, but it's larger than 0x200 bytes"
+            )]
+        );
+
+        // the threshold is configurable
+        let options = TypecheckOptions {
+            pointer_buffer_warn_size: 0x400,
+        };
+        let file = unwrap_parse(s, |s| {
+            parser::IpcFileParser::new()
+                .parse(0, s)?
+                .typecheck_with_options(&options)
+                .map_err(|error| ParseError::User { error })
+        });
+        assert!(file.warnings().is_empty());
+
+        // marked structs are fine
+        let s = r#"
+struct BigStruct : sf::LargeData {
+    sf::Bytes<0x300> data;
+}
+interface IBig {
+    [0] Send(sf::InPointerArray<BigStruct> data);
+}
+        "#;
+        unwrap_parse(s, parse_typechecked_ipc_file);
+    }
 }
@@ -10,6 +10,7 @@ use std::cell::RefCell;
 use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
 use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
 use std::sync::Arc;
 
 pub type Namespace = Arc<Vec<ArcStr>>;
@@ -269,6 +270,10 @@ pub enum Value {
     InObject(NamespacedIdent, Span),
     /// sf::Out<sf::SharedPointer<T>>
     OutObject(Option<NamespacedIdent>, Span),
+    /// sf::Out<sf::Optional<sf::SharedPointer<T>>>
+    ///
+    /// The server may not return the object, which is detected by the absence of the handle
+    OptionalOutObject(NamespacedIdent, Span),
 
     /// sf::CopyHandle
     /// sf::MoveHandle
@@ -276,6 +281,9 @@ pub enum Value {
     /// sf::OutCopyHandle
     /// sf::OutMoveHandle
     OutHandle(HandleTransferType),
+    /// sf::OutOptionalCopyHandle
+    /// sf::OutOptionalMoveHandle
+    OptionalOutHandle(HandleTransferType),
 
     /// sf::InArray
     /// sf::InMapAliasArray
@@ -344,6 +352,32 @@ impl IntType {
         let max_val = self.max_value();
         value <= max_val
     }
+
+    /// The smallest type with the given signedness that `value` fits into
+    pub fn smallest_fitting(value: u64, signed: bool) -> Option<IntType> {
+        use IntType::*;
+        let types = if signed {
+            [I8, I16, I32, I64]
+        } else {
+            [U8, U16, U32, U64]
+        };
+
+        types.into_iter().find(|t| t.fits_u64(value))
+    }
+
+    /// The name of the type in SwIPC
+    pub fn display(&self) -> &'static str {
+        match self {
+            IntType::U8 => "u8",
+            IntType::U16 => "u16",
+            IntType::U32 => "u32",
+            IntType::U64 => "u64",
+            IntType::I8 => "i8",
+            IntType::I16 => "i16",
+            IntType::I32 => "i32",
+            IntType::I64 => "i64",
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -471,14 +505,90 @@ pub struct Bitflags {
     pub location: Span,
 }
 
+/// A HOS version, like `4.0.0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub micro: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, micro: u32) -> Self {
+        Self {
+            major,
+            minor,
+            micro,
+        }
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.micro)
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s
+            .split('.')
+            .map(|p| p.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid version `{}`: {}", s, e))?;
+
+        match parts.as_slice() {
+            &[major, minor, micro] => Ok(Self::new(major, minor, micro)),
+            _ => Err(format!(
+                "Invalid version `{}`: expected `major.minor.micro`",
+                s
+            )),
+        }
+    }
+}
+
+/// The versions a command exists in, set with the `@version` decorator
+///
+/// `@version(4.0.0+)` sets only the min version, `@version(1.0.0-3.0.2)` both,
+///  and `@version(5.0.0)` limits the command to a single version. Both bounds are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct VersionReq {
+    pub min: Option<Version>,
+    pub max: Option<Version>,
+}
+
+impl VersionReq {
+    pub fn is_any(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    pub fn contains(&self, version: Version) -> bool {
+        self.min.map_or(true, |min| min <= version) && self.max.map_or(true, |max| version <= max)
+    }
+}
+
+impl Display for VersionReq {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.min, self.max) {
+            (None, None) => write!(f, "any"),
+            (Some(min), None) => write!(f, "{}+", min),
+            (None, Some(max)) => write!(f, "-{}", max),
+            (Some(min), Some(max)) if min == max => write!(f, "{}", min),
+            (Some(min), Some(max)) => write!(f, "{}-{}", min, max),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Derivative)]
 #[derivative(PartialEq)]
 pub struct Command {
-    // TODO: do we want to support multiple versions & version requirements at all?
     pub id: u32,
     pub name: ArcStr,
     // those define both in and out arguments
     pub arguments: Vec<(Option<ArcStr>, Arc<Value>)>,
+    pub version_req: VersionReq,
     #[derivative(PartialEq = "ignore")]
     pub location: Span,
 }
@@ -596,22 +706,64 @@ impl CodegenContext {
     }
 }
 
+/// An `include "path";` directive, the path is relative to the including file
+#[derive(Debug, Clone, Derivative)]
+#[derivative(PartialEq)]
+pub struct Include {
+    pub path: ArcStr,
+    #[derivative(PartialEq = "ignore")]
+    pub location: Span,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct IpcFile {
+    pub includes: Vec<Include>,
     pub items: Vec<IpcFileItem>,
 }
 
 impl IpcFile {
     pub fn new() -> Self {
-        Self { items: Vec::new() }
+        Self {
+            includes: Vec::new(),
+            items: Vec::new(),
+        }
     }
 
     pub fn merge_with(&mut self, another: IpcFile) {
+        self.includes.extend(another.includes);
         self.items.extend(another.items)
     }
 
     pub fn typecheck(self) -> diagnostics::Result<TypecheckedIpcFile> {
-        TypecheckedIpcFile::try_new(self.items)
+        self.typecheck_with_options(&TypecheckOptions::default())
+    }
+
+    pub fn typecheck_with_options(
+        self,
+        options: &TypecheckOptions,
+    ) -> diagnostics::Result<TypecheckedIpcFile> {
+        TypecheckedIpcFile::try_new(self.items, options)
+    }
+}
+
+/// Default for [TypecheckOptions::pointer_buffer_warn_size]
+pub const DEFAULT_POINTER_BUFFER_WARN_SIZE: u64 = 0x200;
+
+/// Knobs of the lints run during typecheck
+#[derive(Debug, Clone)]
+pub struct TypecheckOptions {
+    /// Warn about structs larger than this sent via in-pointer buffers without `sf::LargeData`
+    ///
+    /// The pointer buffer of the server is small (usually 0x300-0x500 bytes), so such structs likely need to be
+    ///  transferred with map alias instead.
+    pub pointer_buffer_warn_size: u64,
+}
+
+impl Default for TypecheckOptions {
+    fn default() -> Self {
+        Self {
+            pointer_buffer_warn_size: DEFAULT_POINTER_BUFFER_WARN_SIZE,
+        }
     }
 }
 
@@ -619,10 +771,17 @@ impl IpcFile {
 pub struct TypecheckedIpcFile {
     items: Vec<IpcFileItem>,
     context: CodegenContext,
+    warnings: diagnostics::Error,
 }
 
 impl TypecheckedIpcFile {
-    pub fn try_new(items: Vec<IpcFileItem>) -> diagnostics::Result<Self> {
+    /// Fails only if there are errors, the warnings are returned along with the errors then
+    ///
+    /// Otherwise the warnings are available with [Self::warnings].
+    pub fn try_new(
+        items: Vec<IpcFileItem>,
+        options: &TypecheckOptions,
+    ) -> diagnostics::Result<Self> {
         let mut named_types = BTreeMap::new();
         let mut interfaces = BTreeMap::new();
 
@@ -718,7 +877,29 @@ impl TypecheckedIpcFile {
             resolved_type_names,
         };
 
-        res.map(|_| Self { items, context })
+        // the lints below need the layouts, which can only be computed when everything resolved
+        if !res.as_ref().err().is_some_and(diagnostics::is_diags_fatal) {
+            for interface in context.resolved_interfaces.values() {
+                res.extend_result(interface.check_pointer_buffers(&context, options));
+            }
+        }
+
+        let warnings = match res {
+            Ok(()) => Vec::new(),
+            Err(diags) if diagnostics::is_diags_fatal(&diags) => return Err(diags),
+            Err(warnings) => warnings,
+        };
+
+        Ok(Self {
+            items,
+            context,
+            warnings,
+        })
+    }
+
+    /// The non-fatal diagnostics found during typecheck
+    pub fn warnings(&self) -> &diagnostics::Error {
+        &self.warnings
     }
 
     pub fn iter_items(&self) -> impl Iterator<Item = &IpcFileItem> {
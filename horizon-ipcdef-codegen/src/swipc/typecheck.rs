@@ -1,8 +1,9 @@
 use crate::swipc::diagnostics;
 use crate::swipc::diagnostics::{DiagnosticErrorExt, DiagnosticExt, DiagnosticResultExt, Span};
 use crate::swipc::model::{
-    Bitflags, BitflagsArm, Command, Enum, EnumArm, IntType, Interface, IpcFileItem,
-    NamespacedIdent, Struct, StructField, StructuralType, TypeWithName, TypecheckContext, Value,
+    Bitflags, BitflagsArm, BufferTransferMode, CodegenContext, Command, Enum, EnumArm, IntType,
+    Interface, IpcFileItem, NamespacedIdent, Struct, StructField, StructuralType, TypeWithName,
+    TypecheckContext, TypecheckOptions, Value,
 };
 use arcstr::ArcStr;
 use codespan_reporting::diagnostic::Diagnostic;
@@ -18,12 +19,15 @@ impl Value {
             Value::ClientProcessId
             | Value::InHandle(_)
             | Value::OutHandle(_)
+            | Value::OptionalOutHandle(_)
             | Value::InBuffer(_, _)
             | Value::OutBuffer(_, _) => Ok(()),
             Value::In(t) | Value::Out(t) | Value::InArray(t, _) | Value::OutArray(t, _) => {
                 t.typecheck_resolve(context).map(|_| ())
             }
-            Value::InObject(obj, location) => context.resolve_interface(obj, location).map(|_| ()),
+            Value::InObject(obj, location) | Value::OptionalOutObject(obj, location) => {
+                context.resolve_interface(obj, location).map(|_| ())
+            }
             Value::OutObject(obj, location) => obj
                 .as_ref()
                 .map(|obj| context.resolve_interface(obj, location).map(|_| ()))
@@ -99,15 +103,29 @@ impl Struct {
     }
 }
 
+/// Suggests the smallest underlying type fitting all the arms, the author has likely guessed the declared one
+fn widening_notes(base_type: IntType, max_arm_value: u64) -> Vec<String> {
+    IntType::smallest_fitting(max_arm_value, base_type.is_signed())
+        .map(|t| format!("The values require at least {}", t.display()))
+        .into_iter()
+        .collect()
+}
+
 impl EnumArm {
-    pub fn typecheck(&self, _context: &TypecheckContext, base_type: IntType) -> Result<()> {
+    pub fn typecheck(
+        &self,
+        _context: &TypecheckContext,
+        base_type: IntType,
+        max_arm_value: u64,
+    ) -> Result<()> {
         if !base_type.fits_u64(self.value) {
             return Err(vec![Diagnostic::error()
                 .with_message(format!(
                     "Value {} of enum arm `{}` does not fit into type {:?}",
                     self.value, self.name, base_type
                 ))
-                .with_primary_label(self.location)]);
+                .with_primary_label(self.location)
+                .with_notes(widening_notes(base_type, max_arm_value))]);
         }
         Ok(())
     }
@@ -120,9 +138,11 @@ impl Enum {
         let mut arm_values: BTreeMap<u64, &EnumArm> = BTreeMap::new();
         let mut arm_names: BTreeMap<&ArcStr, &EnumArm> = BTreeMap::new();
 
+        let max_arm_value = self.arms.iter().map(|a| a.value).max().unwrap_or(0);
+
         for arm in self.arms.iter() {
             res.extend_result(
-                arm.typecheck(context, self.base_type)
+                arm.typecheck(context, self.base_type, max_arm_value)
                     .with_context(self.location, || format!("In enum `{}`", self.name)),
             );
 
@@ -168,14 +188,20 @@ impl Enum {
 }
 
 impl BitflagsArm {
-    pub fn typecheck(&self, _context: &TypecheckContext, base_type: IntType) -> Result<()> {
+    pub fn typecheck(
+        &self,
+        _context: &TypecheckContext,
+        base_type: IntType,
+        max_arm_value: u64,
+    ) -> Result<()> {
         if !base_type.fits_u64(self.value) {
             return Err(vec![Diagnostic::error()
                 .with_message(format!(
                     "Value {} of bitflags arm `{}` does not fit into type {:?}",
                     self.value, self.name, base_type
                 ))
-                .with_labels(vec![self.location.primary_label()])]);
+                .with_labels(vec![self.location.primary_label()])
+                .with_notes(widening_notes(base_type, max_arm_value))]);
         }
         Ok(())
     }
@@ -187,9 +213,11 @@ impl Bitflags {
 
         let mut arm_names: BTreeMap<&ArcStr, &BitflagsArm> = BTreeMap::new();
 
+        let max_arm_value = self.arms.iter().map(|a| a.value).max().unwrap_or(0);
+
         for arm in self.arms.iter() {
             res.extend_result(
-                arm.typecheck(context, self.base_type)
+                arm.typecheck(context, self.base_type, max_arm_value)
                     .with_context(self.location, || format!("In bitflags `{}`", self.name)),
             );
 
@@ -276,6 +304,77 @@ impl Interface {
     }
 }
 
+impl Command {
+    /// Warns about big structs sent via in-pointer buffers, see [TypecheckOptions::pointer_buffer_warn_size]
+    ///
+    /// `sf::LargeData` structs are exempt, the marker says the size was taken into account.
+    pub fn check_pointer_buffers(
+        &self,
+        context: &CodegenContext,
+        options: &TypecheckOptions,
+    ) -> Result<()> {
+        let mut res = Ok(());
+
+        for (_, arg) in self.arguments.iter() {
+            let (ty, transfer_mode) = match arg.as_ref() {
+                Value::InArray(ty, transfer_mode) => (ty, transfer_mode),
+                _ => continue,
+            };
+
+            let struct_ty = context.resolve_type(ty);
+            let transfer_mode =
+                transfer_mode.unwrap_or_else(|| struct_ty.preferred_transfer_mode());
+
+            let s = match &struct_ty {
+                StructuralType::Struct(s) => s,
+                _ => continue,
+            };
+            if transfer_mode != BufferTransferMode::Pointer || s.is_large_data {
+                continue;
+            }
+
+            let size = s.layout(context).size();
+            if size > options.pointer_buffer_warn_size {
+                res.push(
+                    Diagnostic::warning()
+                        .with_message(format!(
+                            "Struct `{}` ({:#x} bytes) is sent via an in-pointer buffer, but it's larger than {:#x} bytes",
+                            s.name, size, options.pointer_buffer_warn_size
+                        ))
+                        .with_primary_label(self.location)
+                        .with_secondary_label(s.location, "Struct defined here")
+                        .with_notes(vec![
+                            "It likely needs to be transferred with map alias, or marked with sf::LargeData if the size is fine"
+                                .to_string(),
+                        ]),
+                );
+            }
+        }
+
+        res
+    }
+}
+
+impl Interface {
+    pub fn check_pointer_buffers(
+        &self,
+        context: &CodegenContext,
+        options: &TypecheckOptions,
+    ) -> Result<()> {
+        let mut res = Ok(());
+
+        for command in self.commands.iter() {
+            res.extend_result(
+                command
+                    .check_pointer_buffers(context, options)
+                    .with_context(self.location, || format!("In interface `{}`", self.name)),
+            );
+        }
+
+        res
+    }
+}
+
 fn case_name(case: Case) -> &'static str {
     match case {
         Case::Camel => "camelCase",
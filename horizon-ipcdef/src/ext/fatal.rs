@@ -0,0 +1,86 @@
+//! Reporting errors to the user through the fatal service
+
+ij_core_workaround!();
+
+use crate::fatal::{FatalPolicy, IService};
+use crate::sm::{IUserInterface, ServiceName};
+use horizon_error::{ErrorCode, Result};
+use horizon_ipc::handle_storage::HandleStorage;
+
+impl IService {
+    /// Opens a new session to `fatal:u`
+    ///
+    /// No session is kept around for the process, the service is only needed once
+    pub fn open() -> Result<IService> {
+        let sm = IUserInterface::get()?;
+        Ok(IService::new(
+            sm.get_service(ServiceName::try_new("fatal:u").unwrap())?,
+        ))
+    }
+}
+
+/// Reports `code` to the fatal service, which saves an error report and/or shows the error screen, depending on `policy`
+///
+/// The error screen shows the code to the user and requires the system to be rebooted, so with the policies
+///  showing it the process is never resumed. With [FatalPolicy::ErrorReport] the call returns after the report is saved.
+pub fn throw_fatal(code: ErrorCode, policy: FatalPolicy) -> Result<()> {
+    throw_fatal_with(&IService::open()?, code, policy)
+}
+
+fn throw_fatal_with<S: HandleStorage>(
+    service: &IService<S>,
+    code: ErrorCode,
+    policy: FatalPolicy,
+) -> Result<()> {
+    service.throw_fatal_with_policy(code.repr(), policy)
+}
+
+#[cfg(test)]
+mod test {
+    use super::throw_fatal_with;
+    use crate::fatal::{FatalPolicy, IService};
+    use horizon_error::ErrorCode;
+    use horizon_ipc::buffer::get_ipc_buffer;
+    use horizon_ipc::handle_storage::RefHandle;
+    use horizon_svc::RawHandle;
+
+    #[test]
+    fn policy_values() {
+        // the values the fatal service expects
+        assert_eq!(FatalPolicy::ErrorReportAndErrorScreen as u32, 0);
+        assert_eq!(FatalPolicy::ErrorReport as u32, 1);
+        assert_eq!(FatalPolicy::ErrorScreen as u32, 2);
+        assert_eq!(FatalPolicy::try_from(2), Ok(FatalPolicy::ErrorScreen));
+        assert_eq!(FatalPolicy::try_from(3), Err(3));
+    }
+
+    #[test]
+    fn throw_fatal_request() {
+        // the kernel rejects the invalid handle without touching the request left in the IPC buffer
+        let service = IService::new(RefHandle::new(RawHandle(0)));
+        let code = ErrorCode::from_parts(2, 1);
+        assert!(throw_fatal_with(&service, code, FatalPolicy::ErrorScreen).is_err());
+
+        let request = unsafe { &get_ipc_buffer()[..0x44] };
+        #[rustfmt::skip]
+        let expected = [
+            // hipc header: a request with 12 raw data words and the special header
+            0x04, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x80,
+            // special header: send the pid, no handles
+            0x01, 0x00, 0x00, 0x00,
+            // the pid placeholder filled in by the kernel
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // padding up to the 16-byte aligned cmif header
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // cmif header: "SFCI", version 1, command 1 (ThrowFatalWithPolicy), token 0
+            b'S', b'F', b'C', b'I', 0x01, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // the error code (2002-0001), the policy, the pid placeholder of the raw data
+            0x02, 0x02, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // post padding
+            0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(request, expected);
+    }
+}
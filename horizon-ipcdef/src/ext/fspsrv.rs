@@ -1,10 +1,16 @@
 ij_core_workaround!();
 
-use crate::fssrv::{IFileSystemProxy, Path};
+use crate::fssrv::{
+    IDirectory, IFile, IFileSystem, IFileSystemProxy, OpenDirectoryMode, OpenFileMode, Path,
+};
 use crate::sm::{IUserInterface, ServiceName};
+use core::fmt;
+use core::ops::Deref;
 use core::str::Utf8Error;
-use horizon_error::Result;
+use core::time::Duration;
+use horizon_error::{ErrorCode, ErrorCodeModule, FsErrorCode, Result};
 use horizon_global::services;
+use horizon_ipc::handle_storage::{HandleStorage, OwnedHandle};
 
 impl IFileSystemProxy {
     pub fn get() -> Result<IFileSystemProxy<services::fs::Guard>> {
@@ -51,3 +57,228 @@ impl AsRef<[u8]> for Path {
         &self.str[..len]
     }
 }
+
+/// Delay between the attempts in [with_retry]
+const RETRY_DELAY: Duration = Duration::from_millis(10);
+
+/// Checks whether the fs error can go away on its own, so the operation is worth retrying
+///
+/// The following errors are considered transient:
+/// - `TargetLocked` (2-0007): the file or directory is being used by someone else
+/// - `SdCardAccessFailed` range (2-2000..2-2499), except for `SdCardNoDevice` (2-2001):
+///   communication with the SD card failed
+/// - `AllocationFailure` range (2-3200..2-3499): the fs server temporarily ran out of memory
+pub fn is_transient_fs_error(error: ErrorCode) -> bool {
+    if error.get_module() != FsErrorCode::MODULE {
+        return false;
+    }
+
+    matches!(error.get_description(), 7 | 2000 | 2002..=2499 | 3200..=3499)
+}
+
+/// Runs an fs operation, retrying it up to `attempts` times total if it fails with a transient error
+///
+/// The operation is always run at least once. See [is_transient_fs_error] for the errors that are retried.
+pub fn with_retry<T>(attempts: usize, mut op: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(e) if attempt < attempts && is_transient_fs_error(e) => {
+                horizon_svc::sleep_thread(RETRY_DELAY);
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+impl<S: HandleStorage> IFileSystem<S> {
+    /// Checks whether there is a file or a directory at `path`
+    pub fn exists(&self, path: &Path) -> Result<bool> {
+        const PATH_NOT_FOUND: ErrorCode = FsErrorCode::PathNotFound.code();
+
+        Ok(ErrorCode::or_none_if(self.get_entry_type(path), PATH_NOT_FOUND)?.is_some())
+    }
+
+    /// Starts a transaction over the filesystem, see [FsTransaction]
+    pub fn transaction(&self) -> FsTransaction<'_, S> {
+        FsTransaction {
+            fs: self,
+            done: false,
+        }
+    }
+}
+
+/// A guard committing the changes made to an [IFileSystem] when it goes out of scope
+///
+/// If the guard is dropped during a panic, the commit is skipped, so that a half-done modification
+///  isn't persisted. For journaled filesystems (like save data) the uncommitted changes are discarded
+///  when the filesystem is closed. Detecting the panic requires the `std` feature; without it the
+///  guard always commits.
+///
+/// Errors from the commit on drop are ignored, use [FsTransaction::commit] to handle them.
+#[must_use = "the transaction is committed as soon as the guard is dropped"]
+pub struct FsTransaction<'a, S: HandleStorage> {
+    fs: &'a IFileSystem<S>,
+    done: bool,
+}
+
+impl<S: HandleStorage> FsTransaction<'_, S> {
+    pub fn commit(mut self) -> Result<()> {
+        self.done = true;
+        self.fs.commit()
+    }
+
+    /// Drops the guard without committing the changes
+    pub fn rollback(mut self) {
+        self.done = true;
+    }
+}
+
+impl<S: HandleStorage> Drop for FsTransaction<'_, S> {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+
+        #[cfg(feature = "std")]
+        if std::thread::panicking() {
+            return;
+        }
+
+        let _ = self.fs.commit();
+    }
+}
+
+/// An fs object paired with the path it was opened with, so that the path shows up in its `Debug` output
+///
+/// The methods of the object are available through `Deref`. The generated types don't carry the path
+///  themselves to stay as small as the handle, so this is opt-in, see [IFileSystem::open_file_tracked].
+pub struct Tracked<T> {
+    object: T,
+    path: Path,
+}
+
+pub type TrackedFile<S = OwnedHandle> = Tracked<IFile<S>>;
+pub type TrackedDirectory<S = OwnedHandle> = Tracked<IDirectory<S>>;
+
+impl<T> Tracked<T> {
+    pub fn new(object: T, path: &Path) -> Self {
+        Self {
+            object,
+            path: *path,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn into_inner(self) -> T {
+        self.object
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.object
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Tracked<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("Tracked");
+        match self.path.as_str() {
+            Ok(path) => s.field("path", &path),
+            Err(_) => s.field("path", &self.path.as_ref()),
+        };
+        s.field("object", &self.object).finish()
+    }
+}
+
+impl<S: HandleStorage> IFileSystem<S> {
+    /// Same as [IFileSystem::open_file], keeping the path for diagnostics
+    pub fn open_file_tracked(&self, path: &Path, mode: OpenFileMode) -> Result<TrackedFile> {
+        Ok(Tracked::new(self.open_file(path, mode)?, path))
+    }
+
+    /// Same as [IFileSystem::open_directory], keeping the path for diagnostics
+    pub fn open_directory_tracked(
+        &self,
+        path: &Path,
+        mode: OpenDirectoryMode,
+    ) -> Result<TrackedDirectory> {
+        Ok(Tracked::new(self.open_directory(path, mode)?, path))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{with_retry, TrackedFile};
+    use crate::fssrv::{IFile, Path};
+    use horizon_error::{ErrorCode, FsErrorCode};
+    use horizon_ipc::handle_storage::{OwnedHandle, RefHandle};
+    use horizon_svc::RawHandle;
+    use std::format;
+
+    #[test]
+    fn tracked_file_debug() {
+        let file = TrackedFile::new(
+            IFile::new(OwnedHandle::new(RawHandle(0x1234))),
+            &Path::new("/config/app.ini"),
+        );
+
+        assert_eq!(
+            format!("{:?}", file),
+            r#"Tracked { path: "/config/app.ini", object: IFile(0x1234) }"#
+        );
+
+        // the handle is made up, don't try to close it
+        core::mem::forget(file);
+    }
+
+    #[test]
+    fn interface_eq_by_handle() {
+        let file = IFile::new(RefHandle::new(RawHandle(0x1234)));
+
+        // Debug is implemented only for the owned interfaces, so no assert_eq here
+        assert!(file == IFile::new(RefHandle::new(RawHandle(0x1234))));
+        assert!(file != IFile::new(RefHandle::new(RawHandle(0x1235))));
+    }
+
+    #[test]
+    fn retry_after_transient_error() {
+        let mut calls = 0;
+        let res = with_retry(3, || {
+            calls += 1;
+            if calls == 1 {
+                Err(FsErrorCode::TargetLocked.code())
+            } else {
+                Ok(42)
+            }
+        });
+        assert_eq!(res, Ok(42));
+        assert_eq!(calls, 2);
+
+        // other errors are returned right away
+        let mut calls = 0;
+        let res = with_retry::<()>(3, || {
+            calls += 1;
+            Err(FsErrorCode::PathNotFound.code())
+        });
+        assert_eq!(res, Err(FsErrorCode::PathNotFound.code()));
+        assert_eq!(calls, 1);
+
+        // the last error is returned when out of attempts
+        let sd_card_access_failed = ErrorCode::from_parts(2, 2002);
+        let mut calls = 0;
+        let res = with_retry::<()>(2, || {
+            calls += 1;
+            Err(sd_card_access_failed)
+        });
+        assert_eq!(res, Err(sd_card_access_failed));
+        assert_eq!(calls, 2);
+    }
+}
@@ -1,5 +1,7 @@
 ij_core_workaround!();
 
+pub mod fatal;
 pub mod fspsrv;
+pub mod prelude;
 pub mod sm;
 pub mod spl;
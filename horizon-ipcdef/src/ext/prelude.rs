@@ -0,0 +1,98 @@
+//! Short names for the commonly used services
+//!
+//! The generated types follow the SwIPC names (`IFileSystemProxy`), while the services are usually known
+//!  by the names they are registered with in sm (`fsp-srv`). The aliases here are named after the services,
+//!  and the `open_*` helpers connect to the service and make the proxy in one call.
+
+ij_core_workaround!();
+
+use crate::fssrv::IFileSystemProxy;
+use crate::sm::{IUserInterface, ServiceName};
+use crate::spl::IRandomInterface;
+use horizon_error::Result;
+use horizon_ipc::handle_storage::OwnedHandle;
+
+/// `fsp-srv`, the filesystem service
+pub type FspSrv<S = OwnedHandle> = IFileSystemProxy<S>;
+/// `csrng`, the cryptographically secure random number generator
+pub type Csrng<S = OwnedHandle> = IRandomInterface<S>;
+/// `sm:`, the service manager
+pub type Sm<S = OwnedHandle> = IUserInterface<S>;
+
+/// Opens a new session to the service `name` through the shared sm session
+fn open_service(name: &str) -> Result<OwnedHandle> {
+    IUserInterface::get()?.get_service(ServiceName::try_new(name).unwrap())
+}
+
+/// Opens a new session to `fsp-srv`
+///
+/// Unlike [IFileSystemProxy::get](crate::fssrv::IFileSystemProxy::get), the session is owned by the caller
+///  and not shared with the rest of the process
+pub fn open_fssrv() -> Result<FspSrv> {
+    open_fssrv_with(open_service)
+}
+
+fn open_fssrv_with(open_service: impl FnOnce(&str) -> Result<OwnedHandle>) -> Result<FspSrv> {
+    Ok(FspSrv::new(open_service("fsp-srv")?))
+}
+
+/// Opens a new session to `csrng`
+///
+/// Unlike [IRandomInterface::get](crate::spl::IRandomInterface::get), the session is owned by the caller
+///  and not shared with the rest of the process
+pub fn open_csrng() -> Result<Csrng> {
+    open_csrng_with(open_service)
+}
+
+fn open_csrng_with(open_service: impl FnOnce(&str) -> Result<OwnedHandle>) -> Result<Csrng> {
+    Ok(Csrng::new(open_service("csrng")?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{open_csrng_with, open_fssrv_with, Csrng, FspSrv};
+    use crate::fssrv::IFileSystemProxy;
+    use crate::spl::IRandomInterface;
+    use horizon_error::ErrorCode;
+    use horizon_ipc::handle_storage::{OwnedHandle, RefHandle};
+    use horizon_svc::RawHandle;
+
+    #[test]
+    fn aliases_resolve() {
+        let fs: IFileSystemProxy = FspSrv::new(OwnedHandle::new(RawHandle(0x1234)));
+        let rng: IRandomInterface<RefHandle<'_>> = Csrng::new(RefHandle::new(RawHandle(0x1235)));
+
+        assert!(fs.as_ref() == FspSrv::new(RefHandle::new(RawHandle(0x1234))));
+        assert!(rng == Csrng::new(RefHandle::new(RawHandle(0x1235))));
+
+        // the handle is made up, don't try to close it
+        core::mem::forget(fs);
+    }
+
+    #[test]
+    fn open_helpers() {
+        // sm is replaced with a closure handing out a made-up handle
+        let fs = open_fssrv_with(|name| {
+            assert_eq!(name, "fsp-srv");
+            Ok(OwnedHandle::new(RawHandle(0x1234)))
+        })
+        .unwrap();
+        assert!(fs.as_ref() == FspSrv::new(RefHandle::new(RawHandle(0x1234))));
+        core::mem::forget(fs);
+
+        let rng = open_csrng_with(|name| {
+            assert_eq!(name, "csrng");
+            Ok(OwnedHandle::new(RawHandle(0x1235)))
+        })
+        .unwrap();
+        assert!(rng.as_ref() == Csrng::new(RefHandle::new(RawHandle(0x1235))));
+        core::mem::forget(rng);
+
+        // sm:GetService failing with `NotRegistered`
+        let not_registered = ErrorCode::from_parts(21, 7);
+        assert_eq!(
+            open_csrng_with(|_| Err(not_registered)).err(),
+            Some(not_registered)
+        );
+    }
+}
@@ -3,9 +3,10 @@ ij_core_workaround!();
 use crate::gen::sm::IUserInterface;
 use crate::sm::ServiceName;
 use core::fmt::{Display, Formatter};
-use horizon_error::Result;
+use core::time::Duration;
+use horizon_error::{ErrorCode, ErrorCodeModule, KernelErrorCode, Result};
 use horizon_global::services;
-use horizon_ipc::handle_storage::OwnedHandle;
+use horizon_ipc::handle_storage::{HandleStorage, OwnedHandle};
 use horizon_svc::RawHandle;
 
 pub trait SmServiceType: From<RawHandle> {}
@@ -18,9 +19,7 @@ pub trait SmService {
 impl IUserInterface {
     pub fn open_named_port() -> Result<Self> {
         let handle = unsafe { horizon_svc::connect_to_named_port(b"sm:\0") }?;
-        Ok(Self {
-            handle: OwnedHandle::new(handle),
-        })
+        Ok(Self::new(OwnedHandle::new(handle)))
     }
 
     pub fn get() -> Result<IUserInterface<services::sm::Guard>> {
@@ -32,6 +31,63 @@ impl IUserInterface {
     }
 }
 
+/// Returned by the server for commands it doesn't implement, like the atmosphere extensions on a stock sm
+const UNKNOWN_COMMAND_ID: ErrorCode = ErrorCode::from_parts(10, 221);
+/// sm couldn't find the service
+const SERVICE_NOT_REGISTERED: ErrorCode = ErrorCode::from_parts(21, 7);
+
+/// How often [IUserInterface::wait_for_service] checks for the service when it can't block in sm
+pub const SERVICE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+impl<S: HandleStorage> IUserInterface<S> {
+    /// Gets a session to the service, waiting for it to be registered first
+    ///
+    /// Without a timeout, this blocks in sm until the service is registered (`AtmosphereWaitService`).
+    ///  With a timeout, or when sm doesn't implement the atmosphere extensions, falls back to checking
+    ///  for the service every [SERVICE_POLL_INTERVAL].
+    ///
+    /// Fails with `TimedOut` if the service was not registered in time.
+    pub fn wait_for_service(
+        &self,
+        name: ServiceName,
+        timeout: Option<Duration>,
+    ) -> Result<OwnedHandle> {
+        if timeout.is_none() {
+            match self.atmosphere_wait_service(name) {
+                Ok(()) => return self.get_service(name),
+                Err(e) if e == UNKNOWN_COMMAND_ID => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut waited = Duration::ZERO;
+        loop {
+            // atmosphere's sm defers GetService of a missing service until it's registered,
+            //  so ask whether it's there first to not get stuck
+            match self.atmosphere_has_service(name) {
+                Ok(true) => return self.get_service(name),
+                Ok(false) => {}
+                Err(e) if e == UNKNOWN_COMMAND_ID => match self.get_service(name) {
+                    Ok(handle) => return Ok(handle),
+                    Err(e) if e == SERVICE_NOT_REGISTERED => {}
+                    Err(e) => return Err(e),
+                },
+                Err(e) => return Err(e),
+            }
+
+            if timeout.map_or(false, |timeout| waited >= timeout) {
+                return Err(ErrorCode::from_parts(
+                    KernelErrorCode::MODULE,
+                    KernelErrorCode::TimedOut as u32,
+                ));
+            }
+
+            horizon_svc::sleep_thread(SERVICE_POLL_INTERVAL);
+            waited += SERVICE_POLL_INTERVAL;
+        }
+    }
+}
+
 impl ServiceName {
     pub fn try_new(name: &str) -> Option<Self> {
         if name.bytes().len() >= 8 {
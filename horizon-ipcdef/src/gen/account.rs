@@ -1,6 +1,7 @@
 #![allow(unused_qualifications)]
 ij_core_workaround!();
 #[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
 #[repr(C)]
 pub struct Uid {
     pub uid_part_1: u64,
@@ -0,0 +1,1021 @@
+#![allow(unused_qualifications)]
+ij_core_workaround!();
+use horizon_error::Result;
+use horizon_ipc::buffer::get_ipc_buffer_ptr;
+use horizon_ipc::cmif::{CommandType, SessionHandle};
+use horizon_ipc::handle_storage::{HandleStorage, OwnedHandle, RefHandle, SharedHandle};
+use horizon_ipc::hipc::MapAliasBufferMode;
+use horizon_ipc::raw::cmif::{CmifInHeader, CmifOutHeader};
+use horizon_ipc::raw::hipc::{
+    HipcHeader, HipcMapAliasBufferDescriptor, HipcSpecialHeader,
+};
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum FatalPolicy {
+    #[default]
+    ErrorReportAndErrorScreen = 0,
+    ErrorReport = 1,
+    ErrorScreen = 2,
+}
+impl ::core::convert::TryFrom<u32> for FatalPolicy {
+    type Error = u32;
+    fn try_from(value: u32) -> ::core::result::Result<Self, u32> {
+        match value {
+            0 => Ok(Self::ErrorReportAndErrorScreen),
+            1 => Ok(Self::ErrorReport),
+            2 => Ok(Self::ErrorScreen),
+            _ => Err(value),
+        }
+    }
+}
+#[repr(transparent)]
+pub struct IService<S: HandleStorage = OwnedHandle> {
+    pub(crate) handle: SessionHandle<S>,
+}
+impl<S: HandleStorage> IService<S> {
+    pub fn new(handle: S) -> Self {
+        Self {
+            handle: SessionHandle::new(handle),
+        }
+    }
+    pub fn into_inner(self) -> S {
+        self.handle.into_inner()
+    }
+    pub fn with_storage<S2: HandleStorage>(
+        self,
+        f: impl FnOnce(S) -> S2,
+    ) -> IService<S2> {
+        IService::new(f(self.into_inner()))
+    }
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn throw_fatal(&self, error_code: u32) -> Result<()> {
+        let res = self.throw_fatal_untracked(error_code);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fatal::IService::ThrowFatal",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn throw_fatal_untracked(&self, error_code: u32) -> Result<()> {
+        #[repr(C, packed)]
+        struct In {
+            pub error_code: u32,
+            pub _padding_0: [u8; 4],
+            pub _pid_placeholder: u64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            error_code,
+            _pid_placeholder: 0,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            pid_placeholder: u64,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 68]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        0,
+                        0,
+                        12,
+                        0,
+                        0,
+                        true,
+                    ),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    pid_placeholder: 0,
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 0,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fatal::IService::ThrowFatal", *handle, 68);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fatal::IService::ThrowFatal", *handle, 40);
+        }
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn throw_fatal_with_policy(
+        &self,
+        error_code: u32,
+        policy: FatalPolicy,
+    ) -> Result<()> {
+        let res = self.throw_fatal_with_policy_untracked(error_code, policy);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fatal::IService::ThrowFatalWithPolicy",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn throw_fatal_with_policy_untracked(
+        &self,
+        error_code: u32,
+        policy: FatalPolicy,
+    ) -> Result<()> {
+        #[repr(C, packed)]
+        struct In {
+            pub error_code: u32,
+            pub policy: FatalPolicy,
+            pub _pid_placeholder: u64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            error_code,
+            policy,
+            _pid_placeholder: 0,
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            pid_placeholder: u64,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 68]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        0,
+                        0,
+                        12,
+                        0,
+                        0,
+                        true,
+                    ),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    pid_placeholder: 0,
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 1,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fatal::IService::ThrowFatalWithPolicy", *handle, 68);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fatal::IService::ThrowFatalWithPolicy", *handle, 40);
+        }
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn throw_fatal_with_cpu_context(
+        &self,
+        error_code: u32,
+        policy: FatalPolicy,
+        cpu_context: &[u8],
+    ) -> Result<()> {
+        let res = self
+            .throw_fatal_with_cpu_context_untracked(error_code, policy, cpu_context);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fatal::IService::ThrowFatalWithCpuContext",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn throw_fatal_with_cpu_context_untracked(
+        &self,
+        error_code: u32,
+        policy: FatalPolicy,
+        cpu_context: &[u8],
+    ) -> Result<()> {
+        #[repr(C, packed)]
+        struct In {
+            pub error_code: u32,
+            pub policy: FatalPolicy,
+            pub _pid_placeholder: u64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            error_code,
+            policy,
+            _pid_placeholder: 0,
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            pid_placeholder: u64,
+            in_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 80]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        1,
+                        0,
+                        0,
+                        12,
+                        0,
+                        0,
+                        true,
+                    ),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    pid_placeholder: 0,
+                    in_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::Normal,
+                        cpu_context.as_ptr() as usize,
+                        ::core::mem::size_of_val(cpu_context),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 2,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook(
+                "fatal::IService::ThrowFatalWithCpuContext",
+                *handle,
+                80,
+            );
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook(
+                "fatal::IService::ThrowFatalWithCpuContext",
+                *handle,
+                40,
+            );
+        }
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn throw_fatal_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        error_code: u32,
+    ) -> Result<()> {
+        #[repr(C, packed)]
+        struct In {
+            pub error_code: u32,
+            pub _padding_0: [u8; 4],
+            pub _pid_placeholder: u64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            error_code,
+            _pid_placeholder: 0,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            pid_placeholder: u64,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 68]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        0,
+                        0,
+                        12,
+                        0,
+                        0,
+                        true,
+                    ),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    pid_placeholder: 0,
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 0,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn throw_fatal_with_policy_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E, error_code: u32, policy: FatalPolicy) -> Result<()> {
+        #[repr(C, packed)]
+        struct In {
+            pub error_code: u32,
+            pub policy: FatalPolicy,
+            pub _pid_placeholder: u64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            error_code,
+            policy,
+            _pid_placeholder: 0,
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            pid_placeholder: u64,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 68]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        0,
+                        0,
+                        12,
+                        0,
+                        0,
+                        true,
+                    ),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    pid_placeholder: 0,
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 1,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn throw_fatal_with_cpu_context_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(
+        &self,
+        executor: &E,
+        error_code: u32,
+        policy: FatalPolicy,
+        cpu_context: &[u8],
+    ) -> Result<()> {
+        #[repr(C, packed)]
+        struct In {
+            pub error_code: u32,
+            pub policy: FatalPolicy,
+            pub _pid_placeholder: u64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            error_code,
+            policy,
+            _pid_placeholder: 0,
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            pid_placeholder: u64,
+            in_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 80]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        1,
+                        0,
+                        0,
+                        12,
+                        0,
+                        0,
+                        true,
+                    ),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    pid_placeholder: 0,
+                    in_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::Normal,
+                        cpu_context.as_ptr() as usize,
+                        ::core::mem::size_of_val(cpu_context),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 2,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn throw_fatal_send(
+        &self,
+        error_code: u32,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<()>> {
+        #[repr(C, packed)]
+        struct In {
+            pub error_code: u32,
+            pub _padding_0: [u8; 4],
+            pub _pid_placeholder: u64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            error_code,
+            _pid_placeholder: 0,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            pid_placeholder: u64,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 68]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        0,
+                        0,
+                        12,
+                        0,
+                        0,
+                        true,
+                    ),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    pid_placeholder: 0,
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 0,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(())
+            },
+        )
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn throw_fatal_with_policy_send(
+        &self,
+        error_code: u32,
+        policy: FatalPolicy,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<()>> {
+        #[repr(C, packed)]
+        struct In {
+            pub error_code: u32,
+            pub policy: FatalPolicy,
+            pub _pid_placeholder: u64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            error_code,
+            policy,
+            _pid_placeholder: 0,
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            pid_placeholder: u64,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 68]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        0,
+                        0,
+                        12,
+                        0,
+                        0,
+                        true,
+                    ),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    pid_placeholder: 0,
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 1,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(())
+            },
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn throw_fatal_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            0,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn throw_fatal_with_policy_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            1,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn throw_fatal_with_cpu_context_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            2,
+            raw_in,
+            out,
+        )
+    }
+}
+impl IService<OwnedHandle> {
+    pub const THROW_FATAL_REQUEST_SIZE: usize = 68;
+    pub const THROW_FATAL_RESPONSE_SIZE: usize = 40;
+    pub const THROW_FATAL_WITH_POLICY_REQUEST_SIZE: usize = 68;
+    pub const THROW_FATAL_WITH_POLICY_RESPONSE_SIZE: usize = 40;
+    pub const THROW_FATAL_WITH_CPU_CONTEXT_REQUEST_SIZE: usize = 80;
+    pub const THROW_FATAL_WITH_CPU_CONTEXT_RESPONSE_SIZE: usize = 40;
+    pub fn as_ref(&self) -> IService<RefHandle<'_>> {
+        IService {
+            handle: self.handle.as_ref(),
+        }
+    }
+    pub fn into_shared(self) -> IService<SharedHandle> {
+        IService::new(SharedHandle::new(self.into_inner().leak()))
+    }
+}
+impl<S: HandleStorage> From<S> for IService<S> {
+    fn from(handle: S) -> Self {
+        Self::new(handle)
+    }
+}
+impl<S: HandleStorage> PartialEq for IService<S> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.handle.get() == *other.handle.get()
+    }
+}
+impl<S: HandleStorage> Eq for IService<S> {}
+impl ::core::fmt::Debug for IService {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        write!(f, "IService({})", self.handle)
+    }
+}
+
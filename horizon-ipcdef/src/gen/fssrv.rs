@@ -5,7 +5,7 @@ use core::mem::MaybeUninit;
 use horizon_error::{ErrorCode, Result};
 use horizon_ipc::RawHandle;
 use horizon_ipc::buffer::get_ipc_buffer_ptr;
-use horizon_ipc::cmif::CommandType;
+use horizon_ipc::cmif::{CommandType, INVALID_RESPONSE_VALUE, SessionHandle};
 use horizon_ipc::handle_storage::{HandleStorage, OwnedHandle, RefHandle, SharedHandle};
 use horizon_ipc::hipc::MapAliasBufferMode;
 use horizon_ipc::raw::cmif::{CmifInHeader, CmifOutHeader};
@@ -16,6 +16,7 @@ use horizon_ipc::raw::hipc::{
 use super::account::Uid;
 use super::ncm::ProgramId;
 #[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
 #[repr(C)]
 pub struct FsSaveDataCreationInfo {
     pub save_data_size: i64,
@@ -33,6 +34,7 @@ const _: fn() = || {
 };
 
 #[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
 #[repr(C)]
 pub struct FsSaveDataAttribute {
     pub application_id: u64,
@@ -72,6 +74,16 @@ pub enum DirectoryEntryType {
     Directory = 0,
     File = 1,
 }
+impl ::core::convert::TryFrom<u8> for DirectoryEntryType {
+    type Error = u8;
+    fn try_from(value: u8) -> ::core::result::Result<Self, u8> {
+        match value {
+            0 => Ok(Self::Directory),
+            1 => Ok(Self::File),
+            _ => Err(value),
+        }
+    }
+}
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 #[repr(u32)]
 pub enum Partition {
@@ -91,6 +103,28 @@ pub enum Partition {
     SystemProperEncryption = 30,
     User = 31,
 }
+impl ::core::convert::TryFrom<u32> for Partition {
+    type Error = u32;
+    fn try_from(value: u32) -> ::core::result::Result<Self, u32> {
+        match value {
+            0 => Ok(Self::BootPartition1Root),
+            10 => Ok(Self::BootPartition2Root),
+            20 => Ok(Self::UserDataRoot),
+            21 => Ok(Self::BootConfigAndPackage2Part1),
+            22 => Ok(Self::BootConfigAndPackage2Part2),
+            23 => Ok(Self::BootConfigAndPackage2Part3),
+            24 => Ok(Self::BootConfigAndPackage2Part4),
+            25 => Ok(Self::BootConfigAndPackage2Part5),
+            26 => Ok(Self::BootConfigAndPackage2Part6),
+            27 => Ok(Self::CalibrationBinary),
+            28 => Ok(Self::CalibrationFile),
+            29 => Ok(Self::SafeMode),
+            30 => Ok(Self::SystemProperEncryption),
+            31 => Ok(Self::User),
+            _ => Err(value),
+        }
+    }
+}
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 #[repr(u32)]
 pub enum FileSystemType {
@@ -104,19 +138,59 @@ pub enum FileSystemType {
     ContentData = 6,
     ApplicationPackage = 7,
 }
+impl ::core::convert::TryFrom<u32> for FileSystemType {
+    type Error = u32;
+    fn try_from(value: u32) -> ::core::result::Result<Self, u32> {
+        match value {
+            0 => Ok(Self::Invalid),
+            1 => Ok(Self::Invalid2),
+            2 => Ok(Self::Logo),
+            3 => Ok(Self::ContentControl),
+            4 => Ok(Self::ContentManual),
+            5 => Ok(Self::ContentMeta),
+            6 => Ok(Self::ContentData),
+            7 => Ok(Self::ApplicationPackage),
+            _ => Err(value),
+        }
+    }
+}
+#[repr(transparent)]
 pub struct IFileSystemProxy<S: HandleStorage = OwnedHandle> {
-    pub(crate) handle: S,
+    pub(crate) handle: SessionHandle<S>,
 }
 impl<S: HandleStorage> IFileSystemProxy<S> {
     pub fn new(handle: S) -> Self {
-        Self { handle }
+        Self {
+            handle: SessionHandle::new(handle),
+        }
     }
     pub fn into_inner(self) -> S {
-        self.handle
+        self.handle.into_inner()
+    }
+    pub fn with_storage<S2: HandleStorage>(
+        self,
+        f: impl FnOnce(S) -> S2,
+    ) -> IFileSystemProxy<S2> {
+        IFileSystemProxy::new(f(self.into_inner()))
     }
+    #[must_use = "the returned object owns a kernel handle"]
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn open_sd_card_file_system(&self) -> Result<IFileSystem> {
+        let res = self.open_sd_card_file_system_untracked();
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystemProxy::OpenSdCardFileSystem",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn open_sd_card_file_system_untracked(&self) -> Result<IFileSystem> {
         let data_in = ();
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -127,7 +201,7 @@ impl<S: HandleStorage> IFileSystemProxy<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 40]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             special_header: HipcSpecialHeader,
@@ -140,29 +214,40 @@ impl<S: HandleStorage> IFileSystemProxy<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_out)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 18,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        0,
-                        0,
-                        0,
-                        0,
-                        8,
-                        0,
-                        0,
-                        false,
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 18,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
@@ -174,11 +259,13 @@ impl<S: HandleStorage> IFileSystemProxy<S> {
             crate::pre_ipc_hook(
                 "fssrv::IFileSystemProxy::OpenSdCardFileSystem",
                 *handle,
+                40,
             );
             horizon_svc::send_sync_request(*handle)?;
             crate::post_ipc_hook(
                 "fssrv::IFileSystemProxy::OpenSdCardFileSystem",
                 *handle,
+                48,
             );
         }
         let Response { hipc, special_header, handle_out: out, cmif, raw_data: (), .. } = unsafe {
@@ -200,27 +287,269 @@ impl<S: HandleStorage> IFileSystemProxy<S> {
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
         debug_assert_eq!(hipc.has_special_header(), 1);
         debug_assert_eq!(special_header.send_pid(), 0);
-        debug_assert_eq!(special_header.num_copy_handles(), 0);
-        debug_assert_eq!(special_header.num_move_handles(), 1);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        let out = IFileSystem {
-            handle: OwnedHandle::new(out),
+        let out = IFileSystem::new(OwnedHandle::new(out));
+        Ok(out)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn open_sd_card_file_system_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E) -> Result<IFileSystem> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_out: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_out)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 18,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, special_header, handle_out: out, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
+        if hipc.has_special_header() != 0 {
+            if cmif.result.is_failure() {
+                return Err(cmif.result);
+            }
+        } else {
+            return Err(unsafe {
+                ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+            })
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 1);
+        debug_assert_eq!(special_header.send_pid(), 0);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        let out = IFileSystem::new(OwnedHandle::new(out));
         Ok(out)
     }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn open_sd_card_file_system_send(
+        &self,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<IFileSystem>> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_out: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_out)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 18,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response {
+                    hipc,
+                    special_header,
+                    handle_out: out,
+                    cmif,
+                    raw_data: (),
+                    ..
+                } = unsafe { ::core::ptr::read(ipc_buffer_ptr as *const _) };
+                if hipc.has_special_header() != 0 {
+                    if cmif.result.is_failure() {
+                        return Err(cmif.result);
+                    }
+                } else {
+                    return Err(unsafe {
+                        ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+                    })
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 1);
+                debug_assert_eq!(special_header.send_pid(), 0);
+                debug_assert_eq!(
+                    special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES
+                );
+                debug_assert_eq!(
+                    special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES
+                );
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                let out = IFileSystem::new(OwnedHandle::new(out));
+                Ok(out)
+            },
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn open_sd_card_file_system_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            18,
+            raw_in,
+            out,
+        )
+    }
 }
 impl IFileSystemProxy<OwnedHandle> {
+    pub const OPEN_SD_CARD_FILE_SYSTEM_REQUEST_SIZE: usize = 40;
+    pub const OPEN_SD_CARD_FILE_SYSTEM_RESPONSE_SIZE: usize = 48;
     pub fn as_ref(&self) -> IFileSystemProxy<RefHandle<'_>> {
         IFileSystemProxy {
             handle: self.handle.as_ref(),
         }
     }
     pub fn into_shared(self) -> IFileSystemProxy<SharedHandle> {
-        IFileSystemProxy {
-            handle: SharedHandle::new(self.handle.leak()),
-        }
+        IFileSystemProxy::new(SharedHandle::new(self.into_inner().leak()))
+    }
+}
+impl<S: HandleStorage> From<S> for IFileSystemProxy<S> {
+    fn from(handle: S) -> Self {
+        Self::new(handle)
+    }
+}
+impl<S: HandleStorage> PartialEq for IFileSystemProxy<S> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.handle.get() == *other.handle.get()
     }
 }
+impl<S: HandleStorage> Eq for IFileSystemProxy<S> {}
 impl ::core::fmt::Debug for IFileSystemProxy {
     fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
         write!(f, "IFileSystemProxy({})", self.handle)
@@ -251,23 +580,51 @@ impl Default for CodeVerificationData {
     }
 }
 
+#[repr(transparent)]
 pub struct IFileSystemProxyForLoader<S: HandleStorage = OwnedHandle> {
-    pub(crate) handle: S,
+    pub(crate) handle: SessionHandle<S>,
 }
 impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
     pub fn new(handle: S) -> Self {
-        Self { handle }
+        Self {
+            handle: SessionHandle::new(handle),
+        }
     }
     pub fn into_inner(self) -> S {
-        self.handle
+        self.handle.into_inner()
+    }
+    pub fn with_storage<S2: HandleStorage>(
+        self,
+        f: impl FnOnce(S) -> S2,
+    ) -> IFileSystemProxyForLoader<S2> {
+        IFileSystemProxyForLoader::new(f(self.into_inner()))
     }
+    #[must_use = "the returned object owns a kernel handle"]
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn open_code_file_system(
         &self,
         path: &Path,
         program_id: ProgramId,
+    ) -> Result<(IFileSystem, CodeVerificationData)> {
+        let res = self.open_code_file_system_untracked(path, program_id);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystemProxyForLoader::OpenCodeFileSystem",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn open_code_file_system_untracked(
+        &self,
+        path: &Path,
+        program_id: ProgramId,
     ) -> Result<(IFileSystem, CodeVerificationData)> {
         let data_in = program_id;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             in_pointer_desc_0: HipcInPointerBufferDescriptor,
@@ -280,7 +637,7 @@ impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 64]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             special_header: HipcSpecialHeader,
@@ -294,6 +651,15 @@ impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 56]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, in_pointer_desc_0)
+            - ::core::mem::offset_of!(Response, handle_out_fs)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
         let out_verif = MaybeUninit::<CodeVerificationData>::uninit();
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
         unsafe {
@@ -338,11 +704,13 @@ impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
             crate::pre_ipc_hook(
                 "fssrv::IFileSystemProxyForLoader::OpenCodeFileSystem",
                 *handle,
+                64,
             );
             horizon_svc::send_sync_request(*handle)?;
             crate::post_ipc_hook(
                 "fssrv::IFileSystemProxyForLoader::OpenCodeFileSystem",
                 *handle,
+                56,
             );
         }
         let Response {
@@ -369,19 +737,31 @@ impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
         debug_assert_eq!(hipc.has_special_header(), 1);
         debug_assert_eq!(special_header.send_pid(), 0);
-        debug_assert_eq!(special_header.num_copy_handles(), 0);
-        debug_assert_eq!(special_header.num_move_handles(), 1);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
         let out_verif = unsafe { out_verif.assume_init() };
-        let out_fs = IFileSystem {
-            handle: OwnedHandle::new(out_fs),
-        };
+        let out_fs = IFileSystem::new(OwnedHandle::new(out_fs));
         Ok((out_fs, out_verif))
     }
 
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn is_archived_program(&self, process_id: u64) -> Result<bool> {
+        let res = self.is_archived_program_untracked(process_id);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystemProxyForLoader::IsArchivedProgram",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn is_archived_program_untracked(&self, process_id: u64) -> Result<bool> {
         let data_in = process_id;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -392,7 +772,7 @@ impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -404,28 +784,30 @@ impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 44]>;
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 1,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        0,
-                        0,
-                        0,
-                        0,
-                        10,
-                        0,
-                        0,
-                        false,
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 1,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
@@ -437,11 +819,13 @@ impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
             crate::pre_ipc_hook(
                 "fssrv::IFileSystemProxyForLoader::IsArchivedProgram",
                 *handle,
+                48,
             );
             horizon_svc::send_sync_request(*handle)?;
             crate::post_ipc_hook(
                 "fssrv::IFileSystemProxyForLoader::IsArchivedProgram",
                 *handle,
+                44,
             );
         }
         let Response { hipc, cmif, raw_data: out, .. } = unsafe {
@@ -460,9 +844,23 @@ impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
         Ok(out)
     }
 
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn set_current_process(&self) -> Result<()> {
+        let res = self.set_current_process_untracked();
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystemProxyForLoader::SetCurrentProcess",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn set_current_process_untracked(&self) -> Result<()> {
         let data_in = 0u64;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             special_header: HipcSpecialHeader,
@@ -475,7 +873,9 @@ impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 60]>;
-        #[repr(packed)]
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -502,7 +902,11 @@ impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
                         0,
                         true,
                     ),
-                    special_header: HipcSpecialHeader::new(true, 0, 0),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
                     pid_placeholder: 0,
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
@@ -522,11 +926,13 @@ impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
             crate::pre_ipc_hook(
                 "fssrv::IFileSystemProxyForLoader::SetCurrentProcess",
                 *handle,
+                60,
             );
             horizon_svc::send_sync_request(*handle)?;
             crate::post_ipc_hook(
                 "fssrv::IFileSystemProxyForLoader::SetCurrentProcess",
                 *handle,
+                40,
             );
         }
         let Response { hipc, cmif, raw_data: (), .. } = unsafe {
@@ -544,127 +950,56 @@ impl<S: HandleStorage> IFileSystemProxyForLoader<S> {
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
         Ok(())
     }
-}
-impl IFileSystemProxyForLoader<OwnedHandle> {
-    pub fn as_ref(&self) -> IFileSystemProxyForLoader<RefHandle<'_>> {
-        IFileSystemProxyForLoader {
-            handle: self.handle.as_ref(),
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn open_code_file_system_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(
+        &self,
+        executor: &E,
+        path: &Path,
+        program_id: ProgramId,
+    ) -> Result<(IFileSystem, CodeVerificationData)> {
+        let data_in = program_id;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: ProgramId,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+            out_pointer_desc_0: HipcOutPointerBufferDescriptor,
         }
-    }
-    pub fn into_shared(self) -> IFileSystemProxyForLoader<SharedHandle> {
-        IFileSystemProxyForLoader {
-            handle: SharedHandle::new(self.handle.leak()),
-        }
-    }
-}
-impl ::core::fmt::Debug for IFileSystemProxyForLoader {
-    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
-        write!(f, "IFileSystemProxyForLoader({})", self.handle)
-    }
-}
-
-/// This struct is marked with sf::LargeData
-#[derive(Debug, Clone, Copy)]
-#[repr(C)]
-pub struct Path {
-    pub str: [u8; 769],
-}
-// Static size check for Path (expect 769 bytes)
-const _: fn() = || {
-    let _ = ::core::mem::transmute::<Path, [u8; 769]>;
-};
-impl Default for Path {
-    fn default() -> Self {
-        Self { str: [0; 769] }
-    }
-}
-
-#[derive(Debug, Clone, Copy, Default)]
-#[repr(C)]
-pub struct FileTimeStampRaw {
-    pub create: i64,
-    pub modify: i64,
-    pub access: i64,
-    pub is_local_time: bool,
-    pub pad: [u8; 7],
-}
-// Static size check for FileTimeStampRaw (expect 32 bytes)
-const _: fn() = || {
-    let _ = ::core::mem::transmute::<FileTimeStampRaw, [u8; 32]>;
-};
-
-bitflags! {
-    #[derive(Default)] pub struct CreateOption : u32 { const BigFile = 0x1; }
-}
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
-#[repr(u32)]
-pub enum QueryId {
-    #[default]
-    SetConcatenationFileAttribute = 0,
-    UpdateMac = 1,
-    IsSignedSystemPartitionOnSdCardValid = 2,
-    QueryUnpreparedFileInformation = 3,
-}
-bitflags! {
-    #[derive(Default)] pub struct OpenDirectoryMode : u32 { const ReadDirs = 0x1; const
-    ReadFiles = 0x2; const NoFileSize = 0x8000000; }
-}
-bitflags! {
-    #[derive(Default)] pub struct OpenFileMode : u32 { const Read = 0x1; const Write =
-    0x2; const Append = 0x4; }
-}
-pub struct IFileSystem<S: HandleStorage = OwnedHandle> {
-    pub(crate) handle: S,
-}
-impl<S: HandleStorage> IFileSystem<S> {
-    pub fn new(handle: S) -> Self {
-        Self { handle }
-    }
-    pub fn into_inner(self) -> S {
-        self.handle
-    }
-    pub fn create_file(
-        &self,
-        path: &Path,
-        size: i64,
-        option: CreateOption,
-    ) -> Result<()> {
-        #[repr(C, packed)]
-        struct In {
-            pub option: CreateOption,
-            pub _padding_0: [u8; 4],
-            pub size: i64,
-        }
-        let _ = ::core::mem::transmute::<In, [u8; 16]>;
-        let data_in: In = In {
-            option,
-            size,
-            _padding_0: Default::default(),
-        };
-        #[repr(packed)]
-        struct Request {
-            hipc: HipcHeader,
-            in_pointer_desc_0: HipcInPointerBufferDescriptor,
-            pre_padding: [u8; 0],
-            cmif: CmifInHeader,
-            raw_data: In,
-            raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 16],
-        }
-        // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 64]>;
-        #[repr(packed)]
-        struct Response {
-            hipc: HipcHeader,
-            pre_padding: [u8; 8],
-            cmif: CmifOutHeader,
-            raw_data: (),
-            raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 8],
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 64]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_out_fs: RawHandle,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
-        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        let _ = ::core::mem::transmute::<Response, [u8; 56]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, in_pointer_desc_0)
+            - ::core::mem::offset_of!(Response, handle_out_fs)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        let out_verif = MaybeUninit::<CodeVerificationData>::uninit();
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
@@ -675,8 +1010,8 @@ impl<S: HandleStorage> IFileSystem<S> {
                         0,
                         0,
                         0,
-                        12,
-                        0,
+                        10,
+                        3,
                         0,
                         false,
                     ),
@@ -695,84 +1030,103 @@ impl<S: HandleStorage> IFileSystem<S> {
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
+                    out_pointer_desc_0: HipcOutPointerBufferDescriptor::new(
+                        out_verif.as_ptr() as usize,
+                        ::core::mem::size_of_val(&out_verif),
+                    ),
                 },
             )
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::CreateFile", *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::CreateFile", *handle);
+            async_request.send(executor, *handle).await?;
         }
-        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
-            ::core::ptr::read(ipc_buffer_ptr as *const _)
-        };
-        if cmif.result.is_failure() {
-            return Err(cmif.result);
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response {
+            hipc,
+            special_header,
+            handle_out_fs: out_fs,
+            cmif,
+            raw_data: (),
+            ..
+        } = unsafe { ::core::ptr::read(ipc_buffer_ptr as *const _) };
+        if hipc.has_special_header() != 0 {
+            if cmif.result.is_failure() {
+                return Err(cmif.result);
+            }
+        } else {
+            return Err(unsafe {
+                ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+            })
         }
-        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_pointers(), 1);
         debug_assert_eq!(hipc.num_in_map_aliases(), 0);
         debug_assert_eq!(hipc.num_out_map_aliases(), 0);
         debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
-        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 1);
+        debug_assert_eq!(special_header.send_pid(), 0);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(())
+        let out_verif = unsafe { out_verif.assume_init() };
+        let out_fs = IFileSystem::new(OwnedHandle::new(out_fs));
+        Ok((out_fs, out_verif))
     }
 
-    pub fn delete_file(&self, path: &Path) -> Result<()> {
-        let data_in = ();
-        #[repr(packed)]
+    #[cfg(feature = "async-ipc")]
+    pub async fn is_archived_program_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E, process_id: u64) -> Result<bool> {
+        let data_in = process_id;
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
-            in_pointer_desc_0: HipcInPointerBufferDescriptor,
-            pre_padding: [u8; 0],
+            pre_padding: [u8; 8],
             cmif: CmifInHeader,
-            raw_data: (),
+            raw_data: u64,
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 16],
+            post_padding: [u8; 8],
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
             cmif: CmifOutHeader,
-            raw_data: (),
-            raw_data_word_padding: [u8; 0],
+            raw_data: bool,
+            raw_data_word_padding: [u8; 3],
             post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
-        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        let _ = ::core::mem::transmute::<Response, [u8; 44]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 1,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        1,
-                        0,
-                        0,
-                        0,
-                        8,
-                        0,
-                        0,
-                        false,
-                    ),
-                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
-                        0,
-                        path as *const _ as usize,
-                        ::core::mem::size_of_val(path),
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 1,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
@@ -781,11 +1135,10 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::DeleteFile", *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::DeleteFile", *handle);
+            async_request.send(executor, *handle).await?;
         }
-        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
         if cmif.result.is_failure() {
@@ -798,24 +1151,30 @@ impl<S: HandleStorage> IFileSystem<S> {
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
         debug_assert_eq!(hipc.has_special_header(), 0);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(())
+        Ok(out)
     }
 
-    pub fn create_directory(&self, path: &Path) -> Result<()> {
-        let data_in = ();
-        #[repr(packed)]
+    #[cfg(feature = "async-ipc")]
+    pub async fn set_current_process_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E) -> Result<()> {
+        let data_in = 0u64;
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
-            in_pointer_desc_0: HipcInPointerBufferDescriptor,
-            pre_padding: [u8; 0],
+            special_header: HipcSpecialHeader,
+            pid_placeholder: u64,
+            pre_padding: [u8; 12],
             cmif: CmifInHeader,
-            raw_data: (),
+            raw_data: u64,
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 16],
+            post_padding: [u8; 4],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 60]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -826,27 +1185,29 @@ impl<S: HandleStorage> IFileSystem<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 40]>;
-        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
                     hipc: HipcHeader::new(
                         CommandType::Request,
-                        1,
                         0,
                         0,
                         0,
-                        8,
                         0,
+                        10,
                         0,
-                        false,
-                    ),
-                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
                         0,
-                        path as *const _ as usize,
-                        ::core::mem::size_of_val(path),
+                        true,
                     ),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    pid_placeholder: 0,
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
@@ -862,10 +1223,9 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::CreateDirectory", *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::CreateDirectory", *handle);
+            async_request.send(executor, *handle).await?;
         }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
         let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
@@ -882,102 +1242,110 @@ impl<S: HandleStorage> IFileSystem<S> {
         Ok(())
     }
 
-    pub fn delete_directory(&self, path: &Path) -> Result<()> {
-        let data_in = ();
-        #[repr(packed)]
+    #[cfg(feature = "async-ipc")]
+    pub fn is_archived_program_send(
+        &self,
+        process_id: u64,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<bool>> {
+        let data_in = process_id;
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
-            in_pointer_desc_0: HipcInPointerBufferDescriptor,
-            pre_padding: [u8; 0],
+            pre_padding: [u8; 8],
             cmif: CmifInHeader,
-            raw_data: (),
+            raw_data: u64,
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 16],
+            post_padding: [u8; 8],
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
             cmif: CmifOutHeader,
-            raw_data: (),
-            raw_data_word_padding: [u8; 0],
+            raw_data: bool,
+            raw_data_word_padding: [u8; 3],
             post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
-        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        let _ = ::core::mem::transmute::<Response, [u8; 44]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 1,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        1,
-                        0,
-                        0,
-                        0,
-                        8,
-                        0,
-                        0,
-                        false,
-                    ),
-                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
-                        0,
-                        path as *const _ as usize,
-                        ::core::mem::size_of_val(path),
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 3,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
                 },
             )
         };
-        {
-            let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::DeleteDirectory", *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::DeleteDirectory", *handle);
-        }
-        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
-            ::core::ptr::read(ipc_buffer_ptr as *const _)
-        };
-        if cmif.result.is_failure() {
-            return Err(cmif.result);
-        }
-        debug_assert_eq!(hipc.num_in_pointers(), 0);
-        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
-        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
-        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
-        debug_assert_eq!(hipc.out_pointer_mode(), 0);
-        debug_assert_eq!(hipc.has_special_header(), 0);
-        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(())
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(out)
+            },
+        )
     }
 
-    pub fn delete_directory_recursively(&self, path: &Path) -> Result<()> {
-        let data_in = ();
-        #[repr(packed)]
+    #[cfg(feature = "async-ipc")]
+    pub fn set_current_process_send(
+        &self,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<()>> {
+        let data_in = 0u64;
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
-            in_pointer_desc_0: HipcInPointerBufferDescriptor,
-            pre_padding: [u8; 0],
+            special_header: HipcSpecialHeader,
+            pid_placeholder: u64,
+            pre_padding: [u8; 12],
             cmif: CmifInHeader,
-            raw_data: (),
+            raw_data: u64,
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 16],
+            post_padding: [u8; 4],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 60]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -988,32 +1356,34 @@ impl<S: HandleStorage> IFileSystem<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 40]>;
-        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
                     hipc: HipcHeader::new(
                         CommandType::Request,
-                        1,
                         0,
                         0,
                         0,
-                        8,
                         0,
+                        10,
                         0,
-                        false,
-                    ),
-                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
                         0,
-                        path as *const _ as usize,
-                        ::core::mem::size_of_val(path),
+                        true,
                     ),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    pid_placeholder: 0,
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 4,
+                        command_id: 2,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -1022,50 +1392,254 @@ impl<S: HandleStorage> IFileSystem<S> {
                 },
             )
         };
-        {
-            let handle = self.handle.get();
-            crate::pre_ipc_hook(
-                "fssrv::IFileSystem::DeleteDirectoryRecursively",
-                *handle,
-            );
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook(
-                "fssrv::IFileSystem::DeleteDirectoryRecursively",
-                *handle,
-            );
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(())
+            },
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn open_code_file_system_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            0,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn is_archived_program_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            1,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn set_current_process_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            2,
+            raw_in,
+            out,
+        )
+    }
+}
+impl IFileSystemProxyForLoader<OwnedHandle> {
+    pub const OPEN_CODE_FILE_SYSTEM_REQUEST_SIZE: usize = 64;
+    pub const OPEN_CODE_FILE_SYSTEM_RESPONSE_SIZE: usize = 56;
+    pub const IS_ARCHIVED_PROGRAM_REQUEST_SIZE: usize = 48;
+    pub const IS_ARCHIVED_PROGRAM_RESPONSE_SIZE: usize = 44;
+    pub const SET_CURRENT_PROCESS_REQUEST_SIZE: usize = 60;
+    pub const SET_CURRENT_PROCESS_RESPONSE_SIZE: usize = 40;
+    pub fn as_ref(&self) -> IFileSystemProxyForLoader<RefHandle<'_>> {
+        IFileSystemProxyForLoader {
+            handle: self.handle.as_ref(),
         }
-        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
-            ::core::ptr::read(ipc_buffer_ptr as *const _)
-        };
-        if cmif.result.is_failure() {
-            return Err(cmif.result);
+    }
+    pub fn into_shared(self) -> IFileSystemProxyForLoader<SharedHandle> {
+        IFileSystemProxyForLoader::new(SharedHandle::new(self.into_inner().leak()))
+    }
+}
+impl<S: HandleStorage> From<S> for IFileSystemProxyForLoader<S> {
+    fn from(handle: S) -> Self {
+        Self::new(handle)
+    }
+}
+impl<S: HandleStorage> PartialEq for IFileSystemProxyForLoader<S> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.handle.get() == *other.handle.get()
+    }
+}
+impl<S: HandleStorage> Eq for IFileSystemProxyForLoader<S> {}
+impl ::core::fmt::Debug for IFileSystemProxyForLoader {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        write!(f, "IFileSystemProxyForLoader({})", self.handle)
+    }
+}
+
+/// This struct is marked with sf::LargeData
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+#[repr(C)]
+pub struct Path {
+    pub str: [u8; 769],
+}
+// Static size check for Path (expect 769 bytes)
+const _: fn() = || {
+    let _ = ::core::mem::transmute::<Path, [u8; 769]>;
+};
+impl Default for Path {
+    fn default() -> Self {
+        Self { str: [0; 769] }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct FileTimeStampRaw {
+    pub create: i64,
+    pub modify: i64,
+    pub access: i64,
+    pub is_local_time: bool,
+    pub pad: [u8; 7],
+}
+// Static size check for FileTimeStampRaw (expect 32 bytes)
+const _: fn() = || {
+    let _ = ::core::mem::transmute::<FileTimeStampRaw, [u8; 32]>;
+};
+
+bitflags! {
+    #[derive(Default)] pub struct CreateOption : u32 { const BigFile = 0x1; }
+}
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum QueryId {
+    #[default]
+    SetConcatenationFileAttribute = 0,
+    UpdateMac = 1,
+    IsSignedSystemPartitionOnSdCardValid = 2,
+    QueryUnpreparedFileInformation = 3,
+}
+impl ::core::convert::TryFrom<u32> for QueryId {
+    type Error = u32;
+    fn try_from(value: u32) -> ::core::result::Result<Self, u32> {
+        match value {
+            0 => Ok(Self::SetConcatenationFileAttribute),
+            1 => Ok(Self::UpdateMac),
+            2 => Ok(Self::IsSignedSystemPartitionOnSdCardValid),
+            3 => Ok(Self::QueryUnpreparedFileInformation),
+            _ => Err(value),
         }
-        debug_assert_eq!(hipc.num_in_pointers(), 0);
-        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
-        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
-        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
-        debug_assert_eq!(hipc.out_pointer_mode(), 0);
-        debug_assert_eq!(hipc.has_special_header(), 0);
-        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(())
+    }
+}
+bitflags! {
+    #[derive(Default)] pub struct OpenDirectoryMode : u32 { const ReadDirs = 0x1; const
+    ReadFiles = 0x2; const NoFileSize = 0x8000000; }
+}
+bitflags! {
+    #[derive(Default)] pub struct OpenFileMode : u32 { const Read = 0x1; const Write =
+    0x2; const Append = 0x4; }
+}
+#[repr(transparent)]
+pub struct IFileSystem<S: HandleStorage = OwnedHandle> {
+    pub(crate) handle: SessionHandle<S>,
+}
+impl<S: HandleStorage> IFileSystem<S> {
+    pub fn new(handle: S) -> Self {
+        Self {
+            handle: SessionHandle::new(handle),
+        }
+    }
+    pub fn into_inner(self) -> S {
+        self.handle.into_inner()
+    }
+    pub fn with_storage<S2: HandleStorage>(
+        self,
+        f: impl FnOnce(S) -> S2,
+    ) -> IFileSystem<S2> {
+        IFileSystem::new(f(self.into_inner()))
+    }
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn create_file(
+        &self,
+        path: &Path,
+        size: i64,
+        option: CreateOption,
+    ) -> Result<()> {
+        let res = self.create_file_untracked(path, size, option);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::CreateFile",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
     }
 
-    pub fn rename_file(&self, old_path: &Path, new_path: &Path) -> Result<()> {
-        let data_in = ();
-        #[repr(packed)]
+    #[inline(always)]
+    fn create_file_untracked(
+        &self,
+        path: &Path,
+        size: i64,
+        option: CreateOption,
+    ) -> Result<()> {
+        #[repr(C, packed)]
+        struct In {
+            pub option: CreateOption,
+            pub _padding_0: [u8; 4],
+            pub size: i64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            option,
+            size,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             in_pointer_desc_0: HipcInPointerBufferDescriptor,
-            in_pointer_desc_1: HipcInPointerBufferDescriptor,
-            pre_padding: [u8; 8],
+            pre_padding: [u8; 0],
             cmif: CmifInHeader,
-            raw_data: (),
+            raw_data: In,
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 8],
+            post_padding: [u8; 16],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 56]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 64]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -1083,30 +1657,25 @@ impl<S: HandleStorage> IFileSystem<S> {
                 Request {
                     hipc: HipcHeader::new(
                         CommandType::Request,
-                        2,
+                        1,
                         0,
                         0,
                         0,
-                        8,
+                        12,
                         0,
                         0,
                         false,
                     ),
                     in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
                         0,
-                        old_path as *const _ as usize,
-                        ::core::mem::size_of_val(old_path),
-                    ),
-                    in_pointer_desc_1: HipcInPointerBufferDescriptor::new(
-                        1,
-                        new_path as *const _ as usize,
-                        ::core::mem::size_of_val(new_path),
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
                     ),
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 5,
+                        command_id: 0,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -1117,9 +1686,9 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::RenameFile", *handle);
+            crate::pre_ipc_hook("fssrv::IFileSystem::CreateFile", *handle, 64);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::RenameFile", *handle);
+            crate::post_ipc_hook("fssrv::IFileSystem::CreateFile", *handle, 40);
         }
         let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
@@ -1137,22 +1706,35 @@ impl<S: HandleStorage> IFileSystem<S> {
         Ok(())
     }
 
-    pub fn rename_directory(&self, old_path: &Path, new_path: &Path) -> Result<()> {
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn delete_file(&self, path: &Path) -> Result<()> {
+        let res = self.delete_file_untracked(path);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::DeleteFile",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn delete_file_untracked(&self, path: &Path) -> Result<()> {
         let data_in = ();
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             in_pointer_desc_0: HipcInPointerBufferDescriptor,
-            in_pointer_desc_1: HipcInPointerBufferDescriptor,
-            pre_padding: [u8; 8],
+            pre_padding: [u8; 0],
             cmif: CmifInHeader,
             raw_data: (),
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 8],
+            post_padding: [u8; 16],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 56]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -1170,7 +1752,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                 Request {
                     hipc: HipcHeader::new(
                         CommandType::Request,
-                        2,
+                        1,
                         0,
                         0,
                         0,
@@ -1181,19 +1763,14 @@ impl<S: HandleStorage> IFileSystem<S> {
                     ),
                     in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
                         0,
-                        old_path as *const _ as usize,
-                        ::core::mem::size_of_val(old_path),
-                    ),
-                    in_pointer_desc_1: HipcInPointerBufferDescriptor::new(
-                        1,
-                        new_path as *const _ as usize,
-                        ::core::mem::size_of_val(new_path),
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
                     ),
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 6,
+                        command_id: 1,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -1204,9 +1781,9 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::RenameDirectory", *handle);
+            crate::pre_ipc_hook("fssrv::IFileSystem::DeleteFile", *handle, 48);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::RenameDirectory", *handle);
+            crate::post_ipc_hook("fssrv::IFileSystem::DeleteFile", *handle, 40);
         }
         let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
@@ -1224,9 +1801,23 @@ impl<S: HandleStorage> IFileSystem<S> {
         Ok(())
     }
 
-    pub fn get_entry_type(&self, path: &Path) -> Result<u32> {
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn create_directory(&self, path: &Path) -> Result<()> {
+        let res = self.create_directory_untracked(path);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::CreateDirectory",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn create_directory_untracked(&self, path: &Path) -> Result<()> {
         let data_in = ();
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             in_pointer_desc_0: HipcInPointerBufferDescriptor,
@@ -1238,17 +1829,17 @@ impl<S: HandleStorage> IFileSystem<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
             cmif: CmifOutHeader,
-            raw_data: u32,
+            raw_data: (),
             raw_data_word_padding: [u8; 0],
             post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Response, [u8; 44]>;
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
         unsafe {
             ::core::ptr::write(
@@ -1274,7 +1865,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 7,
+                        command_id: 2,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -1285,11 +1876,11 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::GetEntryType", *handle);
+            crate::pre_ipc_hook("fssrv::IFileSystem::CreateDirectory", *handle, 48);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::GetEntryType", *handle);
+            crate::post_ipc_hook("fssrv::IFileSystem::CreateDirectory", *handle, 40);
         }
-        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
         if cmif.result.is_failure() {
@@ -1302,36 +1893,48 @@ impl<S: HandleStorage> IFileSystem<S> {
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
         debug_assert_eq!(hipc.has_special_header(), 0);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(out)
+        Ok(())
     }
 
-    pub fn open_file(&self, path: &Path, mode: OpenFileMode) -> Result<IFile> {
-        let data_in = mode;
-        #[repr(packed)]
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn delete_directory(&self, path: &Path) -> Result<()> {
+        let res = self.delete_directory_untracked(path);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::DeleteDirectory",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn delete_directory_untracked(&self, path: &Path) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             in_pointer_desc_0: HipcInPointerBufferDescriptor,
             pre_padding: [u8; 0],
             cmif: CmifInHeader,
-            raw_data: OpenFileMode,
+            raw_data: (),
             raw_data_word_padding: [u8; 0],
             post_padding: [u8; 16],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 52]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
-            special_header: HipcSpecialHeader,
-            handle_out: RawHandle,
-            pre_padding: [u8; 0],
+            pre_padding: [u8; 8],
             cmif: CmifOutHeader,
             raw_data: (),
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 16],
+            post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
         unsafe {
             ::core::ptr::write(
@@ -1343,7 +1946,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                         0,
                         0,
                         0,
-                        9,
+                        8,
                         0,
                         0,
                         false,
@@ -1357,7 +1960,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 8,
+                        command_id: 3,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -1368,69 +1971,65 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::OpenFile", *handle);
+            crate::pre_ipc_hook("fssrv::IFileSystem::DeleteDirectory", *handle, 48);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::OpenFile", *handle);
+            crate::post_ipc_hook("fssrv::IFileSystem::DeleteDirectory", *handle, 40);
         }
-        let Response { hipc, special_header, handle_out: out, cmif, raw_data: (), .. } = unsafe {
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
-        if hipc.has_special_header() != 0 {
-            if cmif.result.is_failure() {
-                return Err(cmif.result);
-            }
-        } else {
-            return Err(unsafe {
-                ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
-            })
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
         }
         debug_assert_eq!(hipc.num_in_pointers(), 0);
         debug_assert_eq!(hipc.num_in_map_aliases(), 0);
         debug_assert_eq!(hipc.num_out_map_aliases(), 0);
         debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
-        debug_assert_eq!(hipc.has_special_header(), 1);
-        debug_assert_eq!(special_header.send_pid(), 0);
-        debug_assert_eq!(special_header.num_copy_handles(), 0);
-        debug_assert_eq!(special_header.num_move_handles(), 1);
+        debug_assert_eq!(hipc.has_special_header(), 0);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        let out = IFile {
-            handle: OwnedHandle::new(out),
-        };
-        Ok(out)
+        Ok(())
     }
 
-    pub fn open_directory(
-        &self,
-        path: &Path,
-        mode: OpenDirectoryMode,
-    ) -> Result<IDirectory> {
-        let data_in = mode;
-        #[repr(packed)]
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn delete_directory_recursively(&self, path: &Path) -> Result<()> {
+        let res = self.delete_directory_recursively_untracked(path);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::DeleteDirectoryRecursively",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn delete_directory_recursively_untracked(&self, path: &Path) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             in_pointer_desc_0: HipcInPointerBufferDescriptor,
             pre_padding: [u8; 0],
             cmif: CmifInHeader,
-            raw_data: OpenDirectoryMode,
+            raw_data: (),
             raw_data_word_padding: [u8; 0],
             post_padding: [u8; 16],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 52]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
-            special_header: HipcSpecialHeader,
-            handle_out: RawHandle,
-            pre_padding: [u8; 0],
+            pre_padding: [u8; 8],
             cmif: CmifOutHeader,
             raw_data: (),
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 16],
+            post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
         unsafe {
             ::core::ptr::write(
@@ -1442,7 +2041,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                         0,
                         0,
                         0,
-                        9,
+                        8,
                         0,
                         0,
                         false,
@@ -1456,7 +2055,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 9,
+                        command_id: 4,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -1467,43 +2066,55 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::OpenDirectory", *handle);
+            crate::pre_ipc_hook(
+                "fssrv::IFileSystem::DeleteDirectoryRecursively",
+                *handle,
+                48,
+            );
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::OpenDirectory", *handle);
+            crate::post_ipc_hook(
+                "fssrv::IFileSystem::DeleteDirectoryRecursively",
+                *handle,
+                40,
+            );
         }
-        let Response { hipc, special_header, handle_out: out, cmif, raw_data: (), .. } = unsafe {
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
-        if hipc.has_special_header() != 0 {
-            if cmif.result.is_failure() {
-                return Err(cmif.result);
-            }
-        } else {
-            return Err(unsafe {
-                ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
-            })
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
         }
         debug_assert_eq!(hipc.num_in_pointers(), 0);
         debug_assert_eq!(hipc.num_in_map_aliases(), 0);
         debug_assert_eq!(hipc.num_out_map_aliases(), 0);
         debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
-        debug_assert_eq!(hipc.has_special_header(), 1);
-        debug_assert_eq!(special_header.send_pid(), 0);
-        debug_assert_eq!(special_header.num_copy_handles(), 0);
-        debug_assert_eq!(special_header.num_move_handles(), 1);
+        debug_assert_eq!(hipc.has_special_header(), 0);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        let out = IDirectory {
-            handle: OwnedHandle::new(out),
-        };
-        Ok(out)
+        Ok(())
     }
 
-    pub fn commit(&self) -> Result<()> {
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn rename_file(&self, old_path: &Path, new_path: &Path) -> Result<()> {
+        let res = self.rename_file_untracked(old_path, new_path);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::RenameFile",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn rename_file_untracked(&self, old_path: &Path, new_path: &Path) -> Result<()> {
         let data_in = ();
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            in_pointer_desc_1: HipcInPointerBufferDescriptor,
             pre_padding: [u8; 8],
             cmif: CmifInHeader,
             raw_data: (),
@@ -1511,8 +2122,8 @@ impl<S: HandleStorage> IFileSystem<S> {
             post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 56]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -1530,7 +2141,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                 Request {
                     hipc: HipcHeader::new(
                         CommandType::Request,
-                        0,
+                        2,
                         0,
                         0,
                         0,
@@ -1539,11 +2150,21 @@ impl<S: HandleStorage> IFileSystem<S> {
                         0,
                         false,
                     ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        old_path as *const _ as usize,
+                        ::core::mem::size_of_val(old_path),
+                    ),
+                    in_pointer_desc_1: HipcInPointerBufferDescriptor::new(
+                        1,
+                        new_path as *const _ as usize,
+                        ::core::mem::size_of_val(new_path),
+                    ),
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 10,
+                        command_id: 5,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -1554,9 +2175,9 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::Commit", *handle);
+            crate::pre_ipc_hook("fssrv::IFileSystem::RenameFile", *handle, 56);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::Commit", *handle);
+            crate::post_ipc_hook("fssrv::IFileSystem::RenameFile", *handle, 40);
         }
         let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
@@ -1574,31 +2195,50 @@ impl<S: HandleStorage> IFileSystem<S> {
         Ok(())
     }
 
-    pub fn get_free_space_size(&self, path: &Path) -> Result<i64> {
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn rename_directory(&self, old_path: &Path, new_path: &Path) -> Result<()> {
+        let res = self.rename_directory_untracked(old_path, new_path);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::RenameDirectory",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn rename_directory_untracked(
+        &self,
+        old_path: &Path,
+        new_path: &Path,
+    ) -> Result<()> {
         let data_in = ();
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             in_pointer_desc_0: HipcInPointerBufferDescriptor,
-            pre_padding: [u8; 0],
+            in_pointer_desc_1: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 8],
             cmif: CmifInHeader,
             raw_data: (),
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 16],
+            post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 56]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
             cmif: CmifOutHeader,
-            raw_data: i64,
+            raw_data: (),
             raw_data_word_padding: [u8; 0],
             post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
         unsafe {
             ::core::ptr::write(
@@ -1606,7 +2246,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                 Request {
                     hipc: HipcHeader::new(
                         CommandType::Request,
-                        1,
+                        2,
                         0,
                         0,
                         0,
@@ -1617,14 +2257,19 @@ impl<S: HandleStorage> IFileSystem<S> {
                     ),
                     in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
                         0,
-                        path as *const _ as usize,
-                        ::core::mem::size_of_val(path),
+                        old_path as *const _ as usize,
+                        ::core::mem::size_of_val(old_path),
+                    ),
+                    in_pointer_desc_1: HipcInPointerBufferDescriptor::new(
+                        1,
+                        new_path as *const _ as usize,
+                        ::core::mem::size_of_val(new_path),
                     ),
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 11,
+                        command_id: 6,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -1635,11 +2280,11 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::GetFreeSpaceSize", *handle);
+            crate::pre_ipc_hook("fssrv::IFileSystem::RenameDirectory", *handle, 56);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::GetFreeSpaceSize", *handle);
+            crate::post_ipc_hook("fssrv::IFileSystem::RenameDirectory", *handle, 40);
         }
-        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
         if cmif.result.is_failure() {
@@ -1652,12 +2297,26 @@ impl<S: HandleStorage> IFileSystem<S> {
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
         debug_assert_eq!(hipc.has_special_header(), 0);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(out)
+        Ok(())
     }
 
-    pub fn get_total_space_size(&self, path: &Path) -> Result<i64> {
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn get_entry_type(&self, path: &Path) -> Result<DirectoryEntryType> {
+        let res = self.get_entry_type_untracked(path);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::GetEntryType",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn get_entry_type_untracked(&self, path: &Path) -> Result<DirectoryEntryType> {
         let data_in = ();
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             in_pointer_desc_0: HipcInPointerBufferDescriptor,
@@ -1669,17 +2328,17 @@ impl<S: HandleStorage> IFileSystem<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
             cmif: CmifOutHeader,
-            raw_data: i64,
-            raw_data_word_padding: [u8; 0],
+            raw_data: u8,
+            raw_data_word_padding: [u8; 3],
             post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let _ = ::core::mem::transmute::<Response, [u8; 44]>;
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
         unsafe {
             ::core::ptr::write(
@@ -1705,7 +2364,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 12,
+                        command_id: 7,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -1716,9 +2375,9 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::GetTotalSpaceSize", *handle);
+            crate::pre_ipc_hook("fssrv::IFileSystem::GetEntryType", *handle, 48);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::GetTotalSpaceSize", *handle);
+            crate::post_ipc_hook("fssrv::IFileSystem::GetEntryType", *handle, 44);
         }
         let Response { hipc, cmif, raw_data: out, .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
@@ -1733,34 +2392,61 @@ impl<S: HandleStorage> IFileSystem<S> {
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
         debug_assert_eq!(hipc.has_special_header(), 0);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        let out = DirectoryEntryType::try_from(out).map_err(|_| INVALID_RESPONSE_VALUE)?;
         Ok(out)
     }
 
-    pub fn clean_directory_recursively(&self, path: &Path) -> Result<()> {
-        let data_in = ();
-        #[repr(packed)]
+    #[must_use = "the returned object owns a kernel handle"]
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn open_file(&self, path: &Path, mode: OpenFileMode) -> Result<IFile> {
+        let res = self.open_file_untracked(path, mode);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::OpenFile",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn open_file_untracked(&self, path: &Path, mode: OpenFileMode) -> Result<IFile> {
+        let data_in = mode;
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             in_pointer_desc_0: HipcInPointerBufferDescriptor,
             pre_padding: [u8; 0],
             cmif: CmifInHeader,
-            raw_data: (),
+            raw_data: OpenFileMode,
             raw_data_word_padding: [u8; 0],
             post_padding: [u8; 16],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 52]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
-            pre_padding: [u8; 8],
+            special_header: HipcSpecialHeader,
+            handle_out: RawHandle,
+            pre_padding: [u8; 0],
             cmif: CmifOutHeader,
             raw_data: (),
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 8],
+            post_padding: [u8; 16],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_out)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
         unsafe {
             ::core::ptr::write(
@@ -1772,7 +2458,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                         0,
                         0,
                         0,
-                        8,
+                        9,
                         0,
                         0,
                         false,
@@ -1786,7 +2472,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 13,
+                        command_id: 8,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -1797,57 +2483,95 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook(
-                "fssrv::IFileSystem::CleanDirectoryRecursively",
-                *handle,
-            );
+            crate::pre_ipc_hook("fssrv::IFileSystem::OpenFile", *handle, 52);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook(
-                "fssrv::IFileSystem::CleanDirectoryRecursively",
-                *handle,
-            );
+            crate::post_ipc_hook("fssrv::IFileSystem::OpenFile", *handle, 48);
         }
-        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+        let Response { hipc, special_header, handle_out: out, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
-        if cmif.result.is_failure() {
-            return Err(cmif.result);
+        if hipc.has_special_header() != 0 {
+            if cmif.result.is_failure() {
+                return Err(cmif.result);
+            }
+        } else {
+            return Err(unsafe {
+                ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+            })
         }
         debug_assert_eq!(hipc.num_in_pointers(), 0);
         debug_assert_eq!(hipc.num_in_map_aliases(), 0);
         debug_assert_eq!(hipc.num_out_map_aliases(), 0);
         debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
-        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 1);
+        debug_assert_eq!(special_header.send_pid(), 0);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(())
+        let out = IFile::new(OwnedHandle::new(out));
+        Ok(out)
     }
 
-    pub fn get_file_time_stamp_raw(&self, path: &Path) -> Result<FileTimeStampRaw> {
-        let data_in = ();
-        #[repr(packed)]
+    #[must_use = "the returned object owns a kernel handle"]
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn open_directory(
+        &self,
+        path: &Path,
+        mode: OpenDirectoryMode,
+    ) -> Result<IDirectory> {
+        let res = self.open_directory_untracked(path, mode);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::OpenDirectory",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn open_directory_untracked(
+        &self,
+        path: &Path,
+        mode: OpenDirectoryMode,
+    ) -> Result<IDirectory> {
+        let data_in = mode;
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             in_pointer_desc_0: HipcInPointerBufferDescriptor,
             pre_padding: [u8; 0],
             cmif: CmifInHeader,
-            raw_data: (),
+            raw_data: OpenDirectoryMode,
             raw_data_word_padding: [u8; 0],
             post_padding: [u8; 16],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 52]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
-            pre_padding: [u8; 8],
-            cmif: CmifOutHeader,
-            raw_data: FileTimeStampRaw,
+            special_header: HipcSpecialHeader,
+            handle_out: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifOutHeader,
+            raw_data: (),
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 8],
+            post_padding: [u8; 16],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Response, [u8; 72]>;
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_out)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
         unsafe {
             ::core::ptr::write(
@@ -1859,7 +2583,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                         0,
                         0,
                         0,
-                        8,
+                        9,
                         0,
                         0,
                         false,
@@ -1873,7 +2597,7 @@ impl<S: HandleStorage> IFileSystem<S> {
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 14,
+                        command_id: 9,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -1884,49 +2608,64 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::GetFileTimeStampRaw", *handle);
+            crate::pre_ipc_hook("fssrv::IFileSystem::OpenDirectory", *handle, 52);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::GetFileTimeStampRaw", *handle);
+            crate::post_ipc_hook("fssrv::IFileSystem::OpenDirectory", *handle, 48);
         }
-        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+        let Response { hipc, special_header, handle_out: out, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
-        if cmif.result.is_failure() {
-            return Err(cmif.result);
+        if hipc.has_special_header() != 0 {
+            if cmif.result.is_failure() {
+                return Err(cmif.result);
+            }
+        } else {
+            return Err(unsafe {
+                ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+            })
         }
         debug_assert_eq!(hipc.num_in_pointers(), 0);
         debug_assert_eq!(hipc.num_in_map_aliases(), 0);
         debug_assert_eq!(hipc.num_out_map_aliases(), 0);
         debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
-        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 1);
+        debug_assert_eq!(special_header.send_pid(), 0);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        let out = IDirectory::new(OwnedHandle::new(out));
         Ok(out)
     }
 
-    pub fn query_entry(
-        &self,
-        out_buf: &mut [u8],
-        in_buf: &[u8],
-        query_id: QueryId,
-        path: &Path,
-    ) -> Result<()> {
-        let data_in = query_id;
-        #[repr(packed)]
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn commit(&self) -> Result<()> {
+        let res = self.commit_untracked();
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::Commit",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn commit_untracked(&self) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
-            in_pointer_desc_0: HipcInPointerBufferDescriptor,
-            in_map_alias_desc_0: HipcMapAliasBufferDescriptor,
-            out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
             pre_padding: [u8; 8],
             cmif: CmifInHeader,
-            raw_data: QueryId,
+            raw_data: (),
             raw_data_word_padding: [u8; 0],
             post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 76]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -1938,6 +2677,98 @@ impl<S: HandleStorage> IFileSystem<S> {
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 40]>;
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 10,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fssrv::IFileSystem::Commit", *handle, 40);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fssrv::IFileSystem::Commit", *handle, 40);
+        }
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn get_free_space_size(&self, path: &Path) -> Result<i64> {
+        let res = self.get_free_space_size_untracked(path);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::GetFreeSpaceSize",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn get_free_space_size_untracked(&self, path: &Path) -> Result<i64> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: i64,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
@@ -1945,10 +2776,10 @@ impl<S: HandleStorage> IFileSystem<S> {
                     hipc: HipcHeader::new(
                         CommandType::Request,
                         1,
-                        1,
-                        1,
                         0,
-                        9,
+                        0,
+                        0,
+                        8,
                         0,
                         0,
                         false,
@@ -1958,21 +2789,11 @@ impl<S: HandleStorage> IFileSystem<S> {
                         path as *const _ as usize,
                         ::core::mem::size_of_val(path),
                     ),
-                    in_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
-                        MapAliasBufferMode::NonSecure,
-                        in_buf.as_ptr() as usize,
-                        ::core::mem::size_of_val(in_buf),
-                    ),
-                    out_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
-                        MapAliasBufferMode::NonSecure,
-                        out_buf.as_ptr() as usize,
-                        ::core::mem::size_of_val(out_buf),
-                    ),
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 15,
+                        command_id: 11,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -1983,11 +2804,11 @@ impl<S: HandleStorage> IFileSystem<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFileSystem::QueryEntry", *handle);
+            crate::pre_ipc_hook("fssrv::IFileSystem::GetFreeSpaceSize", *handle, 48);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFileSystem::QueryEntry", *handle);
+            crate::post_ipc_hook("fssrv::IFileSystem::GetFreeSpaceSize", *handle, 48);
         }
-        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
         if cmif.result.is_failure() {
@@ -2000,107 +2821,38 @@ impl<S: HandleStorage> IFileSystem<S> {
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
         debug_assert_eq!(hipc.has_special_header(), 0);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(())
-    }
-}
-impl IFileSystem<OwnedHandle> {
-    pub fn as_ref(&self) -> IFileSystem<RefHandle<'_>> {
-        IFileSystem {
-            handle: self.handle.as_ref(),
-        }
-    }
-    pub fn into_shared(self) -> IFileSystem<SharedHandle> {
-        IFileSystem {
-            handle: SharedHandle::new(self.handle.leak()),
-        }
-    }
-}
-impl ::core::fmt::Debug for IFileSystem {
-    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
-        write!(f, "IFileSystem({})", self.handle)
+        Ok(out)
     }
-}
 
-bitflags! {
-    #[derive(Default)] pub struct ReadOption : u32 {}
-}
-bitflags! {
-    #[derive(Default)] pub struct WriteOption : u32 { const Flush = 0x1; }
-}
-#[derive(Debug, Clone, Copy)]
-#[repr(C)]
-pub struct FileQueryRangeInfo {
-    pub aes_ctr_key_type: u32,
-    pub speed_emulation_type: u32,
-    pub reserved: [u8; 56],
-}
-// Static size check for FileQueryRangeInfo (expect 64 bytes)
-const _: fn() = || {
-    let _ = ::core::mem::transmute::<FileQueryRangeInfo, [u8; 64]>;
-};
-impl Default for FileQueryRangeInfo {
-    fn default() -> Self {
-        Self {
-            aes_ctr_key_type: 0,
-            speed_emulation_type: 0,
-            reserved: [0; 56],
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn get_total_space_size(&self, path: &Path) -> Result<i64> {
+        let res = self.get_total_space_size_untracked(path);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::GetTotalSpaceSize",
+                ::core::panic::Location::caller(),
+                *e,
+            );
         }
+        res
     }
-}
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
-#[repr(u32)]
-pub enum OperationId {
-    #[default]
-    Clear = 0,
-    ClearSignature = 1,
-    InvalidateCache = 2,
-    QueryRange = 3,
-}
-pub struct IFile<S: HandleStorage = OwnedHandle> {
-    pub(crate) handle: S,
-}
-impl<S: HandleStorage> IFile<S> {
-    pub fn new(handle: S) -> Self {
-        Self { handle }
-    }
-    pub fn into_inner(self) -> S {
-        self.handle
-    }
-    pub fn read(
-        &self,
-        offset: i64,
-        buffer: &mut [u8],
-        size: i64,
-        option: ReadOption,
-    ) -> Result<i64> {
+    #[inline(always)]
+    fn get_total_space_size_untracked(&self, path: &Path) -> Result<i64> {
+        let data_in = ();
         #[repr(C, packed)]
-        struct In {
-            pub option: ReadOption,
-            pub _padding_0: [u8; 4],
-            pub offset: i64,
-            pub size: i64,
-        }
-        let _ = ::core::mem::transmute::<In, [u8; 24]>;
-        let data_in: In = In {
-            option,
-            offset,
-            size,
-            _padding_0: Default::default(),
-        };
-        #[repr(packed)]
         struct Request {
             hipc: HipcHeader,
-            out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
-            pre_padding: [u8; 12],
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
             cmif: CmifInHeader,
-            raw_data: In,
+            raw_data: (),
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 4],
+            post_padding: [u8; 16],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 76]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -2118,27 +2870,3613 @@ impl<S: HandleStorage> IFile<S> {
                 Request {
                     hipc: HipcHeader::new(
                         CommandType::Request,
+                        1,
                         0,
                         0,
-                        1,
                         0,
-                        14,
+                        8,
                         0,
                         0,
                         false,
                     ),
-                    out_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
-                        MapAliasBufferMode::NonSecure,
-                        buffer.as_ptr() as usize,
-                        ::core::mem::size_of_val(buffer),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
                     ),
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 0,
-                        token: 0,
-                    },
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 12,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fssrv::IFileSystem::GetTotalSpaceSize", *handle, 48);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fssrv::IFileSystem::GetTotalSpaceSize", *handle, 48);
+        }
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(out)
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn clean_directory_recursively(&self, path: &Path) -> Result<()> {
+        let res = self.clean_directory_recursively_untracked(path);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::CleanDirectoryRecursively",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn clean_directory_recursively_untracked(&self, path: &Path) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 13,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook(
+                "fssrv::IFileSystem::CleanDirectoryRecursively",
+                *handle,
+                48,
+            );
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook(
+                "fssrv::IFileSystem::CleanDirectoryRecursively",
+                *handle,
+                40,
+            );
+        }
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn get_file_time_stamp_raw(&self, path: &Path) -> Result<FileTimeStampRaw> {
+        let res = self.get_file_time_stamp_raw_untracked(path);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::GetFileTimeStampRaw",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn get_file_time_stamp_raw_untracked(
+        &self,
+        path: &Path,
+    ) -> Result<FileTimeStampRaw> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: FileTimeStampRaw,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 72]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 14,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fssrv::IFileSystem::GetFileTimeStampRaw", *handle, 48);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fssrv::IFileSystem::GetFileTimeStampRaw", *handle, 72);
+        }
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(out)
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn query_entry(
+        &self,
+        out_buf: &mut [u8],
+        in_buf: &[u8],
+        query_id: QueryId,
+        path: &Path,
+    ) -> Result<()> {
+        let res = self.query_entry_untracked(out_buf, in_buf, query_id, path);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFileSystem::QueryEntry",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn query_entry_untracked(
+        &self,
+        out_buf: &mut [u8],
+        in_buf: &[u8],
+        query_id: QueryId,
+        path: &Path,
+    ) -> Result<()> {
+        let data_in = query_id;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            in_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: QueryId,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 76]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        1,
+                        1,
+                        0,
+                        9,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    in_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::NonSecure,
+                        in_buf.as_ptr() as usize,
+                        ::core::mem::size_of_val(in_buf),
+                    ),
+                    out_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::NonSecure,
+                        out_buf.as_ptr() as usize,
+                        ::core::mem::size_of_val(out_buf),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 15,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fssrv::IFileSystem::QueryEntry", *handle, 76);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fssrv::IFileSystem::QueryEntry", *handle, 40);
+        }
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn create_file_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        path: &Path,
+        size: i64,
+        option: CreateOption,
+    ) -> Result<()> {
+        #[repr(C, packed)]
+        struct In {
+            pub option: CreateOption,
+            pub _padding_0: [u8; 4],
+            pub size: i64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            option,
+            size,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 64]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        12,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 0,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn delete_file_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        path: &Path,
+    ) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 1,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn create_directory_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        path: &Path,
+    ) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 2,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn delete_directory_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        path: &Path,
+    ) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 3,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn delete_directory_recursively_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E, path: &Path) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 4,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn rename_file_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        old_path: &Path,
+        new_path: &Path,
+    ) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            in_pointer_desc_1: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 56]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        2,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        old_path as *const _ as usize,
+                        ::core::mem::size_of_val(old_path),
+                    ),
+                    in_pointer_desc_1: HipcInPointerBufferDescriptor::new(
+                        1,
+                        new_path as *const _ as usize,
+                        ::core::mem::size_of_val(new_path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 5,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn rename_directory_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        old_path: &Path,
+        new_path: &Path,
+    ) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            in_pointer_desc_1: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 56]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        2,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        old_path as *const _ as usize,
+                        ::core::mem::size_of_val(old_path),
+                    ),
+                    in_pointer_desc_1: HipcInPointerBufferDescriptor::new(
+                        1,
+                        new_path as *const _ as usize,
+                        ::core::mem::size_of_val(new_path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 6,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn get_entry_type_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        path: &Path,
+    ) -> Result<DirectoryEntryType> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: u8,
+            raw_data_word_padding: [u8; 3],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 44]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 7,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        let out = DirectoryEntryType::try_from(out).map_err(|_| INVALID_RESPONSE_VALUE)?;
+        Ok(out)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn open_file_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        path: &Path,
+        mode: OpenFileMode,
+    ) -> Result<IFile> {
+        let data_in = mode;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: OpenFileMode,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 52]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_out: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_out)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        9,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 8,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, special_header, handle_out: out, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if hipc.has_special_header() != 0 {
+            if cmif.result.is_failure() {
+                return Err(cmif.result);
+            }
+        } else {
+            return Err(unsafe {
+                ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+            })
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 1);
+        debug_assert_eq!(special_header.send_pid(), 0);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        let out = IFile::new(OwnedHandle::new(out));
+        Ok(out)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn open_directory_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        path: &Path,
+        mode: OpenDirectoryMode,
+    ) -> Result<IDirectory> {
+        let data_in = mode;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: OpenDirectoryMode,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 52]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_out: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_out)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        9,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 9,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, special_header, handle_out: out, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if hipc.has_special_header() != 0 {
+            if cmif.result.is_failure() {
+                return Err(cmif.result);
+            }
+        } else {
+            return Err(unsafe {
+                ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+            })
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 1);
+        debug_assert_eq!(special_header.send_pid(), 0);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        let out = IDirectory::new(OwnedHandle::new(out));
+        Ok(out)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn commit_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+    ) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 10,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn get_free_space_size_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E, path: &Path) -> Result<i64> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: i64,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 11,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(out)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn get_total_space_size_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E, path: &Path) -> Result<i64> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: i64,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 12,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(out)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn clean_directory_recursively_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E, path: &Path) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 13,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn get_file_time_stamp_raw_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E, path: &Path) -> Result<FileTimeStampRaw> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: FileTimeStampRaw,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 72]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        0,
+                        0,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 14,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(out)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn query_entry_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        out_buf: &mut [u8],
+        in_buf: &[u8],
+        query_id: QueryId,
+        path: &Path,
+    ) -> Result<()> {
+        let data_in = query_id;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            in_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: QueryId,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 76]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        1,
+                        1,
+                        1,
+                        0,
+                        9,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_pointer_desc_0: HipcInPointerBufferDescriptor::new(
+                        0,
+                        path as *const _ as usize,
+                        ::core::mem::size_of_val(path),
+                    ),
+                    in_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::NonSecure,
+                        in_buf.as_ptr() as usize,
+                        ::core::mem::size_of_val(in_buf),
+                    ),
+                    out_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::NonSecure,
+                        out_buf.as_ptr() as usize,
+                        ::core::mem::size_of_val(out_buf),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 15,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn commit_send(&self) -> Result<horizon_ipc::async_ipc::PendingResponse<()>> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 10,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(())
+            },
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn create_file_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            0,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn delete_file_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            1,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn create_directory_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            2,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn delete_directory_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            3,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn delete_directory_recursively_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            4,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn rename_file_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            5,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn rename_directory_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            6,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn get_entry_type_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            7,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn open_file_raw(&self, raw_in: &[u8], out: &mut [u8]) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            8,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn open_directory_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            9,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn commit_raw(&self, raw_in: &[u8], out: &mut [u8]) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            10,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn get_free_space_size_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            11,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn get_total_space_size_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            12,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn clean_directory_recursively_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            13,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn get_file_time_stamp_raw_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            14,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn query_entry_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            15,
+            raw_in,
+            out,
+        )
+    }
+}
+impl IFileSystem<OwnedHandle> {
+    pub const CREATE_FILE_REQUEST_SIZE: usize = 64;
+    pub const CREATE_FILE_RESPONSE_SIZE: usize = 40;
+    pub const DELETE_FILE_REQUEST_SIZE: usize = 48;
+    pub const DELETE_FILE_RESPONSE_SIZE: usize = 40;
+    pub const CREATE_DIRECTORY_REQUEST_SIZE: usize = 48;
+    pub const CREATE_DIRECTORY_RESPONSE_SIZE: usize = 40;
+    pub const DELETE_DIRECTORY_REQUEST_SIZE: usize = 48;
+    pub const DELETE_DIRECTORY_RESPONSE_SIZE: usize = 40;
+    pub const DELETE_DIRECTORY_RECURSIVELY_REQUEST_SIZE: usize = 48;
+    pub const DELETE_DIRECTORY_RECURSIVELY_RESPONSE_SIZE: usize = 40;
+    pub const RENAME_FILE_REQUEST_SIZE: usize = 56;
+    pub const RENAME_FILE_RESPONSE_SIZE: usize = 40;
+    pub const RENAME_DIRECTORY_REQUEST_SIZE: usize = 56;
+    pub const RENAME_DIRECTORY_RESPONSE_SIZE: usize = 40;
+    pub const GET_ENTRY_TYPE_REQUEST_SIZE: usize = 48;
+    pub const GET_ENTRY_TYPE_RESPONSE_SIZE: usize = 44;
+    pub const OPEN_FILE_REQUEST_SIZE: usize = 52;
+    pub const OPEN_FILE_RESPONSE_SIZE: usize = 48;
+    pub const OPEN_DIRECTORY_REQUEST_SIZE: usize = 52;
+    pub const OPEN_DIRECTORY_RESPONSE_SIZE: usize = 48;
+    pub const COMMIT_REQUEST_SIZE: usize = 40;
+    pub const COMMIT_RESPONSE_SIZE: usize = 40;
+    pub const GET_FREE_SPACE_SIZE_REQUEST_SIZE: usize = 48;
+    pub const GET_FREE_SPACE_SIZE_RESPONSE_SIZE: usize = 48;
+    pub const GET_TOTAL_SPACE_SIZE_REQUEST_SIZE: usize = 48;
+    pub const GET_TOTAL_SPACE_SIZE_RESPONSE_SIZE: usize = 48;
+    pub const CLEAN_DIRECTORY_RECURSIVELY_REQUEST_SIZE: usize = 48;
+    pub const CLEAN_DIRECTORY_RECURSIVELY_RESPONSE_SIZE: usize = 40;
+    pub const GET_FILE_TIME_STAMP_RAW_REQUEST_SIZE: usize = 48;
+    pub const GET_FILE_TIME_STAMP_RAW_RESPONSE_SIZE: usize = 72;
+    pub const QUERY_ENTRY_REQUEST_SIZE: usize = 76;
+    pub const QUERY_ENTRY_RESPONSE_SIZE: usize = 40;
+    pub fn as_ref(&self) -> IFileSystem<RefHandle<'_>> {
+        IFileSystem {
+            handle: self.handle.as_ref(),
+        }
+    }
+    pub fn into_shared(self) -> IFileSystem<SharedHandle> {
+        IFileSystem::new(SharedHandle::new(self.into_inner().leak()))
+    }
+}
+impl<S: HandleStorage> From<S> for IFileSystem<S> {
+    fn from(handle: S) -> Self {
+        Self::new(handle)
+    }
+}
+impl<S: HandleStorage> PartialEq for IFileSystem<S> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.handle.get() == *other.handle.get()
+    }
+}
+impl<S: HandleStorage> Eq for IFileSystem<S> {}
+impl ::core::fmt::Debug for IFileSystem {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        write!(f, "IFileSystem({})", self.handle)
+    }
+}
+
+bitflags! {
+    #[derive(Default)] pub struct ReadOption : u32 {}
+}
+bitflags! {
+    #[derive(Default)] pub struct WriteOption : u32 { const Flush = 0x1; }
+}
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+#[repr(C)]
+pub struct FileQueryRangeInfo {
+    pub aes_ctr_key_type: u32,
+    pub speed_emulation_type: u32,
+    pub reserved: [u8; 56],
+}
+// Static size check for FileQueryRangeInfo (expect 64 bytes)
+const _: fn() = || {
+    let _ = ::core::mem::transmute::<FileQueryRangeInfo, [u8; 64]>;
+};
+impl Default for FileQueryRangeInfo {
+    fn default() -> Self {
+        Self {
+            aes_ctr_key_type: 0,
+            speed_emulation_type: 0,
+            reserved: [0; 56],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum OperationId {
+    #[default]
+    Clear = 0,
+    ClearSignature = 1,
+    InvalidateCache = 2,
+    QueryRange = 3,
+}
+impl ::core::convert::TryFrom<u32> for OperationId {
+    type Error = u32;
+    fn try_from(value: u32) -> ::core::result::Result<Self, u32> {
+        match value {
+            0 => Ok(Self::Clear),
+            1 => Ok(Self::ClearSignature),
+            2 => Ok(Self::InvalidateCache),
+            3 => Ok(Self::QueryRange),
+            _ => Err(value),
+        }
+    }
+}
+#[repr(transparent)]
+pub struct IFile<S: HandleStorage = OwnedHandle> {
+    pub(crate) handle: SessionHandle<S>,
+}
+impl<S: HandleStorage> IFile<S> {
+    pub fn new(handle: S) -> Self {
+        Self {
+            handle: SessionHandle::new(handle),
+        }
+    }
+    pub fn into_inner(self) -> S {
+        self.handle.into_inner()
+    }
+    pub fn with_storage<S2: HandleStorage>(self, f: impl FnOnce(S) -> S2) -> IFile<S2> {
+        IFile::new(f(self.into_inner()))
+    }
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn read(
+        &self,
+        offset: i64,
+        buffer: &mut [u8],
+        size: i64,
+        option: ReadOption,
+    ) -> Result<i64> {
+        let res = self.read_untracked(offset, buffer, size, option);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFile::Read",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn read_untracked(
+        &self,
+        offset: i64,
+        buffer: &mut [u8],
+        size: i64,
+        option: ReadOption,
+    ) -> Result<i64> {
+        #[repr(C, packed)]
+        struct In {
+            pub option: ReadOption,
+            pub _padding_0: [u8; 4],
+            pub offset: i64,
+            pub size: i64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 24]>;
+        let data_in: In = In {
+            option,
+            offset,
+            size,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 76]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: i64,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        1,
+                        0,
+                        14,
+                        0,
+                        0,
+                        false,
+                    ),
+                    out_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::NonSecure,
+                        buffer.as_ptr() as usize,
+                        ::core::mem::size_of_val(buffer),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 0,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fssrv::IFile::Read", *handle, 76);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fssrv::IFile::Read", *handle, 48);
+        }
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(out)
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn write(
+        &self,
+        offset: i64,
+        buffer: &[u8],
+        size: i64,
+        option: WriteOption,
+    ) -> Result<()> {
+        let res = self.write_untracked(offset, buffer, size, option);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFile::Write",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn write_untracked(
+        &self,
+        offset: i64,
+        buffer: &[u8],
+        size: i64,
+        option: WriteOption,
+    ) -> Result<()> {
+        #[repr(C, packed)]
+        struct In {
+            pub option: WriteOption,
+            pub _padding_0: [u8; 4],
+            pub offset: i64,
+            pub size: i64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 24]>;
+        let data_in: In = In {
+            option,
+            offset,
+            size,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 76]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        1,
+                        0,
+                        0,
+                        14,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::NonSecure,
+                        buffer.as_ptr() as usize,
+                        ::core::mem::size_of_val(buffer),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 1,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fssrv::IFile::Write", *handle, 76);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fssrv::IFile::Write", *handle, 40);
+        }
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn flush(&self) -> Result<()> {
+        let res = self.flush_untracked();
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFile::Flush",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn flush_untracked(&self) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 2,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fssrv::IFile::Flush", *handle, 40);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fssrv::IFile::Flush", *handle, 40);
+        }
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn set_size(&self, size: i64) -> Result<()> {
+        let res = self.set_size_untracked(size);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFile::SetSize",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn set_size_untracked(&self, size: i64) -> Result<()> {
+        let data_in = size;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: i64,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 3,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fssrv::IFile::SetSize", *handle, 48);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fssrv::IFile::SetSize", *handle, 40);
+        }
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn get_size(&self) -> Result<i64> {
+        let res = self.get_size_untracked();
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFile::GetSize",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn get_size_untracked(&self) -> Result<i64> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: i64,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 4,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fssrv::IFile::GetSize", *handle, 40);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fssrv::IFile::GetSize", *handle, 48);
+        }
+        let Response { hipc, cmif, raw_data: size, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(size)
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn operate_range(
+        &self,
+        op_id: OperationId,
+        offset: i64,
+        size: i64,
+    ) -> Result<FileQueryRangeInfo> {
+        let res = self.operate_range_untracked(op_id, offset, size);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFile::OperateRange",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn operate_range_untracked(
+        &self,
+        op_id: OperationId,
+        offset: i64,
+        size: i64,
+    ) -> Result<FileQueryRangeInfo> {
+        #[repr(C, packed)]
+        struct In {
+            pub op_id: OperationId,
+            pub _padding_0: [u8; 4],
+            pub offset: i64,
+            pub size: i64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 24]>;
+        let data_in: In = In {
+            op_id,
+            offset,
+            size,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 64]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: FileQueryRangeInfo,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 104]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            14,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 5,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fssrv::IFile::OperateRange", *handle, 64);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fssrv::IFile::OperateRange", *handle, 104);
+        }
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(out)
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn operate_range_with_buffer(
+        &self,
+        out_buf: &mut [u8],
+        in_buf: &[u8],
+        op_id: OperationId,
+        offset: i64,
+        size: i64,
+    ) -> Result<()> {
+        let res = self
+            .operate_range_with_buffer_untracked(out_buf, in_buf, op_id, offset, size);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IFile::OperateRangeWithBuffer",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn operate_range_with_buffer_untracked(
+        &self,
+        out_buf: &mut [u8],
+        in_buf: &[u8],
+        op_id: OperationId,
+        offset: i64,
+        size: i64,
+    ) -> Result<()> {
+        #[repr(C, packed)]
+        struct In {
+            pub op_id: OperationId,
+            pub _padding_0: [u8; 4],
+            pub offset: i64,
+            pub size: i64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 24]>;
+        let data_in: In = In {
+            op_id,
+            offset,
+            size,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 88]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        1,
+                        1,
+                        0,
+                        14,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::NonSecure,
+                        in_buf.as_ptr() as usize,
+                        ::core::mem::size_of_val(in_buf),
+                    ),
+                    out_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::NonSecure,
+                        out_buf.as_ptr() as usize,
+                        ::core::mem::size_of_val(out_buf),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 6,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("fssrv::IFile::OperateRangeWithBuffer", *handle, 88);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("fssrv::IFile::OperateRangeWithBuffer", *handle, 40);
+        }
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn read_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        offset: i64,
+        buffer: &mut [u8],
+        size: i64,
+        option: ReadOption,
+    ) -> Result<i64> {
+        #[repr(C, packed)]
+        struct In {
+            pub option: ReadOption,
+            pub _padding_0: [u8; 4],
+            pub offset: i64,
+            pub size: i64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 24]>;
+        let data_in: In = In {
+            option,
+            offset,
+            size,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 76]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: i64,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        1,
+                        0,
+                        14,
+                        0,
+                        0,
+                        false,
+                    ),
+                    out_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::NonSecure,
+                        buffer.as_ptr() as usize,
+                        ::core::mem::size_of_val(buffer),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 0,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(out)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn write_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        offset: i64,
+        buffer: &[u8],
+        size: i64,
+        option: WriteOption,
+    ) -> Result<()> {
+        #[repr(C, packed)]
+        struct In {
+            pub option: WriteOption,
+            pub _padding_0: [u8; 4],
+            pub offset: i64,
+            pub size: i64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 24]>;
+        let data_in: In = In {
+            option,
+            offset,
+            size,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            in_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 76]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        1,
+                        0,
+                        0,
+                        14,
+                        0,
+                        0,
+                        false,
+                    ),
+                    in_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::NonSecure,
+                        buffer.as_ptr() as usize,
+                        ::core::mem::size_of_val(buffer),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 1,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn flush_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+    ) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 2,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn set_size_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        size: i64,
+    ) -> Result<()> {
+        let data_in = size;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: i64,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 3,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn get_size_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+    ) -> Result<i64> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: i64,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 4,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: size, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(size)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn operate_range_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        op_id: OperationId,
+        offset: i64,
+        size: i64,
+    ) -> Result<FileQueryRangeInfo> {
+        #[repr(C, packed)]
+        struct In {
+            pub op_id: OperationId,
+            pub _padding_0: [u8; 4],
+            pub offset: i64,
+            pub size: i64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 24]>;
+        let data_in: In = In {
+            op_id,
+            offset,
+            size,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 64]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: FileQueryRangeInfo,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 104]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            14,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 5,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
@@ -2147,10 +6485,9 @@ impl<S: HandleStorage> IFile<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFile::Read", *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFile::Read", *handle);
+            async_request.send(executor, *handle).await?;
         }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
         let Response { hipc, cmif, raw_data: out, .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
@@ -2167,40 +6504,46 @@ impl<S: HandleStorage> IFile<S> {
         Ok(out)
     }
 
-    pub fn write(
+    #[cfg(feature = "async-ipc")]
+    pub async fn operate_range_with_buffer_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(
         &self,
+        executor: &E,
+        out_buf: &mut [u8],
+        in_buf: &[u8],
+        op_id: OperationId,
         offset: i64,
-        buffer: &[u8],
         size: i64,
-        option: WriteOption,
     ) -> Result<()> {
         #[repr(C, packed)]
         struct In {
-            pub option: WriteOption,
+            pub op_id: OperationId,
             pub _padding_0: [u8; 4],
             pub offset: i64,
             pub size: i64,
         }
         let _ = ::core::mem::transmute::<In, [u8; 24]>;
         let data_in: In = In {
-            option,
+            op_id,
             offset,
             size,
             _padding_0: Default::default(),
         };
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             in_map_alias_desc_0: HipcMapAliasBufferDescriptor,
-            pre_padding: [u8; 12],
+            out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            pre_padding: [u8; 0],
             cmif: CmifInHeader,
             raw_data: In,
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 4],
+            post_padding: [u8; 16],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 76]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 88]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -2211,7 +6554,8 @@ impl<S: HandleStorage> IFile<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 40]>;
-        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
@@ -2220,7 +6564,7 @@ impl<S: HandleStorage> IFile<S> {
                         CommandType::Request,
                         0,
                         1,
-                        0,
+                        1,
                         0,
                         14,
                         0,
@@ -2229,14 +6573,19 @@ impl<S: HandleStorage> IFile<S> {
                     ),
                     in_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
                         MapAliasBufferMode::NonSecure,
-                        buffer.as_ptr() as usize,
-                        ::core::mem::size_of_val(buffer),
+                        in_buf.as_ptr() as usize,
+                        ::core::mem::size_of_val(in_buf),
+                    ),
+                    out_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::NonSecure,
+                        out_buf.as_ptr() as usize,
+                        ::core::mem::size_of_val(out_buf),
                     ),
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 1,
+                        command_id: 6,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -2247,10 +6596,9 @@ impl<S: HandleStorage> IFile<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFile::Write", *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFile::Write", *handle);
+            async_request.send(executor, *handle).await?;
         }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
         let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
@@ -2267,9 +6615,10 @@ impl<S: HandleStorage> IFile<S> {
         Ok(())
     }
 
-    pub fn flush(&self) -> Result<()> {
+    #[cfg(feature = "async-ipc")]
+    pub fn flush_send(&self) -> Result<horizon_ipc::async_ipc::PendingResponse<()>> {
         let data_in = ();
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -2280,7 +6629,7 @@ impl<S: HandleStorage> IFile<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 40]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -2291,60 +6640,68 @@ impl<S: HandleStorage> IFile<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 40]>;
-        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 2,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        0,
-                        0,
-                        0,
-                        0,
-                        8,
-                        0,
-                        0,
-                        false,
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 2,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
                 },
             )
         };
-        {
-            let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFile::Flush", *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFile::Flush", *handle);
-        }
-        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
-            ::core::ptr::read(ipc_buffer_ptr as *const _)
-        };
-        if cmif.result.is_failure() {
-            return Err(cmif.result);
-        }
-        debug_assert_eq!(hipc.num_in_pointers(), 0);
-        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
-        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
-        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
-        debug_assert_eq!(hipc.out_pointer_mode(), 0);
-        debug_assert_eq!(hipc.has_special_header(), 0);
-        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(())
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(())
+            },
+        )
     }
 
-    pub fn set_size(&self, size: i64) -> Result<()> {
+    #[cfg(feature = "async-ipc")]
+    pub fn set_size_send(
+        &self,
+        size: i64,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<()>> {
         let data_in = size;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -2355,7 +6712,7 @@ impl<S: HandleStorage> IFile<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -2366,71 +6723,448 @@ impl<S: HandleStorage> IFile<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 40]>;
-        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 3,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        0,
-                        0,
-                        0,
-                        0,
-                        10,
-                        0,
-                        0,
-                        false,
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 3,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
                 },
             )
         };
-        {
-            let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFile::SetSize", *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFile::SetSize", *handle);
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(())
+            },
+        )
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn get_size_send(&self) -> Result<horizon_ipc::async_ipc::PendingResponse<i64>> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
         }
-        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
-            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: i64,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 4,
+            token: 0,
         };
-        if cmif.result.is_failure() {
-            return Err(cmif.result);
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: size, .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(size)
+            },
+        )
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn operate_range_send(
+        &self,
+        op_id: OperationId,
+        offset: i64,
+        size: i64,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<FileQueryRangeInfo>> {
+        #[repr(C, packed)]
+        struct In {
+            pub op_id: OperationId,
+            pub _padding_0: [u8; 4],
+            pub offset: i64,
+            pub size: i64,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 24]>;
+        let data_in: In = In {
+            op_id,
+            offset,
+            size,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 64]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: FileQueryRangeInfo,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 104]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            14,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 5,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(out)
+            },
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn read_raw(&self, raw_in: &[u8], out: &mut [u8]) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            0,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn write_raw(&self, raw_in: &[u8], out: &mut [u8]) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            1,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn flush_raw(&self, raw_in: &[u8], out: &mut [u8]) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            2,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn set_size_raw(&self, raw_in: &[u8], out: &mut [u8]) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            3,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn get_size_raw(&self, raw_in: &[u8], out: &mut [u8]) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            4,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn operate_range_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            5,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn operate_range_with_buffer_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            6,
+            raw_in,
+            out,
+        )
+    }
+}
+impl IFile<OwnedHandle> {
+    pub const READ_REQUEST_SIZE: usize = 76;
+    pub const READ_RESPONSE_SIZE: usize = 48;
+    pub const WRITE_REQUEST_SIZE: usize = 76;
+    pub const WRITE_RESPONSE_SIZE: usize = 40;
+    pub const FLUSH_REQUEST_SIZE: usize = 40;
+    pub const FLUSH_RESPONSE_SIZE: usize = 40;
+    pub const SET_SIZE_REQUEST_SIZE: usize = 48;
+    pub const SET_SIZE_RESPONSE_SIZE: usize = 40;
+    pub const GET_SIZE_REQUEST_SIZE: usize = 40;
+    pub const GET_SIZE_RESPONSE_SIZE: usize = 48;
+    pub const OPERATE_RANGE_REQUEST_SIZE: usize = 64;
+    pub const OPERATE_RANGE_RESPONSE_SIZE: usize = 104;
+    pub const OPERATE_RANGE_WITH_BUFFER_REQUEST_SIZE: usize = 88;
+    pub const OPERATE_RANGE_WITH_BUFFER_RESPONSE_SIZE: usize = 40;
+    pub fn as_ref(&self) -> IFile<RefHandle<'_>> {
+        IFile {
+            handle: self.handle.as_ref(),
+        }
+    }
+    pub fn into_shared(self) -> IFile<SharedHandle> {
+        IFile::new(SharedHandle::new(self.into_inner().leak()))
+    }
+}
+impl<S: HandleStorage> From<S> for IFile<S> {
+    fn from(handle: S) -> Self {
+        Self::new(handle)
+    }
+}
+impl<S: HandleStorage> PartialEq for IFile<S> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.handle.get() == *other.handle.get()
+    }
+}
+impl<S: HandleStorage> Eq for IFile<S> {}
+impl ::core::fmt::Debug for IFile {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        write!(f, "IFile({})", self.handle)
+    }
+}
+
+#[repr(transparent)]
+pub struct IDirectory<S: HandleStorage = OwnedHandle> {
+    pub(crate) handle: SessionHandle<S>,
+}
+impl<S: HandleStorage> IDirectory<S> {
+    pub fn new(handle: S) -> Self {
+        Self {
+            handle: SessionHandle::new(handle),
         }
-        debug_assert_eq!(hipc.num_in_pointers(), 0);
-        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
-        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
-        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
-        debug_assert_eq!(hipc.out_pointer_mode(), 0);
-        debug_assert_eq!(hipc.has_special_header(), 0);
-        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(())
+    }
+    pub fn into_inner(self) -> S {
+        self.handle.into_inner()
+    }
+    pub fn with_storage<S2: HandleStorage>(
+        self,
+        f: impl FnOnce(S) -> S2,
+    ) -> IDirectory<S2> {
+        IDirectory::new(f(self.into_inner()))
+    }
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn read(&self, out_entries: &mut [DirectoryEntry]) -> Result<i64> {
+        let res = self.read_untracked(out_entries);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IDirectory::Read",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
     }
 
-    pub fn get_size(&self) -> Result<i64> {
+    #[inline(always)]
+    fn read_untracked(&self, out_entries: &mut [DirectoryEntry]) -> Result<i64> {
         let data_in = ();
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
-            pre_padding: [u8; 8],
+            out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            pre_padding: [u8; 12],
             cmif: CmifInHeader,
             raw_data: (),
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 8],
+            post_padding: [u8; 4],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 52]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -2441,6 +7175,11 @@ impl<S: HandleStorage> IFile<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        // Compiler time array element layout check
+        let _ = ::core::mem::transmute::<DirectoryEntry, [u8; 784]>;
+        const _: () = if ::core::mem::align_of::<DirectoryEntry>() > 8 {
+            panic!("Array element is over-aligned")
+        };
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
         unsafe {
             ::core::ptr::write(
@@ -2450,18 +7189,23 @@ impl<S: HandleStorage> IFile<S> {
                         CommandType::Request,
                         0,
                         0,
-                        0,
+                        1,
                         0,
                         8,
                         0,
                         0,
                         false,
                     ),
+                    out_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::Normal,
+                        out_entries.as_ptr() as usize,
+                        ::core::mem::size_of_val(out_entries),
+                    ),
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 4,
+                        command_id: 0,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -2472,11 +7216,11 @@ impl<S: HandleStorage> IFile<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFile::GetSize", *handle);
+            crate::pre_ipc_hook("fssrv::IDirectory::Read", *handle, 52);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFile::GetSize", *handle);
+            crate::post_ipc_hook("fssrv::IDirectory::Read", *handle, 48);
         }
-        let Response { hipc, cmif, raw_data: size, .. } = unsafe {
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
         if cmif.result.is_failure() {
@@ -2489,74 +7233,72 @@ impl<S: HandleStorage> IFile<S> {
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
         debug_assert_eq!(hipc.has_special_header(), 0);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(size)
+        Ok(out)
     }
 
-    pub fn operate_range(
-        &self,
-        op_id: OperationId,
-        offset: i64,
-        size: i64,
-    ) -> Result<FileQueryRangeInfo> {
-        #[repr(C, packed)]
-        struct In {
-            pub op_id: OperationId,
-            pub _padding_0: [u8; 4],
-            pub offset: i64,
-            pub size: i64,
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn get_entry_count(&self) -> Result<i64> {
+        let res = self.get_entry_count_untracked();
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "fssrv::IDirectory::GetEntryCount",
+                ::core::panic::Location::caller(),
+                *e,
+            );
         }
-        let _ = ::core::mem::transmute::<In, [u8; 24]>;
-        let data_in: In = In {
-            op_id,
-            offset,
-            size,
-            _padding_0: Default::default(),
-        };
-        #[repr(packed)]
+        res
+    }
+
+    #[inline(always)]
+    fn get_entry_count_untracked(&self) -> Result<i64> {
+        let data_in = ();
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
             cmif: CmifInHeader,
-            raw_data: In,
+            raw_data: (),
             raw_data_word_padding: [u8; 0],
             post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 64]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
             cmif: CmifOutHeader,
-            raw_data: FileQueryRangeInfo,
+            raw_data: i64,
             raw_data_word_padding: [u8; 0],
             post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Response, [u8; 104]>;
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 1,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        0,
-                        0,
-                        0,
-                        0,
-                        14,
-                        0,
-                        0,
-                        false,
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 5,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
@@ -2565,9 +7307,9 @@ impl<S: HandleStorage> IFile<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFile::OperateRange", *handle);
+            crate::pre_ipc_hook("fssrv::IDirectory::GetEntryCount", *handle, 40);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFile::OperateRange", *handle);
+            crate::post_ipc_hook("fssrv::IDirectory::GetEntryCount", *handle, 48);
         }
         let Response { hipc, cmif, raw_data: out, .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
@@ -2585,53 +7327,43 @@ impl<S: HandleStorage> IFile<S> {
         Ok(out)
     }
 
-    pub fn operate_range_with_buffer(
+    #[cfg(feature = "async-ipc")]
+    pub async fn read_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
         &self,
-        out_buf: &mut [u8],
-        in_buf: &[u8],
-        op_id: OperationId,
-        offset: i64,
-        size: i64,
-    ) -> Result<()> {
+        executor: &E,
+        out_entries: &mut [DirectoryEntry],
+    ) -> Result<i64> {
+        let data_in = ();
         #[repr(C, packed)]
-        struct In {
-            pub op_id: OperationId,
-            pub _padding_0: [u8; 4],
-            pub offset: i64,
-            pub size: i64,
-        }
-        let _ = ::core::mem::transmute::<In, [u8; 24]>;
-        let data_in: In = In {
-            op_id,
-            offset,
-            size,
-            _padding_0: Default::default(),
-        };
-        #[repr(packed)]
         struct Request {
             hipc: HipcHeader,
-            in_map_alias_desc_0: HipcMapAliasBufferDescriptor,
             out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
-            pre_padding: [u8; 0],
+            pre_padding: [u8; 12],
             cmif: CmifInHeader,
-            raw_data: In,
+            raw_data: (),
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 16],
+            post_padding: [u8; 4],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 88]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 52]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
             cmif: CmifOutHeader,
-            raw_data: (),
+            raw_data: i64,
             raw_data_word_padding: [u8; 0],
             post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
-        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        // Compiler time array element layout check
+        let _ = ::core::mem::transmute::<DirectoryEntry, [u8; 784]>;
+        const _: () = if ::core::mem::align_of::<DirectoryEntry>() > 8 {
+            panic!("Array element is over-aligned")
+        };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
@@ -2639,29 +7371,24 @@ impl<S: HandleStorage> IFile<S> {
                     hipc: HipcHeader::new(
                         CommandType::Request,
                         0,
-                        1,
+                        0,
                         1,
                         0,
-                        14,
+                        8,
                         0,
                         0,
                         false,
                     ),
-                    in_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
-                        MapAliasBufferMode::NonSecure,
-                        in_buf.as_ptr() as usize,
-                        ::core::mem::size_of_val(in_buf),
-                    ),
                     out_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
-                        MapAliasBufferMode::NonSecure,
-                        out_buf.as_ptr() as usize,
-                        ::core::mem::size_of_val(out_buf),
+                        MapAliasBufferMode::Normal,
+                        out_entries.as_ptr() as usize,
+                        ::core::mem::size_of_val(out_entries),
                     ),
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 6,
+                        command_id: 0,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -2672,11 +7399,10 @@ impl<S: HandleStorage> IFile<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IFile::OperateRangeWithBuffer", *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IFile::OperateRangeWithBuffer", *handle);
+            async_request.send(executor, *handle).await?;
         }
-        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
         if cmif.result.is_failure() {
@@ -2689,52 +7415,27 @@ impl<S: HandleStorage> IFile<S> {
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
         debug_assert_eq!(hipc.has_special_header(), 0);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(())
-    }
-}
-impl IFile<OwnedHandle> {
-    pub fn as_ref(&self) -> IFile<RefHandle<'_>> {
-        IFile {
-            handle: self.handle.as_ref(),
-        }
-    }
-    pub fn into_shared(self) -> IFile<SharedHandle> {
-        IFile {
-            handle: SharedHandle::new(self.handle.leak()),
-        }
-    }
-}
-impl ::core::fmt::Debug for IFile {
-    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
-        write!(f, "IFile({})", self.handle)
+        Ok(out)
     }
-}
 
-pub struct IDirectory<S: HandleStorage = OwnedHandle> {
-    pub(crate) handle: S,
-}
-impl<S: HandleStorage> IDirectory<S> {
-    pub fn new(handle: S) -> Self {
-        Self { handle }
-    }
-    pub fn into_inner(self) -> S {
-        self.handle
-    }
-    pub fn read(&self, out_entries: &mut [DirectoryEntry]) -> Result<i64> {
+    #[cfg(feature = "async-ipc")]
+    pub async fn get_entry_count_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+    ) -> Result<i64> {
         let data_in = ();
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
-            out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
-            pre_padding: [u8; 12],
+            pre_padding: [u8; 8],
             cmif: CmifInHeader,
             raw_data: (),
             raw_data_word_padding: [u8; 0],
-            post_padding: [u8; 4],
+            post_padding: [u8; 8],
         }
         // Compiler time request size check
-        let _ = ::core::mem::transmute::<Request, [u8; 52]>;
-        #[repr(packed)]
+        let _ = ::core::mem::transmute::<Request, [u8; 40]>;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -2745,34 +7446,32 @@ impl<S: HandleStorage> IDirectory<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 48]>;
-        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 1,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        0,
-                        0,
-                        1,
-                        0,
-                        8,
-                        0,
-                        0,
-                        false,
-                    ),
-                    out_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
-                        MapAliasBufferMode::Normal,
-                        out_entries.as_ptr() as usize,
-                        ::core::mem::size_of_val(out_entries),
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 0,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
@@ -2781,10 +7480,9 @@ impl<S: HandleStorage> IDirectory<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IDirectory::Read", *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IDirectory::Read", *handle);
+            async_request.send(executor, *handle).await?;
         }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
         let Response { hipc, cmif, raw_data: out, .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
@@ -2801,9 +7499,12 @@ impl<S: HandleStorage> IDirectory<S> {
         Ok(out)
     }
 
-    pub fn get_entry_count(&self) -> Result<i64> {
+    #[cfg(feature = "async-ipc")]
+    pub fn get_entry_count_send(
+        &self,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<i64>> {
         let data_in = ();
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -2814,7 +7515,7 @@ impl<S: HandleStorage> IDirectory<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 40]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -2825,69 +7526,122 @@ impl<S: HandleStorage> IDirectory<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 48]>;
-        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            8,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 1,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        0,
-                        0,
-                        0,
-                        0,
-                        8,
-                        0,
-                        0,
-                        false,
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 1,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
                 },
             )
         };
-        {
-            let handle = self.handle.get();
-            crate::pre_ipc_hook("fssrv::IDirectory::GetEntryCount", *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("fssrv::IDirectory::GetEntryCount", *handle);
-        }
-        let Response { hipc, cmif, raw_data: out, .. } = unsafe {
-            ::core::ptr::read(ipc_buffer_ptr as *const _)
-        };
-        if cmif.result.is_failure() {
-            return Err(cmif.result);
-        }
-        debug_assert_eq!(hipc.num_in_pointers(), 0);
-        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
-        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
-        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
-        debug_assert_eq!(hipc.out_pointer_mode(), 0);
-        debug_assert_eq!(hipc.has_special_header(), 0);
-        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
-        Ok(out)
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: out, .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(out)
+            },
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn read_raw(&self, raw_in: &[u8], out: &mut [u8]) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            0,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn get_entry_count_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            1,
+            raw_in,
+            out,
+        )
     }
 }
 impl IDirectory<OwnedHandle> {
+    pub const READ_REQUEST_SIZE: usize = 52;
+    pub const READ_RESPONSE_SIZE: usize = 48;
+    pub const GET_ENTRY_COUNT_REQUEST_SIZE: usize = 40;
+    pub const GET_ENTRY_COUNT_RESPONSE_SIZE: usize = 48;
     pub fn as_ref(&self) -> IDirectory<RefHandle<'_>> {
         IDirectory {
             handle: self.handle.as_ref(),
         }
     }
     pub fn into_shared(self) -> IDirectory<SharedHandle> {
-        IDirectory {
-            handle: SharedHandle::new(self.handle.leak()),
-        }
+        IDirectory::new(SharedHandle::new(self.into_inner().leak()))
+    }
+}
+impl<S: HandleStorage> From<S> for IDirectory<S> {
+    fn from(handle: S) -> Self {
+        Self::new(handle)
+    }
+}
+impl<S: HandleStorage> PartialEq for IDirectory<S> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.handle.get() == *other.handle.get()
     }
 }
+impl<S: HandleStorage> Eq for IDirectory<S> {}
 impl ::core::fmt::Debug for IDirectory {
     fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
         write!(f, "IDirectory({})", self.handle)
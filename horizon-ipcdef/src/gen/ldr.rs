@@ -4,7 +4,7 @@ use core::mem::MaybeUninit;
 use horizon_error::{ErrorCode, Result};
 use horizon_ipc::RawHandle;
 use horizon_ipc::buffer::get_ipc_buffer_ptr;
-use horizon_ipc::cmif::CommandType;
+use horizon_ipc::cmif::{CommandType, SessionHandle};
 use horizon_ipc::handle_storage::{HandleStorage, OwnedHandle, RefHandle, SharedHandle};
 use horizon_ipc::raw::cmif::{CmifInHeader, CmifOutHeader};
 use horizon_ipc::raw::hipc::{
@@ -14,6 +14,7 @@ use horizon_ipc::raw::hipc::{
 use super::ncm::{ProgramId, ProgramLocation};
 /// This struct is marked with sf::LargeData
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
 #[repr(C)]
 pub struct ProgramInfo {
     pub main_thread_priority: u8,
@@ -49,6 +50,7 @@ impl Default for ProgramInfo {
 }
 
 #[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
 #[repr(C)]
 pub struct PinId {
     pub value: u64,
@@ -58,21 +60,50 @@ const _: fn() = || {
     let _ = ::core::mem::transmute::<PinId, [u8; 8]>;
 };
 
+#[repr(transparent)]
 pub struct IProcessManagerInterface<S: HandleStorage = OwnedHandle> {
-    pub(crate) handle: S,
+    pub(crate) handle: SessionHandle<S>,
 }
 impl<S: HandleStorage> IProcessManagerInterface<S> {
     pub fn new(handle: S) -> Self {
-        Self { handle }
+        Self {
+            handle: SessionHandle::new(handle),
+        }
     }
     pub fn into_inner(self) -> S {
-        self.handle
+        self.handle.into_inner()
     }
+    pub fn with_storage<S2: HandleStorage>(
+        self,
+        f: impl FnOnce(S) -> S2,
+    ) -> IProcessManagerInterface<S2> {
+        IProcessManagerInterface::new(f(self.into_inner()))
+    }
+    #[must_use = "the returned object owns a kernel handle"]
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn create_process(
         &self,
         id: PinId,
         flags: u32,
         reslimit_h: RawHandle,
+    ) -> Result<OwnedHandle> {
+        let res = self.create_process_untracked(id, flags, reslimit_h);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "ldr::IProcessManagerInterface::CreateProcess",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn create_process_untracked(
+        &self,
+        id: PinId,
+        flags: u32,
+        reslimit_h: RawHandle,
     ) -> Result<OwnedHandle> {
         #[repr(C, packed)]
         struct In {
@@ -86,7 +117,7 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
             id,
             _padding_0: Default::default(),
         };
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             special_header: HipcSpecialHeader,
@@ -99,7 +130,16 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 64]>;
-        #[repr(packed)]
+        const REQUEST_NUM_COPY_HANDLES: u32 = 1;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        // Compiler time request handle count check
+        const _: () = if ::core::mem::offset_of!(Request, pre_padding)
+            - ::core::mem::offset_of!(Request, handle_reslimit_h)
+            != 4 * (REQUEST_NUM_COPY_HANDLES + REQUEST_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             special_header: HipcSpecialHeader,
@@ -112,6 +152,15 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_proc_h)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
         unsafe {
             ::core::ptr::write(
@@ -128,7 +177,11 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
                         0,
                         true,
                     ),
-                    special_header: HipcSpecialHeader::new(false, 1, 0),
+                    special_header: HipcSpecialHeader::new(
+                        false,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
                     handle_reslimit_h: reslimit_h,
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
@@ -145,11 +198,16 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("ldr::IProcessManagerInterface::CreateProcess", *handle);
+            crate::pre_ipc_hook(
+                "ldr::IProcessManagerInterface::CreateProcess",
+                *handle,
+                64,
+            );
             horizon_svc::send_sync_request(*handle)?;
             crate::post_ipc_hook(
                 "ldr::IProcessManagerInterface::CreateProcess",
                 *handle,
+                48,
             );
         }
         let Response {
@@ -176,16 +234,30 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
         debug_assert_eq!(hipc.has_special_header(), 1);
         debug_assert_eq!(special_header.send_pid(), 0);
-        debug_assert_eq!(special_header.num_copy_handles(), 0);
-        debug_assert_eq!(special_header.num_move_handles(), 1);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
         let proc_h = OwnedHandle::new(proc_h);
         Ok(proc_h)
     }
 
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn get_program_info(&self, loc: ProgramLocation) -> Result<ProgramInfo> {
+        let res = self.get_program_info_untracked(loc);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "ldr::IProcessManagerInterface::GetProgramInfo",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn get_program_info_untracked(&self, loc: ProgramLocation) -> Result<ProgramInfo> {
         let data_in = loc;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -197,7 +269,7 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 64]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             in_pointer_desc_0: HipcInPointerBufferDescriptor,
@@ -248,11 +320,13 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
             crate::pre_ipc_hook(
                 "ldr::IProcessManagerInterface::GetProgramInfo",
                 *handle,
+                64,
             );
             horizon_svc::send_sync_request(*handle)?;
             crate::post_ipc_hook(
                 "ldr::IProcessManagerInterface::GetProgramInfo",
                 *handle,
+                48,
             );
         }
         let Response { hipc, cmif, raw_data: (), .. } = unsafe {
@@ -272,9 +346,23 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         Ok(out_program_info)
     }
 
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn pin_program(&self, loc: ProgramLocation) -> Result<PinId> {
+        let res = self.pin_program_untracked(loc);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "ldr::IProcessManagerInterface::PinProgram",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn pin_program_untracked(&self, loc: ProgramLocation) -> Result<PinId> {
         let data_in = loc;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -285,7 +373,7 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 56]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -297,28 +385,30 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 48]>;
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            12,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 2,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        0,
-                        0,
-                        0,
-                        0,
-                        12,
-                        0,
-                        0,
-                        false,
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 2,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
@@ -327,9 +417,17 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("ldr::IProcessManagerInterface::PinProgram", *handle);
+            crate::pre_ipc_hook(
+                "ldr::IProcessManagerInterface::PinProgram",
+                *handle,
+                56,
+            );
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("ldr::IProcessManagerInterface::PinProgram", *handle);
+            crate::post_ipc_hook(
+                "ldr::IProcessManagerInterface::PinProgram",
+                *handle,
+                48,
+            );
         }
         let Response { hipc, cmif, raw_data: out_id, .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
@@ -347,9 +445,23 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         Ok(out_id)
     }
 
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn unpin_program(&self, id: PinId) -> Result<()> {
+        let res = self.unpin_program_untracked(id);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "ldr::IProcessManagerInterface::UnpinProgram",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn unpin_program_untracked(&self, id: PinId) -> Result<()> {
         let data_in = id;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -360,7 +472,7 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -372,28 +484,30 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 40]>;
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 3,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        0,
-                        0,
-                        0,
-                        0,
-                        10,
-                        0,
-                        0,
-                        false,
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 3,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
@@ -402,9 +516,17 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("ldr::IProcessManagerInterface::UnpinProgram", *handle);
+            crate::pre_ipc_hook(
+                "ldr::IProcessManagerInterface::UnpinProgram",
+                *handle,
+                48,
+            );
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("ldr::IProcessManagerInterface::UnpinProgram", *handle);
+            crate::post_ipc_hook(
+                "ldr::IProcessManagerInterface::UnpinProgram",
+                *handle,
+                40,
+            );
         }
         let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
@@ -422,9 +544,23 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         Ok(())
     }
 
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn set_enabled_program_verification(&self, enabled: bool) -> Result<()> {
+        let res = self.set_enabled_program_verification_untracked(enabled);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "ldr::IProcessManagerInterface::SetEnabledProgramVerification",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn set_enabled_program_verification_untracked(&self, enabled: bool) -> Result<()> {
         let data_in = enabled;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -435,7 +571,7 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 44]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -447,28 +583,30 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 40]>;
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            9,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 4,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        0,
-                        0,
-                        0,
-                        0,
-                        9,
-                        0,
-                        0,
-                        false,
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 4,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
@@ -480,11 +618,13 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
             crate::pre_ipc_hook(
                 "ldr::IProcessManagerInterface::SetEnabledProgramVerification",
                 *handle,
+                44,
             );
             horizon_svc::send_sync_request(*handle)?;
             crate::post_ipc_hook(
                 "ldr::IProcessManagerInterface::SetEnabledProgramVerification",
                 *handle,
+                40,
             );
         }
         let Response { hipc, cmif, raw_data: (), .. } = unsafe {
@@ -502,19 +642,996 @@ impl<S: HandleStorage> IProcessManagerInterface<S> {
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
         Ok(())
     }
-}
-impl IProcessManagerInterface<OwnedHandle> {
-    pub fn as_ref(&self) -> IProcessManagerInterface<RefHandle<'_>> {
-        IProcessManagerInterface {
-            handle: self.handle.as_ref(),
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn create_process_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        id: PinId,
+        flags: u32,
+        reslimit_h: RawHandle,
+    ) -> Result<OwnedHandle> {
+        #[repr(C, packed)]
+        struct In {
+            pub flags: u32,
+            pub _padding_0: [u8; 4],
+            pub id: PinId,
         }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            flags,
+            id,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_reslimit_h: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 64]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 1;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        // Compiler time request handle count check
+        const _: () = if ::core::mem::offset_of!(Request, pre_padding)
+            - ::core::mem::offset_of!(Request, handle_reslimit_h)
+            != 4 * (REQUEST_NUM_COPY_HANDLES + REQUEST_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_proc_h: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_proc_h)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        0,
+                        0,
+                        12,
+                        0,
+                        0,
+                        true,
+                    ),
+                    special_header: HipcSpecialHeader::new(
+                        false,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    handle_reslimit_h: reslimit_h,
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 0,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response {
+            hipc,
+            special_header,
+            handle_proc_h: proc_h,
+            cmif,
+            raw_data: (),
+            ..
+        } = unsafe { ::core::ptr::read(ipc_buffer_ptr as *const _) };
+        if hipc.has_special_header() != 0 {
+            if cmif.result.is_failure() {
+                return Err(cmif.result);
+            }
+        } else {
+            return Err(unsafe {
+                ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+            })
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 1);
+        debug_assert_eq!(special_header.send_pid(), 0);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        let proc_h = OwnedHandle::new(proc_h);
+        Ok(proc_h)
     }
-    pub fn into_shared(self) -> IProcessManagerInterface<SharedHandle> {
-        IProcessManagerInterface {
-            handle: SharedHandle::new(self.handle.leak()),
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn get_program_info_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        loc: ProgramLocation,
+    ) -> Result<ProgramInfo> {
+        let data_in = loc;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ProgramLocation,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+            out_pointer_desc_0: HipcOutPointerBufferDescriptor,
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 64]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            in_pointer_desc_0: HipcInPointerBufferDescriptor,
+            pre_padding: [u8; 0],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
         }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let out_program_info = MaybeUninit::<ProgramInfo>::uninit();
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        0,
+                        0,
+                        12,
+                        3,
+                        0,
+                        false,
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 1,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                    out_pointer_desc_0: HipcOutPointerBufferDescriptor::new(
+                        out_program_info.as_ptr() as usize,
+                        ::core::mem::size_of_val(&out_program_info),
+                    ),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 1);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        let out_program_info = unsafe { out_program_info.assume_init() };
+        Ok(out_program_info)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn pin_program_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        loc: ProgramLocation,
+    ) -> Result<PinId> {
+        let data_in = loc;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ProgramLocation,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 56]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: PinId,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            12,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 2,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: out_id, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(out_id)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn unpin_program_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        id: PinId,
+    ) -> Result<()> {
+        let data_in = id;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: PinId,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 3,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn set_enabled_program_verification_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E, enabled: bool) -> Result<()> {
+        let data_in = enabled;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: bool,
+            raw_data_word_padding: [u8; 3],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 44]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            9,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 4,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn create_process_send(
+        &self,
+        id: PinId,
+        flags: u32,
+        reslimit_h: RawHandle,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<OwnedHandle>> {
+        #[repr(C, packed)]
+        struct In {
+            pub flags: u32,
+            pub _padding_0: [u8; 4],
+            pub id: PinId,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            flags,
+            id,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_reslimit_h: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 64]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 1;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        // Compiler time request handle count check
+        const _: () = if ::core::mem::offset_of!(Request, pre_padding)
+            - ::core::mem::offset_of!(Request, handle_reslimit_h)
+            != 4 * (REQUEST_NUM_COPY_HANDLES + REQUEST_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_proc_h: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_proc_h)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        0,
+                        0,
+                        12,
+                        0,
+                        0,
+                        true,
+                    ),
+                    special_header: HipcSpecialHeader::new(
+                        false,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    handle_reslimit_h: reslimit_h,
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 0,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response {
+                    hipc,
+                    special_header,
+                    handle_proc_h: proc_h,
+                    cmif,
+                    raw_data: (),
+                    ..
+                } = unsafe { ::core::ptr::read(ipc_buffer_ptr as *const _) };
+                if hipc.has_special_header() != 0 {
+                    if cmif.result.is_failure() {
+                        return Err(cmif.result);
+                    }
+                } else {
+                    return Err(unsafe {
+                        ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+                    })
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 1);
+                debug_assert_eq!(special_header.send_pid(), 0);
+                debug_assert_eq!(
+                    special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES
+                );
+                debug_assert_eq!(
+                    special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES
+                );
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                let proc_h = OwnedHandle::new(proc_h);
+                Ok(proc_h)
+            },
+        )
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn pin_program_send(
+        &self,
+        loc: ProgramLocation,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<PinId>> {
+        let data_in = loc;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ProgramLocation,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 56]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: PinId,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            12,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 2,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: out_id, .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(out_id)
+            },
+        )
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn unpin_program_send(
+        &self,
+        id: PinId,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<()>> {
+        let data_in = id;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: PinId,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 3,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(())
+            },
+        )
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn set_enabled_program_verification_send(
+        &self,
+        enabled: bool,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<()>> {
+        let data_in = enabled;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: bool,
+            raw_data_word_padding: [u8; 3],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 44]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            9,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 4,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(())
+            },
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn create_process_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            0,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn get_program_info_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            1,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn pin_program_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            2,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn unpin_program_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            3,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn set_enabled_program_verification_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            4,
+            raw_in,
+            out,
+        )
+    }
+}
+impl IProcessManagerInterface<OwnedHandle> {
+    pub const CREATE_PROCESS_REQUEST_SIZE: usize = 64;
+    pub const CREATE_PROCESS_RESPONSE_SIZE: usize = 48;
+    pub const GET_PROGRAM_INFO_REQUEST_SIZE: usize = 64;
+    pub const GET_PROGRAM_INFO_RESPONSE_SIZE: usize = 48;
+    pub const PIN_PROGRAM_REQUEST_SIZE: usize = 56;
+    pub const PIN_PROGRAM_RESPONSE_SIZE: usize = 48;
+    pub const UNPIN_PROGRAM_REQUEST_SIZE: usize = 48;
+    pub const UNPIN_PROGRAM_RESPONSE_SIZE: usize = 40;
+    pub const SET_ENABLED_PROGRAM_VERIFICATION_REQUEST_SIZE: usize = 44;
+    pub const SET_ENABLED_PROGRAM_VERIFICATION_RESPONSE_SIZE: usize = 40;
+    pub fn as_ref(&self) -> IProcessManagerInterface<RefHandle<'_>> {
+        IProcessManagerInterface {
+            handle: self.handle.as_ref(),
+        }
+    }
+    pub fn into_shared(self) -> IProcessManagerInterface<SharedHandle> {
+        IProcessManagerInterface::new(SharedHandle::new(self.into_inner().leak()))
+    }
+}
+impl<S: HandleStorage> From<S> for IProcessManagerInterface<S> {
+    fn from(handle: S) -> Self {
+        Self::new(handle)
+    }
+}
+impl<S: HandleStorage> PartialEq for IProcessManagerInterface<S> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.handle.get() == *other.handle.get()
     }
 }
+impl<S: HandleStorage> Eq for IProcessManagerInterface<S> {}
 impl ::core::fmt::Debug for IProcessManagerInterface {
     fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
         write!(f, "IProcessManagerInterface({})", self.handle)
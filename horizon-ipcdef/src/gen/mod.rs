@@ -7,6 +7,7 @@
 )]
 ij_core_workaround!();
 pub mod account;
+pub mod fatal;
 pub mod fssrv;
 pub mod ldr;
 pub mod ncm;
@@ -13,6 +13,21 @@ pub enum StorageId {
     SdCard = 5,
     Any = 6,
 }
+impl ::core::convert::TryFrom<u8> for StorageId {
+    type Error = u8;
+    fn try_from(value: u8) -> ::core::result::Result<Self, u8> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Host),
+            2 => Ok(Self::GameCard),
+            3 => Ok(Self::BuiltInSystem),
+            4 => Ok(Self::BuiltInUser),
+            5 => Ok(Self::SdCard),
+            6 => Ok(Self::Any),
+            _ => Err(value),
+        }
+    }
+}
 #[derive(Debug, Clone, Copy, Default)]
 #[repr(C)]
 pub struct ProgramLocation {
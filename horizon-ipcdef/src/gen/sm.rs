@@ -3,11 +3,12 @@ ij_core_workaround!();
 use horizon_error::{ErrorCode, Result};
 use horizon_ipc::RawHandle;
 use horizon_ipc::buffer::get_ipc_buffer_ptr;
-use horizon_ipc::cmif::CommandType;
+use horizon_ipc::cmif::{CommandType, SessionHandle};
 use horizon_ipc::handle_storage::{HandleStorage, OwnedHandle, RefHandle, SharedHandle};
 use horizon_ipc::raw::cmif::{CmifInHeader, CmifOutHeader};
 use horizon_ipc::raw::hipc::{HipcHeader, HipcSpecialHeader};
 #[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
 #[repr(C)]
 pub struct ServiceName {
     pub name: [u8; 8],
@@ -17,19 +18,42 @@ const _: fn() = || {
     let _ = ::core::mem::transmute::<ServiceName, [u8; 8]>;
 };
 
+#[repr(transparent)]
 pub struct IUserInterface<S: HandleStorage = OwnedHandle> {
-    pub(crate) handle: S,
+    pub(crate) handle: SessionHandle<S>,
 }
 impl<S: HandleStorage> IUserInterface<S> {
     pub fn new(handle: S) -> Self {
-        Self { handle }
+        Self {
+            handle: SessionHandle::new(handle),
+        }
     }
     pub fn into_inner(self) -> S {
-        self.handle
+        self.handle.into_inner()
+    }
+    pub fn with_storage<S2: HandleStorage>(
+        self,
+        f: impl FnOnce(S) -> S2,
+    ) -> IUserInterface<S2> {
+        IUserInterface::new(f(self.into_inner()))
     }
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn initialize(&self) -> Result<()> {
+        let res = self.initialize_untracked();
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "sm::IUserInterface::Initialize",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn initialize_untracked(&self) -> Result<()> {
         let data_in = 0u64;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             special_header: HipcSpecialHeader,
@@ -42,7 +66,9 @@ impl<S: HandleStorage> IUserInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 60]>;
-        #[repr(packed)]
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -69,7 +95,11 @@ impl<S: HandleStorage> IUserInterface<S> {
                         0,
                         true,
                     ),
-                    special_header: HipcSpecialHeader::new(true, 0, 0),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
                     pid_placeholder: 0,
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
@@ -86,9 +116,9 @@ impl<S: HandleStorage> IUserInterface<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("sm::IUserInterface::Initialize", *handle);
+            crate::pre_ipc_hook("sm::IUserInterface::Initialize", *handle, 60);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("sm::IUserInterface::Initialize", *handle);
+            crate::post_ipc_hook("sm::IUserInterface::Initialize", *handle, 40);
         }
         let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
@@ -106,9 +136,24 @@ impl<S: HandleStorage> IUserInterface<S> {
         Ok(())
     }
 
+    #[must_use = "the returned object owns a kernel handle"]
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn get_service(&self, name: ServiceName) -> Result<OwnedHandle> {
+        let res = self.get_service_untracked(name);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "sm::IUserInterface::GetService",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn get_service_untracked(&self, name: ServiceName) -> Result<OwnedHandle> {
         let data_in = name;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -119,7 +164,7 @@ impl<S: HandleStorage> IUserInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             special_header: HipcSpecialHeader,
@@ -132,29 +177,40 @@ impl<S: HandleStorage> IUserInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_session_handle)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 1,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        0,
-                        0,
-                        0,
-                        0,
-                        10,
-                        0,
-                        0,
-                        false,
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 1,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
@@ -163,9 +219,9 @@ impl<S: HandleStorage> IUserInterface<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("sm::IUserInterface::GetService", *handle);
+            crate::pre_ipc_hook("sm::IUserInterface::GetService", *handle, 48);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("sm::IUserInterface::GetService", *handle);
+            crate::post_ipc_hook("sm::IUserInterface::GetService", *handle, 48);
         }
         let Response {
             hipc,
@@ -191,18 +247,38 @@ impl<S: HandleStorage> IUserInterface<S> {
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
         debug_assert_eq!(hipc.has_special_header(), 1);
         debug_assert_eq!(special_header.send_pid(), 0);
-        debug_assert_eq!(special_header.num_copy_handles(), 0);
-        debug_assert_eq!(special_header.num_move_handles(), 1);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
         let session_handle = OwnedHandle::new(session_handle);
         Ok(session_handle)
     }
 
+    #[must_use = "the returned object owns a kernel handle"]
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn register_service(
         &self,
         name: ServiceName,
         max_sessions: u32,
         is_light: bool,
+    ) -> Result<OwnedHandle> {
+        let res = self.register_service_untracked(name, max_sessions, is_light);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "sm::IUserInterface::RegisterService",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn register_service_untracked(
+        &self,
+        name: ServiceName,
+        max_sessions: u32,
+        is_light: bool,
     ) -> Result<OwnedHandle> {
         #[repr(C, packed)]
         struct In {
@@ -218,7 +294,7 @@ impl<S: HandleStorage> IUserInterface<S> {
             max_sessions,
             _padding_0: Default::default(),
         };
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -229,7 +305,7 @@ impl<S: HandleStorage> IUserInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 56]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             special_header: HipcSpecialHeader,
@@ -242,29 +318,40 @@ impl<S: HandleStorage> IUserInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_port_handle)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            12,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 2,
+            token: 0,
+        };
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
                 Request {
-                    hipc: HipcHeader::new(
-                        CommandType::Request,
-                        0,
-                        0,
-                        0,
-                        0,
-                        12,
-                        0,
-                        0,
-                        false,
-                    ),
+                    hipc: HIPC_HEADER,
                     pre_padding: Default::default(),
-                    cmif: CmifInHeader {
-                        magic: CmifInHeader::MAGIC,
-                        version: 1,
-                        command_id: 2,
-                        token: 0,
-                    },
+                    cmif: CMIF_HEADER,
                     raw_data: data_in,
                     raw_data_word_padding: Default::default(),
                     post_padding: Default::default(),
@@ -273,9 +360,9 @@ impl<S: HandleStorage> IUserInterface<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("sm::IUserInterface::RegisterService", *handle);
+            crate::pre_ipc_hook("sm::IUserInterface::RegisterService", *handle, 56);
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("sm::IUserInterface::RegisterService", *handle);
+            crate::post_ipc_hook("sm::IUserInterface::RegisterService", *handle, 48);
         }
         let Response {
             hipc,
@@ -301,16 +388,216 @@ impl<S: HandleStorage> IUserInterface<S> {
         debug_assert_eq!(hipc.out_pointer_mode(), 0);
         debug_assert_eq!(hipc.has_special_header(), 1);
         debug_assert_eq!(special_header.send_pid(), 0);
-        debug_assert_eq!(special_header.num_copy_handles(), 0);
-        debug_assert_eq!(special_header.num_move_handles(), 1);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
         let port_handle = OwnedHandle::new(port_handle);
         Ok(port_handle)
     }
 
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn unregister_service(&self, name: ServiceName) -> Result<()> {
+        let res = self.unregister_service_untracked(name);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "sm::IUserInterface::UnregisterService",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn unregister_service_untracked(&self, name: ServiceName) -> Result<()> {
+        let data_in = name;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ServiceName,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 3,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("sm::IUserInterface::UnregisterService", *handle, 48);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook("sm::IUserInterface::UnregisterService", *handle, 40);
+        }
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn atmosphere_has_service(&self, name: ServiceName) -> Result<bool> {
+        let res = self.atmosphere_has_service_untracked(name);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "sm::IUserInterface::AtmosphereHasService",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn atmosphere_has_service_untracked(&self, name: ServiceName) -> Result<bool> {
+        let data_in = name;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ServiceName,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: bool,
+            raw_data_word_padding: [u8; 3],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 44]>;
+        let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 65100,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook("sm::IUserInterface::AtmosphereHasService", *handle, 48);
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook(
+                "sm::IUserInterface::AtmosphereHasService",
+                *handle,
+                44,
+            );
+        }
+        let Response { hipc, cmif, raw_data: has_service, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(has_service)
+    }
+
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
+    pub fn atmosphere_wait_service(&self, name: ServiceName) -> Result<()> {
+        let res = self.atmosphere_wait_service_untracked(name);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "sm::IUserInterface::AtmosphereWaitService",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn atmosphere_wait_service_untracked(&self, name: ServiceName) -> Result<()> {
         let data_in = name;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -321,7 +608,7 @@ impl<S: HandleStorage> IUserInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 48]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -333,6 +620,100 @@ impl<S: HandleStorage> IUserInterface<S> {
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Response, [u8; 40]>;
         let ipc_buffer_ptr = unsafe { get_ipc_buffer_ptr() };
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 65101,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            crate::pre_ipc_hook(
+                "sm::IUserInterface::AtmosphereWaitService",
+                *handle,
+                48,
+            );
+            horizon_svc::send_sync_request(*handle)?;
+            crate::post_ipc_hook(
+                "sm::IUserInterface::AtmosphereWaitService",
+                *handle,
+                40,
+            );
+        }
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn initialize_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+    ) -> Result<()> {
+        let data_in = 0u64;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            pid_placeholder: u64,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: u64,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 60]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
         unsafe {
             ::core::ptr::write(
                 ipc_buffer_ptr as *mut _,
@@ -346,13 +727,19 @@ impl<S: HandleStorage> IUserInterface<S> {
                         10,
                         0,
                         0,
-                        false,
+                        true,
                     ),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    pid_placeholder: 0,
                     pre_padding: Default::default(),
                     cmif: CmifInHeader {
                         magic: CmifInHeader::MAGIC,
                         version: 1,
-                        command_id: 3,
+                        command_id: 0,
                         token: 0,
                     },
                     raw_data: data_in,
@@ -363,10 +750,9 @@ impl<S: HandleStorage> IUserInterface<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("sm::IUserInterface::UnregisterService", *handle);
-            horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("sm::IUserInterface::UnregisterService", *handle);
+            async_request.send(executor, *handle).await?;
         }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
         let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
         };
@@ -382,19 +768,1207 @@ impl<S: HandleStorage> IUserInterface<S> {
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
         Ok(())
     }
-}
-impl IUserInterface<OwnedHandle> {
-    pub fn as_ref(&self) -> IUserInterface<RefHandle<'_>> {
-        IUserInterface {
-            handle: self.handle.as_ref(),
-        }
-    }
-    pub fn into_shared(self) -> IUserInterface<SharedHandle> {
-        IUserInterface {
-            handle: SharedHandle::new(self.handle.leak()),
-        }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn get_service_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        name: ServiceName,
+    ) -> Result<OwnedHandle> {
+        let data_in = name;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ServiceName,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_session_handle: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_session_handle)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 1,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response {
+            hipc,
+            special_header,
+            handle_session_handle: session_handle,
+            cmif,
+            raw_data: (),
+            ..
+        } = unsafe { ::core::ptr::read(ipc_buffer_ptr as *const _) };
+        if hipc.has_special_header() != 0 {
+            if cmif.result.is_failure() {
+                return Err(cmif.result);
+            }
+        } else {
+            return Err(unsafe {
+                ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+            })
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 1);
+        debug_assert_eq!(special_header.send_pid(), 0);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        let session_handle = OwnedHandle::new(session_handle);
+        Ok(session_handle)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn register_service_async<E: horizon_ipc::async_ipc::IpcExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        name: ServiceName,
+        max_sessions: u32,
+        is_light: bool,
+    ) -> Result<OwnedHandle> {
+        #[repr(C, packed)]
+        struct In {
+            pub name: ServiceName,
+            pub is_light: bool,
+            pub _padding_0: [u8; 3],
+            pub max_sessions: u32,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            name,
+            is_light,
+            max_sessions,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 56]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_port_handle: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_port_handle)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            12,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 2,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response {
+            hipc,
+            special_header,
+            handle_port_handle: port_handle,
+            cmif,
+            raw_data: (),
+            ..
+        } = unsafe { ::core::ptr::read(ipc_buffer_ptr as *const _) };
+        if hipc.has_special_header() != 0 {
+            if cmif.result.is_failure() {
+                return Err(cmif.result);
+            }
+        } else {
+            return Err(unsafe {
+                ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+            })
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 1);
+        debug_assert_eq!(special_header.send_pid(), 0);
+        debug_assert_eq!(special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES);
+        debug_assert_eq!(special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        let port_handle = OwnedHandle::new(port_handle);
+        Ok(port_handle)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn unregister_service_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E, name: ServiceName) -> Result<()> {
+        let data_in = name;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ServiceName,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 3,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn atmosphere_has_service_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E, name: ServiceName) -> Result<bool> {
+        let data_in = name;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ServiceName,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: bool,
+            raw_data_word_padding: [u8; 3],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 44]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 65100,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: has_service, .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(has_service)
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn atmosphere_wait_service_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E, name: ServiceName) -> Result<()> {
+        let data_in = name;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ServiceName,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 65101,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn initialize_send(
+        &self,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<()>> {
+        let data_in = 0u64;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            pid_placeholder: u64,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: u64,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 60]>;
+        const REQUEST_NUM_COPY_HANDLES: u32 = 0;
+        const REQUEST_NUM_MOVE_HANDLES: u32 = 0;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        0,
+                        0,
+                        10,
+                        0,
+                        0,
+                        true,
+                    ),
+                    special_header: HipcSpecialHeader::new(
+                        true,
+                        REQUEST_NUM_COPY_HANDLES,
+                        REQUEST_NUM_MOVE_HANDLES,
+                    ),
+                    pid_placeholder: 0,
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 0,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(())
+            },
+        )
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn get_service_send(
+        &self,
+        name: ServiceName,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<OwnedHandle>> {
+        let data_in = name;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ServiceName,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_session_handle: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_session_handle)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 1,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response {
+                    hipc,
+                    special_header,
+                    handle_session_handle: session_handle,
+                    cmif,
+                    raw_data: (),
+                    ..
+                } = unsafe { ::core::ptr::read(ipc_buffer_ptr as *const _) };
+                if hipc.has_special_header() != 0 {
+                    if cmif.result.is_failure() {
+                        return Err(cmif.result);
+                    }
+                } else {
+                    return Err(unsafe {
+                        ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+                    })
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 1);
+                debug_assert_eq!(special_header.send_pid(), 0);
+                debug_assert_eq!(
+                    special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES
+                );
+                debug_assert_eq!(
+                    special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES
+                );
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                let session_handle = OwnedHandle::new(session_handle);
+                Ok(session_handle)
+            },
+        )
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn register_service_send(
+        &self,
+        name: ServiceName,
+        max_sessions: u32,
+        is_light: bool,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<OwnedHandle>> {
+        #[repr(C, packed)]
+        struct In {
+            pub name: ServiceName,
+            pub is_light: bool,
+            pub _padding_0: [u8; 3],
+            pub max_sessions: u32,
+        }
+        let _ = ::core::mem::transmute::<In, [u8; 16]>;
+        let data_in: In = In {
+            name,
+            is_light,
+            max_sessions,
+            _padding_0: Default::default(),
+        };
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: In,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 56]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            special_header: HipcSpecialHeader,
+            handle_port_handle: RawHandle,
+            pre_padding: [u8; 0],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 16],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 48]>;
+        const RESPONSE_NUM_COPY_HANDLES: u32 = 0;
+        const RESPONSE_NUM_MOVE_HANDLES: u32 = 1;
+        // Compiler time response handle count check
+        const _: () = if ::core::mem::offset_of!(Response, pre_padding)
+            - ::core::mem::offset_of!(Response, handle_port_handle)
+            != 4 * (RESPONSE_NUM_COPY_HANDLES + RESPONSE_NUM_MOVE_HANDLES) as usize
+        {
+            panic!("Handle fields don't match the special header")
+        };
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            12,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 2,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response {
+                    hipc,
+                    special_header,
+                    handle_port_handle: port_handle,
+                    cmif,
+                    raw_data: (),
+                    ..
+                } = unsafe { ::core::ptr::read(ipc_buffer_ptr as *const _) };
+                if hipc.has_special_header() != 0 {
+                    if cmif.result.is_failure() {
+                        return Err(cmif.result);
+                    }
+                } else {
+                    return Err(unsafe {
+                        ::core::ptr::read(ipc_buffer_ptr.offset(24) as *const ErrorCode)
+                    })
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 1);
+                debug_assert_eq!(special_header.send_pid(), 0);
+                debug_assert_eq!(
+                    special_header.num_copy_handles(), RESPONSE_NUM_COPY_HANDLES
+                );
+                debug_assert_eq!(
+                    special_header.num_move_handles(), RESPONSE_NUM_MOVE_HANDLES
+                );
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                let port_handle = OwnedHandle::new(port_handle);
+                Ok(port_handle)
+            },
+        )
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn unregister_service_send(
+        &self,
+        name: ServiceName,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<()>> {
+        let data_in = name;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ServiceName,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 3,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(())
+            },
+        )
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn atmosphere_has_service_send(
+        &self,
+        name: ServiceName,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<bool>> {
+        let data_in = name;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ServiceName,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: bool,
+            raw_data_word_padding: [u8; 3],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 44]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 65100,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: has_service, .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(has_service)
+            },
+        )
+    }
+
+    #[cfg(feature = "async-ipc")]
+    pub fn atmosphere_wait_service_send(
+        &self,
+        name: ServiceName,
+    ) -> Result<horizon_ipc::async_ipc::PendingResponse<()>> {
+        let data_in = name;
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifInHeader,
+            raw_data: ServiceName,
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 48]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new_boxed();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        const HIPC_HEADER: HipcHeader = HipcHeader::new(
+            CommandType::Request,
+            0,
+            0,
+            0,
+            0,
+            10,
+            0,
+            0,
+            false,
+        );
+        const CMIF_HEADER: CmifInHeader = CmifInHeader {
+            magic: CmifInHeader::MAGIC,
+            version: 1,
+            command_id: 65101,
+            token: 0,
+        };
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HIPC_HEADER,
+                    pre_padding: Default::default(),
+                    cmif: CMIF_HEADER,
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        let handle = self.handle.get();
+        horizon_ipc::async_ipc::PendingResponse::send(
+            async_request,
+            *handle,
+            |ipc_buffer_ptr| {
+                let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+                    ::core::ptr::read(ipc_buffer_ptr as *const _)
+                };
+                if cmif.result.is_failure() {
+                    return Err(cmif.result);
+                }
+                debug_assert_eq!(hipc.num_in_pointers(), 0);
+                debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+                debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+                debug_assert_eq!(hipc.out_pointer_mode(), 0);
+                debug_assert_eq!(hipc.has_special_header(), 0);
+                debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+                Ok(())
+            },
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn initialize_raw(&self, raw_in: &[u8], out: &mut [u8]) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            0,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn get_service_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            1,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn register_service_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            2,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn unregister_service_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            3,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn atmosphere_has_service_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            65100,
+            raw_in,
+            out,
+        )
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn atmosphere_wait_service_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            65101,
+            raw_in,
+            out,
+        )
+    }
+}
+impl IUserInterface<OwnedHandle> {
+    pub const INITIALIZE_REQUEST_SIZE: usize = 60;
+    pub const INITIALIZE_RESPONSE_SIZE: usize = 40;
+    pub const GET_SERVICE_REQUEST_SIZE: usize = 48;
+    pub const GET_SERVICE_RESPONSE_SIZE: usize = 48;
+    pub const REGISTER_SERVICE_REQUEST_SIZE: usize = 56;
+    pub const REGISTER_SERVICE_RESPONSE_SIZE: usize = 48;
+    pub const UNREGISTER_SERVICE_REQUEST_SIZE: usize = 48;
+    pub const UNREGISTER_SERVICE_RESPONSE_SIZE: usize = 40;
+    pub const ATMOSPHERE_HAS_SERVICE_REQUEST_SIZE: usize = 48;
+    pub const ATMOSPHERE_HAS_SERVICE_RESPONSE_SIZE: usize = 44;
+    pub const ATMOSPHERE_WAIT_SERVICE_REQUEST_SIZE: usize = 48;
+    pub const ATMOSPHERE_WAIT_SERVICE_RESPONSE_SIZE: usize = 40;
+    pub fn as_ref(&self) -> IUserInterface<RefHandle<'_>> {
+        IUserInterface {
+            handle: self.handle.as_ref(),
+        }
+    }
+    pub fn into_shared(self) -> IUserInterface<SharedHandle> {
+        IUserInterface::new(SharedHandle::new(self.into_inner().leak()))
+    }
+}
+impl<S: HandleStorage> From<S> for IUserInterface<S> {
+    fn from(handle: S) -> Self {
+        Self::new(handle)
+    }
+}
+impl<S: HandleStorage> PartialEq for IUserInterface<S> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.handle.get() == *other.handle.get()
     }
 }
+impl<S: HandleStorage> Eq for IUserInterface<S> {}
 impl ::core::fmt::Debug for IUserInterface {
     fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
         write!(f, "IUserInterface({})", self.handle)
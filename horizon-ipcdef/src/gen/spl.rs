@@ -2,24 +2,47 @@
 ij_core_workaround!();
 use horizon_error::Result;
 use horizon_ipc::buffer::get_ipc_buffer_ptr;
-use horizon_ipc::cmif::CommandType;
+use horizon_ipc::cmif::{CommandType, SessionHandle};
 use horizon_ipc::handle_storage::{HandleStorage, OwnedHandle, RefHandle, SharedHandle};
 use horizon_ipc::hipc::MapAliasBufferMode;
 use horizon_ipc::raw::cmif::{CmifInHeader, CmifOutHeader};
 use horizon_ipc::raw::hipc::{HipcHeader, HipcMapAliasBufferDescriptor};
+#[repr(transparent)]
 pub struct IRandomInterface<S: HandleStorage = OwnedHandle> {
-    pub(crate) handle: S,
+    pub(crate) handle: SessionHandle<S>,
 }
 impl<S: HandleStorage> IRandomInterface<S> {
     pub fn new(handle: S) -> Self {
-        Self { handle }
+        Self {
+            handle: SessionHandle::new(handle),
+        }
     }
     pub fn into_inner(self) -> S {
-        self.handle
+        self.handle.into_inner()
+    }
+    pub fn with_storage<S2: HandleStorage>(
+        self,
+        f: impl FnOnce(S) -> S2,
+    ) -> IRandomInterface<S2> {
+        IRandomInterface::new(f(self.into_inner()))
     }
+    #[cfg_attr(feature = "track-ipc-errors", track_caller)]
     pub fn generate_random_bytes(&self, buffer: &mut [u8]) -> Result<()> {
+        let res = self.generate_random_bytes_untracked(buffer);
+        if let Err(e) = &res {
+            crate::ipc_error_hook(
+                "spl::IRandomInterface::GenerateRandomBytes",
+                ::core::panic::Location::caller(),
+                *e,
+            );
+        }
+        res
+    }
+
+    #[inline(always)]
+    fn generate_random_bytes_untracked(&self, buffer: &mut [u8]) -> Result<()> {
         let data_in = ();
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Request {
             hipc: HipcHeader,
             out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
@@ -31,7 +54,7 @@ impl<S: HandleStorage> IRandomInterface<S> {
         }
         // Compiler time request size check
         let _ = ::core::mem::transmute::<Request, [u8; 52]>;
-        #[repr(packed)]
+        #[repr(C, packed)]
         struct Response {
             hipc: HipcHeader,
             pre_padding: [u8; 8],
@@ -78,9 +101,17 @@ impl<S: HandleStorage> IRandomInterface<S> {
         };
         {
             let handle = self.handle.get();
-            crate::pre_ipc_hook("spl::IRandomInterface::GenerateRandomBytes", *handle);
+            crate::pre_ipc_hook(
+                "spl::IRandomInterface::GenerateRandomBytes",
+                *handle,
+                52,
+            );
             horizon_svc::send_sync_request(*handle)?;
-            crate::post_ipc_hook("spl::IRandomInterface::GenerateRandomBytes", *handle);
+            crate::post_ipc_hook(
+                "spl::IRandomInterface::GenerateRandomBytes",
+                *handle,
+                40,
+            );
         }
         let Response { hipc, cmif, raw_data: (), .. } = unsafe {
             ::core::ptr::read(ipc_buffer_ptr as *const _)
@@ -97,19 +128,134 @@ impl<S: HandleStorage> IRandomInterface<S> {
         debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
         Ok(())
     }
+
+    #[cfg(feature = "async-ipc")]
+    pub async fn generate_random_bytes_async<
+        E: horizon_ipc::async_ipc::IpcExecutor + ?Sized,
+    >(&self, executor: &E, buffer: &mut [u8]) -> Result<()> {
+        let data_in = ();
+        #[repr(C, packed)]
+        struct Request {
+            hipc: HipcHeader,
+            out_map_alias_desc_0: HipcMapAliasBufferDescriptor,
+            pre_padding: [u8; 12],
+            cmif: CmifInHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 4],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Request, [u8; 52]>;
+        #[repr(C, packed)]
+        struct Response {
+            hipc: HipcHeader,
+            pre_padding: [u8; 8],
+            cmif: CmifOutHeader,
+            raw_data: (),
+            raw_data_word_padding: [u8; 0],
+            post_padding: [u8; 8],
+        }
+        // Compiler time request size check
+        let _ = ::core::mem::transmute::<Response, [u8; 40]>;
+        let mut async_request = horizon_ipc::async_ipc::AsyncRequest::new();
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        unsafe {
+            ::core::ptr::write(
+                ipc_buffer_ptr as *mut _,
+                Request {
+                    hipc: HipcHeader::new(
+                        CommandType::Request,
+                        0,
+                        0,
+                        1,
+                        0,
+                        8,
+                        0,
+                        0,
+                        false,
+                    ),
+                    out_map_alias_desc_0: HipcMapAliasBufferDescriptor::new(
+                        MapAliasBufferMode::Normal,
+                        buffer.as_ptr() as usize,
+                        ::core::mem::size_of_val(buffer),
+                    ),
+                    pre_padding: Default::default(),
+                    cmif: CmifInHeader {
+                        magic: CmifInHeader::MAGIC,
+                        version: 1,
+                        command_id: 0,
+                        token: 0,
+                    },
+                    raw_data: data_in,
+                    raw_data_word_padding: Default::default(),
+                    post_padding: Default::default(),
+                },
+            )
+        };
+        {
+            let handle = self.handle.get();
+            async_request.send(executor, *handle).await?;
+        }
+        let ipc_buffer_ptr = async_request.buffer_ptr();
+        let Response { hipc, cmif, raw_data: (), .. } = unsafe {
+            ::core::ptr::read(ipc_buffer_ptr as *const _)
+        };
+        if cmif.result.is_failure() {
+            return Err(cmif.result);
+        }
+        debug_assert_eq!(hipc.num_in_pointers(), 0);
+        debug_assert_eq!(hipc.num_in_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_out_map_aliases(), 0);
+        debug_assert_eq!(hipc.num_inout_map_aliases(), 0);
+        debug_assert_eq!(hipc.out_pointer_mode(), 0);
+        debug_assert_eq!(hipc.has_special_header(), 0);
+        debug_assert_eq!(cmif.magic, CmifOutHeader::MAGIC);
+        Ok(())
+    }
+
+    /// Sends the command with `raw_in` as the raw data, see `horizon_ipc::cmif::raw_command`
+    ///
+    /// # Safety
+    ///
+    /// `raw_in` must be a valid payload for the command
+    #[cfg(feature = "raw-ipc")]
+    pub unsafe fn generate_random_bytes_raw(
+        &self,
+        raw_in: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize> {
+        horizon_ipc::cmif::raw_command::send_request(
+            *self.handle.get(),
+            horizon_ipc::cmif::domain::Framing::Session,
+            0,
+            raw_in,
+            out,
+        )
+    }
 }
 impl IRandomInterface<OwnedHandle> {
+    pub const GENERATE_RANDOM_BYTES_REQUEST_SIZE: usize = 52;
+    pub const GENERATE_RANDOM_BYTES_RESPONSE_SIZE: usize = 40;
     pub fn as_ref(&self) -> IRandomInterface<RefHandle<'_>> {
         IRandomInterface {
             handle: self.handle.as_ref(),
         }
     }
     pub fn into_shared(self) -> IRandomInterface<SharedHandle> {
-        IRandomInterface {
-            handle: SharedHandle::new(self.handle.leak()),
-        }
+        IRandomInterface::new(SharedHandle::new(self.into_inner().leak()))
+    }
+}
+impl<S: HandleStorage> From<S> for IRandomInterface<S> {
+    fn from(handle: S) -> Self {
+        Self::new(handle)
+    }
+}
+impl<S: HandleStorage> PartialEq for IRandomInterface<S> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.handle.get() == *other.handle.get()
     }
 }
+impl<S: HandleStorage> Eq for IRandomInterface<S> {}
 impl ::core::fmt::Debug for IRandomInterface {
     fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
         write!(f, "IRandomInterface({})", self.handle)
@@ -13,6 +13,10 @@ extern crate rustc_std_workspace_core as core;
 #[allow(unused_extern_crates)]
 extern crate core;
 
+// the tests use std even when the crate is no_std
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
+
 // See <https://github.com/intellij-rust/intellij-rust/issues/8954>
 #[doc(hidden)]
 #[macro_export]
@@ -32,7 +36,14 @@ mod gen;
 
 #[cfg(feature = "log-ipc-buffers")]
 mod log;
+#[cfg(feature = "track-ipc-errors")]
+mod track;
 
+pub use ext::fatal::throw_fatal;
+pub use ext::fspsrv::{
+    is_transient_fs_error, with_retry, FsTransaction, Tracked, TrackedDirectory, TrackedFile,
+};
+pub use ext::prelude;
 pub use gen::*;
 pub use gen::*;
 
@@ -41,8 +52,20 @@ use log::{post_ipc_hook, pre_ipc_hook};
 
 #[cfg(not(feature = "log-ipc-buffers"))]
 #[inline]
-fn pre_ipc_hook(_name: &str, _handle: horizon_svc::RawHandle) {}
+fn pre_ipc_hook(_name: &str, _handle: horizon_svc::RawHandle, _size: usize) {}
 
 #[cfg(not(feature = "log-ipc-buffers"))]
 #[inline]
-fn post_ipc_hook(_name: &str, _handle: horizon_svc::RawHandle) {}
+fn post_ipc_hook(_name: &str, _handle: horizon_svc::RawHandle, _size: usize) {}
+
+#[cfg(feature = "track-ipc-errors")]
+use track::ipc_error_hook;
+
+#[cfg(not(feature = "track-ipc-errors"))]
+#[inline]
+fn ipc_error_hook(
+    _name: &str,
+    _location: &'static core::panic::Location<'static>,
+    _error: horizon_error::ErrorCode,
+) {
+}
@@ -3,34 +3,78 @@ ij_core_workaround!();
 use horizon_svc::RawHandle;
 use std::fmt::{Display, Formatter};
 
+const BYTES_PER_LINE: usize = 16;
+
+/// Formats the buffer 16 bytes per line, prefixed with the offset, the bytes grouped in words
 struct HexDump<'a> {
     buffer: &'a [u8],
 }
 
 impl Display for HexDump<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        assert_eq!(self.buffer.len() % 4, 0);
-        for w in self.buffer.chunks(4) {
-            let w: [u8; 4] = w.try_into().unwrap();
-
-            write!(f, "{:02x}{:02x}{:02x}{:02x} ", w[0], w[1], w[2], w[3])?;
+        for (i, line) in self.buffer.chunks(BYTES_PER_LINE).enumerate() {
+            write!(f, "\n    {:04x}:", i * BYTES_PER_LINE)?;
+            for word in line.chunks(4) {
+                write!(f, " ")?;
+                for byte in word {
+                    write!(f, "{:02x}", byte)?;
+                }
+            }
         }
         Ok(())
     }
 }
 
-fn hex_dump(buffer: &[u8]) -> HexDump {
-    HexDump { buffer }
+/// Dumps the first `size` bytes of the IPC buffer, the rest of it is left over from the previous commands
+fn hex_dump(buffer: &[u8], size: usize) -> HexDump<'_> {
+    HexDump {
+        buffer: &buffer[..size.min(buffer.len())],
+    }
 }
 
-pub fn pre_ipc_hook(name: &str, _handle: RawHandle) {
+/// Called before sending the request, `request_size` is the size of the request message put by the command
+pub fn pre_ipc_hook(name: &str, _handle: RawHandle, request_size: usize) {
     let buffer = unsafe { horizon_ipc::buffer::get_ipc_buffer() };
-    let name = format!("[{}]", name);
-    eprintln!("{:50} IPC CALL   = {}", name, hex_dump(buffer));
+    eprintln!(
+        "[{}] IPC CALL, 0x{:x} bytes{}",
+        name,
+        request_size,
+        hex_dump(buffer, request_size)
+    );
 }
 
-pub fn post_ipc_hook(name: &str, _handle: RawHandle) {
+/// Called after receiving the response, `response_size` is the size of a successful response to the command
+///
+/// An error response is shorter, as it has no output data, so the end of the dump may be garbage then.
+pub fn post_ipc_hook(name: &str, _handle: RawHandle, response_size: usize) {
     let buffer = unsafe { horizon_ipc::buffer::get_ipc_buffer() };
-    let name = format!("[{}]", name);
-    eprintln!("{:50} IPC RESULT = {}", name, hex_dump(buffer));
+    eprintln!(
+        "[{}] IPC RESULT, 0x{:x} bytes{}",
+        name,
+        response_size,
+        hex_dump(buffer, response_size)
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::hex_dump;
+
+    #[test]
+    fn hex_dump_lines() {
+        let buffer = (0..0x40).collect::<Vec<u8>>();
+
+        assert_eq!(
+            hex_dump(&buffer, 0x16).to_string(),
+            concat!(
+                "\n    0000: 00010203 04050607 08090a0b 0c0d0e0f",
+                "\n    0010: 10111213 1415",
+            )
+        );
+        // bounded by the buffer size
+        assert_eq!(
+            hex_dump(&buffer[..4], 0x100).to_string(),
+            "\n    0000: 00010203"
+        );
+    }
 }
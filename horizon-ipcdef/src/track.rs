@@ -0,0 +1,42 @@
+ij_core_workaround!();
+
+use core::panic::Location;
+use horizon_error::ErrorCode;
+
+#[cfg(test)]
+std::thread_local! {
+    /// The location the last error was reported with, checked by the tests
+    static LAST_LOCATION: core::cell::Cell<Option<&'static Location<'static>>> =
+        const { core::cell::Cell::new(None) };
+}
+
+/// Called by the `#[track_caller]` command wrappers (see `--gen-track-caller`) when the command fails
+///
+/// `location` is the place in the user code the command was called from
+pub fn ipc_error_hook(name: &str, location: &'static Location<'static>, error: ErrorCode) {
+    #[cfg(test)]
+    LAST_LOCATION.with(|last| last.set(Some(location)));
+
+    let name = format!("[{}]", name);
+    eprintln!("{:50} IPC ERROR  = {} at {}", name, error, location);
+}
+
+#[cfg(test)]
+mod test {
+    use super::LAST_LOCATION;
+    use crate::sm::IUserInterface;
+    use horizon_ipc::handle_storage::RefHandle;
+    use horizon_svc::RawHandle;
+
+    #[test]
+    fn caller_location() {
+        // the handle is made up, so the command fails
+        let sm = IUserInterface::new(RefHandle::new(RawHandle(0)));
+        let line = line!() + 1;
+        assert!(sm.initialize().is_err());
+
+        // the location of the call above, not the one in the generated code
+        let location = LAST_LOCATION.with(|last| last.get()).unwrap();
+        assert_eq!((location.file(), location.line()), (file!(), line));
+    }
+}
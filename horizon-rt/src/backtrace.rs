@@ -0,0 +1,137 @@
+//! Minimal frame pointer based backtraces, meant for crash reports
+//!
+//! The walk follows the AArch64 frame records (`[previous fp, saved lr]` pairs pointed to by `x29`),
+//!  so it only sees the frames of code compiled with frame pointers (`-C force-frame-pointers=yes`).
+//!  The `.eh_frame` info (located by the `__eh_frame_hdr_start/end` offsets in MOD0) would allow to
+//!  unwind through the other frames too, but it's not used yet.
+//!
+//! The startup code sets LR to `~0` before calling into the rest of the runtime, so the outermost
+//!  frame record is terminated by it.
+
+ij_core_workaround!();
+
+/// Maximum number of frames captured by [capture]
+pub const MAX_BACKTRACE_FRAMES: usize = 32;
+
+/// Value of LR at the top of the call stack, as set by the startup code
+const TOP_FRAME_LR: u64 = !0;
+
+/// CPU state of a thread, as returned by `svcGetThreadContext3`
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+pub struct ThreadContext {
+    pub cpu_gprs: [u64; 29],
+    pub fp: u64,
+    pub lr: u64,
+    pub sp: u64,
+    pub pc: u64,
+    pub psr: u32,
+    pub fpu_gprs: [u128; 32],
+    pub fpcr: u32,
+    pub fpsr: u32,
+    pub tpidr: u64,
+}
+// Compile time layout check
+const _: () = assert!(core::mem::size_of::<ThreadContext>() == 0x320);
+
+/// Return addresses of the captured frames, innermost first
+#[derive(Copy, Clone, Debug)]
+pub struct BacktraceFrames {
+    frames: [u64; MAX_BACKTRACE_FRAMES],
+    len: usize,
+}
+
+impl BacktraceFrames {
+    const fn new() -> Self {
+        Self {
+            frames: [0; MAX_BACKTRACE_FRAMES],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, address: u64) -> bool {
+        if self.len == MAX_BACKTRACE_FRAMES {
+            return false;
+        }
+        self.frames[self.len] = address;
+        self.len += 1;
+        true
+    }
+
+    #[inline]
+    pub fn frames(&self) -> &[u64] {
+        &self.frames[..self.len]
+    }
+
+    /// Whether the walk stopped because there was no more space, not because the top frame was reached
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        self.len == MAX_BACKTRACE_FRAMES
+    }
+}
+
+impl<'a> IntoIterator for &'a BacktraceFrames {
+    type Item = &'a u64;
+    type IntoIter = core::slice::Iter<'a, u64>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames().iter()
+    }
+}
+
+/// Captures the backtrace of the thread in the state described by `ctx`
+///
+/// The first frame is the PC itself, the rest are the return addresses found in the frame records.
+///  The walk stops at a null or misaligned frame pointer, at a frame record that is not above
+///  the previous one on the stack or at the `~0` LR of the top frame.
+///
+/// # Safety
+///
+/// The frame pointer chain starting at `ctx.fp` should be readable memory, which is not a given
+///  if the crash was caused by a stack corruption.
+pub unsafe fn capture(ctx: &ThreadContext) -> BacktraceFrames {
+    walk(ctx.pc, ctx.fp)
+}
+
+unsafe fn walk(pc: u64, mut fp: u64) -> BacktraceFrames {
+    let mut frames = BacktraceFrames::new();
+    frames.push(pc);
+
+    while fp != 0 && fp % 16 == 0 {
+        let record = fp as usize as *const [u64; 2];
+        let [next_fp, lr] = record.read();
+
+        if lr == TOP_FRAME_LR || lr == 0 || !frames.push(lr) {
+            break;
+        }
+        // the stack grows down, so the callers' records must be above
+        if next_fp <= fp {
+            break;
+        }
+        fp = next_fp;
+    }
+
+    frames
+}
+
+#[cfg(test)]
+mod test {
+    use super::walk;
+
+    #[repr(C, align(16))]
+    struct Stack([u64; 6]);
+
+    #[test]
+    fn synthetic_frame_chain() {
+        let mut stack = Stack([0; 6]);
+        let base = stack.0.as_ptr() as u64;
+
+        // three frame records going up the stack, the last one is the top frame set up by the startup code
+        stack.0 = [base + 16, 0x7100001000, base + 32, 0x7100002000, 0, !0];
+
+        let frames = unsafe { walk(0x7100000500, base) };
+
+        assert_eq!(frames.frames(), &[0x7100000500, 0x7100001000, 0x7100002000]);
+        assert!(!frames.is_truncated());
+    }
+}
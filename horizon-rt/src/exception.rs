@@ -0,0 +1,120 @@
+//! User-mode exceptions, which the kernel reports by calling the entrypoint again with the exception info
+//!
+//! There is no way for the application to handle them yet: the exception is reported to the debugger (if any),
+//!  and the process exits.
+
+ij_core_workaround!();
+
+use horizon_svc::BreakReason;
+
+/// Kind of the exception, passed in x0, see <https://switchbrew.org/wiki/SVC#ExceptionType>
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExceptionType {
+    InstructionAbort,
+    DataAbort,
+    UnalignedInstruction,
+    UnalignedData,
+    UndefinedInstruction,
+    /// A `brk` instruction
+    ExceptionInstruction,
+    MemorySystemError,
+    FpuException,
+    InvalidSystemCall,
+    SystemCallBreak,
+    Unknown(u32),
+}
+
+impl ExceptionType {
+    pub const fn from_raw(raw: u32) -> Self {
+        match raw {
+            0x100 => Self::InstructionAbort,
+            0x101 => Self::DataAbort,
+            0x102 => Self::UnalignedInstruction,
+            0x103 => Self::UnalignedData,
+            0x104 => Self::UndefinedInstruction,
+            0x105 => Self::ExceptionInstruction,
+            0x106 => Self::MemorySystemError,
+            0x200 => Self::FpuException,
+            0x301 => Self::InvalidSystemCall,
+            0x302 => Self::SystemCallBreak,
+            raw => Self::Unknown(raw),
+        }
+    }
+
+    /// The reason the exception is reported to the debugger with
+    ///
+    /// The traps the code has placed deliberately (like `core::intrinsics::abort`, which is an `udf`)
+    ///  are reported as failed assertions, the faults as panics.
+    pub fn break_reason(self) -> BreakReason {
+        match self {
+            Self::UndefinedInstruction | Self::ExceptionInstruction | Self::SystemCallBreak => {
+                BreakReason::ASSERT
+            }
+            _ => BreakReason::PANIC,
+        }
+    }
+}
+
+/// Registers of the thread at the moment of the exception, saved by the kernel. The pointer is passed in x1
+///
+/// Only the registers the kernel needs to call the handler are saved,
+///  the rest of them still hold the values they had when the exception occurred.
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+pub struct ExceptionContext {
+    /// x0..x8
+    pub gprs: [u64; 9],
+    pub lr: u64,
+    pub sp: u64,
+    pub pc: u64,
+    pub pstate: u32,
+    pub afsr0: u32,
+    pub afsr1: u32,
+    /// Exception syndrome register, describes the exact cause
+    pub esr: u32,
+    /// Fault address register, the address that was accessed for the aborts
+    pub far: u64,
+}
+// Compile time layout check
+const _: () = assert!(core::mem::size_of::<ExceptionContext>() == 0x78);
+
+/// Reports the exception to the debugger and exits the process
+///
+/// # Safety
+///
+/// `ctx` should point to the exception context saved by the kernel
+pub unsafe fn handle(ty: ExceptionType, ctx: *const ExceptionContext) -> ! {
+    // with the notification flag the break returns right away if there is no debugger attached
+    let _ = horizon_svc::r#break(
+        ty.break_reason() | BreakReason::NOTIFICATION_ONLY_FLAG,
+        ctx as *const u8,
+        core::mem::size_of::<ExceptionContext>(),
+    );
+
+    horizon_svc::exit_process()
+}
+
+#[cfg(test)]
+mod test {
+    use super::ExceptionType;
+    use horizon_svc::BreakReason;
+
+    #[test]
+    fn exception_type() {
+        assert_eq!(ExceptionType::from_raw(0x101), ExceptionType::DataAbort);
+        assert_eq!(
+            ExceptionType::from_raw(0x302),
+            ExceptionType::SystemCallBreak
+        );
+        assert_eq!(
+            ExceptionType::from_raw(0x107),
+            ExceptionType::Unknown(0x107)
+        );
+
+        assert_eq!(ExceptionType::DataAbort.break_reason(), BreakReason::PANIC);
+        assert_eq!(
+            ExceptionType::UndefinedInstruction.break_reason(),
+            BreakReason::ASSERT
+        );
+    }
+}
@@ -1,10 +1,10 @@
 ij_core_workaround!();
 
+use crate::rt_abort::RtAbortReason;
 use bitflags::bitflags;
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 #[repr(u32)]
-#[allow(unused)] // TODO: implement HBABI keys parsing
 pub enum AbiConfigEntryKey {
     EndOfList = 0,
     MainThreadHandle = 1,
@@ -21,6 +21,34 @@ pub enum AbiConfigEntryKey {
     RandomSeed = 14,
     UserIdStorage = 15,
     HosVersion = 16,
+    SyscallAvailableHint2 = 17,
+}
+
+impl TryFrom<u32> for AbiConfigEntryKey {
+    type Error = ();
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        use AbiConfigEntryKey::*;
+        Ok(match value {
+            0 => EndOfList,
+            1 => MainThreadHandle,
+            2 => NextLoadPath,
+            3 => OverrideHeap,
+            4 => OverrideService,
+            5 => Argv,
+            6 => SyscallAvailableHint,
+            7 => AppletType,
+            8 => AppletWorkaround,
+            9 => Reserved9,
+            10 => ProcessHandle,
+            11 => LastLoadResult,
+            14 => RandomSeed,
+            15 => UserIdStorage,
+            16 => HosVersion,
+            17 => SyscallAvailableHint2,
+            _ => return Err(()),
+        })
+    }
 }
 
 bitflags! {
@@ -38,7 +66,219 @@ bitflags! {
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 #[repr(C)]
 pub struct AbiConfigEntry {
-    pub key: AbiConfigEntryKey,
-    pub flags: AbiConfigEntryFlags,
+    /// Raw [AbiConfigEntryKey], the loader can pass keys we don't know about
+    pub key: u32,
+    pub flags: u32,
     pub value: [u64; 2],
 }
+
+impl AbiConfigEntry {
+    pub fn key(&self) -> Option<AbiConfigEntryKey> {
+        AbiConfigEntryKey::try_from(self.key).ok()
+    }
+
+    pub fn flags(&self) -> AbiConfigEntryFlags {
+        AbiConfigEntryFlags::from_bits_truncate(self.flags)
+    }
+}
+
+/// Same limit as libnx has
+pub const MAX_SERVICE_OVERRIDES: usize = 32;
+
+/// Values passed by the homebrew loader in the config entries
+///
+/// See <https://switchbrew.org/wiki/Homebrew_ABI#Loader_Configuration_Keys>
+#[derive(Copy, Clone, Debug)]
+pub struct HblConfig {
+    pub main_thread_handle: Option<u32>,
+    /// Buffers for the path and argv of the NRO to be loaded next
+    pub next_load_path: Option<(*mut u8, *mut u8)>,
+    pub heap_override: Option<(*mut u8, usize)>,
+    /// Pairs of sm service name and the session handle to use for it
+    pub service_overrides: [(u64, u32); MAX_SERVICE_OVERRIDES],
+    pub service_overrides_count: usize,
+    pub argv: Option<*const u8>,
+    /// Bitmask of syscalls 0x00..=0xbf that are allowed (the bits may have false positives)
+    pub syscall_available_hints: Option<[u64; 3]>,
+    pub applet_type: Option<u32>,
+    pub applet_flags: AbiConfigAppletFlags,
+    pub process_handle: Option<u32>,
+    pub last_load_result: Option<u32>,
+    pub random_seed: Option<[u64; 2]>,
+    pub user_id_storage: Option<*mut u8>,
+    /// Packed as `major << 16 | minor << 8 | micro`
+    pub hos_version: Option<u32>,
+}
+
+impl HblConfig {
+    pub fn service_overrides(&self) -> &[(u64, u32)] {
+        &self.service_overrides[..self.service_overrides_count]
+    }
+}
+
+/// Parses the config entries passed by the homebrew loader, up to the `EndOfList` entry
+///
+/// Fails if there is an entry marked as mandatory that we don't understand.
+///
+/// # Safety
+///
+/// `entries` should point to a valid config array terminated with an `EndOfList` entry
+pub unsafe fn parse_abi_config(
+    mut entries: *const AbiConfigEntry,
+) -> Result<HblConfig, RtAbortReason> {
+    let mut config = HblConfig {
+        main_thread_handle: None,
+        next_load_path: None,
+        heap_override: None,
+        service_overrides: [(0, 0); MAX_SERVICE_OVERRIDES],
+        service_overrides_count: 0,
+        argv: None,
+        syscall_available_hints: None,
+        applet_type: None,
+        applet_flags: AbiConfigAppletFlags::empty(),
+        process_handle: None,
+        last_load_result: None,
+        random_seed: None,
+        user_id_storage: None,
+        hos_version: None,
+    };
+
+    loop {
+        let entry = entries.read();
+        entries = entries.add(1);
+
+        let [value0, value1] = entry.value;
+
+        match entry.key() {
+            Some(AbiConfigEntryKey::EndOfList) => break,
+            Some(AbiConfigEntryKey::MainThreadHandle) => {
+                config.main_thread_handle = Some(value0 as u32);
+            }
+            Some(AbiConfigEntryKey::NextLoadPath) => {
+                config.next_load_path = Some((value0 as *mut u8, value1 as *mut u8));
+            }
+            Some(AbiConfigEntryKey::OverrideHeap) => {
+                config.heap_override = Some((value0 as *mut u8, value1 as usize));
+            }
+            Some(AbiConfigEntryKey::OverrideService) => {
+                if config.service_overrides_count == MAX_SERVICE_OVERRIDES {
+                    return Err(RtAbortReason::TooManyServiceOverrides);
+                }
+                config.service_overrides[config.service_overrides_count] = (value0, value1 as u32);
+                config.service_overrides_count += 1;
+            }
+            Some(AbiConfigEntryKey::Argv) => {
+                // value0 is reserved
+                config.argv = Some(value1 as *const u8);
+            }
+            Some(AbiConfigEntryKey::SyscallAvailableHint) => {
+                let hints = config.syscall_available_hints.get_or_insert([0; 3]);
+                hints[0] = value0;
+                hints[1] = value1;
+            }
+            Some(AbiConfigEntryKey::SyscallAvailableHint2) => {
+                let hints = config.syscall_available_hints.get_or_insert([0; 3]);
+                hints[2] = value0;
+            }
+            Some(AbiConfigEntryKey::AppletType) => {
+                config.applet_type = Some(value0 as u32);
+                config.applet_flags = AbiConfigAppletFlags::from_bits_truncate(value1 as u32);
+            }
+            Some(AbiConfigEntryKey::ProcessHandle) => {
+                config.process_handle = Some(value0 as u32);
+            }
+            Some(AbiConfigEntryKey::LastLoadResult) => {
+                config.last_load_result = Some(value0 as u32);
+            }
+            Some(AbiConfigEntryKey::RandomSeed) => {
+                config.random_seed = Some([value0, value1]);
+            }
+            Some(AbiConfigEntryKey::UserIdStorage) => {
+                config.user_id_storage = Some(value0 as *mut u8);
+            }
+            Some(AbiConfigEntryKey::HosVersion) => {
+                // value1 has a magic identifying atmosphere, we don't need it
+                config.hos_version = Some(value0 as u32);
+            }
+            // the workaround is about applet services we don't implement
+            Some(AbiConfigEntryKey::AppletWorkaround) | Some(AbiConfigEntryKey::Reserved9) => {}
+            None => {
+                if entry.flags().contains(AbiConfigEntryFlags::MANDATORY) {
+                    return Err(RtAbortReason::UnknownMandatoryAbiConfigEntry);
+                }
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_abi_config, AbiConfigEntry, AbiConfigEntryFlags, AbiConfigEntryKey};
+    use crate::rt_abort::RtAbortReason;
+
+    fn entry(key: u32, flags: AbiConfigEntryFlags, value: [u64; 2]) -> AbiConfigEntry {
+        AbiConfigEntry {
+            key,
+            flags: flags.bits(),
+            value,
+        }
+    }
+
+    #[test]
+    fn abi_config() {
+        let entries = [
+            entry(
+                AbiConfigEntryKey::MainThreadHandle as u32,
+                AbiConfigEntryFlags::MANDATORY,
+                [0x1234, 0],
+            ),
+            entry(
+                AbiConfigEntryKey::OverrideHeap as u32,
+                AbiConfigEntryFlags::MANDATORY,
+                [0x8000_0000, 0x20_0000],
+            ),
+            // unknown, but not mandatory, so it's skipped
+            entry(0x100, AbiConfigEntryFlags::empty(), [0, 0]),
+            entry(
+                AbiConfigEntryKey::EndOfList as u32,
+                AbiConfigEntryFlags::MANDATORY,
+                [0, 0],
+            ),
+            // past the end of the list, must not be read
+            entry(
+                AbiConfigEntryKey::MainThreadHandle as u32,
+                AbiConfigEntryFlags::MANDATORY,
+                [0x5678, 0],
+            ),
+        ];
+
+        let config = unsafe { parse_abi_config(entries.as_ptr()) }.unwrap();
+
+        assert_eq!(config.main_thread_handle, Some(0x1234));
+        assert_eq!(
+            config.heap_override,
+            Some((0x8000_0000 as *mut u8, 0x20_0000))
+        );
+        assert_eq!(config.argv, None);
+        assert_eq!(config.service_overrides(), &[]);
+    }
+
+    #[test]
+    fn unknown_mandatory_entry() {
+        let entries = [
+            entry(0x100, AbiConfigEntryFlags::MANDATORY, [0, 0]),
+            entry(
+                AbiConfigEntryKey::EndOfList as u32,
+                AbiConfigEntryFlags::MANDATORY,
+                [0, 0],
+            ),
+        ];
+
+        assert!(matches!(
+            unsafe { parse_abi_config(entries.as_ptr()) },
+            Err(RtAbortReason::UnknownMandatoryAbiConfigEntry)
+        ));
+    }
+}
@@ -1,21 +1,22 @@
 ij_core_workaround!();
 
-use crate::hbl::AbiConfigEntry;
+use crate::hbl::{parse_abi_config, AbiConfigEntry, HblConfig};
 use crate::{rt_abort, RtAbortReason};
 use horizon_error::Result;
 use horizon_global::environment::{Environment, EnvironmentType, HorizonVersion};
 use horizon_global::mounts::MountDevice;
+use horizon_global::services;
 use horizon_global::virtual_memory::{MemoryMap, MemoryRegion};
-use horizon_svc::{InfoType, CURRENT_PROCESS_PSEUDO_HANDLE};
+use horizon_ipc::handle_storage::OwnedHandle;
+use horizon_svc::{InfoType, RawHandle};
+use horizon_sync::kernel_mutex::set_current_thread_handle;
 
 use crate::rt_abort::rt_unwrap;
 use horizon_svc as svc;
 
 fn get_memory_region(start_info: InfoType, size_info: InfoType) -> Result<MemoryRegion> {
-    let handle = Some(CURRENT_PROCESS_PSEUDO_HANDLE);
-
-    let start = svc::get_info(start_info, handle)?;
-    let size = svc::get_info(size_info, handle)?;
+    let start = svc::get_info_self(start_info)?;
+    let size = svc::get_info_self(size_info)?;
 
     Ok(MemoryRegion {
         start: start as *const u8,
@@ -41,14 +42,8 @@ fn get_memory_map() -> Result<MemoryMap> {
 }
 
 fn make_heap() -> Result<(*mut u8, usize)> {
-    let total_memory = horizon_svc::get_info(
-        InfoType::TotalMemorySize,
-        Some(CURRENT_PROCESS_PSEUDO_HANDLE),
-    )? as usize;
-    let used_memory = horizon_svc::get_info(
-        InfoType::UsedMemorySize,
-        Some(CURRENT_PROCESS_PSEUDO_HANDLE),
-    )? as usize;
+    let total_memory = horizon_svc::get_info_self(InfoType::TotalMemorySize)? as usize;
+    let used_memory = horizon_svc::get_info_self(InfoType::UsedMemorySize)? as usize;
 
     const HEAP_GRANULARITY: usize = 2 * 1024 * 1024; // 2 MiB
 
@@ -66,12 +61,61 @@ fn make_heap() -> Result<(*mut u8, usize)> {
     Ok((heap_addr, size))
 }
 
+/// Encodes the service name the way sm (and the homebrew loader) does
+const fn service_name(name: &[u8]) -> u64 {
+    let mut res = 0;
+    let mut i = 0;
+    while i < name.len() {
+        res |= (name[i] as u64) << (i * 8);
+        i += 1;
+    }
+    res
+}
+
+/// Makes the services the loader gave us a session to use it instead of connecting by themselves
+///
+/// The override always wins: the session is stored before anything had a chance to connect,
+///  so `get_or_connect` of the service will never open a session of its own.
+fn apply_service_overrides(config: &HblConfig) {
+    for &(name, handle) in config.service_overrides() {
+        let handle = OwnedHandle::new(RawHandle(handle));
+
+        // overrides for services we don't keep global sessions for are just ignored
+        let _ = match name {
+            n if n == service_name(b"sm:") => services::sm::replace(handle),
+            n if n == service_name(b"fsp-srv") => services::fs::replace(handle),
+            n if n == service_name(b"csrng") => services::csrng::replace(handle),
+            _ => {
+                // don't close the session, the loader might still want to use it
+                handle.leak();
+                None
+            }
+        };
+    }
+}
+
+/// Picks the heap region for the process
+///
+/// A heap passed by the loader (`OverrideHeap`) is adopted as-is and `set_heap_size` is not called:
+///  the loader owns the heap region of the process and has already set it up for us.
+fn select_heap(config: &HblConfig) -> (*mut u8, usize) {
+    match config.heap_override {
+        Some(heap) => heap,
+        None => rt_unwrap(make_heap(), RtAbortReason::MakeHeapFailed),
+    }
+}
+
+fn unpack_hos_version(version: u32) -> HorizonVersion {
+    HorizonVersion::new((version >> 16) as u8, (version >> 8) as u8, version as u8)
+}
+
 // SAFETY: only call it once
+// returns the top of the main thread stack to switch to (null to keep the current one)
 pub unsafe fn init(
     maybe_abi_cfg_entries_ptr: *const AbiConfigEntry,
     maybe_main_thread_handle: usize,
     _saved_lr: usize,
-) {
+) -> *mut u8 {
     let environment_type =
         match !maybe_abi_cfg_entries_ptr.is_null() && (maybe_main_thread_handle == usize::MAX) {
             true => EnvironmentType::Nro,
@@ -80,14 +124,50 @@ pub unsafe fn init(
 
     let (environment, heap) = match environment_type {
         EnvironmentType::Nro => {
-            // TODO: read the HBABI keys
+            let config = match parse_abi_config(maybe_abi_cfg_entries_ptr) {
+                Ok(config) => config,
+                Err(reason) => rt_abort(reason),
+            };
+
+            let main_thread_handle = match config.main_thread_handle {
+                Some(handle) => handle,
+                None => rt_abort(RtAbortReason::NoMainThreadHandleInNroEnv),
+            };
+            set_current_thread_handle(RawHandle(main_thread_handle));
 
-            rt_abort(RtAbortReason::NotImplemented)
+            apply_service_overrides(&config);
+
+            let heap = select_heap(&config);
+
+            let (next_load_path, next_load_argv) = config
+                .next_load_path
+                .unwrap_or((core::ptr::null_mut(), core::ptr::null_mut()));
+
+            (
+                Environment {
+                    environment_type,
+                    main_thread_handle,
+                    hos_version: config
+                        .hos_version
+                        .map(unpack_hos_version)
+                        .unwrap_or(HorizonVersion::new(12, 1, 0)),
+                    process_handle: config.process_handle,
+                    argv: config.argv.unwrap_or(core::ptr::null()),
+                    next_load_path,
+                    next_load_argv,
+                    last_load_result: config.last_load_result,
+                    syscall_available_hints: config.syscall_available_hints,
+                    applet_type: config.applet_type,
+                    random_seed: config.random_seed,
+                },
+                heap,
+            )
         }
         EnvironmentType::Nso => {
             if maybe_main_thread_handle == usize::MAX {
                 rt_abort(RtAbortReason::NoMainThreadHandleInNsoEnv);
             }
+            set_current_thread_handle(RawHandle(maybe_main_thread_handle as u32));
             let heap = match make_heap() {
                 Ok(heap) => heap,
                 Err(_) => rt_abort(RtAbortReason::MakeHeapFailed),
@@ -98,13 +178,21 @@ pub unsafe fn init(
                     environment_type,
                     main_thread_handle: maybe_main_thread_handle as u32,
                     hos_version: HorizonVersion::new(12, 1, 0),
+                    process_handle: None,
+                    argv: core::ptr::null(),
+                    next_load_path: core::ptr::null_mut(),
+                    next_load_argv: core::ptr::null_mut(),
+                    last_load_result: None,
+                    syscall_available_hints: None,
+                    applet_type: None,
+                    random_seed: None,
                 },
                 heap,
             )
         }
     };
 
-    // TODO: store the _saver_lr somewhere (in case of Nro env) so that we can return to loader later
+    // the saved lr is stashed by the entrypoint code already, `__horizon_rt_exit` uses it to return to the loader
 
     let memory_map = rt_unwrap(get_memory_map(), RtAbortReason::MemoryMapReadFailed);
 
@@ -147,4 +235,35 @@ pub unsafe fn init(
         horizon_global::mounts::write().add("sdmc", MountDevice::IFileSystem(sd_fs.into_inner())),
         RtAbortReason::SdFsMountFailed,
     );
+
+    // the services are reachable from now on, so the aborts can be reported to the user
+    crate::rt_abort::enable_fatal_reporting();
+
+    crate::stack::allocate_main_stack().unwrap_or(core::ptr::null_mut())
+}
+
+#[cfg(test)]
+mod test {
+    use super::select_heap;
+    use crate::hbl::{parse_abi_config, AbiConfigEntry, AbiConfigEntryFlags, AbiConfigEntryKey};
+
+    #[test]
+    fn heap_override() {
+        let entries = [
+            AbiConfigEntry {
+                key: AbiConfigEntryKey::OverrideHeap as u32,
+                flags: AbiConfigEntryFlags::MANDATORY.bits(),
+                value: [0x8000_0000, 0x20_0000],
+            },
+            AbiConfigEntry {
+                key: AbiConfigEntryKey::EndOfList as u32,
+                flags: AbiConfigEntryFlags::MANDATORY.bits(),
+                value: [0, 0],
+            },
+        ];
+        let config = unsafe { parse_abi_config(entries.as_ptr()) }.unwrap();
+
+        // the default heap would be made with set_heap_size, which doesn't happen on the host
+        assert_eq!(select_heap(&config), (0x8000_0000 as *mut u8, 0x20_0000));
+    }
 }
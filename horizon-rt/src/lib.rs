@@ -7,6 +7,7 @@
 #![no_std]
 #![allow(clippy::missing_safety_doc)]
 #![deny(rust_2018_idioms)]
+#![feature(linkage)]
 #![cfg_attr(feature = "rustc-dep-of-std", feature(no_core), no_core)]
 
 #[cfg(feature = "rustc-dep-of-std")]
@@ -32,17 +33,22 @@ macro_rules! ij_core_workaround {
     };
 }
 
+pub mod backtrace;
+pub mod exception;
 mod hbl;
 mod init;
 mod relocate;
 mod rt_abort;
+mod stack;
 mod tls;
 
+use crate::exception::{ExceptionContext, ExceptionType};
 use crate::hbl::AbiConfigEntry;
 use crate::relocate::{relocate_with_dyn, Dyn};
-use crate::rt_abort::{rt_abort, RtAbortReason};
-use core::arch::global_asm;
+pub use crate::rt_abort::report_fatal;
+use core::arch::{asm, global_asm};
 use horizon_global::environment::EnvironmentType;
+use horizon_global::thread_local::Dtor;
 
 // define _start
 // what happens at the start of a file
@@ -142,7 +148,7 @@ global_asm! {
      str  x8, [x9, #:lo12:__HORIZON_RT_STACK_TOP]",
 
     // same for the lr
-    "mov x8, x29
+    "mov x8, x27
      adrp x9, __HORIZON_RT_SAVED_LR
      str  x8, [x9, #:lo12:__HORIZON_RT_SAVED_LR]",
 
@@ -164,6 +170,11 @@ global_asm! {
     // restore LR to be ~0
     "mov lr, x24",
 
+    // switch to the main thread stack requested by the application, if any (x0 is its top or 0)
+    "cbz  x0, .Lkeep_main_stack
+     mov  sp, x0
+     .Lkeep_main_stack:",
+
     // load addr of TLS storage for the main thread
     "adrp x0, __main_thread_tls_start
      add  x0, x0, #:lo12:__main_thread_tls_start",
@@ -189,14 +200,48 @@ global_asm! {
 #[no_mangle]
 static mut __HORIZON_RT_STACK_TOP: u64 = 0;
 
+/// The return address to the homebrew loader, saved along with [__HORIZON_RT_STACK_TOP]
 #[no_mangle]
 static mut __HORIZON_RT_SAVED_LR: u64 = 0;
 
-// called when HOS calls our entrypoint with an exception
+/// Returns from `_start` to the homebrew loader, same as libnx does
+///
+/// The homebrew ABI passes the result in x0. The loader doesn't expect any of the callee-saved registers
+///  to be preserved, only the stack pointer is restored (which also drops the main stack we might have switched to).
+unsafe fn return_to_loader(exit_code: u32) -> ! {
+    asm!(
+        "mov sp, {stack_top}",
+        "br  {saved_lr}",
+        stack_top = in(reg) __HORIZON_RT_STACK_TOP,
+        saved_lr = in(reg) __HORIZON_RT_SAVED_LR,
+        in("x0") exit_code as u64,
+        options(noreturn),
+    )
+}
+
+// define __horizon_rt_exception_entry
+// called when HOS calls our entrypoint with an exception, x0 and x1 are passed through to the handler
+global_asm! {
+    ".cfi_sections .debug_frame
+     .global __horizon_rt_exception_entry
+     .type __horizon_rt_exception_entry,%function
+     .cfi_startproc
+     __horizon_rt_exception_entry:",
+
+    // set lr to 0xffffffff, same as on the normal entry, the handler never returns
+    "mov x8, #0
+     mvn lr, x8",
+
+    "b __horizon_rt_handle_exception",
+
+    ".cfi_endproc
+     .size __horizon_rt_exception_entry, . - __horizon_rt_exception_entry",
+}
+
+/// Handles the user-mode exception, see the [exception] module
 #[no_mangle]
-pub unsafe extern "C" fn __horizon_rt_exception_entry() {
-    // TODO: implement this
-    rt_abort(RtAbortReason::NotImplemented)
+pub unsafe extern "C" fn __horizon_rt_handle_exception(ty: u32, ctx: *const ExceptionContext) -> ! {
+    exception::handle(ExceptionType::from_raw(ty), ctx)
 }
 
 /// called to parse the .dynamic section and perform relocations
@@ -216,23 +261,48 @@ pub unsafe extern "C" fn __horizon_rt_init_tls(tls_storage_addr: *mut u8) {
     tls::init(tls_storage_addr);
 }
 
+/// Registers a destructor of a thread-local variable, see [horizon_global::thread_local::register_dtor]
+///
+/// Returns false if the current thread has too many destructors registered already
+#[no_mangle]
+pub unsafe extern "C" fn __horizon_rt_tls_register_dtor(key: *mut u8, dtor: Dtor) -> bool {
+    horizon_global::thread_local::register_dtor(key, dtor).is_ok()
+}
+
 /// Perform most of initialization for horizon-global
+///
+/// Returns the top of the main thread stack to switch to, or null to keep the current one
 #[no_mangle]
-pub unsafe extern "C" fn __horizon_rt_init(x0: usize, x1: usize, saved_lr: usize) {
+pub unsafe extern "C" fn __horizon_rt_init(x0: usize, x1: usize, saved_lr: usize) -> *mut u8 {
     init::init(x0 as *const AbiConfigEntry, x1, saved_lr)
 }
 
 /// Clean up the process & return to loader/exit process (depending on the env)
 #[no_mangle]
-pub unsafe extern "C" fn __horizon_rt_exit(_exit_code: u32) -> ! {
+pub unsafe extern "C" fn __horizon_rt_exit(exit_code: u32) -> ! {
+    // main has returned, so its thread-locals can go
+    horizon_global::thread_local::run_dtors();
+
     if horizon_global::environment::get().environment_type == EnvironmentType::Nro {
-        // TODO: return to the loader
-        rt_abort(RtAbortReason::NotImplemented)
+        return_to_loader(exit_code)
     } else {
         horizon_svc::exit_process()
     }
 }
 
+/// Runs the thread-local destructors of the current thread and exits it
+///
+/// The threads other than the main one should exit through this, the main one returns from `main` to
+///  [__horizon_rt_exit] instead. horizon-rt doesn't create threads, so nothing calls this on its own:
+///  the code creating a thread (like the libstd thread implementation) must end it with a call to this
+///  instead of a bare `svc::exit_thread`, or the destructors of its thread-locals never run.
+#[no_mangle]
+pub unsafe extern "C" fn __horizon_rt_exit_thread() -> ! {
+    horizon_global::thread_local::run_dtors();
+
+    horizon_svc::exit_thread()
+}
+
 // define the MOD0 header
 global_asm! {
     // put it into the .text.mod0 section
@@ -38,26 +38,45 @@ pub enum Tag {
 #[repr(u32)]
 #[allow(unused)]
 pub enum RelocationType {
+    AArch64None = 0,
     AArch64Abs64 = 257,
     AArch64GlobDat = 1025,
     AArch64JumpSlot = 1026,
     AArch64Relative = 1027,
 }
 
+impl RelocationType {
+    fn from_raw(value: u32) -> Option<Self> {
+        use RelocationType::*;
+        Some(match value {
+            0 => AArch64None,
+            257 => AArch64Abs64,
+            1025 => AArch64GlobDat,
+            1026 => AArch64JumpSlot,
+            1027 => AArch64Relative,
+            _ => return None,
+        })
+    }
+}
+
+/// An entry of the `.dynamic` section
+///
+/// The tag is kept raw, as the section has lots of entries we don't know about
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 #[repr(C)]
 pub struct Dyn {
-    pub tag: Tag,
+    pub tag: i64,
     pub val_ptr: u64,
 }
 
 impl Dyn {
-    pub unsafe fn find_value(&self, tag: Tag) -> u64 {
+    /// Finds the value of an optional entry, aborting if it's present more than once
+    pub unsafe fn find_optional_value(&self, tag: Tag) -> Option<u64> {
         let mut found: *const u64 = core::ptr::null();
         let mut self_ptr = self as *const Self;
 
-        while (*self_ptr).tag != Tag::Invalid {
-            if (*self_ptr).tag == tag {
+        while (*self_ptr).tag != Tag::Invalid as i64 {
+            if (*self_ptr).tag == tag as i64 {
                 if !found.is_null() {
                     rt_abort(RtAbortReason::DuplicatedDtEntry)
                 }
@@ -65,57 +84,263 @@ impl Dyn {
             }
             self_ptr = self_ptr.offset(1);
         }
+
         if found.is_null() {
-            rt_abort(RtAbortReason::MissingDtEntry)
+            None
+        } else {
+            Some(*found)
         }
+    }
 
-        *found
+    pub unsafe fn find_value(&self, tag: Tag) -> u64 {
+        match self.find_optional_value(tag) {
+            Some(value) => value,
+            None => rt_abort(RtAbortReason::MissingDtEntry),
+        }
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone)]
 #[repr(C)]
-pub struct InfoSymbol {
-    pub relocation_type: RelocationType,
-    pub symbol: u32,
+pub struct Rela {
+    pub offset: u64,
+    /// Relocation type in the low 32 bits, symbol index in the high ones
+    pub info: u64,
+    pub addend: i64,
+}
+
+impl Rela {
+    pub fn relocation_type(&self) -> u32 {
+        self.info as u32
+    }
+
+    pub fn symbol(&self) -> u32 {
+        (self.info >> 32) as u32
+    }
 }
 
+/// An entry of the dynamic symbol table
 #[derive(Copy, Clone)]
 #[repr(C)]
-pub union Info {
+pub struct Sym {
+    pub name: u32,
+    pub info: u8,
+    pub other: u8,
+    /// Index of the section the symbol is defined in, 0 for undefined symbols
+    pub section_index: u16,
     pub value: u64,
-    pub symbol: InfoSymbol,
+    pub size: u64,
 }
 
-#[derive(Copy, Clone)]
-#[repr(C)]
-pub struct Rela {
-    pub offset: u64,
-    pub info: Info,
-    pub addend: i64,
+/// Symbol binding (in the high bits of [Sym::info]) of weak symbols
+const STB_WEAK: u8 = 2;
+
+/// Computes the address of the symbol referenced by a relocation
+///
+/// We are not a dynamic linker, so only the symbols defined in our own module can be resolved.
+///  Undefined weak symbols resolve to zero, the rest abort the startup.
+unsafe fn resolve_symbol(base_address: *const u8, symtab: Option<*const Sym>, symbol: u32) -> u64 {
+    let symtab = match symtab {
+        Some(symtab) => symtab,
+        None => rt_abort(RtAbortReason::MissingDtEntry),
+    };
+
+    let sym = &*symtab.offset(symbol as isize);
+    if sym.section_index != 0 {
+        base_address as u64 + sym.value
+    } else if sym.info >> 4 == STB_WEAK {
+        0
+    } else {
+        rt_abort(RtAbortReason::UnresolvedSymbol)
+    }
+}
+
+unsafe fn apply_relocations(
+    base_address: *const u8,
+    symtab: Option<*const Sym>,
+    relas: *const Rela,
+    count: u64,
+) {
+    for i in 0..count {
+        let rela = &*relas.offset(i as isize);
+        let target = base_address.offset(rela.offset as isize) as *mut u64;
+
+        match RelocationType::from_raw(rela.relocation_type()) {
+            Some(RelocationType::AArch64None) => {}
+            // Delta(S) + A, the symbol is always 0 for our position-independent executables
+            Some(RelocationType::AArch64Relative) => {
+                *target = (base_address as u64).wrapping_add(rela.addend as u64);
+            }
+            // S + A
+            Some(RelocationType::AArch64Abs64)
+            | Some(RelocationType::AArch64GlobDat)
+            | Some(RelocationType::AArch64JumpSlot) => {
+                let symbol_address = resolve_symbol(base_address, symtab, rela.symbol());
+                *target = symbol_address.wrapping_add(rela.addend as u64);
+            }
+            None => rt_abort(RtAbortReason::UnsupportedRelocationType),
+        }
+    }
 }
 
+/// Applies the relocations from the `DT_RELA` and `DT_JMPREL` tables
+///
+/// `DT_RELACOUNT` only counts the leading RELATIVE relocations, so the table sizes are used instead
 pub unsafe fn relocate_with_dyn(base_address: *const u8, dynamic: *const Dyn) {
-    let rela_offset = (*dynamic).find_value(Tag::RelaOffset);
-    let rela_size = (*dynamic).find_value(Tag::RelaSize);
-    let rela_entry_size = (*dynamic).find_value(Tag::RelaEntrySize);
-    let rela_count = (*dynamic).find_value(Tag::RelaCount);
-    if rela_size != rela_entry_size * rela_count {
+    let dynamic = &*dynamic;
+
+    let symtab = dynamic
+        .find_optional_value(Tag::SymTab)
+        .map(|offset| base_address.offset(offset as isize) as *const Sym);
+
+    let rela_offset = dynamic.find_value(Tag::RelaOffset);
+    let rela_size = dynamic.find_value(Tag::RelaSize);
+    let rela_entry_size = dynamic.find_value(Tag::RelaEntrySize);
+    if rela_entry_size != core::mem::size_of::<Rela>() as u64
+        || rela_size % rela_entry_size != 0
+        || dynamic
+            .find_optional_value(Tag::RelaCount)
+            .is_some_and(|count| count > rela_size / rela_entry_size)
+    {
         rt_abort(RtAbortReason::RelaSizeMismatch)
     }
 
-    let rela_base = base_address.offset(rela_offset as isize) as *const Rela;
-    for i in 0..rela_count {
-        let rela = rela_base.offset(i as isize);
-        match (*rela).info.symbol.relocation_type {
-            RelocationType::AArch64Relative => {
-                if (*rela).info.symbol.symbol == 0 {
-                    let relocation_offset =
-                        base_address.offset((*rela).offset as isize) as *mut *const u8;
-                    *relocation_offset = base_address.offset((*rela).addend as isize);
-                }
-            }
-            _ => rt_abort(RtAbortReason::UnsupportedRelocationType),
+    apply_relocations(
+        base_address,
+        symtab,
+        base_address.offset(rela_offset as isize) as *const Rela,
+        rela_size / rela_entry_size,
+    );
+
+    // the PLT relocations are only present when something is called through the PLT
+    if let Some(jmprel_offset) = dynamic.find_optional_value(Tag::JmpRel) {
+        let plt_rel_size = dynamic.find_value(Tag::PltRelSize);
+        // DT_PLTREL says whether these are REL or RELA, only the latter is used on aarch64
+        if dynamic.find_value(Tag::PltRel) != Tag::RelaOffset as u64
+            || plt_rel_size % rela_entry_size != 0
+        {
+            rt_abort(RtAbortReason::RelaSizeMismatch)
+        }
+
+        apply_relocations(
+            base_address,
+            symtab,
+            base_address.offset(jmprel_offset as isize) as *const Rela,
+            plt_rel_size / rela_entry_size,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{relocate_with_dyn, Dyn, Rela, Sym, Tag};
+    use core::ptr::addr_of;
+
+    /// Synthetic module with a relocation of each supported type
+    #[repr(C)]
+    struct Module {
+        dynamic: [Dyn; 10],
+        rela: [Rela; 3],
+        jmprel: [Rela; 1],
+        symtab: [Sym; 3],
+        slots: [u64; 4],
+    }
+
+    fn rela(offset: usize, relocation_type: u32, symbol: u32, addend: i64) -> Rela {
+        Rela {
+            offset: offset as u64,
+            info: (symbol as u64) << 32 | relocation_type as u64,
+            addend,
+        }
+    }
+
+    fn sym(section_index: u16, value: u64) -> Sym {
+        Sym {
+            name: 0,
+            info: 0x12, // global function
+            other: 0,
+            section_index,
+            value,
+            size: 0,
         }
     }
+
+    #[test]
+    fn each_relocation_type() {
+        let mut module = Module {
+            dynamic: [Dyn { tag: 0, val_ptr: 0 }; 10],
+            rela: [rela(0, 0, 0, 0); 3],
+            jmprel: [rela(0, 0, 0, 0); 1],
+            symtab: [sym(0, 0), sym(1, 0x1000), sym(1, 0x2000)],
+            slots: [0; 4],
+        };
+
+        let base = addr_of!(module) as *const u8;
+        let offset = |p: *const u8| p as usize - base as usize;
+
+        let rela_offset = offset(addr_of!(module.rela) as *const u8);
+        let jmprel_offset = offset(addr_of!(module.jmprel) as *const u8);
+        let symtab_offset = offset(addr_of!(module.symtab) as *const u8);
+        let slots_offset = offset(addr_of!(module.slots) as *const u8);
+        let slot = |i: usize| slots_offset + i * 8;
+        let rela_size = core::mem::size_of::<Rela>();
+
+        module.rela = [
+            rela(slot(0), 1027, 0, 0x40), // RELATIVE
+            rela(slot(1), 257, 1, 8),     // ABS64
+            rela(slot(2), 1025, 2, 0),    // GLOB_DAT
+        ];
+        module.jmprel = [rela(slot(3), 1026, 1, 0)]; // JUMP_SLOT
+        module.dynamic = [
+            Dyn {
+                tag: Tag::RelaOffset as i64,
+                val_ptr: rela_offset as u64,
+            },
+            Dyn {
+                tag: Tag::RelaSize as i64,
+                val_ptr: (rela_size * 3) as u64,
+            },
+            Dyn {
+                tag: Tag::RelaEntrySize as i64,
+                val_ptr: rela_size as u64,
+            },
+            Dyn {
+                tag: Tag::RelaCount as i64,
+                val_ptr: 1,
+            },
+            Dyn {
+                tag: Tag::SymTab as i64,
+                val_ptr: symtab_offset as u64,
+            },
+            Dyn {
+                tag: Tag::JmpRel as i64,
+                val_ptr: jmprel_offset as u64,
+            },
+            Dyn {
+                tag: Tag::PltRelSize as i64,
+                val_ptr: rela_size as u64,
+            },
+            Dyn {
+                tag: Tag::PltRel as i64,
+                val_ptr: Tag::RelaOffset as u64,
+            },
+            // an entry we don't know about (DT_GNU_HASH)
+            Dyn {
+                tag: 0x6ffffef5,
+                val_ptr: 0,
+            },
+            Dyn {
+                tag: Tag::Invalid as i64,
+                val_ptr: 0,
+            },
+        ];
+
+        unsafe { relocate_with_dyn(base, addr_of!(module.dynamic) as *const Dyn) };
+
+        let base = base as u64;
+        assert_eq!(
+            module.slots,
+            [base + 0x40, base + 0x1008, base + 0x2000, base + 0x1000]
+        );
+    }
 }
@@ -1,10 +1,13 @@
 ij_core_workaround!();
 
 use core::arch::asm;
-// use horizon_error::ErrorCode;
+use core::sync::atomic::{AtomicBool, Ordering};
+use horizon_error::ErrorCode;
+use horizon_ipcdef::fatal::FatalPolicy;
 // use horizon_svc::BreakReason;
 
 // TODO: make it into its own error code module
+#[derive(Debug, Clone, Copy)]
 #[repr(u16)]
 pub enum RtAbortReason {
     NotImplemented = 1,
@@ -22,17 +25,82 @@ pub enum RtAbortReason {
     FsOpenFailed,
     SdFsOpenFailed,
     SdFsMountFailed,
+
+    // homebrew ABI
+    UnknownMandatoryAbiConfigEntry,
+    TooManyServiceOverrides,
+    NoMainThreadHandleInNroEnv,
+
+    // relocation code, continued
+    UnresolvedSymbol,
+
+    // other stuff, continued
+    MainStackAllocFailed,
+}
+
+const MODULE_CODE: u32 = 390; // TODO: need to to talk to people how to select this number
+
+/// Policy for reporting the aborts through the `fatal:u` service, so that the user gets an error screen
+///  with the error code instead of the process silently disappearing
+///
+/// The default of `None` doesn't report anything, the aborts only break into the debugger (if any) and exit.
+///
+/// Applications can opt in by defining a symbol with the same name:
+///
+/// ```ignore
+/// #[no_mangle]
+/// static __horizon_rt_fatal_policy: Option<FatalPolicy> = Some(FatalPolicy::ErrorScreen);
+/// ```
+///
+/// Only the aborts after the runtime is initialized are reported, before that there is no IPC to report them with.
+#[no_mangle]
+#[linkage = "weak"]
+#[allow(non_upper_case_globals)]
+pub static __horizon_rt_fatal_policy: Option<FatalPolicy> = None;
+
+/// Set once the runtime is initialized and the services can be reached
+static FATAL_REPORTING_READY: AtomicBool = AtomicBool::new(false);
+
+/// Allows [report_fatal] to reach the fatal service, called at the end of the initialization
+pub fn enable_fatal_reporting() {
+    FATAL_REPORTING_READY.store(true, Ordering::Release);
+}
+
+/// The error code the abort is reported with
+fn abort_error_code(reason: RtAbortReason) -> ErrorCode {
+    ErrorCode::from_parts(MODULE_CODE, reason as u16 as u32)
+}
+
+/// The policy to report with, `None` if the reporting is not configured or can't be done yet
+fn reporting_policy(configured: Option<FatalPolicy>, ready: bool) -> Option<FatalPolicy> {
+    configured.filter(|_| ready)
 }
 
-// const MODULE_CODE: u32 = 390; // TODO: need to to talk to people how to select this number
+/// Reports `code` through `fatal:u` if the application has configured it with [__horizon_rt_fatal_policy]
+///
+/// Meant for the panic and abort paths: with the policies showing the error screen it doesn't return,
+///  otherwise (including when the reporting is not configured or fails) the caller should proceed with the abort.
+pub fn report_fatal(code: ErrorCode) {
+    // the value is not known until link time, make sure it's not assumed to be the default
+    // SAFETY: the static is always initialized
+    let configured = unsafe { core::ptr::read_volatile(&__horizon_rt_fatal_policy) };
+
+    if let Some(policy) =
+        reporting_policy(configured, FATAL_REPORTING_READY.load(Ordering::Acquire))
+    {
+        // we are going down anyway, nothing to do if the fatal service can't be reached
+        let _ = horizon_ipcdef::throw_fatal(code, policy);
+    }
+}
 
 /// This is a very low-level abort function
 #[inline(never)]
 pub fn rt_abort(reason: RtAbortReason) -> ! {
-    // let code = ErrorCode::from_parts(MODULE_CODE, reason as u16 as u32);
-
+    let code = abort_error_code(reason);
     let reason_raw = reason as u16 as u32;
 
+    report_fatal(code);
+
     unsafe {
         asm!("brk #1", in("x0") reason_raw);
     }
@@ -56,3 +124,28 @@ pub fn rt_unwrap<T, E>(result: core::result::Result<T, E>, reason: RtAbortReason
         Err(_) => rt_abort(reason),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        __horizon_rt_fatal_policy, abort_error_code, reporting_policy, RtAbortReason, MODULE_CODE,
+    };
+    use horizon_ipcdef::fatal::FatalPolicy;
+
+    #[test]
+    fn abort_reporting() {
+        assert_eq!(__horizon_rt_fatal_policy, None);
+        assert_eq!(reporting_policy(None, true), None);
+
+        let policy = Some(FatalPolicy::ErrorScreen);
+        assert_eq!(reporting_policy(policy, false), None);
+        assert_eq!(reporting_policy(policy, true), policy);
+
+        let code = abort_error_code(RtAbortReason::MainStackAllocFailed);
+        assert_eq!(code.get_module(), MODULE_CODE);
+        assert_eq!(
+            code.get_description(),
+            RtAbortReason::MainStackAllocFailed as u32
+        );
+    }
+}
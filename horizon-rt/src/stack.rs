@@ -0,0 +1,99 @@
+ij_core_workaround!();
+
+use crate::{rt_abort, RtAbortReason};
+use core::alloc::Layout;
+
+/// Page size, the main thread stack is aligned to it
+const PAGE_SIZE: usize = 0x1000;
+
+/// Size of the main thread stack requested by the application, in bytes
+///
+/// The default of 0 keeps the stack set up by the kernel (or the homebrew loader),
+///  whose size comes from the NPDM (or the loader itself). Libnx has a similar override.
+///
+/// Applications can request a larger stack by defining a symbol with the same name:
+///
+/// ```ignore
+/// #[no_mangle]
+/// static __horizon_rt_main_stack_size: usize = 0x100000;
+/// ```
+///
+/// The size should be a multiple of the page size (0x1000), otherwise it's rounded up.
+///  The stack is allocated from the heap during `init`, before `main` is called.
+#[no_mangle]
+#[linkage = "weak"]
+#[allow(non_upper_case_globals)]
+pub static __horizon_rt_main_stack_size: usize = 0;
+
+/// Layout of the main thread stack of `size` bytes, `None` if the default stack should be kept
+fn main_stack_layout(size: usize) -> Option<Layout> {
+    if size == 0 {
+        return None;
+    }
+
+    let size = size.checked_add(PAGE_SIZE - 1)? & !(PAGE_SIZE - 1);
+    Layout::from_size_align(size, PAGE_SIZE).ok()
+}
+
+/// Allocates the stack of `size` bytes with `allocate`, returning its top
+fn make_main_stack(
+    size: usize,
+    allocate: impl FnOnce(Layout) -> Result<*mut u8, ()>,
+) -> Option<Result<*mut u8, ()>> {
+    let layout = main_stack_layout(size)?;
+
+    // the stack grows down, so the top is at the end of the allocation
+    Some(allocate(layout).map(|bottom| bottom.wrapping_add(layout.size())))
+}
+
+/// Allocates the main thread stack requested with [__horizon_rt_main_stack_size], returning its top
+///
+/// Must be called after the heap is initialized. The stack is never freed.
+pub fn allocate_main_stack() -> Option<*mut u8> {
+    // the value is not known until link time, make sure it's not assumed to be the default
+    // SAFETY: the static is always initialized
+    let size = unsafe { core::ptr::read_volatile(&__horizon_rt_main_stack_size) };
+
+    make_main_stack(size, horizon_global::heap::allocate).map(|stack| match stack {
+        Ok(top) => top,
+        Err(()) => rt_abort(RtAbortReason::MainStackAllocFailed),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::{__horizon_rt_main_stack_size, main_stack_layout, make_main_stack};
+    use core::alloc::Layout;
+    use std::vec::Vec;
+
+    #[test]
+    fn default_stack_is_kept() {
+        assert_eq!(__horizon_rt_main_stack_size, 0);
+        assert_eq!(main_stack_layout(0), None);
+        assert_eq!(
+            make_main_stack(__horizon_rt_main_stack_size, |_| unreachable!()),
+            None
+        );
+    }
+
+    #[test]
+    fn requested_stack() {
+        assert_eq!(
+            main_stack_layout(0x10001),
+            Some(Layout::from_size_align(0x11000, 0x1000).unwrap())
+        );
+
+        let mut layouts = Vec::new();
+        let top = make_main_stack(0x8000, |layout| {
+            layouts.push(layout);
+            Ok(0x10000 as *mut u8)
+        });
+
+        assert_eq!(layouts, [Layout::from_size_align(0x8000, 0x1000).unwrap()]);
+        assert_eq!(top, Some(Ok(0x18000 as *mut u8)));
+
+        assert_eq!(make_main_stack(0x1000, |_| Err(())), Some(Err(())));
+    }
+}
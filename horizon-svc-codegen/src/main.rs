@@ -1,7 +1,8 @@
 use anyhow::{anyhow, Context};
+use clap::Parser;
 use heck::ToSnakeCase;
 use lazy_static::lazy_static;
-use proc_macro2::TokenStream;
+use proc_macro2::{Literal, TokenStream};
 use quote::{format_ident, quote};
 use regex::Regex;
 use scraper::{ElementRef, Html, Selector};
@@ -9,7 +10,7 @@ use semver::Version;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::str::FromStr;
 use strum::EnumString;
@@ -22,11 +23,188 @@ lazy_static! {
     static ref LAST_SUPPORTED_VERSION: Version = Version::parse("13.0.0").unwrap();
 }
 
-const SYSCALL_IGNORE_LIST: &[&str] = &[
-    "CallSecureMonitor", // it has a problematic argument list. And it's not like it is useful for most applications (I think?)
-    "ContinueDebugEvent", // [3.0.0+] and friends in argument names
+const SYSCALL_IGNORE_LIST: &[&str] = &[];
+
+/// Syscalls with a hand-written binding instead of the one generated from the parameter list,
+///  the function gets the syscall id
+const SYSCALL_OVERRIDES: &[(&str, fn(u32) -> TokenStream)] = &[
+    ("CallSecureMonitor", call_secure_monitor), // the arguments are an array of registers
+    ("StartThread", start_thread),              // the wiki doesn't list the result
+    ("CreateSharedMemory", create_shared_memory), // the wiki has the size in w1, the kernel reads x1
+    ("ContinueDebugEvent", continue_debug_event), // [3.0.0+] and friends in argument names
+    ("GetDebugFutureThreadInfo", get_debug_future_thread_info), // the wiki misses the debug handle
 ];
 
+fn syscall_override(name: &str) -> Option<fn(u32) -> TokenStream> {
+    SYSCALL_OVERRIDES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, f)| f)
+}
+
+/// The secure monitor takes and returns the values in x0..x7, they are passed in an array
+fn call_secure_monitor(id: u32) -> TokenStream {
+    let asm_str = format!("svc {:#04x}", id);
+    let registers = (0..8).map(|i| format!("x{}", i)).collect::<Vec<_>>();
+    let indices = (0..8).map(Literal::usize_unsuffixed);
+
+    quote! {
+        /// Passes `args` to the secure monitor in x0..x7 and overwrites them with the values it returns there
+        #[inline(always)]
+        pub unsafe fn call_secure_monitor(args: &mut [u64; 8]) {
+            asm!(#asm_str, #(inout(#registers) args[#indices],)*);
+        }
+    }
+}
+
+/// Same as the generated binding, but with the result the wiki's parameter list misses
+///
+/// The docs go on the function, so the result struct comes after it
+fn start_thread(id: u32) -> TokenStream {
+    let asm_str = format!("svc {:#04x}", id);
+
+    quote! {
+        #[inline(always)]
+        #[must_use]
+        pub unsafe fn start_thread(thread_handle: u32) -> StartThreadResult {
+            let result: u32;
+
+            asm!(#asm_str, in("w0") thread_handle, lateout("w0") result,);
+
+            StartThreadResult {
+                result: ErrorCode::new_unchecked(result),
+            }
+        }
+
+        pub struct StartThreadResult {
+            pub result: ErrorCode,
+        }
+    }
+}
+
+/// Same as the generated binding, but with the size taking the whole x1
+fn create_shared_memory(id: u32) -> TokenStream {
+    let asm_str = format!("svc {:#04x}", id);
+
+    quote! {
+        #[inline(always)]
+        #[must_use]
+        pub unsafe fn create_shared_memory(
+            size: u64,
+            local_memory_permission: u32,
+            remote_memory_permission: u32,
+        ) -> CreateSharedMemoryResult {
+            let result: u32;
+            let shared_memory_handle: u32;
+
+            asm!(
+                #asm_str,
+                in("x1") size,
+                in("w2") local_memory_permission,
+                in("w3") remote_memory_permission,
+                lateout("w0") result,
+                lateout("w1") shared_memory_handle,
+            );
+
+            CreateSharedMemoryResult {
+                result: ErrorCode::new_unchecked(result),
+                shared_memory_handle: shared_memory_handle,
+            }
+        }
+
+        pub struct CreateSharedMemoryResult {
+            pub result: ErrorCode,
+            pub shared_memory_handle: u32,
+        }
+    }
+}
+
+/// The [3.0.0+] form, taking a list of thread ids to resume
+fn continue_debug_event(id: u32) -> TokenStream {
+    let asm_str = format!("svc {:#04x}", id);
+
+    quote! {
+        #[inline(always)]
+        #[must_use]
+        pub unsafe fn continue_debug_event(
+            debug_handle: u32,
+            flags: u32,
+            thread_ids: *const u64,
+            thread_id_count: u32,
+        ) -> ContinueDebugEventResult {
+            let result: u32;
+
+            asm!(
+                #asm_str,
+                in("w0") debug_handle,
+                in("w1") flags,
+                in("x2") thread_ids,
+                in("w3") thread_id_count,
+                lateout("w0") result,
+            );
+
+            ContinueDebugEventResult {
+                result: ErrorCode::new_unchecked(result),
+            }
+        }
+
+        pub struct ContinueDebugEventResult {
+            pub result: ErrorCode,
+        }
+    }
+}
+
+/// Takes the debug handle in w2, returns the thread context (fp, sp, lr, pc) and the thread id
+fn get_debug_future_thread_info(id: u32) -> TokenStream {
+    let asm_str = format!("svc {:#04x}", id);
+
+    quote! {
+        #[inline(always)]
+        #[must_use]
+        pub unsafe fn get_debug_future_thread_info(
+            debug_handle: u32,
+            timeout: u64,
+        ) -> GetDebugFutureThreadInfoResult {
+            let result: u32;
+            let fp: u64;
+            let sp: u64;
+            let lr: u64;
+            let pc: u64;
+            let thread_id: u64;
+
+            asm!(
+                #asm_str,
+                in("w2") debug_handle,
+                in("x3") timeout,
+                lateout("w0") result,
+                lateout("x1") fp,
+                lateout("x2") sp,
+                lateout("x3") lr,
+                lateout("x4") pc,
+                lateout("x5") thread_id,
+            );
+
+            GetDebugFutureThreadInfoResult {
+                result: ErrorCode::new_unchecked(result),
+                fp: fp,
+                sp: sp,
+                lr: lr,
+                pc: pc,
+                thread_id: thread_id,
+            }
+        }
+
+        pub struct GetDebugFutureThreadInfoResult {
+            pub result: ErrorCode,
+            pub fp: u64,
+            pub sp: u64,
+            pub lr: u64,
+            pub pc: u64,
+            pub thread_id: u64,
+        }
+    }
+}
+
 lazy_static! {
     static ref VERSION_RANGE_REGEX: Regex =
         Regex::new(r"^\[(\d+\.\d+\.\d+)-(\d+\.\d+\.\d+)\]$").unwrap();
@@ -115,15 +293,86 @@ struct Syscall {
     /// Name of the syscall
     pub name: String,
     /// HOS version requirements for this syscall
-    #[allow(unused)] // TODO: use this to codegen docs
     pub version_req: VersionReq,
     /// Info on in & out params for this syscall (as they are described on switchbrew)
     pub params_info: Option<ParamsInfo>,
     /// raw html from switchbrew in section for this syscall
-    #[allow(unused)] // TODO: use this to codegen docs
     pub raw_docs: Option<String>,
 }
 
+fn collapse_whitespace<'a>(text: impl Iterator<Item = &'a str>) -> String {
+    text.collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Converts the switchbrew section of a syscall to plain text paragraphs
+///
+/// The heading and the tables are skipped, the parameters are already described by the signature
+fn docs_to_text(html: &str) -> Vec<String> {
+    let html = Html::parse_fragment(html);
+    let list_item = Selector::parse("li").unwrap();
+
+    let mut paragraphs = Vec::new();
+    for child in html.root_element().children() {
+        if let Some(element) = ElementRef::wrap(child) {
+            match &*element.value().name.local {
+                "h2" | "h3" | "h4" | "table" => {}
+                "ul" | "ol" => paragraphs.push(
+                    element
+                        .select(&list_item)
+                        .map(|item| format!("- {}", collapse_whitespace(item.text())))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ),
+                _ => paragraphs.push(collapse_whitespace(element.text())),
+            }
+        } else if let Some(text) = child.value().as_text() {
+            paragraphs.push(collapse_whitespace(std::iter::once(&**text)));
+        }
+    }
+
+    paragraphs.retain(|p| !p.is_empty());
+    // rustdoc would take the version notes like `[2.0.0+]` for intra-doc links
+    paragraphs
+        .into_iter()
+        .map(|p| p.replace('[', "\\[").replace(']', "\\]"))
+        .collect()
+}
+
+impl Syscall {
+    /// Lines of the doc comment of the generated function
+    fn doc_lines(&self) -> Vec<String> {
+        let mut paragraphs = Vec::new();
+        match &self.version_req {
+            VersionReq::Any => {}
+            VersionReq::MinVersion(min) => {
+                paragraphs.push(format!("Available since HOS {}", min));
+            }
+            VersionReq::VersionRange { min, max } => {
+                paragraphs.push(format!("Available in HOS {} to {}", min, max));
+            }
+        }
+        if let Some(raw_docs) = &self.raw_docs {
+            paragraphs.extend(docs_to_text(raw_docs));
+        }
+
+        // an empty line between the paragraphs, and a space after the `///`
+        paragraphs
+            .join("\n\n")
+            .lines()
+            .map(|line| {
+                if line.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", line)
+                }
+            })
+            .collect()
+    }
+}
+
 fn split_sections(content: ElementRef) -> anyhow::Result<HashMap<String, String>> {
     let mut res = HashMap::new();
     let mut current_section: Option<(String, String)> = None;
@@ -354,7 +603,7 @@ fn parse_syscall_params(html: &str) -> anyhow::Result<ParamsInfo> {
     Ok(res)
 }
 
-fn get_syscalls() -> anyhow::Result<Vec<Syscall>> {
+fn download_html() -> anyhow::Result<String> {
     let url = format!(
         "https://switchbrew.org/w/index.php?title=SVC&oldid={}",
         REVISION
@@ -363,11 +612,30 @@ fn get_syscalls() -> anyhow::Result<Vec<Syscall>> {
     let html = reqwest::blocking::get(url).context("Getting switchbrew SVC page")?;
     let html = html.text().context("Getting switchbrew SVC page text")?;
 
+    Ok(html)
+}
+
+/// Reads the SVC page from `input` if it's specified, downloads it otherwise
+fn load_html(input: Option<&Path>) -> anyhow::Result<String> {
+    match input {
+        Some(input) => std::fs::read_to_string(input)
+            .with_context(|| format!("Reading the SVC page from {}", input.display())),
+        None => download_html().with_context(|| {
+            format!(
+                "Could not download the switchbrew SVC page (revision {}). \
+                Without network access, pass a copy saved with `--dump-html` via `--input`",
+                REVISION
+            )
+        }),
+    }
+}
+
+fn get_syscalls(html: &str) -> anyhow::Result<Vec<Syscall>> {
     let table =
-        table_extract::Table::find_by_headers(&html, &["ID", "Return Type", "Name", "Arguments"])
+        table_extract::Table::find_by_headers(html, &["ID", "Return Type", "Name", "Arguments"])
             .context("Finding syscall table on the page")?;
 
-    let html = Html::parse_fragment(&html);
+    let html = Html::parse_fragment(html);
 
     let document_content = html
         .select(&Selector::parse(".mw-parser-output").unwrap())
@@ -406,11 +674,16 @@ fn get_syscalls() -> anyhow::Result<Vec<Syscall>> {
 
         let raw_docs = sections.get(name).cloned();
 
-        let params_info = raw_docs
-            .as_ref()
-            .map(|docs| parse_syscall_params(docs))
-            .map_or(Ok(None), |v| v.map(Some))
-            .with_context(|| format!("Parsing parameters info for syscall {}", name))?;
+        // the parameters of the overridden syscalls are not needed, and they may not be parseable anyway
+        let params_info = if syscall_override(name).is_some() {
+            None
+        } else {
+            raw_docs
+                .as_ref()
+                .map(|docs| parse_syscall_params(docs))
+                .map_or(Ok(None), |v| v.map(Some))
+                .with_context(|| format!("Parsing parameters info for syscall {}", name))?
+        };
 
         res.push(Syscall {
             id,
@@ -499,14 +772,22 @@ fn codegen(syscalls: &Vec<Syscall>) -> anyhow::Result<String> {
         use horizon_error::ErrorCode;
     };
 
-    for Syscall {
-        id,
-        name,
-        params_info,
-        ..
-    } in syscalls
-    {
-        if let Some(ParamsInfo {
+    for syscall in syscalls {
+        let Syscall {
+            id,
+            name,
+            params_info,
+            ..
+        } = syscall;
+        let docs = syscall.doc_lines();
+
+        if let Some(binding) = syscall_override(name) {
+            let binding = binding(*id);
+            ts.extend([quote! {
+                #(#[doc = #docs])*
+                #binding
+            }]);
+        } else if let Some(ParamsInfo {
             in_params,
             out_params,
         }) = params_info
@@ -572,6 +853,7 @@ fn codegen(syscalls: &Vec<Syscall>) -> anyhow::Result<String> {
                     #(pub #out_names: #out_types,)*
                 }
 
+                #(#[doc = #docs])*
                 #[inline(always)]
                 #[must_use]
                 pub unsafe fn #function_name(#(#in_names: #in_types),*) -> #result_struct_name {
@@ -590,8 +872,27 @@ fn codegen(syscalls: &Vec<Syscall>) -> anyhow::Result<String> {
     rustfmt_generated_string(&ts.to_string())
 }
 
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Read the SVC page from this file instead of downloading it from switchbrew
+    #[clap(long)]
+    input: Option<PathBuf>,
+    /// Save the downloaded SVC page to this file, so that it can be passed to `--input` later
+    #[clap(long, conflicts_with = "input")]
+    dump_html: Option<PathBuf>,
+}
+
 fn main() -> anyhow::Result<()> {
-    let syscalls = get_syscalls()?;
+    let args: Args = Args::parse();
+
+    let html = load_html(args.input.as_deref())?;
+    if let Some(dump_html) = &args.dump_html {
+        std::fs::write(dump_html, &html)
+            .with_context(|| format!("Writing the SVC page to {}", dump_html.display()))?;
+    }
+
+    let syscalls = get_syscalls(&html)?;
 
     let generated = codegen(&syscalls)?;
 
@@ -601,3 +902,61 @@ fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{create_shared_memory, docs_to_text, get_debug_future_thread_info, start_thread};
+
+    #[test]
+    fn docs_text() {
+        let html = r#"
+            <h2><span class="mw-headline" id="SetHeapSize">SetHeapSize</span></h2>
+            <table class="wikitable">
+                <tr><th>Argument</th><th>Type</th><th>Name</th></tr>
+                <tr><td>(In) X1</td><td>u64</td><td>Size</td></tr>
+            </table>
+            <p>Set the process heap to a given size.
+                It can both <b>extend</b> and shrink the heap.</p>
+            <p>[2.0.0+] The size must be a multiple of <a href="/wiki/Memory">0x200000</a>.</p>
+            <ul>
+                <li>Fails with <code>InvalidSize</code> otherwise</li>
+                <li>Fails with <code>OutOfMemory</code> when there's no memory left</li>
+            </ul>
+        "#;
+
+        assert_eq!(
+            docs_to_text(html),
+            [
+                "Set the process heap to a given size. It can both extend and shrink the heap.",
+                "\\[2.0.0+\\] The size must be a multiple of 0x200000.",
+                "- Fails with InvalidSize otherwise\n- Fails with OutOfMemory when there's no memory left",
+            ]
+        );
+    }
+
+    #[test]
+    fn start_thread_binding() {
+        let binding = start_thread(0x09).to_string();
+
+        assert!(binding
+            .contains(r#"asm ! ("svc 0x09" , in ("w0") thread_handle , lateout ("w0") result ,)"#));
+        assert!(binding.contains("pub struct StartThreadResult { pub result : ErrorCode , }"));
+    }
+
+    #[test]
+    fn create_shared_memory_binding() {
+        let binding = create_shared_memory(0x50).to_string();
+
+        assert!(binding.contains("size : u64"));
+        assert!(binding.contains(r#"in ("x1") size"#));
+        assert!(binding.contains(r#"lateout ("w1") shared_memory_handle"#));
+    }
+
+    #[test]
+    fn get_debug_future_thread_info_binding() {
+        let binding = get_debug_future_thread_info(0x2e).to_string();
+
+        assert!(binding.contains(r#"in ("w2") debug_handle , in ("x3") timeout"#));
+        assert!(binding.contains(r#"lateout ("x5") thread_id"#));
+    }
+}
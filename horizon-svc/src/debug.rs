@@ -0,0 +1,390 @@
+//! Wrappers around the process debugging syscalls, the building blocks of a debugger
+//!
+//! A debugger attaches to a process with [debug_active_process], then waits on the debug handle
+//!  and reads the events with [get_debug_event]. An event with the [DebugEventFlags::STOPPED] flag
+//!  leaves the process stopped until [continue_debug_event] is called.
+//!
+//! All of these require the syscalls to be allowed in the kernel capabilities of the process (NPDM).
+//!
+//! `ContinueDebugEvent` and `GetDebugFutureThreadInfo` are not generated (their switchbrew entries
+//!  don't fit the generator), so the syscalls are invoked directly here.
+//!
+//! See <https://switchbrew.org/wiki/SVC#GetDebugEvent> for the event layouts.
+
+use crate::{raw, timeout_to_nanos, RawHandle};
+use bitflags::bitflags;
+use core::time::Duration;
+use horizon_error::{ErrorCode, ErrorCodeModule, KernelErrorCode, Result};
+
+/// Size of the event info structure written by [get_debug_event] ([3.0.0+] layout)
+pub const DEBUG_EVENT_INFO_SIZE: usize = 0x40;
+
+bitflags! {
+    pub struct DebugEventFlags: u32 {
+        /// The process is stopped until [continue_debug_event] is called
+        const STOPPED = 1 << 0;
+    }
+}
+
+bitflags! {
+    pub struct ContinueDebugFlags: u32 {
+        const EXCEPTION_HANDLED       = 1 << 0;
+        const ENABLE_EXCEPTION_EVENT  = 1 << 1;
+        /// Continue all threads of the process, the thread id list is ignored
+        const CONTINUE_ALL            = 1 << 2;
+        /// Continue all threads except for the listed ones
+        const CONTINUE_OTHERS         = 1 << 3;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProcessExitReason {
+    ExitProcess,
+    TerminateProcess,
+    Exception,
+    Unknown(u32),
+}
+
+impl From<u32> for ProcessExitReason {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => Self::ExitProcess,
+            1 => Self::TerminateProcess,
+            2 => Self::Exception,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ThreadExitReason {
+    ExitThread,
+    TerminateThread,
+    ExitProcess,
+    TerminateProcess,
+    Unknown(u32),
+}
+
+impl From<u32> for ThreadExitReason {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => Self::ExitThread,
+            1 => Self::TerminateThread,
+            2 => Self::ExitProcess,
+            3 => Self::TerminateProcess,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+/// Kind of the exception reported by [DebugEvent::Exception], along with its type-specific data
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugException {
+    UndefinedInstruction {
+        instruction: u32,
+    },
+    InstructionAbort,
+    DataAbort,
+    AlignmentFault,
+    DebuggerAttached,
+    BreakPoint {
+        is_watchpoint: bool,
+    },
+    /// Raised by the `Break` syscall
+    UserBreak {
+        reason: u64,
+        address: u64,
+        size: u64,
+    },
+    /// Raised by `BreakDebugProcess`
+    DebuggerBreak {
+        active_thread_ids: [u64; 4],
+    },
+    UndefinedSystemCall {
+        svc_id: u32,
+    },
+    MemorySystemError,
+    Unknown {
+        exception_type: u32,
+        data: [u64; 4],
+    },
+}
+
+/// A decoded debug event
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugEvent {
+    AttachProcess {
+        program_id: u64,
+        process_id: u64,
+        /// Process name, NUL-padded
+        name: [u8; 12],
+        flags: u32,
+        /// [5.0.0+]
+        user_exception_context_address: u64,
+    },
+    AttachThread {
+        thread_id: u64,
+        tls_address: u64,
+        /// [1.0.0-10.2.0] zero on newer firmware
+        entrypoint: u64,
+    },
+    ExitProcess {
+        reason: ProcessExitReason,
+    },
+    ExitThread {
+        reason: ThreadExitReason,
+    },
+    Exception {
+        /// The faulting address (FAR), for the exceptions that have one
+        fault_address: u64,
+        exception: DebugException,
+    },
+}
+
+/// A debug event along with the info common to all events
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DebugEventInfo {
+    pub flags: DebugEventFlags,
+    /// Id of the thread the event happened in, zero for the process-wide events
+    pub thread_id: u64,
+    pub event: DebugEvent,
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_u64s<const N: usize>(bytes: &[u8], offset: usize) -> [u64; N] {
+    let mut res = [0; N];
+    for (i, v) in res.iter_mut().enumerate() {
+        *v = read_u64(bytes, offset + i * 8);
+    }
+    res
+}
+
+impl DebugEventInfo {
+    /// Decodes the event info structure written by the kernel
+    ///
+    /// Returns `None` for an unknown event type
+    pub fn parse(bytes: &[u8; DEBUG_EVENT_INFO_SIZE]) -> Option<Self> {
+        let event_type = read_u32(bytes, 0x0);
+        let flags = DebugEventFlags::from_bits_truncate(read_u32(bytes, 0x4));
+        let thread_id = read_u64(bytes, 0x8);
+
+        // event-specific info
+        let info = &bytes[0x10..];
+
+        let event = match event_type {
+            0 => DebugEvent::AttachProcess {
+                program_id: read_u64(info, 0x0),
+                process_id: read_u64(info, 0x8),
+                name: info[0x10..0x1c].try_into().unwrap(),
+                flags: read_u32(info, 0x1c),
+                user_exception_context_address: read_u64(info, 0x20),
+            },
+            1 => DebugEvent::AttachThread {
+                thread_id: read_u64(info, 0x0),
+                tls_address: read_u64(info, 0x8),
+                entrypoint: read_u64(info, 0x10),
+            },
+            2 => DebugEvent::ExitProcess {
+                reason: read_u32(info, 0x0).into(),
+            },
+            3 => DebugEvent::ExitThread {
+                reason: read_u32(info, 0x0).into(),
+            },
+            4 => {
+                let exception_type = read_u32(info, 0x0);
+                let fault_address = read_u64(info, 0x8);
+                // exception-specific info
+                let specific = &info[0x10..];
+
+                let exception = match exception_type {
+                    0 => DebugException::UndefinedInstruction {
+                        instruction: read_u32(specific, 0x0),
+                    },
+                    1 => DebugException::InstructionAbort,
+                    2 => DebugException::DataAbort,
+                    3 => DebugException::AlignmentFault,
+                    4 => DebugException::DebuggerAttached,
+                    5 => DebugException::BreakPoint {
+                        is_watchpoint: read_u32(specific, 0x0) != 0,
+                    },
+                    6 => DebugException::UserBreak {
+                        reason: read_u32(specific, 0x0) as u64,
+                        address: read_u64(specific, 0x8),
+                        size: read_u64(specific, 0x10),
+                    },
+                    7 => DebugException::DebuggerBreak {
+                        active_thread_ids: read_u64s(specific, 0x0),
+                    },
+                    8 => DebugException::UndefinedSystemCall {
+                        svc_id: read_u32(specific, 0x0),
+                    },
+                    9 => DebugException::MemorySystemError,
+                    exception_type => DebugException::Unknown {
+                        exception_type,
+                        data: read_u64s(specific, 0x0),
+                    },
+                };
+
+                DebugEvent::Exception {
+                    fault_address,
+                    exception,
+                }
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            flags,
+            thread_id,
+            event,
+        })
+    }
+}
+
+/// State of a thread at the moment it was last scheduled out, returned by [get_debug_future_thread_info]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LastThreadContext {
+    pub fp: u64,
+    pub sp: u64,
+    pub lr: u64,
+    pub pc: u64,
+}
+
+/// Attaches to the process with the specified id, returning the debug handle
+pub fn debug_active_process(process_id: u64) -> Result<RawHandle> {
+    let res = unsafe { raw::debug_active_process(process_id) };
+
+    res.result.into_result(RawHandle(res.debug_handle))
+}
+
+/// Reads the next pending event of the debugged process
+///
+/// Fails with `NotFound` if there is no event, wait on the debug handle before calling this.
+///  An event with an unknown type is also reported as `NotFound` after it's consumed.
+pub fn get_debug_event(debug_handle: RawHandle) -> Result<DebugEventInfo> {
+    #[repr(C, align(8))]
+    struct Buffer([u8; DEBUG_EVENT_INFO_SIZE]);
+
+    let mut buffer = Buffer([0; DEBUG_EVENT_INFO_SIZE]);
+
+    // SAFETY: the kernel writes at most DEBUG_EVENT_INFO_SIZE bytes to the buffer
+    unsafe { raw::get_debug_event(buffer.0.as_mut_ptr() as u64, debug_handle.0) }
+        .result
+        .into_result(())?;
+
+    DebugEventInfo::parse(&buffer.0).ok_or_else(|| {
+        ErrorCode::from_parts(KernelErrorCode::MODULE, KernelErrorCode::NotFound as u32)
+    })
+}
+
+/// [3.0.0+] Resumes the threads of the debugged process after a [DebugEventFlags::STOPPED] event
+///
+/// Which threads to resume depends on `flags`, `thread_ids` is ignored with [ContinueDebugFlags::CONTINUE_ALL]
+pub fn continue_debug_event(
+    debug_handle: RawHandle,
+    flags: ContinueDebugFlags,
+    thread_ids: &[u64],
+) -> Result<()> {
+    // SAFETY: the kernel only reads the thread id list
+    unsafe {
+        raw::continue_debug_event(
+            debug_handle.0,
+            flags.bits(),
+            thread_ids.as_ptr(),
+            thread_ids.len() as u32,
+        )
+    }
+    .result
+    .into_result(())
+}
+
+/// [6.0.0+] Waits for a thread of the debugged process to be scheduled out,
+///  returning its context along with its id
+pub fn get_debug_future_thread_info(
+    debug_handle: RawHandle,
+    timeout: Option<Duration>,
+) -> Result<(LastThreadContext, u64)> {
+    let timeout_ns = timeout_to_nanos(timeout);
+
+    // SAFETY: the syscall only returns values in registers
+    let res = unsafe { raw::get_debug_future_thread_info(debug_handle.0, timeout_ns as u64) };
+
+    res.result.into_result((
+        LastThreadContext {
+            fp: res.fp,
+            sp: res.sp,
+            lr: res.lr,
+            pc: res.pc,
+        },
+        res.thread_id,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn event_bytes(event_type: u32, flags: u32, thread_id: u64, info: &[u8]) -> [u8; 0x40] {
+        let mut bytes = [0; DEBUG_EVENT_INFO_SIZE];
+        bytes[0x0..0x4].copy_from_slice(&event_type.to_le_bytes());
+        bytes[0x4..0x8].copy_from_slice(&flags.to_le_bytes());
+        bytes[0x8..0x10].copy_from_slice(&thread_id.to_le_bytes());
+        bytes[0x10..0x10 + info.len()].copy_from_slice(info);
+        bytes
+    }
+
+    #[test]
+    fn attach_thread() {
+        let mut info = [0; 0x18];
+        info[0x0..0x8].copy_from_slice(&0x51u64.to_le_bytes());
+        info[0x8..0x10].copy_from_slice(&0x1f000u64.to_le_bytes());
+        info[0x10..0x18].copy_from_slice(&0x7100000000u64.to_le_bytes());
+
+        let event = DebugEventInfo::parse(&event_bytes(1, 1, 0x51, &info)).unwrap();
+
+        assert_eq!(
+            event,
+            DebugEventInfo {
+                flags: DebugEventFlags::STOPPED,
+                thread_id: 0x51,
+                event: DebugEvent::AttachThread {
+                    thread_id: 0x51,
+                    tls_address: 0x1f000,
+                    entrypoint: 0x7100000000,
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn undefined_instruction_exception() {
+        let mut info = [0; 0x14];
+        info[0x0..0x4].copy_from_slice(&0u32.to_le_bytes());
+        info[0x8..0x10].copy_from_slice(&0x7100001234u64.to_le_bytes());
+        info[0x10..0x14].copy_from_slice(&0xe7ffdefeu32.to_le_bytes());
+
+        let event = DebugEventInfo::parse(&event_bytes(4, 1, 0x52, &info)).unwrap();
+
+        assert_eq!(
+            event.event,
+            DebugEvent::Exception {
+                fault_address: 0x7100001234,
+                exception: DebugException::UndefinedInstruction {
+                    instruction: 0xe7ffdefe
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_event_type() {
+        assert_eq!(DebugEventInfo::parse(&event_bytes(5, 0, 0, &[])), None);
+    }
+}
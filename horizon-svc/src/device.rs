@@ -0,0 +1,171 @@
+//! Wrappers around syscalls needed to interface with hardware devices (mostly to do DMA)
+//!
+//! All of these require the syscalls to be allowed in the kernel capabilities of the process (NPDM),
+//!  which is normally the case only for sysmodules. Mapping to a device address space additionally
+//!  requires the device to be listed in the capabilities.
+
+use crate::{raw, Address, AddressRange, MemoryPermission, RawHandle, Size};
+use horizon_error::Result;
+
+const PAGE_SIZE: usize = 0x1000;
+
+fn debug_assert_page_aligned((address, size): AddressRange, device_address: u64) {
+    debug_assert_eq!(
+        address as usize % PAGE_SIZE,
+        0,
+        "address should be page-aligned"
+    );
+    debug_assert_eq!(size % PAGE_SIZE, 0, "size should be page-aligned");
+    debug_assert_eq!(
+        device_address as usize % PAGE_SIZE,
+        0,
+        "device_address should be page-aligned"
+    );
+}
+
+/// Result of [query_physical_address]
+#[derive(Debug, Copy, Clone)]
+pub struct PhysicalMemoryInfo {
+    pub physical_address: u64,
+    /// Start of the virtual memory region physically contiguous with the queried address
+    pub virtual_address: Address,
+    pub size: Size,
+}
+
+/// Gets the physical address backing the specified virtual address of the current process
+// the kernel only looks the address up, it's never dereferenced
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn query_physical_address(address: Address) -> Result<PhysicalMemoryInfo> {
+    // SAFETY: this syscall should not modify anything
+    let res = unsafe { raw::query_physical_address(address) };
+
+    res.result.into_result(PhysicalMemoryInfo {
+        physical_address: res.physical_memory_info_address,
+        virtual_address: res.physical_memory_info_base_address as Address,
+        size: res.physical_memory_info_size as Size,
+    })
+}
+
+/// Creates a device address space spanning `[start_address, end_address)`
+pub fn create_device_address_space(start_address: u64, end_address: u64) -> Result<RawHandle> {
+    let res = unsafe { raw::create_device_address_space(start_address, end_address) };
+
+    res.result
+        .into_result(RawHandle(res.device_address_space_handle))
+}
+
+pub unsafe fn attach_device_address_space(
+    device_name: u32,
+    device_address_space: RawHandle,
+) -> Result<()> {
+    raw::attach_device_address_space(device_name, device_address_space.0 as _)
+        .result
+        .into_result(())
+}
+
+pub unsafe fn detach_device_address_space(
+    device_name: u32,
+    device_address_space: RawHandle,
+) -> Result<()> {
+    raw::detach_device_address_space(device_name, device_address_space.0 as _)
+        .result
+        .into_result(())
+}
+
+/// Maps the memory of a process into the device address space
+///
+/// `address`, `size` and `device_address` must be page-aligned
+pub unsafe fn map_device_address_space_by_force(
+    device_address_space: RawHandle,
+    process: RawHandle,
+    (address, size): AddressRange,
+    device_address: u64,
+    permission: MemoryPermission,
+) -> Result<()> {
+    debug_assert_page_aligned((address, size), device_address);
+
+    raw::map_device_address_space_by_force(
+        device_address_space.0,
+        process.0,
+        address,
+        size as _,
+        device_address,
+        permission.bits,
+    )
+    .result
+    .into_result(())
+}
+
+/// Same as [map_device_address_space_by_force], but additionally requires the lower 22 bits of
+///  `address` and `device_address` to match
+pub unsafe fn map_device_address_space_aligned(
+    device_address_space: RawHandle,
+    process: RawHandle,
+    (address, size): AddressRange,
+    device_address: u64,
+    permission: MemoryPermission,
+) -> Result<()> {
+    debug_assert_page_aligned((address, size), device_address);
+    debug_assert_eq!(
+        address as u64 & 0x3fffff,
+        device_address & 0x3fffff,
+        "address and device_address should have the same lower 22 bits"
+    );
+
+    raw::map_device_address_space_aligned(
+        device_address_space.0,
+        process.0,
+        address,
+        size as _,
+        device_address,
+        permission.bits,
+    )
+    .result
+    .into_result(())
+}
+
+/// Maps the memory of a process into the device address space, returning the size actually mapped
+///
+/// `address`, `size` and `device_address` must be page-aligned
+pub unsafe fn map_device_address_space(
+    device_address_space: RawHandle,
+    process: RawHandle,
+    (address, size): AddressRange,
+    device_address: u64,
+    permission: MemoryPermission,
+) -> Result<Size> {
+    debug_assert_page_aligned((address, size), device_address);
+
+    let res = raw::map_device_address_space(
+        device_address_space.0,
+        process.0,
+        address,
+        size as _,
+        device_address,
+        permission.bits,
+    );
+
+    res.result.into_result(res.size as Size)
+}
+
+/// Unmaps memory previously mapped with one of the `map_device_address_space*` functions
+///
+/// `address`, `size` and `device_address` must be page-aligned
+pub unsafe fn unmap_device_address_space(
+    device_address_space: RawHandle,
+    process: RawHandle,
+    (address, size): AddressRange,
+    device_address: u64,
+) -> Result<()> {
+    debug_assert_page_aligned((address, size), device_address);
+
+    raw::unmap_device_address_space(
+        device_address_space.0,
+        process.0,
+        address,
+        size as _,
+        device_address,
+    )
+    .result
+    .into_result(())
+}
@@ -0,0 +1,132 @@
+//! Wrappers around the kernel debugging syscalls implemented by mesosphere
+//!
+//! The stock kernel has these syscalls too, but they do nothing on retail units. To not have
+//!  the calls silently succeed, the wrappers check whether the kernel is mesosphere first and
+//!  fail with `KernelErrorCode::NotImplemented` otherwise.
+//!
+//! svc 0x3c was called `DumpInfo` (with a different set of arguments) before 4.0.0. Only the newer
+//!  `KernelDebug` form is wrapped here, as that's the one mesosphere implements.
+
+use crate::{get_info, raw, InfoType};
+use horizon_error::{ErrorCode, ErrorCodeModule, KernelErrorCode, Result};
+
+/// What to dump with [kernel_debug]
+///
+/// Each dump is printed to the kernel debug log (uart) by mesosphere
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum KernelDebugType {
+    /// Dumps all threads, or the thread with the id passed as the argument
+    Thread = 0,
+    /// Dumps the call stack of all threads, or of the thread with the id passed as the argument
+    ThreadCallStack = 1,
+    KernelObject = 2,
+    /// Dumps the handle table of all processes, or of the process with the id passed as the argument
+    Handle = 3,
+    /// Dumps the memory map of a process with the id passed as the argument
+    Memory = 4,
+    /// Dumps the page table of a process with the id passed as the argument
+    PageTable = 5,
+    CpuUtilization = 6,
+    Process = 7,
+    SuspendProcess = 8,
+    ResumeProcess = 9,
+    Port = 10,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum KernelTraceState {
+    Disabled = 0,
+    Enabled = 1,
+}
+
+/// Checks whether the current kernel is mesosphere by querying one of its [InfoType] extensions
+pub fn is_mesosphere() -> bool {
+    get_info(InfoType::MesosphereMetaKernelVersion, None).is_ok()
+}
+
+fn ensure_mesosphere() -> Result<()> {
+    require_mesosphere(get_info(InfoType::MesosphereMetaKernelVersion, None))
+}
+
+/// Maps the answer to the mesosphere kernel version query to the result of [ensure_mesosphere]
+///
+/// The stock kernel fails the query (with `InvalidEnumValue`), which is reported as `NotImplemented`
+fn require_mesosphere(kernel_version: Result<u64>) -> Result<()> {
+    match kernel_version {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ErrorCode::from_parts(
+            KernelErrorCode::MODULE,
+            KernelErrorCode::NotImplemented as u32,
+        )),
+    }
+}
+
+/// Makes the kernel dump some of its state to the debug log
+///
+/// The meaning of `arg0`..`arg2` depends on `debug_type`, most dumps take an optional
+///  thread or process id in `arg0` (`-1` meaning "all").
+///
+/// \[mesosphere\] Fails with `NotImplemented` on the stock kernel.
+pub fn kernel_debug(debug_type: KernelDebugType, arg0: u64, arg1: u64, arg2: u64) -> Result<()> {
+    ensure_mesosphere()?;
+
+    // SAFETY: the syscall only prints (and suspends/resumes processes for the respective types),
+    //  it doesn't touch our memory
+    unsafe { raw::kernel_debug(debug_type as u32, arg0, arg1, arg2) }
+        .result
+        .into_result(())
+}
+
+/// Enables or disables the kernel trace (KTrace) buffer recording
+///
+/// \[mesosphere\] Fails with `NotImplemented` on the stock kernel. Does nothing unless mesosphere
+///  was built with KTrace support, which can be checked with [InfoType::MesosphereMetaIsKTraceEnabled].
+pub fn change_kernel_trace_state(state: KernelTraceState) -> Result<()> {
+    ensure_mesosphere()?;
+
+    // SAFETY: the syscall only changes the state of the trace buffer
+    unsafe { raw::change_kernel_trace_state(state as u32) }
+        .result
+        .into_result(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{require_mesosphere, KernelDebugType, KernelTraceState};
+    use horizon_error::{ErrorCode, ErrorCodeModule, KernelErrorCode};
+
+    #[test]
+    fn kernel_debug_encoding() {
+        assert_eq!(KernelDebugType::Thread as u32, 0);
+        assert_eq!(KernelDebugType::ThreadCallStack as u32, 1);
+        assert_eq!(KernelDebugType::KernelObject as u32, 2);
+        assert_eq!(KernelDebugType::Handle as u32, 3);
+        assert_eq!(KernelDebugType::Memory as u32, 4);
+        assert_eq!(KernelDebugType::PageTable as u32, 5);
+        assert_eq!(KernelDebugType::CpuUtilization as u32, 6);
+        assert_eq!(KernelDebugType::Process as u32, 7);
+        assert_eq!(KernelDebugType::SuspendProcess as u32, 8);
+        assert_eq!(KernelDebugType::ResumeProcess as u32, 9);
+        assert_eq!(KernelDebugType::Port as u32, 10);
+
+        assert_eq!(KernelTraceState::Disabled as u32, 0);
+        assert_eq!(KernelTraceState::Enabled as u32, 1);
+    }
+
+    #[test]
+    fn stock_kernel_unsupported() {
+        // what the stock kernel answers to the mesosphere info query
+        let stock = Err(ErrorCode::from_parts(
+            KernelErrorCode::MODULE,
+            KernelErrorCode::InvalidEnumValue as u32,
+        ));
+        assert_eq!(
+            require_mesosphere(stock).unwrap_err().get_description(),
+            KernelErrorCode::NotImplemented as u32
+        );
+
+        assert_eq!(require_mesosphere(Ok(0x0001_0000)), Ok(()));
+    }
+}
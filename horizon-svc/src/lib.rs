@@ -4,13 +4,25 @@
 
 //! Defines wrappers around horizon kernel system calls and related types
 
+#[cfg(feature = "debug-svc")]
+pub mod debug;
+#[cfg(feature = "device")]
+pub mod device;
+#[cfg(feature = "kernel-debug")]
+pub mod kernel_debug;
+#[cfg(feature = "process-management")]
+pub mod process;
 mod raw;
+pub mod resource_limit;
+#[cfg(feature = "system-info")]
+pub mod system_info;
 
 use bitflags::bitflags;
 use core::hint::unreachable_unchecked;
-use core::sync::atomic::AtomicI32;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicI32, AtomicU32};
 use core::time::Duration;
-use horizon_error::Result;
+use horizon_error::{ErrorCode, ErrorCodeModule, KernelErrorCode, Result};
 
 pub type Address = *const u8;
 pub type Size = usize;
@@ -42,6 +54,169 @@ bitflags! {
     }
 }
 
+/// Error returned by [MemoryPermission::try_from_bits], holds the bits not corresponding to any permission
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidPermission(pub u32);
+
+impl MemoryPermission {
+    /// Like `from_bits`, but reports the unknown bits instead of discarding them
+    pub fn try_from_bits(bits: u32) -> core::result::Result<Self, InvalidPermission> {
+        Self::from_bits(bits).ok_or(InvalidPermission(bits & !Self::all().bits))
+    }
+
+    /// Read, write and execute at once
+    ///
+    /// The kernel rejects it for most memory, including the code memory (which can't be both writable
+    ///  and executable), it's mostly useful to check the permissions reported by [query_memory]
+    pub const fn rwx() -> Self {
+        Self::from_bits_truncate(Self::READ.bits | Self::WRITE.bits | Self::EXECUTE.bits)
+    }
+}
+
+bitflags! {
+    /// Attributes of a memory region, see <https://switchbrew.org/wiki/SVC#MemoryAttribute>
+    ///
+    /// Only [MemoryAttribute::UNCACHED] can be changed with [set_memory_attribute],
+    ///  the others are managed by the kernel and reported in [MemoryInfo::attribute]
+    pub struct MemoryAttribute: u32 {
+        const LOCKED        = 1 << 0;
+        const IPC_LOCKED    = 1 << 1;
+        const DEVICE_SHARED = 1 << 2;
+        const UNCACHED      = 1 << 3;
+        /// [17.0.0+]
+        const PERMISSION_LOCKED = 1 << 4;
+    }
+}
+
+/// What a memory region is used for, see <https://switchbrew.org/wiki/SVC#MemoryState>
+///
+/// New firmware versions add states from time to time, so the enum is not exhaustive
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+#[non_exhaustive]
+pub enum MemoryState {
+    Free = 0x00,
+    Io = 0x01,
+    Static = 0x02,
+    Code = 0x03,
+    CodeData = 0x04,
+    Normal = 0x05,
+    Shared = 0x06,
+    Alias = 0x07,
+    AliasCode = 0x08,
+    AliasCodeData = 0x09,
+    Ipc = 0x0A,
+    Stack = 0x0B,
+    ThreadLocal = 0x0C,
+    Transferred = 0x0D,
+    SharedTransferred = 0x0E,
+    SharedCode = 0x0F,
+    Inaccessible = 0x10,
+    NonSecureIpc = 0x11,
+    NonDeviceIpc = 0x12,
+    Kernel = 0x13,
+    GeneratedCode = 0x14,
+    CodeOut = 0x15,
+    /// [15.0.0+]
+    Coverage = 0x16,
+    /// [15.0.0+]
+    Insecure = 0x17,
+}
+
+impl MemoryState {
+    pub fn from_raw(value: u32) -> Option<Self> {
+        use MemoryState::*;
+        Some(match value {
+            0x00 => Free,
+            0x01 => Io,
+            0x02 => Static,
+            0x03 => Code,
+            0x04 => CodeData,
+            0x05 => Normal,
+            0x06 => Shared,
+            0x07 => Alias,
+            0x08 => AliasCode,
+            0x09 => AliasCodeData,
+            0x0A => Ipc,
+            0x0B => Stack,
+            0x0C => ThreadLocal,
+            0x0D => Transferred,
+            0x0E => SharedTransferred,
+            0x0F => SharedCode,
+            0x10 => Inaccessible,
+            0x11 => NonSecureIpc,
+            0x12 => NonDeviceIpc,
+            0x13 => Kernel,
+            0x14 => GeneratedCode,
+            0x15 => CodeOut,
+            0x16 => Coverage,
+            0x17 => Insecure,
+            _ => return None,
+        })
+    }
+}
+
+/// The memory info as written by the kernel, see <https://switchbrew.org/wiki/SVC#MemoryInfo>
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+struct RawMemoryInfo {
+    address: u64,
+    size: u64,
+    state: u32,
+    attribute: u32,
+    permission: u32,
+    ipc_ref_count: u32,
+    device_ref_count: u32,
+    padding: u32,
+}
+
+impl RawMemoryInfo {
+    /// Fails with `InvalidEnumValue` if the kernel reports a state or a permission we don't know about
+    fn decode(&self) -> Result<MemoryInfo> {
+        let invalid = || {
+            ErrorCode::from_parts(
+                KernelErrorCode::MODULE,
+                KernelErrorCode::InvalidEnumValue as u32,
+            )
+        };
+
+        Ok(MemoryInfo {
+            address: self.address as Address,
+            size: self.size as Size,
+            state: MemoryState::from_raw(self.state).ok_or_else(invalid)?,
+            permission: MemoryPermission::try_from_bits(self.permission).map_err(|_| invalid())?,
+            // the attributes are informational, it's fine to skip the ones added in newer versions
+            attribute: MemoryAttribute::from_bits_truncate(self.attribute),
+            ipc_ref_count: self.ipc_ref_count,
+            device_ref_count: self.device_ref_count,
+        })
+    }
+}
+
+/// Memory region info returned by [query_memory]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MemoryInfo {
+    pub address: Address,
+    pub size: Size,
+    pub state: MemoryState,
+    pub permission: MemoryPermission,
+    pub attribute: MemoryAttribute,
+    pub ipc_ref_count: u32,
+    pub device_ref_count: u32,
+}
+
+impl MemoryInfo {
+    /// The address range of the region
+    pub fn range(&self) -> AddressRange {
+        (self.address, self.size)
+    }
+}
+
+/// Page flags of the queried address returned by [query_memory], not documented further
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct PageInfo(pub u32);
+
 bitflags! {
     pub struct BreakReason: u64 {
         const PANIC                  = 0;
@@ -104,8 +279,8 @@ pub enum InfoType {
     ///
     /// Used to have a different id prior to 12.1.0, so wouldn't work w/o mesosphere before 12.1.0?
     ThreadTickCount(Option<u64>),
-    /// [14.0.0+]
-    IsSvcPermitted,
+    /// [14.0.0+] Whether the svc with the specified id is permitted for the process, see [is_svc_permitted]
+    IsSvcPermitted(u32),
 
     // mesosphere extensions
     MesosphereMetaKernelVersion,
@@ -144,13 +319,33 @@ impl InfoType {
             InfoType::IsApplication =>                      (23, 0),
             InfoType::FreeThreadCount =>                    (24, 0),
             InfoType::ThreadTickCount(core_id) =>           (25, core_id.unwrap_or(-1i64 as u64)),
-            InfoType::IsSvcPermitted =>                     (26, 0),
+            InfoType::IsSvcPermitted(svc_id) =>             (26, svc_id as u64),
             InfoType::MesosphereMetaKernelVersion =>        (65000, 0),
             InfoType::MesosphereMetaIsKTraceEnabled =>      (65000, 1),
             InfoType::MesosphereMetaIsSingleStepEnabled =>  (65000, 2),
             InfoType::MesosphereCurrentProcess =>           (65001, 0),
         }
     }
+
+    /// The handle to query this info about the current process or thread with, used by [get_info_self]
+    ///
+    /// The system-wide infos (and the ones the kernel answers only for the current process anyway)
+    ///  are queried with the invalid handle (0), represented as `None`.
+    pub fn self_handle(&self) -> Option<RawHandle> {
+        match self {
+            InfoType::DebuggerAttached
+            | InfoType::ResourceLimit
+            | InfoType::IdleTickCount(_)
+            | InfoType::RandomEntropy(_)
+            | InfoType::IsSvcPermitted(_)
+            | InfoType::MesosphereMetaKernelVersion
+            | InfoType::MesosphereMetaIsKTraceEnabled
+            | InfoType::MesosphereMetaIsSingleStepEnabled
+            | InfoType::MesosphereCurrentProcess => None,
+            InfoType::ThreadTickCount(_) => Some(CURRENT_THREAD_PSEUDO_HANDLE),
+            _ => Some(CURRENT_PROCESS_PSEUDO_HANDLE),
+        }
+    }
 }
 
 #[repr(u32)]
@@ -160,6 +355,18 @@ pub enum ArbitrationType {
     WaitIfEqual = 2,
 }
 
+/// Operation performed by [control_code_memory]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum CodeMemoryOperation {
+    /// Maps the code memory at the owner (writable) address
+    MapOwner = 0,
+    /// Maps the code memory at the slave (executable) address
+    MapSlave = 1,
+    UnmapOwner = 2,
+    UnmapSlave = 3,
+}
+
 #[repr(u32)]
 pub enum SignalType {
     Signal = 0,
@@ -173,6 +380,24 @@ pub unsafe fn set_heap_size(size: Size) -> Result<Address> {
     res.result.into_result(res.heap_address)
 }
 
+/// Gets the info about the memory region containing `address`, along with its page info
+///
+/// # Safety
+///
+/// The info is a snapshot: other threads can change the memory map right after the call,
+///  so it can't be used alone to prove that accessing the region is sound
+pub unsafe fn query_memory(address: Address) -> Result<(MemoryInfo, PageInfo)> {
+    let mut info = MaybeUninit::<RawMemoryInfo>::uninit();
+
+    // the kernel only writes the RawMemoryInfo
+    let res = raw::query_memory(info.as_mut_ptr() as u64, address);
+    res.result.into_result(())?;
+
+    // the kernel has filled the info on success
+    let info = info.assume_init();
+    Ok((info.decode()?, PageInfo(res.page_info)))
+}
+
 pub unsafe fn set_memory_permission(
     (address, size): AddressRange,
     permission: MemoryPermission,
@@ -182,12 +407,159 @@ pub unsafe fn set_memory_permission(
         .into_result(())
 }
 
+/// Changes the attributes selected by `mask` to the ones in `value` for a memory range
+///
+/// This is used to make the DMA buffers uncached:
+///
+/// ```ignore
+/// set_memory_attribute(range, MemoryAttribute::UNCACHED, MemoryAttribute::UNCACHED)?;
+/// ```
+///
+/// The address and size must be page-aligned (0x1000), and `value` must be a subset of `mask`.
+///  The kernel only allows [MemoryAttribute::UNCACHED] in the `mask`.
+pub unsafe fn set_memory_attribute(
+    (address, size): AddressRange,
+    mask: MemoryAttribute,
+    value: MemoryAttribute,
+) -> Result<()> {
+    debug_assert_eq!(
+        address as usize % 0x1000,
+        0,
+        "address should be page-aligned"
+    );
+    debug_assert_eq!(size % 0x1000, 0, "size should be page-aligned");
+
+    raw::set_memory_attribute(address, size as _, mask.bits, value.bits)
+        .result
+        .into_result(())
+}
+
+/// Returns from a user-mode exception handler
+///
+/// Pass a success code to resume the execution of the thread at the point of the exception,
+///  the handler must have left the thread context in a resumable state (fixed up the cause of the fault).
+/// A failure code makes the kernel handle the exception as unhandled (usually terminating the process).
+///
+/// Must be called only from the exception handler (`__horizon_rt_exception_entry`)
+pub unsafe fn return_from_exception(result: ErrorCode) -> ! {
+    let _ = raw::return_from_exception(result);
+
+    unreachable_unchecked()
+}
+
+/// Converts an optional timeout to nanoseconds the way the kernel expects them
+fn timeout_to_nanos(timeout: Option<Duration>) -> i64 {
+    // horizon treats any negative timeout as infinite, so transform None -> -1
+    timeout
+        .and_then(|timeout| {
+            // eh, we have to do a lossy conversion from Duration to nanoseconds
+            // it's fine though, only VERY long duration (100s of years) can hit the i64 limit
+            // treat those cases as "basically infinite" (return None which is "no limit")
+            let sub_nanos = timeout.subsec_nanos() as i64;
+            let full_secs: Option<i64> = timeout.as_secs().try_into().ok();
+
+            full_secs
+                .and_then(|v| v.checked_mul(1_000_000_000))
+                .and_then(|v| v.checked_add(sub_nanos))
+        })
+        .unwrap_or(-1)
+}
+
 pub unsafe fn exit_process() -> ! {
     let _ = raw::exit_process();
 
     unreachable_unchecked()
 }
 
+/// Exits the current thread, nothing is cleaned up
+pub unsafe fn exit_thread() -> ! {
+    let _ = raw::exit_thread();
+
+    unreachable_unchecked()
+}
+
+/// Converts the duration to sleep for to nanoseconds, saturating at `i64::MAX`
+fn sleep_duration_to_nanos(duration: Duration) -> i64 {
+    // negative values are special "yield" values, so sleep for i64::MAX ns when duration is too long
+    match timeout_to_nanos(Some(duration)) {
+        -1 => i64::MAX,
+        ns => ns,
+    }
+}
+
+/// Suspends the current thread for (at least) the specified duration
+///
+/// A zero duration doesn't sleep, but yields to the other threads on the same core, see [YieldType::WithoutCoreMigration]
+pub fn sleep_thread(duration: Duration) {
+    let _ = unsafe { raw::sleep_thread(sleep_duration_to_nanos(duration) as u64) };
+}
+
+/// Ways to give up the rest of the time slice with [yield_execution], passed to `svcSleepThread` instead of the duration
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(i64)]
+pub enum YieldType {
+    /// Yields to the threads of the same priority on the same core
+    WithoutCoreMigration = 0,
+    /// Same as [YieldType::WithoutCoreMigration], but lets the other cores take over the thread
+    WithCoreMigration = -1,
+    /// Yields to any thread on the same core, even a lower priority one
+    ToAnyThread = -2,
+}
+
+/// Gives up the rest of the time slice of the current thread
+pub fn yield_execution(ty: YieldType) {
+    let _ = unsafe { raw::sleep_thread(ty as i64 as u64) };
+}
+
+/// Creates a thread in the current process running `entry(arg)`, it doesn't run until [start_thread] is called
+///
+/// `priority` is from 0 (highest) to 0x3F (lowest), `core` is the number of the core to run the thread on,
+///  or -2 to use the default core of the process.
+///
+/// The stack ending at `stack_top` must be page-aligned and must stay allocated (and not used for anything else)
+///  for the whole lifetime of the thread. The kernel doesn't allocate or free it.
+///
+/// Nothing is cleaned up when the thread exits. In a process using horizon-rt the thread should end with
+///  `__horizon_rt_exit_thread`, which runs the destructors of its thread-locals.
+pub unsafe fn create_thread(
+    entry: ThreadEntrypointFn,
+    arg: *mut u8,
+    stack_top: *mut u8,
+    priority: i32,
+    core: i32,
+) -> Result<RawHandle> {
+    debug_assert_eq!(
+        stack_top as usize % 0x1000,
+        0,
+        "stack_top should be page-aligned"
+    );
+
+    // the kernel takes both as 32-bit registers, the negative core values are special
+    let res = raw::create_thread(
+        entry as usize as u64,
+        arg,
+        stack_top,
+        priority as u32,
+        core as u32,
+    );
+
+    res.result.into_result(RawHandle(res.thread_handle))
+}
+
+/// Starts a thread created with [create_thread]
+pub fn start_thread(thread_handle: RawHandle) -> Result<()> {
+    unsafe { raw::start_thread(thread_handle.0) }
+        .result
+        .into_result(())
+}
+
+/// Gets the id of the thread, use [CURRENT_THREAD_PSEUDO_HANDLE] to get the id of the current thread
+pub fn get_thread_id(thread_handle: RawHandle) -> Result<u64> {
+    let res = unsafe { raw::get_thread_id(thread_handle.0) };
+
+    res.result.into_result(res.thread_id)
+}
+
 pub fn close_handle(handle: RawHandle) -> Result<()> {
     unsafe { raw::close_handle(handle.0).result.into_result(()) }
 }
@@ -229,6 +601,167 @@ pub fn send_sync_request_with_user_buffer(buffer: &[u8], session_handle: RawHand
     .into_result(())
 }
 
+/// Sends an IPC request from a user-supplied buffer without waiting for the reply
+///
+/// Returns an event that gets signaled when the server has written the reply to `buffer`.
+///  The event handle should be closed after that.
+///
+/// `buffer` must be 0x1000-aligned
+///
+/// # Safety
+///
+/// The kernel writes the reply to `buffer` at an arbitrary point in the future,
+///  so it must stay alive and untouched until the event is signaled
+pub unsafe fn send_async_request_with_user_buffer(
+    buffer: &mut [u8],
+    session_handle: RawHandle,
+) -> Result<RawHandle> {
+    debug_assert_eq!(
+        buffer.as_ptr() as usize % 0x1000,
+        0,
+        "buffer should be page-aligned"
+    );
+
+    let res = raw::send_async_request_with_user_buffer(
+        buffer.as_mut_ptr(),
+        buffer.len() as u64,
+        session_handle.0,
+    );
+
+    res.result.into_result(RawHandle(res.event_handle))
+}
+
+/// Maximum number of handles [wait_synchronization] can wait on at once
+pub const MAX_WAIT_HANDLES: usize = 0x40;
+
+/// Fails with `OutOfRange` (same as the kernel would) when there are too many handles to wait on
+fn check_wait_handle_count(count: usize) -> Result<()> {
+    if count > MAX_WAIT_HANDLES {
+        return Err(ErrorCode::from_parts(
+            KernelErrorCode::MODULE,
+            KernelErrorCode::OutOfRange as u32,
+        ));
+    }
+    Ok(())
+}
+
+/// Waits for one of the `handles` to get signaled, returns its index in `handles`
+///
+/// At most [MAX_WAIT_HANDLES] handles can be waited on. Fails with `Cancelled` if the wait was interrupted
+///  with [cancel_synchronization].
+pub fn wait_synchronization(handles: &[RawHandle], timeout: Option<Duration>) -> Result<usize> {
+    // the count is passed as u32, make sure a huge slice is not truncated into a valid one
+    check_wait_handle_count(handles.len())?;
+    let timeout_ns = timeout_to_nanos(timeout);
+
+    let res = unsafe {
+        raw::wait_synchronization(
+            handles.as_ptr() as *const u8,
+            handles.len() as u32,
+            timeout_ns as u64,
+        )
+    };
+
+    res.result.into_result(res.handle_index as usize)
+}
+
+/// Interrupts the [wait_synchronization] the thread is blocked in, making it fail with `Cancelled`
+///
+/// If the thread is not waiting, its next wait is cancelled instead.
+pub fn cancel_synchronization(thread_handle: RawHandle) -> Result<()> {
+    let res = unsafe { raw::cancel_synchronization(thread_handle.0) };
+
+    res.result.into_result(())
+}
+
+/// Creates an event, returns its writable and readable handles (in this order)
+///
+/// The writable handle is the one [signal_event] and [clear_event] are called on, the readable one is what
+///  is passed to [wait_synchronization] (and can also be cleared with [reset_signal]). Usually the readable
+///  handle is given to the other side, while the writable one stays with the code that signals.
+///  Both handles should be closed with [close_handle].
+///
+/// ```ignore
+/// let (writable, readable) = create_event()?;
+///
+/// // on another thread
+/// signal_event(writable)?;
+///
+/// wait_synchronization(&[readable], None)?;
+/// // the event stays signaled until cleared
+/// reset_signal(readable)?;
+/// ```
+pub fn create_event() -> Result<(RawHandle, RawHandle)> {
+    let res = unsafe { raw::create_event() };
+
+    res.result.into_result((
+        RawHandle(res.writable_event_handle),
+        RawHandle(res.readable_event_handle),
+    ))
+}
+
+/// Signals the event through its writable handle, waking up the threads waiting on the readable one
+///
+/// The event stays signaled until cleared with [clear_event] or [reset_signal].
+pub fn signal_event(writable_event: RawHandle) -> Result<()> {
+    let res = unsafe { raw::signal_event(writable_event.0) };
+
+    res.result.into_result(())
+}
+
+/// Clears the event through its writable handle
+pub fn clear_event(writable_event: RawHandle) -> Result<()> {
+    let res = unsafe { raw::clear_event(writable_event.0) };
+
+    res.result.into_result(())
+}
+
+/// Clears the signaled state of the readable event handle (or of a process)
+///
+/// Fails with `InvalidState` if it was not signaled, unlike [clear_event].
+pub fn reset_signal(handle: RawHandle) -> Result<()> {
+    let res = unsafe { raw::reset_signal(handle.0) };
+
+    res.result.into_result(())
+}
+
+/// Sends the reply in the IPC buffer to `reply_target` (if any), then waits for a request on one of `handles`
+///
+/// Returns the index of the handle in `handles` that received a request (or got signaled, for ports).
+///
+/// Fails with `SessionClosed` when the client of the reply target or of the receiving session
+///  has closed its handle, the server should then close its side of the session.
+///  At most [MAX_WAIT_HANDLES] handles can be waited on, same as with [wait_synchronization].
+pub fn reply_and_receive(
+    handles: &[RawHandle],
+    reply_target: Option<RawHandle>,
+    timeout: Option<Duration>,
+) -> Result<usize> {
+    check_wait_handle_count(handles.len())?;
+    let timeout_ns = timeout_to_nanos(timeout);
+
+    let res = unsafe {
+        raw::reply_and_receive(
+            handles.as_ptr() as *const u8,
+            handles.len() as u32,
+            reply_target.map_or(0, |h| h.0),
+            timeout_ns as u64,
+        )
+    };
+
+    res.result.into_result(res.handle_index as usize)
+}
+
+/// Accepts a pending connection on the server port, returning the server side of the new session
+///
+/// Should be called once [reply_and_receive] (or [wait_synchronization]) reports the port as signaled,
+///  fails with `NotFound` if there is no client waiting to connect.
+pub fn accept_session(port: RawHandle) -> Result<RawHandle> {
+    let res = unsafe { raw::accept_session(port.0) };
+
+    res.result.into_result(RawHandle(res.server_session_handle))
+}
+
 pub unsafe fn r#break(reason: BreakReason, buffer_ptr: *const u8, size: usize) -> Result<()> {
     raw::r#break(reason.bits, buffer_ptr as usize as _, size as _)
         .result
@@ -249,6 +782,119 @@ pub fn get_info(info_type: InfoType, handle: Option<RawHandle>) -> Result<u64> {
     res.result.into_result(res.info)
 }
 
+/// Like [get_info], but queries the current process or thread, depending on the info type
+///
+/// See [InfoType::self_handle] for the handle used
+pub fn get_info_self(info_type: InfoType) -> Result<u64> {
+    let handle = info_type.self_handle();
+    get_info(info_type, handle)
+}
+
+/// Returns the size of the system resource (kernel memory pool) of the current process
+///
+/// [3.0.0+], fails with `InvalidEnumValue` on older firmware
+pub fn system_resource_size_total() -> Result<usize> {
+    get_info_self(InfoType::SystemResourceSizeTotal).map(|v| v as usize)
+}
+
+/// Returns how much of the system resource of the current process is in use
+///
+/// [3.0.0+], fails with `InvalidEnumValue` on older firmware
+pub fn system_resource_size_used() -> Result<usize> {
+    get_info_self(InfoType::SystemResourceSizeUsed).map(|v| v as usize)
+}
+
+/// Returns the total memory available to the current process, not counting the system resource
+///
+/// [6.0.0+], fails with `InvalidEnumValue` on older firmware
+pub fn total_non_system_memory_size() -> Result<usize> {
+    get_info_self(InfoType::TotalNonSystemMemorySize).map(|v| v as usize)
+}
+
+/// Returns the memory used by the current process, not counting the system resource
+///
+/// [6.0.0+], fails with `InvalidEnumValue` on older firmware
+pub fn used_non_system_memory_size() -> Result<usize> {
+    get_info_self(InfoType::UsedNonSystemMemorySize).map(|v| v as usize)
+}
+
+/// Frequency of the system counter, in ticks per second
+pub const TICKS_PER_SECOND: u64 = 19_200_000;
+
+/// Converts a tick count, like the one returned by [thread_tick_count], to a [Duration]
+pub fn ticks_to_duration(ticks: u64) -> Duration {
+    let nanos = (ticks % TICKS_PER_SECOND) * 1_000_000_000 / TICKS_PER_SECOND;
+    Duration::new(ticks / TICKS_PER_SECOND, nanos as u32)
+}
+
+/// Returns the value of the system counter, counting [TICKS_PER_SECOND] ticks per second since boot
+pub fn get_system_tick() -> u64 {
+    // SAFETY: this syscall only reads the counter
+    unsafe { raw::get_system_tick() }.ticks
+}
+
+/// A point in time read from the system counter, monotonic like `std::time::Instant`
+///
+/// Good for timeouts and simple profiling, the resolution is a bit over 52ns.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SystemTick(pub u64);
+
+impl SystemTick {
+    #[inline]
+    pub fn now() -> Self {
+        Self(get_system_tick())
+    }
+
+    /// Ticks between `earlier` and this point, zero if `earlier` is actually later
+    #[inline]
+    pub fn ticks_since(&self, earlier: SystemTick) -> u64 {
+        self.0.saturating_sub(earlier.0)
+    }
+
+    /// Same as [ticks_since](Self::ticks_since), converted with [ticks_to_duration]
+    #[inline]
+    pub fn duration_since(&self, earlier: SystemTick) -> Duration {
+        ticks_to_duration(self.ticks_since(earlier))
+    }
+
+    /// Ticks passed since this point
+    #[inline]
+    pub fn elapsed_ticks(&self) -> u64 {
+        Self::now().ticks_since(*self)
+    }
+
+    /// Time passed since this point
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        ticks_to_duration(self.elapsed_ticks())
+    }
+}
+
+/// Returns the CPU ticks the thread spent running on the core, or on all the cores when `core` is `None`
+///
+/// Use [CURRENT_THREAD_PSEUDO_HANDLE] for the current thread and [ticks_to_duration] to get the CPU time.
+///
+/// Before 12.1.0 the kernel exposed this info under a different id, so this fails with `InvalidEnumValue`
+///  there unless running under mesosphere.
+pub fn thread_tick_count(thread_handle: RawHandle, core: Option<u8>) -> Result<u64> {
+    get_info(
+        InfoType::ThreadTickCount(core.map(u64::from)),
+        Some(thread_handle),
+    )
+}
+
+/// Checks whether the current process is allowed to call the svc with the specified id
+///
+/// Useful to fail cleanly instead of getting killed by the kernel when calling a privileged syscall.
+///
+/// [14.0.0+], fails with `InvalidEnumValue` on older firmware. The svc id is passed as the info subtype,
+///  and the kernel may refuse to answer for some ids (as of 14.0.0 only `SynchronizePreemptionState` (0x36)
+///  is accepted), failing with `InvalidCombination`.
+pub fn is_svc_permitted(svc_id: u32) -> Result<bool> {
+    // the handle must be the invalid one (0) for this query
+    get_info(InfoType::IsSvcPermitted(svc_id), None).map(|v| v != 0)
+}
+
 pub unsafe fn map_physical_memory((address, size): AddressRange) -> Result<()> {
     raw::map_physical_memory(address, size as _)
         .result
@@ -261,26 +907,326 @@ pub unsafe fn unmap_physical_memory((address, size): AddressRange) -> Result<()>
         .into_result(())
 }
 
+/// [5.0.0+] Maps physical memory from the unsafe pool, on top of the process memory limit
+///
+/// The total size of the unsafe mappings is capped by [set_unsafe_limit]
+pub unsafe fn map_physical_memory_unsafe((address, size): AddressRange) -> Result<()> {
+    raw::map_physical_memory_unsafe(address, size as _)
+        .result
+        .into_result(())
+}
+
+/// [5.0.0+] Unmaps memory mapped with [map_physical_memory_unsafe]
+pub unsafe fn unmap_physical_memory_unsafe((address, size): AddressRange) -> Result<()> {
+    raw::unmap_physical_memory_unsafe(address, size as _)
+        .result
+        .into_result(())
+}
+
+/// [5.0.0+] Sets how much memory (in bytes) can be mapped with [map_physical_memory_unsafe]
+///
+/// This is the way for a process to use more memory than its resource limit (the applet budget) allows.
+///  The limit is system-wide, the memory is taken from the pool left unused by the other processes.
+///
+/// Fails with:
+///  - `InvalidSize` if `size` is not page-aligned
+///  - `OutOfRange` if `size` exceeds the free memory of the system pool
+///  - `LimitReached` if more than `size` is already mapped
+///
+/// The syscall must be allowed in the kernel capabilities (NPDM) of the process, which is normally
+///  not the case for applications. Calling it without the permission gets the process killed,
+///  and it can't be probed beforehand: [is_svc_permitted] only answers for `SynchronizePreemptionState` (0x36).
+pub fn set_unsafe_limit(size: Size) -> Result<()> {
+    let size = check_unsafe_limit_size(size)?;
+
+    // SAFETY: the syscall only changes the kernel bookkeeping
+    unsafe { raw::set_unsafe_limit(size) }
+        .result
+        .into_result(())
+}
+
+/// Fails with `InvalidSize` (same as the kernel would) when the limit is not page-aligned
+fn check_unsafe_limit_size(size: Size) -> Result<u64> {
+    if !size.is_multiple_of(0x1000) {
+        return Err(ErrorCode::from_parts(
+            KernelErrorCode::MODULE,
+            KernelErrorCode::InvalidSize as u32,
+        ));
+    }
+    Ok(size as u64)
+}
+
+/// Creates a code memory object from a page-aligned region of the current process memory
+///
+/// [4.0.0+] Together with [control_code_memory] this is the way to do JIT: the memory is mapped
+///  as RW at the owner address to write the code and as RX at the slave address to execute it.
+///  Requires the syscalls to be allowed in the kernel capabilities (NPDM) of the process.
+pub unsafe fn create_code_memory((address, size): AddressRange) -> Result<RawHandle> {
+    debug_assert_eq!(
+        address as usize % 0x1000,
+        0,
+        "address should be page-aligned"
+    );
+    debug_assert_eq!(size % 0x1000, 0, "size should be page-aligned");
+
+    let res = raw::create_code_memory(address, size as _);
+
+    res.result.into_result(RawHandle(res.code_memory_handle))
+}
+
+/// Maps or unmaps the code memory created by [create_code_memory]
+///
+/// [4.0.0+] The owner mapping must be `READ | WRITE`, the slave one either `READ` or `READ | EXECUTE`.
+///  For unmap operations the permission is ignored, no permission is passed to the kernel.
+pub unsafe fn control_code_memory(
+    code_memory: RawHandle,
+    operation: CodeMemoryOperation,
+    (address, size): AddressRange,
+    permission: MemoryPermission,
+) -> Result<()> {
+    debug_assert_eq!(
+        address as usize % 0x1000,
+        0,
+        "address should be page-aligned"
+    );
+    debug_assert_eq!(size % 0x1000, 0, "size should be page-aligned");
+
+    let (operation, permission) = code_memory_arguments(operation, permission);
+
+    raw::control_code_memory(code_memory.0, operation, address, size as _, permission)
+        .result
+        .into_result(())
+}
+
+/// The operation and permission values passed to `svcControlCodeMemory`
+fn code_memory_arguments(
+    operation: CodeMemoryOperation,
+    permission: MemoryPermission,
+) -> (u32, u32) {
+    let permission = match operation {
+        CodeMemoryOperation::MapOwner | CodeMemoryOperation::MapSlave => permission,
+        CodeMemoryOperation::UnmapOwner | CodeMemoryOperation::UnmapSlave => {
+            MemoryPermission::empty()
+        }
+    };
+
+    (operation as u32, permission.bits)
+}
+
+/// Creates a shared memory object of `size` bytes, to be shared with another process (usually a sysmodule)
+///
+/// The size must be page-aligned (0x1000). `local` is the permission this process maps the memory with,
+///  `remote` is the one for the other process. Depending on the permissions, the syscall may need to be
+///  allowed in the kernel capabilities (NPDM) of the process.
+pub fn create_shared_memory(
+    size: Size,
+    local: MemoryPermission,
+    remote: MemoryPermission,
+) -> Result<RawHandle> {
+    debug_assert_ne!(size, 0, "size should not be zero");
+    debug_assert_eq!(size % 0x1000, 0, "size should be page-aligned");
+
+    let res = unsafe { raw::create_shared_memory(size as u64, local.bits, remote.bits) };
+
+    res.result.into_result(RawHandle(res.shared_memory_handle))
+}
+
+/// Maps the shared memory at a free page-aligned address range of the current process
+///
+/// The size must match the size of the shared memory object, `permission` must be the one it was created with
+///  for this side (the local one for the creator, the remote one for the other process).
+pub unsafe fn map_shared_memory(
+    handle: RawHandle,
+    (address, size): AddressRange,
+    permission: MemoryPermission,
+) -> Result<()> {
+    debug_assert_eq!(
+        address as usize % 0x1000,
+        0,
+        "address should be page-aligned"
+    );
+    debug_assert_eq!(size % 0x1000, 0, "size should be page-aligned");
+
+    raw::map_shared_memory(handle.0, address, size as _, permission.bits)
+        .result
+        .into_result(())
+}
+
+/// Unmaps the shared memory mapped with [map_shared_memory], the range must be the same
+pub unsafe fn unmap_shared_memory(handle: RawHandle, (address, size): AddressRange) -> Result<()> {
+    debug_assert_eq!(
+        address as usize % 0x1000,
+        0,
+        "address should be page-aligned"
+    );
+    debug_assert_eq!(size % 0x1000, 0, "size should be page-aligned");
+
+    raw::unmap_shared_memory(handle.0, address, size as _)
+        .result
+        .into_result(())
+}
+
+/// Maps `size` bytes of the process memory at `src` to `dst` as code, like the loader does with the NSOs/NROs
+///
+/// The addresses are in the address space of `process`, which can be [CURRENT_PROCESS_PSEUDO_HANDLE].
+///  `dst` must be a free range inside the alias code (ASLR) region of the process, `src` should be normal memory,
+///  like the heap or memory from [map_physical_memory]. Everything must be page-aligned.
+///
+/// After the call the memory at `src` is inaccessible, and `dst` is mapped read-only:
+///  use [set_process_memory_permission] to make parts of it executable or writable.
+pub unsafe fn map_process_code_memory(
+    process: RawHandle,
+    dst: u64,
+    src: u64,
+    size: u64,
+) -> Result<()> {
+    debug_assert_eq!(dst % 0x1000, 0, "dst should be page-aligned");
+    debug_assert_eq!(src % 0x1000, 0, "src should be page-aligned");
+    debug_assert_eq!(size % 0x1000, 0, "size should be page-aligned");
+
+    raw::map_process_code_memory(process.0, dst as _, src as _, size)
+        .result
+        .into_result(())
+}
+
+/// Undoes [map_process_code_memory], the arguments must be the same. The memory at `src` becomes accessible again
+pub unsafe fn unmap_process_code_memory(
+    process: RawHandle,
+    dst: u64,
+    src: u64,
+    size: u64,
+) -> Result<()> {
+    debug_assert_eq!(dst % 0x1000, 0, "dst should be page-aligned");
+    debug_assert_eq!(src % 0x1000, 0, "src should be page-aligned");
+    debug_assert_eq!(size % 0x1000, 0, "size should be page-aligned");
+
+    raw::unmap_process_code_memory(process.0, dst as _, src as _, size)
+        .result
+        .into_result(())
+}
+
+/// Changes the permission of memory mapped with [map_process_code_memory] in the address space of `process`
+///
+/// The range should be page-aligned. Write and execute can't be combined.
+pub unsafe fn set_process_memory_permission(
+    process: RawHandle,
+    address: u64,
+    size: u64,
+    permission: MemoryPermission,
+) -> Result<()> {
+    debug_assert_eq!(address % 0x1000, 0, "address should be page-aligned");
+    debug_assert_eq!(size % 0x1000, 0, "size should be page-aligned");
+
+    raw::set_process_memory_permission(process.0, address as _, size, permission.bits)
+        .result
+        .into_result(())
+}
+
+/// Bit set in the mutex tag when there are threads waiting for the mutex
+///
+/// The rest of the tag is the handle of the owning thread, see [arbitrate_lock]
+pub const HANDLE_WAIT_MASK: u32 = 0x40000000;
+
+/// Splits the mutex tag into the handle of the owning thread and whether there are waiters
+///
+/// A zero tag means that the mutex is not locked.
+pub const fn decode_mutex_tag(tag: u32) -> (RawHandle, bool) {
+    (
+        RawHandle(tag & !HANDLE_WAIT_MASK),
+        tag & HANDLE_WAIT_MASK != 0,
+    )
+}
+
+/// Waits for the kernel-arbitrated mutex at `tag_address` to be released by its owner `owner_thread`
+///
+/// The kernel-arbitrated mutex is a 32-bit tag word: zero when unlocked, otherwise the handle
+///  of the owning thread, with [HANDLE_WAIT_MASK] set if other threads are waiting for it. The handle
+///  is the real handle of the thread, not [CURRENT_THREAD_PSEUDO_HANDLE]. Same as libnx, locking goes as follows:
+///
+/// 1. Try to change the tag from 0 to `self_thread`, the mutex is locked if it succeeds
+/// 2. Otherwise set [HANDLE_WAIT_MASK] in the tag (with a compare-exchange, starting over if the tag changed)
+/// 3. Call this function with the owner taken from the tag. The kernel checks that the tag is still
+///    `owner_thread | HANDLE_WAIT_MASK` and puts the thread to sleep until the mutex is handed over to it.
+///    On success the tag holds `self_thread` (with [HANDLE_WAIT_MASK] if there are more waiters).
+///
+/// Unlocking tries to change the tag from `self_thread` to 0, calling [arbitrate_unlock] if that fails
+///  because of the waiters bit. The lock never busy-waits, the kernel keeps the queue of the waiters.
+pub unsafe fn arbitrate_lock(
+    owner_thread: RawHandle,
+    tag_address: *const AtomicU32,
+    self_thread: RawHandle,
+) -> Result<()> {
+    raw::arbitrate_lock(owner_thread.0, tag_address as *const u8, self_thread.0)
+        .result
+        .into_result(())
+}
+
+/// Releases the kernel-arbitrated mutex at `tag_address`, handing it over to one of the waiters
+///
+/// The kernel writes the handle of the new owner to the tag (or 0 if there were no waiters after all).
+///  Should only be called by the owner, when [HANDLE_WAIT_MASK] is set in the tag.
+pub unsafe fn arbitrate_unlock(tag_address: *const AtomicU32) -> Result<()> {
+    raw::arbitrate_unlock(tag_address as *const u8)
+        .result
+        .into_result(())
+}
+
+/// Releases the kernel-arbitrated mutex at `tag_address` and waits on the condition variable `key_address`
+///
+/// When signaled, the thread goes back to waiting for the mutex, so it holds the mutex again when
+///  the call returns. Fails with `TimedOut` if the timeout expired, the mutex is *not* held then
+///  and has to be locked again by the caller.
+///
+/// Unlike [wait_for_address], the mutex must be in the kernel format (see [arbitrate_lock])
+pub unsafe fn wait_process_wide_key_atomic(
+    key_address: *const AtomicU32,
+    tag_address: *const AtomicU32,
+    self_thread: RawHandle,
+    timeout: Option<Duration>,
+) -> Result<()> {
+    let (key_address, tag_address, self_thread, timeout_ns) =
+        wait_process_wide_key_arguments(key_address, tag_address, self_thread, timeout);
+
+    raw::wait_process_wide_key_atomic(key_address, tag_address, self_thread, timeout_ns)
+        .result
+        .into_result(())
+}
+
+fn wait_process_wide_key_arguments(
+    key_address: *const AtomicU32,
+    tag_address: *const AtomicU32,
+    self_thread: RawHandle,
+    timeout: Option<Duration>,
+) -> (Address, Address, u32, u64) {
+    (
+        key_address as Address,
+        tag_address as Address,
+        self_thread.0,
+        timeout_to_nanos(timeout) as u64,
+    )
+}
+
+/// Wakes up to `count` threads waiting on the condition variable `key_address`, all of them if `count` is -1
+///
+/// The woken threads are moved to wait for their mutex, instead of racing for it
+pub unsafe fn signal_process_wide_key(key_address: *const AtomicU32, count: i32) {
+    let (key_address, count) = signal_process_wide_key_arguments(key_address, count);
+
+    // the syscall doesn't fail
+    let _ = raw::signal_process_wide_key(key_address, count);
+}
+
+fn signal_process_wide_key_arguments(key_address: *const AtomicU32, count: i32) -> (Address, u32) {
+    (key_address as Address, count as u32)
+}
+
 pub unsafe fn wait_for_address(
     address: *const AtomicI32,
     arbitration_type: ArbitrationType,
     expected_value: i32,
     timeout: Option<Duration>,
 ) -> Result<()> {
-    // horizon treats any negative timeout as infinite, so transform None -> -1
-    let timeout_ns = timeout
-        .and_then(|timeout| {
-            // eh, we have to do a lossy conversion from Duration to nanoseconds
-            // it's fine though, only VERY long duration (100s of years) can hit the i64 limit
-            // treat those cases as "basically infinite" (return None which is "no limit")
-            let sub_nanos = timeout.subsec_nanos() as i64;
-            let full_secs: Option<i64> = timeout.as_secs().try_into().ok();
-
-            full_secs
-                .and_then(|v| v.checked_mul(1_000_000_000))
-                .and_then(|v| v.checked_add(sub_nanos))
-        })
-        .unwrap_or(-1);
+    let timeout_ns = timeout_to_nanos(timeout);
 
     raw::wait_for_address(
         address as *const u8,
@@ -307,3 +1253,288 @@ pub unsafe fn signal_to_address(
     .result
     .into_result(())
 }
+
+/// Calls the secure monitor with the values of x0..x7 in `args`, which are overwritten with the returned ones
+///
+/// Only available to the processes that have the syscall enabled in their NPDM (and only used by `spl` normally),
+///  see <https://switchbrew.org/wiki/SMC>
+pub unsafe fn call_secure_monitor(args: &mut [u64; 8]) {
+    raw::call_secure_monitor(args)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        check_unsafe_limit_size, check_wait_handle_count, code_memory_arguments, decode_mutex_tag,
+        signal_process_wide_key_arguments, sleep_duration_to_nanos, ticks_to_duration,
+        wait_process_wide_key_arguments, CodeMemoryOperation, InfoType, InvalidPermission,
+        MemoryAttribute, MemoryInfo, MemoryPermission, MemoryState, RawHandle, RawMemoryInfo,
+        SystemTick, YieldType, CURRENT_PROCESS_PSEUDO_HANDLE, CURRENT_THREAD_PSEUDO_HANDLE,
+        HANDLE_WAIT_MASK, MAX_WAIT_HANDLES,
+    };
+    use core::sync::atomic::AtomicU32;
+    use core::time::Duration;
+    use horizon_error::KernelErrorCode;
+
+    #[test]
+    fn permission_from_bits() {
+        assert_eq!(
+            MemoryPermission::try_from_bits(0b101),
+            Ok(MemoryPermission::READ | MemoryPermission::EXECUTE)
+        );
+        assert_eq!(
+            MemoryPermission::try_from_bits(0b11 | 1 << 8),
+            Err(InvalidPermission(1 << 8))
+        );
+        assert_eq!(MemoryPermission::rwx().bits(), 0b111);
+    }
+
+    #[test]
+    fn memory_attribute_encoding() {
+        assert_eq!(MemoryAttribute::UNCACHED.bits(), 8);
+        assert_eq!(
+            (MemoryAttribute::LOCKED
+                | MemoryAttribute::IPC_LOCKED
+                | MemoryAttribute::DEVICE_SHARED)
+                .bits(),
+            0b111
+        );
+        // the attribute reported by query_memory decodes to the same flags
+        assert_eq!(
+            MemoryAttribute::from_bits(0b1100),
+            Some(MemoryAttribute::DEVICE_SHARED | MemoryAttribute::UNCACHED)
+        );
+    }
+
+    #[test]
+    fn memory_info_decode() {
+        // laid out the way the kernel writes it
+        let mut buffer = [0u8; 0x28];
+        buffer[0x00..0x08].copy_from_slice(&0x7100000000u64.to_le_bytes());
+        buffer[0x08..0x10].copy_from_slice(&0x5000u64.to_le_bytes());
+        buffer[0x10..0x14].copy_from_slice(&0x03u32.to_le_bytes());
+        buffer[0x14..0x18].copy_from_slice(&0b1_0001u32.to_le_bytes());
+        buffer[0x18..0x1c].copy_from_slice(&0b101u32.to_le_bytes());
+        buffer[0x1c..0x20].copy_from_slice(&2u32.to_le_bytes());
+        buffer[0x20..0x24].copy_from_slice(&1u32.to_le_bytes());
+        assert_eq!(core::mem::size_of::<RawMemoryInfo>(), buffer.len());
+
+        let mut raw: RawMemoryInfo =
+            unsafe { core::ptr::read_unaligned(buffer.as_ptr() as *const RawMemoryInfo) };
+        assert_eq!(
+            raw.decode(),
+            Ok(MemoryInfo {
+                address: 0x7100000000usize as _,
+                size: 0x5000,
+                state: MemoryState::Code,
+                permission: MemoryPermission::READ | MemoryPermission::EXECUTE,
+                attribute: MemoryAttribute::LOCKED | MemoryAttribute::PERMISSION_LOCKED,
+                ipc_ref_count: 2,
+                device_ref_count: 1,
+            })
+        );
+
+        // unknown attributes are dropped, unknown states and permissions are rejected
+        raw.attribute = 1 << 20;
+        assert_eq!(raw.decode().unwrap().attribute, MemoryAttribute::empty());
+        raw.state = 0x100;
+        assert_eq!(
+            raw.decode().unwrap_err().get_description(),
+            KernelErrorCode::InvalidEnumValue as u32
+        );
+        raw.state = MemoryState::Insecure as u32;
+        raw.permission = 1 << 8;
+        assert_eq!(
+            raw.decode().unwrap_err().get_description(),
+            KernelErrorCode::InvalidEnumValue as u32
+        );
+    }
+
+    #[test]
+    fn self_handle_selection() {
+        assert_eq!(
+            InfoType::HeapRegionAddress.self_handle(),
+            Some(CURRENT_PROCESS_PSEUDO_HANDLE)
+        );
+        assert_eq!(
+            InfoType::TotalMemorySize.self_handle(),
+            Some(CURRENT_PROCESS_PSEUDO_HANDLE)
+        );
+        assert_eq!(
+            InfoType::ThreadTickCount(None).self_handle(),
+            Some(CURRENT_THREAD_PSEUDO_HANDLE)
+        );
+        // the system-wide ones take the invalid handle
+        assert_eq!(InfoType::RandomEntropy(0).self_handle(), None);
+        assert_eq!(InfoType::IdleTickCount(Some(1)).self_handle(), None);
+        assert_eq!(InfoType::IsSvcPermitted(0x36).self_handle(), None);
+    }
+
+    #[test]
+    fn memory_size_infos() {
+        // queried by system_resource_size_total, system_resource_size_used,
+        //  total_non_system_memory_size and used_non_system_memory_size
+        for (info, encoding) in [
+            (InfoType::SystemResourceSizeTotal, (16, 0)),
+            (InfoType::SystemResourceSizeUsed, (17, 0)),
+            (InfoType::TotalNonSystemMemorySize, (21, 0)),
+            (InfoType::UsedNonSystemMemorySize, (22, 0)),
+        ] {
+            assert_eq!(info.self_handle(), Some(CURRENT_PROCESS_PSEUDO_HANDLE));
+            assert_eq!(info.into_type_and_subtype(), encoding);
+        }
+    }
+
+    #[test]
+    fn code_memory_encoding() {
+        let rw = MemoryPermission::READ | MemoryPermission::WRITE;
+        let rx = MemoryPermission::READ | MemoryPermission::EXECUTE;
+
+        assert_eq!(
+            code_memory_arguments(CodeMemoryOperation::MapOwner, rw),
+            (0, 0b011)
+        );
+        assert_eq!(
+            code_memory_arguments(CodeMemoryOperation::MapSlave, rx),
+            (1, 0b101)
+        );
+        // the permission doesn't matter for the unmaps
+        assert_eq!(
+            code_memory_arguments(CodeMemoryOperation::UnmapOwner, rw),
+            (2, 0)
+        );
+        assert_eq!(
+            code_memory_arguments(CodeMemoryOperation::UnmapSlave, rx),
+            (3, 0)
+        );
+    }
+
+    #[test]
+    fn is_svc_permitted_encoding() {
+        // the svc id goes into the subtype
+        assert_eq!(
+            InfoType::IsSvcPermitted(0x36).into_type_and_subtype(),
+            (26, 0x36)
+        );
+    }
+
+    #[test]
+    fn thread_tick_count_encoding() {
+        assert_eq!(
+            InfoType::ThreadTickCount(Some(2)).into_type_and_subtype(),
+            (25, 2)
+        );
+        // the total is requested with core id -1
+        assert_eq!(
+            InfoType::ThreadTickCount(None).into_type_and_subtype(),
+            (25, u64::MAX)
+        );
+
+        assert_eq!(ticks_to_duration(19_200_000), Duration::from_secs(1));
+        assert_eq!(ticks_to_duration(28_800_000), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn system_tick_arithmetic() {
+        let earlier = SystemTick(1_000);
+        let later = SystemTick(1_000 + 9_600_000);
+
+        assert!(earlier < later);
+        assert_eq!(later.ticks_since(earlier), 9_600_000);
+        assert_eq!(later.duration_since(earlier), Duration::from_millis(500));
+        // the wrong order doesn't underflow
+        assert_eq!(earlier.ticks_since(later), 0);
+    }
+
+    #[test]
+    fn mutex_tag() {
+        assert_eq!(decode_mutex_tag(0), (RawHandle(0), false));
+        assert_eq!(decode_mutex_tag(0x1234), (RawHandle(0x1234), false));
+        assert_eq!(
+            decode_mutex_tag(0x1234 | HANDLE_WAIT_MASK),
+            (RawHandle(0x1234), true)
+        );
+    }
+
+    #[test]
+    fn process_wide_key_encoding() {
+        let key = AtomicU32::new(0);
+        let tag = AtomicU32::new(0x1234);
+
+        let (key_address, tag_address, self_thread, timeout) = wait_process_wide_key_arguments(
+            &key,
+            &tag,
+            RawHandle(0x1234),
+            Some(Duration::from_micros(1500)),
+        );
+        assert_eq!(key_address, &key as *const AtomicU32 as *const u8);
+        assert_eq!(tag_address, &tag as *const AtomicU32 as *const u8);
+        assert_eq!(self_thread, 0x1234);
+        assert_eq!(timeout, 1_500_000);
+
+        // no timeout is passed as -1
+        let (_, _, _, timeout) =
+            wait_process_wide_key_arguments(&key, &tag, RawHandle(0x1234), None);
+        assert_eq!(timeout, u64::MAX);
+
+        assert_eq!(
+            signal_process_wide_key_arguments(&key, 1),
+            (&key as *const AtomicU32 as *const u8, 1)
+        );
+        // waking all the waiters
+        assert_eq!(signal_process_wide_key_arguments(&key, -1).1, 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn wait_handle_count() {
+        assert_eq!(check_wait_handle_count(0), Ok(()));
+        assert_eq!(check_wait_handle_count(MAX_WAIT_HANDLES), Ok(()));
+
+        let err = check_wait_handle_count(MAX_WAIT_HANDLES + 1).unwrap_err();
+        assert_eq!(
+            err.try_as::<KernelErrorCode>(),
+            Some(KernelErrorCode::OutOfRange)
+        );
+        // would be truncated to 0 if passed to the kernel as is
+        assert!(check_wait_handle_count(1 << 32).is_err());
+    }
+
+    #[test]
+    fn unsafe_limit_encoding() {
+        assert_eq!(check_unsafe_limit_size(0x20_0000), Ok(0x20_0000));
+        assert_eq!(check_unsafe_limit_size(0), Ok(0));
+        assert_eq!(
+            check_unsafe_limit_size(0x1001)
+                .unwrap_err()
+                .get_description(),
+            KernelErrorCode::InvalidSize as u32
+        );
+    }
+
+    #[test]
+    fn sleep_nanos_conversion() {
+        // zero is a yield, not an infinite sleep
+        assert_eq!(sleep_duration_to_nanos(Duration::ZERO), 0);
+        assert_eq!(sleep_duration_to_nanos(Duration::from_nanos(1)), 1);
+        assert_eq!(
+            sleep_duration_to_nanos(Duration::from_millis(1500)),
+            1_500_000_000
+        );
+
+        // the longest representable sleep
+        assert_eq!(
+            sleep_duration_to_nanos(Duration::from_nanos(i64::MAX as u64)),
+            i64::MAX
+        );
+        // longer ones saturate instead of wrapping into the yield values
+        assert_eq!(
+            sleep_duration_to_nanos(Duration::from_nanos(i64::MAX as u64 + 1)),
+            i64::MAX
+        );
+        assert_eq!(sleep_duration_to_nanos(Duration::MAX), i64::MAX);
+
+        assert_eq!(YieldType::WithoutCoreMigration as i64 as u64, 0);
+        assert_eq!(YieldType::WithCoreMigration as i64 as u64, u64::MAX);
+        assert_eq!(YieldType::ToAnyThread as i64 as u64, u64::MAX - 1);
+    }
+}
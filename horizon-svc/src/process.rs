@@ -0,0 +1,145 @@
+//! Wrappers around the process lifecycle syscalls, needed to implement a process loader or manager
+//!
+//! All of these require the syscalls to be allowed in the kernel capabilities of the process (NPDM),
+//!  which is normally the case only for `loader` and `pm`.
+
+use crate::{raw, RawHandle, Size};
+use bitflags::bitflags;
+use horizon_error::Result;
+
+bitflags! {
+    /// Flags of [ProcessCreationInfo]
+    ///
+    /// See <https://switchbrew.org/wiki/SVC#CreateProcessFlags>
+    #[repr(transparent)]
+    pub struct ProcessCreationFlags: u32 {
+        const IS_64BIT_INSTRUCTION                  = 1 << 0;
+
+        /// Address space type, occupies bits 1..=3
+        const ADDRESS_SPACE_32BIT                   = 0 << 1;
+        const ADDRESS_SPACE_64BIT_DEPRECATED        = 1 << 1;
+        const ADDRESS_SPACE_32BIT_WITHOUT_ALIAS     = 2 << 1;
+        const ADDRESS_SPACE_64BIT                   = 3 << 1;
+
+        const ENABLE_DEBUG                          = 1 << 4;
+        const ENABLE_ASLR                           = 1 << 5;
+        const IS_APPLICATION                        = 1 << 6;
+
+        /// Memory pool partition `[5.0.0+]`, occupies bits 7..=10
+        const POOL_PARTITION_APPLICATION            = 0 << 7;
+        const POOL_PARTITION_APPLET                 = 1 << 7;
+        const POOL_PARTITION_SYSTEM                 = 2 << 7;
+        const POOL_PARTITION_SYSTEM_NON_SECURE      = 3 << 7;
+
+        /// `[7.0.0+]`, only valid for applications
+        const OPTIMIZE_MEMORY_ALLOCATION            = 1 << 11;
+        /// `[11.0.0+]`
+        const DISABLE_DEVICE_ADDRESS_SPACE_MERGE    = 1 << 12;
+    }
+}
+
+/// Parameters of the process for [create_process]
+///
+/// See <https://switchbrew.org/wiki/SVC#CreateProcessParameter>
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub struct ProcessCreationInfo {
+    /// Process name, not necessarily NUL-terminated
+    pub name: [u8; 12],
+    /// Process category (0 for regular processes, 1 for kernel builtins)
+    pub version: u32,
+    pub program_id: u64,
+    /// Address of the code region in the new process, see `map_process_code_memory`
+    pub code_address: u64,
+    pub code_num_pages: u32,
+    pub flags: ProcessCreationFlags,
+    /// Resource limit for the process, `RawHandle(0)` to use the system one
+    pub resource_limit: RawHandle,
+    /// Size of the secure memory for the kernel objects of the process, in pages `[3.0.0+]`
+    pub system_resource_num_pages: u32,
+}
+
+/// Creates a new process, returning its handle
+///
+/// `capabilities` are the kernel capability descriptors of the process (as found in the NPDM).
+///  The process starts with no code mapped, it's up to the caller to load it before [start_process].
+pub fn create_process(info: &ProcessCreationInfo, capabilities: &[u32]) -> Result<RawHandle> {
+    let res = unsafe {
+        raw::create_process(
+            info as *const ProcessCreationInfo as u64,
+            capabilities.as_ptr() as *const u8,
+            capabilities.len() as u64,
+        )
+    };
+
+    res.result.into_result(RawHandle(res.process_handle))
+}
+
+/// Starts the main thread of a process created with [create_process]
+pub fn start_process(
+    process: RawHandle,
+    main_thread_priority: u32,
+    default_cpu_id: u32,
+    main_thread_stack_size: Size,
+) -> Result<()> {
+    unsafe {
+        raw::start_process(
+            process.0,
+            main_thread_priority,
+            default_cpu_id,
+            main_thread_stack_size as u64,
+        )
+    }
+    .result
+    .into_result(())
+}
+
+pub fn terminate_process(process: RawHandle) -> Result<()> {
+    unsafe { raw::terminate_process(process.0) }
+        .result
+        .into_result(())
+}
+
+/// Fills `process_ids` with the ids of the running processes, returning the number of ids written
+pub fn get_process_list(process_ids: &mut [u64]) -> Result<usize> {
+    let res = unsafe {
+        raw::get_process_list(
+            process_ids.as_mut_ptr() as *const u8,
+            process_ids.len() as u32,
+        )
+    };
+
+    res.result.into_result(res.num_processes as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn creation_info_layout() {
+        let info = ProcessCreationInfo {
+            name: *b"hello\0\0\0\0\0\0\0",
+            version: 0,
+            program_id: 0x0100000000001000,
+            code_address: 0x8000000,
+            code_num_pages: 0x10,
+            flags: ProcessCreationFlags::IS_64BIT_INSTRUCTION
+                | ProcessCreationFlags::ADDRESS_SPACE_64BIT
+                | ProcessCreationFlags::ENABLE_ASLR
+                | ProcessCreationFlags::POOL_PARTITION_SYSTEM,
+            resource_limit: RawHandle(0),
+            system_resource_num_pages: 0,
+        };
+
+        // SAFETY: the struct has no padding
+        let bytes: [u8; 0x30] = unsafe { core::mem::transmute(info) };
+
+        assert_eq!(&bytes[0x0..0xc], b"hello\0\0\0\0\0\0\0");
+        assert_eq!(bytes[0x10..0x18], 0x0100000000001000u64.to_le_bytes());
+        assert_eq!(bytes[0x18..0x20], 0x8000000u64.to_le_bytes());
+        assert_eq!(bytes[0x20..0x24], 0x10u32.to_le_bytes());
+        assert_eq!(bytes[0x24..0x28], 0x127u32.to_le_bytes());
+        assert_eq!(bytes[0x28..0x30], [0; 8]);
+    }
+}
@@ -127,12 +127,17 @@ pub unsafe fn create_thread(
         thread_handle: thread_handle,
     }
 }
-pub struct StartThreadResult {}
 #[inline(always)]
 #[must_use]
 pub unsafe fn start_thread(thread_handle: u32) -> StartThreadResult {
-    asm ! ("svc 0x09" , in ("w0") thread_handle ,);
-    StartThreadResult {}
+    let result: u32;
+    asm ! ("svc 0x09" , in ("w0") thread_handle , lateout ("w0") result ,);
+    StartThreadResult {
+        result: ErrorCode::new_unchecked(result),
+    }
+}
+pub struct StartThreadResult {
+    pub result: ErrorCode,
 }
 pub struct ExitThreadResult {}
 #[inline(always)]
@@ -613,36 +618,36 @@ pub unsafe fn unmap_physical_memory(address: *const u8, size: u64) -> UnmapPhysi
         result: ErrorCode::new_unchecked(result),
     }
 }
-pub struct GetDebugFutureThreadInfoResult {
-    pub result: ErrorCode,
-    pub unnamed_3: u64,
-    pub unnamed_4: u64,
-    pub unnamed_5: u64,
-    pub unnamed_6: u64,
-    pub unnamed_7: u64,
-    pub unnamed_8: u32,
-}
 #[inline(always)]
 #[must_use]
-pub unsafe fn get_debug_future_thread_info(timeout: u64) -> GetDebugFutureThreadInfoResult {
+pub unsafe fn get_debug_future_thread_info(
+    debug_handle: u32,
+    timeout: u64,
+) -> GetDebugFutureThreadInfoResult {
     let result: u32;
-    let unnamed_3: u64;
-    let unnamed_4: u64;
-    let unnamed_5: u64;
-    let unnamed_6: u64;
-    let unnamed_7: u64;
-    let unnamed_8: u32;
-    asm ! ("svc 0x2e" , in ("x3") timeout , lateout ("w0") result , lateout ("x1") unnamed_3 , lateout ("x2") unnamed_4 , lateout ("x3") unnamed_5 , lateout ("x4") unnamed_6 , lateout ("x5") unnamed_7 , lateout ("w6") unnamed_8 ,);
+    let fp: u64;
+    let sp: u64;
+    let lr: u64;
+    let pc: u64;
+    let thread_id: u64;
+    asm ! ("svc 0x2e" , in ("w2") debug_handle , in ("x3") timeout , lateout ("w0") result , lateout ("x1") fp , lateout ("x2") sp , lateout ("x3") lr , lateout ("x4") pc , lateout ("x5") thread_id ,);
     GetDebugFutureThreadInfoResult {
         result: ErrorCode::new_unchecked(result),
-        unnamed_3: unnamed_3,
-        unnamed_4: unnamed_4,
-        unnamed_5: unnamed_5,
-        unnamed_6: unnamed_6,
-        unnamed_7: unnamed_7,
-        unnamed_8: unnamed_8,
+        fp: fp,
+        sp: sp,
+        lr: lr,
+        pc: pc,
+        thread_id: thread_id,
     }
 }
+pub struct GetDebugFutureThreadInfoResult {
+    pub result: ErrorCode,
+    pub fp: u64,
+    pub sp: u64,
+    pub lr: u64,
+    pub pc: u64,
+    pub thread_id: u64,
+}
 pub struct GetLastThreadInfoResult {
     pub result: ErrorCode,
     pub last_thread_context_param0: u64,
@@ -1060,25 +1065,25 @@ pub unsafe fn set_process_activity(
         result: ErrorCode::new_unchecked(result),
     }
 }
-pub struct CreateSharedMemoryResult {
-    pub result: ErrorCode,
-    pub shared_memory_handle: u32,
-}
 #[inline(always)]
 #[must_use]
 pub unsafe fn create_shared_memory(
-    size: u32,
+    size: u64,
     local_memory_permission: u32,
     remote_memory_permission: u32,
 ) -> CreateSharedMemoryResult {
     let result: u32;
     let shared_memory_handle: u32;
-    asm ! ("svc 0x50" , in ("w1") size , in ("w2") local_memory_permission , in ("w3") remote_memory_permission , lateout ("w0") result , lateout ("w1") shared_memory_handle ,);
+    asm ! ("svc 0x50" , in ("x1") size , in ("w2") local_memory_permission , in ("w3") remote_memory_permission , lateout ("w0") result , lateout ("w1") shared_memory_handle ,);
     CreateSharedMemoryResult {
         result: ErrorCode::new_unchecked(result),
         shared_memory_handle: shared_memory_handle,
     }
 }
+pub struct CreateSharedMemoryResult {
+    pub result: ErrorCode,
+    pub shared_memory_handle: u32,
+}
 pub struct MapTransferMemoryResult {
     pub result: ErrorCode,
 }
@@ -1391,6 +1396,23 @@ pub unsafe fn get_debug_event(debug_event_info: u64, debug_handle: u32) -> GetDe
         result: ErrorCode::new_unchecked(result),
     }
 }
+#[inline(always)]
+#[must_use]
+pub unsafe fn continue_debug_event(
+    debug_handle: u32,
+    flags: u32,
+    thread_ids: *const u64,
+    thread_id_count: u32,
+) -> ContinueDebugEventResult {
+    let result: u32;
+    asm ! ("svc 0x64" , in ("w0") debug_handle , in ("w1") flags , in ("x2") thread_ids , in ("w3") thread_id_count , lateout ("w0") result ,);
+    ContinueDebugEventResult {
+        result: ErrorCode::new_unchecked(result),
+    }
+}
+pub struct ContinueDebugEventResult {
+    pub result: ErrorCode,
+}
 pub struct GetProcessListResult {
     pub result: ErrorCode,
     pub num_processes: u32,
@@ -1821,3 +1843,8 @@ pub unsafe fn set_resource_limit_limit_value(
         result: ErrorCode::new_unchecked(result),
     }
 }
+#[doc = r" Passes `args` to the secure monitor in x0..x7 and overwrites them with the values it returns there"]
+#[inline(always)]
+pub unsafe fn call_secure_monitor(args: &mut [u64; 8]) {
+    asm ! ("svc 0x7f" , inout ("x0") args [0] , inout ("x1") args [1] , inout ("x2") args [2] , inout ("x3") args [3] , inout ("x4") args [4] , inout ("x5") args [5] , inout ("x6") args [6] , inout ("x7") args [7] ,);
+}
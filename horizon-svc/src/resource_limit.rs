@@ -0,0 +1,170 @@
+//! Wrappers around the syscalls querying resource limits
+//!
+//! The resource limit of the current process can be obtained with
+//!  [get_info_self](crate::get_info_self)`(InfoType::ResourceLimit)`, the returned handle has to be closed.
+
+use crate::{get_info, get_info_self, raw, InfoType, RawHandle};
+use horizon_error::Result;
+
+/// Resources tracked by a resource limit, see <https://switchbrew.org/wiki/SVC#LimitableResource>
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum LimitableResource {
+    /// Memory, in bytes
+    PhysicalMemory = 0,
+    Threads = 1,
+    Events = 2,
+    TransferMemories = 3,
+    Sessions = 4,
+}
+
+/// Returns the maximum amount of the resource the processes using the resource limit can have
+pub fn get_resource_limit_limit_value(
+    resource_limit: RawHandle,
+    resource: LimitableResource,
+) -> Result<u64> {
+    // SAFETY: this syscall should not modify anything
+    let res = unsafe { raw::get_resource_limit_limit_value(resource_limit.0, resource as u32) };
+
+    res.result.into_result(res.limit_value)
+}
+
+/// Returns the amount of the resource the processes using the resource limit have right now
+pub fn get_resource_limit_current_value(
+    resource_limit: RawHandle,
+    resource: LimitableResource,
+) -> Result<u64> {
+    // SAFETY: this syscall should not modify anything
+    let res = unsafe { raw::get_resource_limit_current_value(resource_limit.0, resource as u32) };
+
+    res.result.into_result(res.current_value)
+}
+
+/// Returns the highest amount of the resource the processes using the resource limit have ever had
+///
+/// [11.0.0+] The syscall doesn't exist on older firmware (unless the kernel is mesosphere),
+///  calling it there gets the process killed. Check [is_peak_value_supported] first.
+pub unsafe fn get_resource_limit_peak_value(
+    resource_limit: RawHandle,
+    resource: LimitableResource,
+) -> Result<u64> {
+    let res = raw::get_resource_limit_peak_value(resource_limit.0, resource as u32);
+
+    res.result.into_result(res.peak_value)
+}
+
+/// Checks whether [get_resource_limit_peak_value] can be called
+///
+/// It's available on [11.0.0+] and on any version of mesosphere. The firmware version is detected by querying
+///  [InfoType::FreeThreadCount], which was added in the same version.
+pub fn is_peak_value_supported() -> bool {
+    get_info_self(InfoType::FreeThreadCount).is_ok()
+        || get_info(InfoType::MesosphereMetaKernelVersion, None).is_ok()
+}
+
+/// The values of a resource limit for a single resource, fetched with [get_resource_limit_view]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ResourceLimitView {
+    pub resource: LimitableResource,
+    pub current: u64,
+    pub limit: u64,
+    /// `None` if the kernel doesn't support querying the peak value
+    pub peak: Option<u64>,
+}
+
+impl ResourceLimitView {
+    /// How much more of the resource can be acquired before hitting the limit
+    pub fn headroom(&self) -> u64 {
+        self.limit.saturating_sub(self.current)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum LimitValue {
+    Limit,
+    Current,
+    Peak,
+}
+
+fn make_view(
+    resource: LimitableResource,
+    peak_supported: bool,
+    mut query: impl FnMut(LimitValue) -> Result<u64>,
+) -> Result<ResourceLimitView> {
+    Ok(ResourceLimitView {
+        resource,
+        current: query(LimitValue::Current)?,
+        limit: query(LimitValue::Limit)?,
+        peak: if peak_supported {
+            Some(query(LimitValue::Peak)?)
+        } else {
+            None
+        },
+    })
+}
+
+/// Fetches the current, limit and peak values of the resource at once
+///
+/// The peak value is only queried when [is_peak_value_supported], it's `None` otherwise.
+pub fn get_resource_limit_view(
+    resource_limit: RawHandle,
+    resource: LimitableResource,
+) -> Result<ResourceLimitView> {
+    make_view(resource, is_peak_value_supported(), |value| match value {
+        LimitValue::Limit => get_resource_limit_limit_value(resource_limit, resource),
+        LimitValue::Current => get_resource_limit_current_value(resource_limit, resource),
+        // SAFETY: make_view only queries the peak value when it's supported
+        LimitValue::Peak => unsafe { get_resource_limit_peak_value(resource_limit, resource) },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{make_view, LimitValue, LimitableResource, ResourceLimitView};
+    use horizon_error::ErrorCode;
+
+    fn query(value: LimitValue) -> horizon_error::Result<u64> {
+        match value {
+            LimitValue::Limit => Ok(100),
+            LimitValue::Current => Ok(30),
+            LimitValue::Peak => Ok(70),
+        }
+    }
+
+    #[test]
+    fn view_with_peak() {
+        let view = make_view(LimitableResource::Threads, true, query).unwrap();
+
+        assert_eq!(
+            view,
+            ResourceLimitView {
+                resource: LimitableResource::Threads,
+                current: 30,
+                limit: 100,
+                peak: Some(70),
+            }
+        );
+        assert_eq!(view.headroom(), 70);
+    }
+
+    #[test]
+    fn view_without_peak() {
+        let view = make_view(LimitableResource::Sessions, false, |value| {
+            assert_ne!(value, LimitValue::Peak, "peak queried while unsupported");
+            query(value)
+        })
+        .unwrap();
+
+        assert_eq!(view.peak, None);
+        assert_eq!((view.current, view.limit), (30, 100));
+
+        let failure = ErrorCode::from_parts(1, 2);
+        assert_eq!(
+            make_view(LimitableResource::Sessions, true, |value| match value {
+                LimitValue::Peak => Err(failure),
+                value => query(value),
+            }),
+            Err(failure)
+        );
+    }
+}
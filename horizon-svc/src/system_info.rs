@@ -0,0 +1,78 @@
+//! Wrapper around the `GetSystemInfo` syscall, querying system-wide info (as opposed to [get_info](crate::get_info))
+//!
+//! The syscall has to be allowed in the kernel capabilities of the process (NPDM),
+//!  which is normally the case only for sysmodules like `pm`.
+
+use crate::{raw, RawHandle};
+use horizon_error::Result;
+
+/// Physical memory pool, see <https://switchbrew.org/wiki/SVC#PhysicalMemorySystemInfo>
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u64)]
+pub enum MemoryPool {
+    Application = 0,
+    Applet = 1,
+    System = 2,
+    SystemNonSecure = 3,
+}
+
+/// Used in [get_system_info] svc
+///
+/// See <https://switchbrew.org/wiki/SVC#SystemInfoType>
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SystemInfoType {
+    /// Size of the memory pool
+    TotalPhysicalMemorySize(MemoryPool),
+    /// Used size of the memory pool
+    UsedPhysicalMemorySize(MemoryPool),
+    /// [5.0.0+] Lowest process id of the processes started by the kernel itself (KIPs)
+    InitialProcessIdRangeLowerBound,
+    /// [5.0.0+] Highest process id of the processes started by the kernel itself (KIPs)
+    InitialProcessIdRangeUpperBound,
+}
+
+impl SystemInfoType {
+    #[rustfmt::skip]
+    pub fn into_type_and_subtype(self) -> (u64, u64) {
+        match self {
+            SystemInfoType::TotalPhysicalMemorySize(pool) =>    (0, pool as u64),
+            SystemInfoType::UsedPhysicalMemorySize(pool) =>     (1, pool as u64),
+            SystemInfoType::InitialProcessIdRangeLowerBound =>  (2, 0),
+            SystemInfoType::InitialProcessIdRangeUpperBound =>  (2, 1),
+        }
+    }
+}
+
+/// Queries system-wide info, none of the currently known types use the handle
+pub fn get_system_info(info_type: SystemInfoType, handle: Option<RawHandle>) -> Result<u64> {
+    let (info_type, info_sub_type) = info_type.into_type_and_subtype();
+
+    // SAFETY: this syscall should not modify anything
+    let res =
+        unsafe { raw::get_system_info(info_type, handle.unwrap_or(RawHandle(0)).0, info_sub_type) };
+
+    res.result.into_result(res.system_info)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MemoryPool, SystemInfoType};
+
+    #[test]
+    fn system_info_encoding() {
+        assert_eq!(
+            SystemInfoType::TotalPhysicalMemorySize(MemoryPool::Application)
+                .into_type_and_subtype(),
+            (0, 0)
+        );
+        assert_eq!(
+            SystemInfoType::UsedPhysicalMemorySize(MemoryPool::SystemNonSecure)
+                .into_type_and_subtype(),
+            (1, 3)
+        );
+        assert_eq!(
+            SystemInfoType::InitialProcessIdRangeUpperBound.into_type_and_subtype(),
+            (2, 1)
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! RwLock implementation following the libnx one: a mutex guarding the reader and writer counters,
+//! with separate condition variables for the waiting readers and writers
+
+ij_core_workaround!();
+
+use crate::core::cell::UnsafeCell;
+use crate::raw_condvar::RawCondvar;
+use crate::raw_mutex::RawMutex;
+
+struct State {
+    /// Number of threads holding a read lock
+    readers: u32,
+    /// Number of readers waiting on `reader_wait`
+    readers_waiting: u32,
+    write_locked: bool,
+    /// Number of writers waiting on `writer_wait`
+    writers_waiting: u32,
+}
+
+/// Same interface as [RawRwLock](crate::raw_rw_lock::RawRwLock), selected with the `arbiter-rw-lock` feature
+///
+/// All the operations take the internal mutex, so even an uncontended lock costs two atomic operations
+///  more than the futex-based one. In exchange the waiting readers and writers are tracked separately:
+///  the lock is strictly writer-preferring and wakes exactly one writer at a time.
+pub struct RawArbiterRwLock {
+    mutex: RawMutex,
+    reader_wait: RawCondvar,
+    writer_wait: RawCondvar,
+    state: UnsafeCell<State>,
+}
+
+// The state is only accessed with the mutex locked
+unsafe impl Sync for RawArbiterRwLock {}
+
+impl RawArbiterRwLock {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            mutex: RawMutex::new(),
+            reader_wait: RawCondvar::new(),
+            writer_wait: RawCondvar::new(),
+            state: UnsafeCell::new(State {
+                readers: 0,
+                readers_waiting: 0,
+                write_locked: false,
+                writers_waiting: 0,
+            }),
+        }
+    }
+
+    #[inline]
+    pub unsafe fn destroy(&self) {}
+
+    /// Runs `f` with the mutex locked
+    #[inline]
+    fn with_state<R>(&self, f: impl FnOnce(&mut State) -> R) -> R {
+        // SAFETY: the mutex is unlocked before returning, and the state is accessed only under it
+        unsafe {
+            self.mutex.lock();
+            let res = f(&mut *self.state.get());
+            self.mutex.unlock();
+            res
+        }
+    }
+
+    /// Runs `f` if the mutex can be locked without waiting, returning `None` otherwise
+    #[inline]
+    fn try_with_state<R>(&self, f: impl FnOnce(&mut State) -> R) -> Option<R> {
+        // SAFETY: same as in `with_state`
+        unsafe {
+            if !self.mutex.try_lock() {
+                return None;
+            }
+            let res = f(&mut *self.state.get());
+            self.mutex.unlock();
+            Some(res)
+        }
+    }
+
+    /// Never waits: fails if the internal mutex is taken, in addition to when the lock itself is not available
+    #[inline]
+    pub unsafe fn try_read(&self) -> bool {
+        self.try_with_state(|s| {
+            let lockable = !s.write_locked && s.writers_waiting == 0;
+            if lockable {
+                s.readers += 1;
+            }
+            lockable
+        })
+        .unwrap_or(false)
+    }
+
+    pub unsafe fn read(&self) {
+        self.mutex.lock();
+
+        let mut s = &mut *self.state.get();
+        // waiting writers go first, otherwise a stream of readers could starve them
+        while s.write_locked || s.writers_waiting > 0 {
+            s.readers_waiting += 1;
+            self.reader_wait.wait(&self.mutex);
+            // other threads have modified the state in the meantime
+            s = &mut *self.state.get();
+            s.readers_waiting -= 1;
+        }
+        s.readers += 1;
+
+        self.mutex.unlock();
+    }
+
+    pub unsafe fn read_unlock(&self) {
+        self.with_state(|s| {
+            s.readers -= 1;
+            if s.readers == 0 && s.writers_waiting > 0 {
+                self.writer_wait.notify_one();
+            }
+        })
+    }
+
+    /// Never waits, same as [try_read](Self::try_read)
+    #[inline]
+    pub unsafe fn try_write(&self) -> bool {
+        self.try_with_state(|s| {
+            let lockable = !s.write_locked && s.readers == 0;
+            if lockable {
+                s.write_locked = true;
+            }
+            lockable
+        })
+        .unwrap_or(false)
+    }
+
+    pub unsafe fn write(&self) {
+        self.mutex.lock();
+
+        let mut s = &mut *self.state.get();
+        while s.write_locked || s.readers > 0 {
+            s.writers_waiting += 1;
+            self.writer_wait.wait(&self.mutex);
+            // other threads have modified the state in the meantime
+            s = &mut *self.state.get();
+            s.writers_waiting -= 1;
+        }
+        s.write_locked = true;
+
+        self.mutex.unlock();
+    }
+
+    pub unsafe fn write_unlock(&self) {
+        self.with_state(|s| {
+            s.write_locked = false;
+            if s.writers_waiting > 0 {
+                self.writer_wait.notify_one();
+            } else if s.readers_waiting > 0 {
+                self.reader_wait.notify_all();
+            }
+        })
+    }
+
+    pub unsafe fn write_downgrade(&self) {
+        self.with_state(|s| {
+            s.write_locked = false;
+            s.readers = 1;
+            // the readers would go back to sleep if there are writers waiting
+            if s.writers_waiting == 0 && s.readers_waiting > 0 {
+                self.reader_wait.notify_all();
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::RawArbiterRwLock;
+    use crate::raw_rw_lock::RawRwLock;
+    use core::cell::UnsafeCell;
+    use std::sync::Arc;
+
+    const THREADS: usize = 4;
+    const ITERATIONS: usize = 10000;
+
+    /// The operations of both backends, to run the same workload on them
+    trait Backend: Send + Sync + 'static {
+        fn new() -> Self;
+        fn read<R>(&self, f: impl FnOnce() -> R) -> R;
+        fn write<R>(&self, f: impl FnOnce() -> R) -> R;
+    }
+
+    macro_rules! impl_backend {
+        ($ty:ty) => {
+            impl Backend for $ty {
+                fn new() -> Self {
+                    <$ty>::new()
+                }
+
+                fn read<R>(&self, f: impl FnOnce() -> R) -> R {
+                    unsafe {
+                        <$ty>::read(self);
+                        let res = f();
+                        self.read_unlock();
+                        res
+                    }
+                }
+
+                fn write<R>(&self, f: impl FnOnce() -> R) -> R {
+                    unsafe {
+                        <$ty>::write(self);
+                        let res = f();
+                        self.write_unlock();
+                        res
+                    }
+                }
+            }
+        };
+    }
+
+    impl_backend!(RawRwLock);
+    impl_backend!(RawArbiterRwLock);
+
+    struct Shared<L> {
+        lock: L,
+        /// Two counters updated together, so a reader can check that it never sees a half-done write
+        value: UnsafeCell<(usize, usize)>,
+    }
+
+    unsafe impl<L: Sync> Sync for Shared<L> {}
+
+    /// Runs a workload where every `write_every`-th operation of each thread is a write,
+    ///  checking that readers never see a torn write and that no write is lost
+    fn contention<L: Backend>(write_every: usize) {
+        let shared = Arc::new(Shared {
+            lock: L::new(),
+            value: UnsafeCell::new((0, 0)),
+        });
+
+        let threads = (0..THREADS)
+            .map(|_| {
+                let shared = shared.clone();
+                std::thread::spawn(move || {
+                    for i in 0..ITERATIONS {
+                        if i % write_every == 0 {
+                            shared.lock.write(|| unsafe {
+                                let value = &mut *shared.value.get();
+                                value.0 += 1;
+                                value.1 += 1;
+                            });
+                        } else {
+                            let (a, b) = shared.lock.read(|| unsafe { *shared.value.get() });
+                            assert_eq!(a, b);
+                        }
+                    }
+                })
+            })
+            .collect::<std::vec::Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let writes = THREADS * ITERATIONS.div_ceil(write_every);
+        assert_eq!(unsafe { *shared.value.get() }, (writes, writes));
+    }
+
+    #[test]
+    fn try_lock_does_not_wait() {
+        let lock = RawArbiterRwLock::new();
+
+        unsafe {
+            lock.read();
+            assert!(!lock.try_write());
+            lock.read_unlock();
+
+            lock.write();
+            assert!(!lock.try_read());
+            assert!(!lock.try_write());
+            let state = &*lock.state.get();
+            assert_eq!((state.readers_waiting, state.writers_waiting), (0, 0));
+            lock.write_unlock();
+
+            // the internal mutex being taken is treated as contention too
+            lock.mutex.lock();
+            assert!(!lock.try_read());
+            lock.mutex.unlock();
+            assert!(lock.try_read());
+            lock.read_unlock();
+        }
+    }
+
+    #[test]
+    fn read_heavy_contention() {
+        contention::<RawRwLock>(100);
+        contention::<RawArbiterRwLock>(100);
+    }
+
+    #[test]
+    fn write_heavy_contention() {
+        contention::<RawRwLock>(2);
+        contention::<RawArbiterRwLock>(2);
+    }
+}
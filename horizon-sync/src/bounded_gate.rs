@@ -0,0 +1,173 @@
+ij_core_workaround!();
+
+use core::sync::atomic::{
+    AtomicI32,
+    Ordering::{Acquire, Relaxed, Release},
+};
+use horizon_error::KernelErrorCode;
+use horizon_svc::{ArbitrationType, SignalType};
+
+/// A counting gate: consumers take permits and block when there are none, producers add them
+///
+/// The blocking path uses a single `svc::wait_for_address` with
+/// [ArbitrationType::DecrementAndWaitIfLessThan], so taking the slow path does not need a separate
+/// atomic operation before the wait.
+///
+/// The kernel semantics of `DecrementAndWaitIfLessThan(value = 1)`, all done under the arbiter lock:
+/// - the counter is read; if it is `>= 1`, the svc fails with `InvalidState` without waiting
+///   (a permit became available, we should retry taking it from userspace)
+/// - otherwise the counter is decremented by one and the thread is queued on the address
+///
+/// So a negative counter value is minus the number of waiting threads. A producer increments
+/// the counter and, if it was negative, wakes exactly one waiter. The woken waiter already
+/// "paid" for the permit with its decrement, so it does not touch the counter again.
+///
+/// Because the decrement and the enqueueing happen atomically in the kernel, a producer can't
+///  observe the negative value before the waiter is queued, so no wakeups are lost.
+///
+/// There is no timed wait: a timed out waiter would have to undo its decrement, racing with
+///  producers that already counted it as a waiter.
+///
+/// It makes this primitive `[4.0.0+]`, same as other futex-based ones.
+pub struct BoundedGate {
+    /// - `> 0`: number of available permits
+    /// - `<= 0`: minus the number of threads waiting for a permit
+    value: AtomicI32,
+}
+
+impl BoundedGate {
+    #[inline]
+    pub const fn new(permits: i32) -> Self {
+        assert!(permits >= 0);
+
+        Self {
+            value: AtomicI32::new(permits),
+        }
+    }
+
+    /// Takes a permit if one is available without blocking
+    #[inline]
+    pub fn try_acquire(&self) -> bool {
+        let mut state = self.value.load(Relaxed);
+        while state > 0 {
+            match self
+                .value
+                .compare_exchange_weak(state, state - 1, Acquire, Relaxed)
+            {
+                Ok(_) => return true,
+                Err(s) => state = s,
+            }
+        }
+        false
+    }
+
+    /// Takes a permit, blocking until a producer calls [BoundedGate::signal] if there are none
+    #[inline]
+    pub fn acquire(&self) {
+        if !self.try_acquire() {
+            self.acquire_contended();
+        }
+    }
+
+    #[cold]
+    fn acquire_contended(&self) {
+        loop {
+            match unsafe {
+                horizon_svc::wait_for_address(
+                    &self.value,
+                    ArbitrationType::DecrementAndWaitIfLessThan,
+                    1,
+                    None,
+                )
+            }
+            .map_err(|e| unsafe { e.try_as::<KernelErrorCode>().unwrap_unchecked() })
+            {
+                // we were woken by a producer, the decrement done by the kernel holds our permit
+                Ok(_) => {
+                    core::sync::atomic::fence(Acquire);
+                    return;
+                }
+                // the counter became positive before we got to wait, try to take it normally
+                Err(KernelErrorCode::InvalidState) => {
+                    if self.try_acquire() {
+                        return;
+                    }
+                }
+                // some unknown error, let's panic
+                Err(e) => panic!("BoundedGate::acquire: {:?}", e),
+            }
+        }
+    }
+
+    /// Adds a permit, waking one waiting consumer if there is one
+    #[inline]
+    pub fn signal(&self) {
+        if self.value.fetch_add(1, Release) < 0 {
+            self.wake();
+        }
+    }
+
+    #[cold]
+    fn wake(&self) {
+        unsafe {
+            horizon_svc::signal_to_address(&self.value, SignalType::Signal, 0, 1).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::BoundedGate;
+    use core::sync::atomic::Ordering::Relaxed;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn try_acquire() {
+        let gate = BoundedGate::new(2);
+        assert!(gate.try_acquire());
+        assert!(gate.try_acquire());
+        assert!(!gate.try_acquire());
+
+        gate.signal();
+        assert!(gate.try_acquire());
+        assert!(!gate.try_acquire());
+    }
+
+    #[test]
+    fn producer_consumer() {
+        const CONSUMERS: usize = 4;
+        const PERMITS_PER_CONSUMER: usize = 1000;
+
+        let gate = Arc::new(BoundedGate::new(0));
+
+        let consumers = (0..CONSUMERS)
+            .map(|_| {
+                let gate = gate.clone();
+                thread::spawn(move || {
+                    for _ in 0..PERMITS_PER_CONSUMER {
+                        gate.acquire();
+                    }
+                })
+            })
+            .collect::<std::vec::Vec<_>>();
+
+        for i in 0..CONSUMERS * PERMITS_PER_CONSUMER {
+            gate.signal();
+            if i % 64 == 0 {
+                thread::yield_now();
+            }
+        }
+
+        // a lost wakeup would leave a consumer blocked forever
+        for consumer in consumers {
+            consumer.join().unwrap();
+        }
+
+        // every signal was consumed exactly once
+        assert_eq!(gate.value.load(Relaxed), 0);
+        assert!(!gate.try_acquire());
+    }
+}
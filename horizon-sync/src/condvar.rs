@@ -0,0 +1,117 @@
+//! This module implements a condition variable working with the [Mutex](crate::mutex::Mutex) guards
+//! The interface follows the libstd one
+
+ij_core_workaround!();
+
+use crate::core::time::Duration;
+#[cfg(feature = "kernel-mutex")]
+use crate::kernel_condvar::RawKernelCondvar as RawCondvar;
+use crate::mutex::MutexGuard;
+#[cfg(not(feature = "kernel-mutex"))]
+use crate::raw_condvar::RawCondvar;
+
+/// Whether [Condvar::wait_timeout] returned because the timeout expired
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct WaitTimeoutResult(bool);
+
+impl WaitTimeoutResult {
+    pub fn timed_out(&self) -> bool {
+        self.0
+    }
+}
+
+/// Condition variable, see [RawCondvar] for the implementation (the backend follows the [Mutex](crate::mutex::Mutex) one)
+///
+/// A condvar should be used with a single mutex at a time, as usual
+pub struct Condvar {
+    inner: RawCondvar,
+}
+
+impl Condvar {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            inner: RawCondvar::new(),
+        }
+    }
+
+    /// Releases the mutex and waits for a notification, locking the mutex again before returning
+    ///
+    /// Spurious wakeups are filtered out, but the condition should be checked in a loop anyway:
+    ///  another thread can lock the mutex and change the data between the notification and this one relocking it.
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        // SAFETY: the guard proves that the current thread holds the mutex, it's locked again before returning
+        unsafe { self.inner.wait(guard.raw_mutex()) };
+        guard
+    }
+
+    /// Same as [wait](Self::wait), but gives up after `timeout`
+    ///
+    /// Can return early on a spurious wakeup, without the timeout being reported.
+    pub fn wait_timeout<'a, T: ?Sized>(
+        &self,
+        guard: MutexGuard<'a, T>,
+        timeout: Duration,
+    ) -> (MutexGuard<'a, T>, WaitTimeoutResult) {
+        // SAFETY: same as in `wait`
+        let notified = unsafe { self.inner.wait_timeout(guard.raw_mutex(), timeout) };
+        (guard, WaitTimeoutResult(!notified))
+    }
+
+    pub fn notify_one(&self) {
+        self.inner.notify_one()
+    }
+
+    pub fn notify_all(&self) {
+        self.inner.notify_all()
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::Condvar;
+    use crate::mutex::Mutex;
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+    use std::vec::Vec;
+
+    #[test]
+    fn producer_consumer() {
+        const ITEMS: usize = 1000;
+
+        let queue = Arc::new((Mutex::new(VecDeque::new()), Condvar::new()));
+
+        let consumer = {
+            let queue = queue.clone();
+            std::thread::spawn(move || {
+                let (items, condvar) = &*queue;
+                let mut received = Vec::new();
+
+                let mut guard = items.lock();
+                while received.len() < ITEMS {
+                    match guard.pop_front() {
+                        Some(item) => received.push(item),
+                        None => guard = condvar.wait(guard),
+                    }
+                }
+                received
+            })
+        };
+
+        let (items, condvar) = &*queue;
+        for i in 0..ITEMS {
+            items.lock().push_back(i);
+            condvar.notify_one();
+        }
+
+        assert_eq!(consumer.join().unwrap(), (0..ITEMS).collect::<Vec<_>>());
+    }
+}
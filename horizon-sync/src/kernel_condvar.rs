@@ -0,0 +1,89 @@
+//! Condition variable based on the process-wide key syscalls, selected for [Condvar](crate::condvar::Condvar)
+//! with the `kernel-mutex` feature
+
+ij_core_workaround!();
+
+use crate::core::sync::atomic::AtomicU32;
+use crate::core::time::Duration;
+use crate::kernel_mutex::{current_thread_handle, RawKernelMutex};
+use horizon_error::KernelErrorCode;
+
+/// Same interface as [RawCondvar](crate::raw_condvar::RawCondvar), but for [RawKernelMutex]
+///
+/// This is the libnx condition variable: `svc::wait_process_wide_key_atomic` releases the mutex and
+///  starts waiting on the key in one go, and `svc::signal_process_wide_key` moves the signaled threads
+///  to the kernel queue of the mutex, so they get it handed over one by one instead of all waking up
+///  and racing for it. There are no spurious wakeups either.
+///
+/// The key word is only written by the kernel (it marks whether there are waiters).
+pub struct RawKernelCondvar {
+    key: AtomicU32,
+}
+
+impl RawKernelCondvar {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            key: AtomicU32::new(0),
+        }
+    }
+
+    pub fn notify_one(&self) {
+        unsafe { horizon_svc::signal_process_wide_key(&self.key, 1) }
+    }
+
+    pub fn notify_all(&self) {
+        unsafe { horizon_svc::signal_process_wide_key(&self.key, -1) }
+    }
+
+    /// Waits for a notification, the mutex must be locked by the current thread
+    ///
+    /// # Safety
+    ///
+    /// `mutex` must be locked by the current thread, it's locked again before returning
+    pub unsafe fn wait(&self, mutex: &RawKernelMutex) {
+        self.wait_optional_timeout(mutex, None);
+    }
+
+    /// Waits for a notification for at most `timeout`, the mutex must be locked by the current thread
+    ///
+    /// Returns false if the timeout expired.
+    ///
+    /// # Safety
+    ///
+    /// Same as for [wait](Self::wait)
+    pub unsafe fn wait_timeout(&self, mutex: &RawKernelMutex, timeout: Duration) -> bool {
+        self.wait_optional_timeout(mutex, Some(timeout))
+    }
+
+    unsafe fn wait_optional_timeout(
+        &self,
+        mutex: &RawKernelMutex,
+        timeout: Option<Duration>,
+    ) -> bool {
+        match horizon_svc::wait_process_wide_key_atomic(
+            &self.key,
+            mutex.tag(),
+            current_thread_handle(),
+            timeout,
+        )
+        .map_err(|e| e.try_as::<KernelErrorCode>().unwrap_unchecked())
+        {
+            // the kernel handed the mutex over to us
+            Ok(_) => true,
+            // the kernel doesn't take the mutex for us on a timeout
+            Err(KernelErrorCode::TimedOut) => {
+                mutex.lock();
+                false
+            }
+            // some unknown error, let's panic
+            Err(e) => panic!("RawKernelCondvar::wait: {:?}", e),
+        }
+    }
+}
+
+impl Default for RawKernelCondvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
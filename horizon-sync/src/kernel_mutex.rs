@@ -0,0 +1,212 @@
+//! Mutex in the kernel format, selected for [Mutex](crate::mutex::Mutex) with the `kernel-mutex` feature
+
+ij_core_workaround!();
+
+use crate::core::arch::asm;
+use crate::core::sync::atomic::{
+    AtomicU32,
+    Ordering::{Acquire, Relaxed, Release},
+};
+use horizon_svc::{decode_mutex_tag, RawHandle, HANDLE_WAIT_MASK};
+
+/// Offset of the thread handle in the thread local region, same as in the `ThreadVars` of libnx
+const THREAD_HANDLE_OFFSET: usize = 0x1E4;
+
+#[inline]
+fn thread_handle_slot() -> *mut u32 {
+    let tls_ptr: *mut u8;
+    unsafe {
+        asm!("mrs {}, TPIDRRO_EL0", out(reg) tls_ptr);
+        tls_ptr.add(THREAD_HANDLE_OFFSET) as *mut u32
+    }
+}
+
+/// The real handle of the current thread, as stored by [set_current_thread_handle]
+///
+/// The kernel-format mutex keeps it in the tag, [CURRENT_THREAD_PSEUDO_HANDLE](horizon_svc::CURRENT_THREAD_PSEUDO_HANDLE)
+///  is not accepted there. There is no syscall returning it, so it is kept in the thread local region.
+#[inline]
+pub fn current_thread_handle() -> RawHandle {
+    RawHandle(unsafe { thread_handle_slot().read_volatile() })
+}
+
+/// Stores the real handle of the current thread for [current_thread_handle]
+///
+/// horizon-rt does it for the main thread, whoever creates other threads must do it when they start.
+///
+/// # Safety
+///
+/// `handle` must be the handle of the current thread, and it must stay open while the thread runs
+pub unsafe fn set_current_thread_handle(handle: RawHandle) {
+    thread_handle_slot().write_volatile(handle.0)
+}
+
+/// Same interface as [RawMutex](crate::raw_mutex::RawMutex), selected with the `kernel-mutex` feature
+///
+/// The state is the kernel mutex tag (see [horizon_svc::arbitrate_lock]): 0 when unlocked, otherwise the
+///  handle of the owner with [HANDLE_WAIT_MASK] set if there are waiters. The waiters are queued by the
+///  kernel, which hands the mutex over to them directly on unlock, so nothing ever spins.
+///
+/// It is the only mutex that [RawKernelCondvar](crate::kernel_condvar::RawKernelCondvar) can be used with.
+///  The uncontended lock and unlock cost the same single compare-exchange as the futex-based mutex, but
+///  locking reads the thread handle from the thread local region too (see [set_current_thread_handle]).
+pub struct RawKernelMutex {
+    tag: AtomicU32,
+}
+
+impl RawKernelMutex {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            tag: AtomicU32::new(0),
+        }
+    }
+
+    /// Does nothing, kept for the same interface as [RawMutex](crate::raw_mutex::RawMutex)
+    ///
+    /// # Safety
+    ///
+    /// Always safe to call
+    #[inline]
+    pub unsafe fn init(&mut self) {}
+
+    /// Does nothing, kept for the same interface as [RawMutex](crate::raw_mutex::RawMutex)
+    ///
+    /// # Safety
+    ///
+    /// Always safe to call
+    #[inline]
+    pub unsafe fn destroy(&self) {}
+
+    /// Attempts to take the lock with a single compare-exchange
+    ///
+    /// Never waits and never marks the mutex as contended
+    ///
+    /// # Safety
+    ///
+    /// The current thread handle must have been stored with [set_current_thread_handle]
+    #[inline]
+    pub unsafe fn try_lock(&self) -> bool {
+        self.try_lock_as(current_thread_handle())
+    }
+
+    #[inline]
+    fn try_lock_as(&self, self_thread: RawHandle) -> bool {
+        self.tag
+            .compare_exchange(0, self_thread.0, Acquire, Relaxed)
+            .is_ok()
+    }
+
+    /// Takes the lock, waiting in the kernel queue of the mutex if it's locked
+    ///
+    /// # Safety
+    ///
+    /// The current thread handle must have been stored with [set_current_thread_handle],
+    ///  and the mutex must not be locked by the current thread already
+    #[inline]
+    pub unsafe fn lock(&self) {
+        let self_thread = current_thread_handle();
+        if !self.try_lock_as(self_thread) {
+            self.lock_contended(self_thread);
+        }
+    }
+
+    #[cold]
+    fn lock_contended(&self, self_thread: RawHandle) {
+        let mut tag = self.tag.load(Relaxed);
+        loop {
+            if tag == 0 {
+                match self
+                    .tag
+                    .compare_exchange(0, self_thread.0, Acquire, Relaxed)
+                {
+                    Ok(_) => return, // Locked!
+                    Err(t) => {
+                        tag = t;
+                        continue;
+                    }
+                }
+            }
+
+            // Tell the owner to hand the mutex over on unlock
+            if tag & HANDLE_WAIT_MASK == 0 {
+                if let Err(t) =
+                    self.tag
+                        .compare_exchange(tag, tag | HANDLE_WAIT_MASK, Relaxed, Relaxed)
+                {
+                    tag = t;
+                    continue;
+                }
+            }
+
+            // The kernel checks that the tag is still `owner | HANDLE_WAIT_MASK`, failing with
+            //  `InvalidState` otherwise, so the error is not interesting: we just look at the tag again
+            let (owner, _) = decode_mutex_tag(tag);
+            let _ = unsafe { horizon_svc::arbitrate_lock(owner, &self.tag, self_thread) };
+
+            tag = self.tag.load(Acquire);
+            if decode_mutex_tag(tag).0 == self_thread {
+                // The mutex was handed over to us
+                return;
+            }
+        }
+    }
+
+    /// Releases the lock, handing it over to the first waiter if there are any
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked by the current thread
+    #[inline]
+    pub unsafe fn unlock(&self) {
+        // Only the owner changes the tag while the mutex is locked, except for setting HANDLE_WAIT_MASK
+        let tag = self.tag.load(Relaxed);
+        if tag & HANDLE_WAIT_MASK != 0
+            || self.tag.compare_exchange(tag, 0, Release, Relaxed).is_err()
+        {
+            self.wake();
+        }
+    }
+
+    #[cold]
+    fn wake(&self) {
+        // The kernel writes the handle of the new owner to the tag
+        unsafe { horizon_svc::arbitrate_unlock(&self.tag) }.unwrap();
+    }
+
+    /// The tag address, for [RawKernelCondvar](crate::kernel_condvar::RawKernelCondvar) to pass to the kernel
+    #[inline]
+    pub(crate) fn tag(&self) -> &AtomicU32 {
+        &self.tag
+    }
+}
+
+impl Default for RawKernelMutex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RawKernelMutex;
+    use core::sync::atomic::Ordering::Relaxed;
+    use horizon_svc::{RawHandle, HANDLE_WAIT_MASK};
+
+    #[test]
+    fn tag_format() {
+        let mutex = RawKernelMutex::new();
+
+        assert!(mutex.try_lock_as(RawHandle(0x1234)));
+        // the owner handle is in the tag
+        assert_eq!(mutex.tag.load(Relaxed), 0x1234);
+
+        assert!(!mutex.try_lock_as(RawHandle(0x5678)));
+        // the failed attempt didn't mark it as contended
+        assert_eq!(mutex.tag.load(Relaxed) & HANDLE_WAIT_MASK, 0);
+
+        unsafe { mutex.unlock() };
+        assert_eq!(mutex.tag.load(Relaxed), 0);
+        assert!(mutex.try_lock_as(RawHandle(0x5678)));
+    }
+}
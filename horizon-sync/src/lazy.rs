@@ -0,0 +1,119 @@
+ij_core_workaround!();
+
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+
+use crate::once::Once;
+
+/// A value computed on the first access, usable in statics
+///
+/// ```ignore
+/// static SERVICES: Lazy<ServiceTable> = Lazy::new(|| ServiceTable::open());
+/// ```
+///
+/// The initializer is only borrowed to be called, so if it panics the value stays uninitialized
+///  and the next access calls it again.
+pub struct Lazy<T, F = fn() -> T> {
+    once: Once,
+    value: UnsafeCell<MaybeUninit<T>>,
+    init: F,
+}
+
+// The value is shared between threads once initialized, and the initializer can run on any of them
+unsafe impl<T: Send + Sync, F: Sync> Sync for Lazy<T, F> {}
+
+impl<T, F> Lazy<T, F> {
+    #[inline]
+    pub const fn new(init: F) -> Self {
+        Self {
+            once: Once::new(),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            init,
+        }
+    }
+
+    /// The value, if it was already computed
+    #[inline]
+    pub fn get(this: &Self) -> Option<&T> {
+        if this.once.is_completed() {
+            // SAFETY: the value is written before the Once is completed
+            Some(unsafe { (*this.value.get()).assume_init_ref() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, F: Fn() -> T> Lazy<T, F> {
+    /// Computes the value if it's the first access, same as dereferencing
+    #[inline]
+    pub fn force(this: &Self) -> &T {
+        this.once.call_once(|| {
+            let value = (this.init)();
+            // SAFETY: the Once guarantees that only one thread gets here, and only once
+            unsafe { (*this.value.get()).write(value) };
+        });
+
+        // SAFETY: call_once returns only after the value was written
+        unsafe { (*this.value.get()).assume_init_ref() }
+    }
+}
+
+impl<T, F: Fn() -> T> Deref for Lazy<T, F> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        Self::force(self)
+    }
+}
+
+impl<T: Default> Default for Lazy<T> {
+    fn default() -> Self {
+        Self::new(T::default)
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for Lazy<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match Self::get(self) {
+            Some(value) => f.debug_tuple("Lazy").field(value).finish(),
+            None => f.write_str("Lazy(<uninit>)"),
+        }
+    }
+}
+
+impl<T, F> Drop for Lazy<T, F> {
+    fn drop(&mut self) {
+        if self.once.is_completed() {
+            // SAFETY: the value was initialized, and it's not accessible anymore
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Lazy;
+    use core::sync::atomic::{AtomicU32, Ordering::Relaxed};
+
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+
+    fn expensive(value: u32) -> u32 {
+        CALLS.fetch_add(1, Relaxed);
+        value * 2
+    }
+
+    static LAZY: Lazy<u32> = Lazy::new(|| expensive(21));
+
+    #[test]
+    fn static_lazy() {
+        assert_eq!(Lazy::get(&LAZY), None);
+        assert_eq!(*LAZY, 42);
+        assert_eq!(*LAZY, 42);
+        assert_eq!(Lazy::get(&LAZY), Some(&42));
+        assert_eq!(CALLS.load(Relaxed), 1);
+    }
+}
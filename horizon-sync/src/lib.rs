@@ -26,8 +26,22 @@ macro_rules! ij_core_workaround {
     };
 }
 
+pub mod arbiter_rw_lock;
+pub mod bounded_gate;
+pub mod condvar;
 pub mod futex;
+pub mod kernel_condvar;
+pub mod kernel_mutex;
+pub mod lazy;
+pub mod lock_owner;
 pub mod mutex;
+pub mod once;
+pub mod raw_condvar;
 pub mod raw_mutex;
 pub mod raw_rw_lock;
+pub mod reentrant_mutex;
 pub mod rw_lock;
+pub mod semaphore;
+
+pub use lazy::Lazy;
+pub use once::OnceCell;
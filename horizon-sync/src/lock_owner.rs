@@ -0,0 +1,60 @@
+//! Tracks the thread holding a lock, to tell when the same thread locks it again
+
+ij_core_workaround!();
+
+use core::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+/// Id of the thread holding a lock, 0 if no one does
+///
+/// The ids are provided by the user, any value unique among the live threads (and not 0) works.
+///  Only the thread holding the lock may [set](LockOwner::set) or [clear](LockOwner::clear) it.
+pub struct LockOwner(AtomicU64);
+
+impl LockOwner {
+    pub const fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    /// Whether the lock is held by the thread `thread_id`
+    #[inline]
+    pub fn is(&self, thread_id: u64) -> bool {
+        // Relaxed is enough: the only way to observe our own id is to have stored it ourselves
+        self.0.load(Relaxed) == thread_id
+    }
+
+    /// Marks the lock as held by `thread_id`, call right after acquiring it
+    #[inline]
+    pub fn set(&self, thread_id: u64) {
+        self.0.store(thread_id, Relaxed)
+    }
+
+    /// Marks the lock as free, call right before releasing it
+    #[inline]
+    pub fn clear(&self) {
+        self.0.store(0, Relaxed)
+    }
+}
+
+impl Default for LockOwner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LockOwner;
+
+    #[test]
+    fn lock_owner() {
+        let owner = LockOwner::new();
+        assert!(!owner.is(1));
+
+        owner.set(1);
+        assert!(owner.is(1));
+        assert!(!owner.is(2));
+
+        owner.clear();
+        assert!(!owner.is(1));
+    }
+}
@@ -1,8 +1,19 @@
 //! This module implements a mutex wrapper type
 //! Most code is borrowed from libstd, but without the poisoning
+//!
+//! There are two backends for the mutex (and the matching [Condvar](crate::condvar::Condvar)):
+//! - [RawMutex](crate::raw_mutex::RawMutex) (the default): a futex, waited on with `svc::wait_for_address`.
+//!   The condvar is a futex too, and wakes all the notified threads to race for the mutex.
+//! - [RawKernelMutex](crate::kernel_mutex::RawKernelMutex) (the `kernel-mutex` feature): the kernel format
+//!   used by libnx, arbitrated with `svc::arbitrate_lock`. The condvar moves the notified threads to the
+//!   kernel queue of the mutex, so it suits the mutexes with many threads waiting on a condition. Needs the
+//!   real handle of every thread using it, see [set_current_thread_handle](crate::kernel_mutex::set_current_thread_handle).
 
 ij_core_workaround!();
 
+#[cfg(feature = "kernel-mutex")]
+use crate::kernel_mutex::RawKernelMutex as RawMutex;
+#[cfg(not(feature = "kernel-mutex"))]
 use crate::raw_mutex::RawMutex;
 use core::cell::UnsafeCell;
 use core::fmt;
@@ -89,6 +100,11 @@ impl<'mutex, T: ?Sized> MutexGuard<'mutex, T> {
     unsafe fn new(lock: &'mutex Mutex<T>) -> MutexGuard<'mutex, T> {
         MutexGuard { lock }
     }
+
+    /// The mutex held by the guard, for [Condvar](crate::condvar::Condvar) to release it while waiting
+    pub(crate) fn raw_mutex(&self) -> &'mutex RawMutex {
+        &self.lock.inner
+    }
 }
 
 impl<T: ?Sized> Deref for MutexGuard<'_, T> {
@@ -125,3 +141,24 @@ impl<T: ?Sized + fmt::Display> fmt::Display for MutexGuard<'_, T> {
         (**self).fmt(f)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::Mutex;
+    use core::sync::atomic::Ordering::Relaxed;
+
+    #[cfg(not(feature = "kernel-mutex"))]
+    #[test]
+    fn try_lock_does_not_wait() {
+        let mutex = Mutex::new(1);
+
+        let guard = mutex.try_lock().unwrap();
+        assert!(mutex.try_lock().is_none());
+        // still locked without waiters, the failed attempt didn't mark it as contended
+        assert_eq!(mutex.inner.value.load(Relaxed), 1);
+        drop(guard);
+
+        assert_eq!(*mutex.try_lock().unwrap(), 1);
+        assert_eq!(mutex.inner.value.load(Relaxed), 0);
+    }
+}
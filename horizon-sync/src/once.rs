@@ -0,0 +1,243 @@
+ij_core_workaround!();
+
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{
+    AtomicU32,
+    Ordering::{Acquire, Relaxed, Release},
+};
+
+use crate::futex::{futex_wait, futex_wake_all};
+
+const INCOMPLETE: u32 = 0;
+const RUNNING: u32 = 1;
+const QUEUED: u32 = 2;
+const COMPLETE: u32 = 3;
+
+/// A one-time initialization primitive, the implementation follows the futex-based one of the rust stdlib
+///
+/// Unlike the stdlib one, it is not poisoned when the initializer panics: the state goes back to
+///  incomplete, so the next [Once::call_once] runs its initializer again.
+///
+/// It makes this primitive `[4.0.0+]`, same as other futex-based ones.
+pub struct Once {
+    /// 0: not initialized yet
+    /// 1: an initializer is running
+    /// 2: an initializer is running, and other threads are waiting for it
+    /// 3: initialized
+    state: AtomicU32,
+}
+
+/// Publishes the new state and wakes the waiting threads, even when the initializer unwinds
+struct CompletionGuard<'a> {
+    state: &'a AtomicU32,
+    set_state_on_drop_to: u32,
+}
+
+impl<'a> Drop for CompletionGuard<'a> {
+    fn drop(&mut self) {
+        if self.state.swap(self.set_state_on_drop_to, Release) == QUEUED {
+            futex_wake_all(self.state);
+        }
+    }
+}
+
+impl Once {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU32::new(INCOMPLETE),
+        }
+    }
+
+    #[inline]
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Acquire) == COMPLETE
+    }
+
+    /// Runs `f` if no call to `call_once` has completed yet, waiting for the running one otherwise
+    ///
+    /// When `call_once` returns, some initializer has completed and its effects are visible
+    #[inline]
+    pub fn call_once<F: FnOnce()>(&self, f: F) {
+        if self.is_completed() {
+            return;
+        }
+
+        let mut f = Some(f);
+        self.call(&mut || f.take().unwrap()());
+    }
+
+    #[cold]
+    fn call(&self, f: &mut dyn FnMut()) {
+        let mut state = self.state.load(Acquire);
+        loop {
+            match state {
+                COMPLETE => return,
+                INCOMPLETE => {
+                    if let Err(new) = self
+                        .state
+                        .compare_exchange_weak(INCOMPLETE, RUNNING, Acquire, Acquire)
+                    {
+                        state = new;
+                        continue;
+                    }
+
+                    // if f panics, the state is reset and the next caller will try again
+                    let mut guard = CompletionGuard {
+                        state: &self.state,
+                        set_state_on_drop_to: INCOMPLETE,
+                    };
+                    f();
+                    guard.set_state_on_drop_to = COMPLETE;
+                    return;
+                }
+                RUNNING | QUEUED => {
+                    // Tell the running thread that there are waiters, so it wakes us up.
+                    if state == RUNNING {
+                        if let Err(new) = self
+                            .state
+                            .compare_exchange_weak(RUNNING, QUEUED, Relaxed, Acquire)
+                        {
+                            state = new;
+                            continue;
+                        }
+                    }
+
+                    futex_wait(&self.state, QUEUED, None);
+                    state = self.state.load(Acquire);
+                }
+                _ => unreachable!("invalid Once state"),
+            }
+        }
+    }
+}
+
+impl Default for Once {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A cell written once, the initializer can be given at the access site (unlike [Lazy](crate::Lazy))
+///
+/// Same as with [Lazy](crate::Lazy), a panicking initializer leaves the cell empty.
+pub struct OnceCell<T> {
+    once: Once,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// The value is shared between threads once initialized, and the initializer can run on any of them
+unsafe impl<T: Send + Sync> Sync for OnceCell<T> {}
+unsafe impl<T: Send> Send for OnceCell<T> {}
+
+impl<T> OnceCell<T> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            once: Once::new(),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// The value, if it was already initialized
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.once.is_completed() {
+            // SAFETY: the value is written before the Once is completed
+            Some(unsafe { (*self.value.get()).assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    /// Initializes the value with `f` if it's empty, waiting for the initializer running on another thread if any
+    #[inline]
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+        self.once.call_once(|| {
+            let value = f();
+            // SAFETY: the Once guarantees that only one thread gets here, and only once
+            unsafe { (*self.value.get()).write(value) };
+        });
+
+        // SAFETY: call_once returns only after the value was written
+        unsafe { (*self.value.get()).assume_init_ref() }
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OnceCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.get() {
+            Some(value) => f.debug_tuple("OnceCell").field(value).finish(),
+            None => f.write_str("OnceCell(<uninit>)"),
+        }
+    }
+}
+
+impl<T> Drop for OnceCell<T> {
+    fn drop(&mut self) {
+        if self.once.is_completed() {
+            // SAFETY: the value was initialized, and it's not accessible anymore
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::{Once, OnceCell};
+    use core::sync::atomic::{AtomicBool, Ordering::SeqCst};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn second_caller_waits() {
+        let once = Arc::new(Once::new());
+        let started = Arc::new(AtomicBool::new(false));
+        let finished = Arc::new(AtomicBool::new(false));
+
+        let first = {
+            let (once, started, finished) = (once.clone(), started.clone(), finished.clone());
+            std::thread::spawn(move || {
+                once.call_once(|| {
+                    started.store(true, SeqCst);
+                    std::thread::sleep(Duration::from_millis(50));
+                    finished.store(true, SeqCst);
+                })
+            })
+        };
+
+        while !started.load(SeqCst) {
+            std::thread::yield_now();
+        }
+        assert!(!once.is_completed());
+
+        // the first initializer is still running, this one should not run, but wait for it instead
+        once.call_once(|| panic!("initializer ran twice"));
+        assert!(finished.load(SeqCst));
+        assert!(once.is_completed());
+
+        first.join().unwrap();
+        // and once completed, returns right away
+        once.call_once(|| panic!("initializer ran twice"));
+    }
+
+    #[test]
+    fn once_cell() {
+        let cell = OnceCell::new();
+        assert_eq!(cell.get(), None);
+
+        assert_eq!(*cell.get_or_init(|| 42), 42);
+        assert_eq!(*cell.get_or_init(|| 0), 42);
+        assert_eq!(cell.get(), Some(&42));
+    }
+}
@@ -0,0 +1,80 @@
+ij_core_workaround!();
+
+use crate::core::sync::atomic::{AtomicU32, Ordering::Relaxed};
+use crate::core::time::Duration;
+use crate::futex::{futex_wait, futex_wake, futex_wake_all};
+use crate::raw_mutex::RawMutex;
+
+/// Condition variable for use with [RawMutex], based on the futex syscalls (same as the new rust stdlib one)
+///
+/// See [Condvar](crate::condvar::Condvar) for the one working with [Mutex](crate::mutex::Mutex) guards
+///
+/// The notified threads are all woken up and race for the mutex. See [RawKernelCondvar](crate::kernel_condvar::RawKernelCondvar)
+///  for the one based on the process-wide key syscalls, which hands the mutex over to them one by one instead.
+///  It needs the kernel-format mutex, so it's selected along with it by the `kernel-mutex` feature.
+pub struct RawCondvar {
+    // The value of this atomic is simply incremented on every notification.
+    // This is used by `.wait()` to not miss any notifications after
+    // unlocking the mutex and before waiting for notifications.
+    futex: AtomicU32,
+}
+
+impl RawCondvar {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            futex: AtomicU32::new(0),
+        }
+    }
+
+    pub fn notify_one(&self) {
+        self.futex.fetch_add(1, Relaxed);
+        futex_wake(&self.futex);
+    }
+
+    pub fn notify_all(&self) {
+        self.futex.fetch_add(1, Relaxed);
+        futex_wake_all(&self.futex);
+    }
+
+    /// Waits for a notification, the mutex must be locked by the current thread
+    ///
+    /// Doesn't return on spurious wakeups, only after the condvar has been notified
+    pub unsafe fn wait(&self, mutex: &RawMutex) {
+        let futex_value = self.futex.load(Relaxed);
+        mutex.unlock();
+
+        // the counter stays the same until someone notifies us
+        while self.futex.load(Relaxed) == futex_value {
+            futex_wait(&self.futex, futex_value, None);
+        }
+
+        mutex.lock();
+    }
+
+    /// Waits for a notification for at most `timeout`, the mutex must be locked by the current thread
+    ///
+    /// Returns false if the timeout expired. Unlike [wait](Self::wait) it can return early on a spurious wakeup.
+    pub unsafe fn wait_timeout(&self, mutex: &RawMutex, timeout: Duration) -> bool {
+        // Examine the notification counter _before_ we unlock the mutex.
+        let futex_value = self.futex.load(Relaxed);
+
+        // Unlock the mutex before going to sleep.
+        mutex.unlock();
+
+        // Wait, but only if there hasn't been any
+        // notification since we unlocked the mutex.
+        let r = futex_wait(&self.futex, futex_value, Some(timeout));
+
+        // Lock the mutex again.
+        mutex.lock();
+
+        r
+    }
+}
+
+impl Default for RawCondvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -35,6 +35,9 @@ impl RawMutex {
     #[inline]
     pub unsafe fn destroy(&self) {}
 
+    /// Attempts to take the lock with a single compare-exchange
+    ///
+    /// Never waits and never marks the mutex as contended
     #[inline]
     pub unsafe fn try_lock(&self) -> bool {
         self.value.compare_exchange(0, 1, Acquire, Relaxed).is_ok()
@@ -77,13 +77,17 @@ impl RawRwLock {
     #[inline]
     pub unsafe fn destroy(&self) {}
 
+    /// Attempts to take a read lock with a single compare-exchange
+    ///
+    /// Never waits and never sets the waiting bits, so a failed attempt leaves no trace in the state
     #[inline]
     pub unsafe fn try_read(&self) -> bool {
-        self.state
-            .fetch_update(Acquire, Relaxed, |s| {
-                is_read_lockable(s).then(|| s + READ_LOCKED)
-            })
-            .is_ok()
+        let state = self.state.load(Relaxed);
+        is_read_lockable(state)
+            && self
+                .state
+                .compare_exchange(state, state + READ_LOCKED, Acquire, Relaxed)
+                .is_ok()
     }
 
     #[inline]
@@ -156,13 +160,17 @@ impl RawRwLock {
         }
     }
 
+    /// Attempts to take the write lock with a single compare-exchange, failing if there are any readers or a writer
+    ///
+    /// Never waits and never sets the waiting bits, so a failed attempt leaves no trace in the state
     #[inline]
     pub unsafe fn try_write(&self) -> bool {
-        self.state
-            .fetch_update(Acquire, Relaxed, |s| {
-                is_unlocked(s).then(|| s + WRITE_LOCKED)
-            })
-            .is_ok()
+        let state = self.state.load(Relaxed);
+        is_unlocked(state)
+            && self
+                .state
+                .compare_exchange(state, state + WRITE_LOCKED, Acquire, Relaxed)
+                .is_ok()
     }
 
     #[inline]
@@ -377,3 +385,32 @@ impl RawRwLock {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{RawRwLock, READ_LOCKED, WRITE_LOCKED};
+    use core::sync::atomic::Ordering::Relaxed;
+
+    #[test]
+    fn try_lock_does_not_wait() {
+        let lock = RawRwLock::new();
+
+        unsafe {
+            lock.read();
+            assert!(!lock.try_write());
+            assert!(lock.try_read());
+            // the failed attempt didn't register a waiting writer
+            assert_eq!(lock.state.load(Relaxed), 2 * READ_LOCKED);
+            lock.read_unlock();
+            lock.read_unlock();
+
+            assert!(lock.try_write());
+            assert!(!lock.try_read());
+            assert!(!lock.try_write());
+            assert_eq!(lock.state.load(Relaxed), WRITE_LOCKED);
+            lock.write_unlock();
+
+            assert_eq!(lock.state.load(Relaxed), 0);
+        }
+    }
+}
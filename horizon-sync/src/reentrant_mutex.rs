@@ -0,0 +1,215 @@
+//! This module implements a mutex that can be locked multiple times by the same thread
+//! The design follows the libstd one
+
+ij_core_workaround!();
+
+use crate::lock_owner::LockOwner;
+use crate::raw_mutex::RawMutex;
+use core::cell::Cell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use horizon_svc::CURRENT_THREAD_PSEUDO_HANDLE;
+
+/// A mutex that can be re-locked by the thread already holding it
+///
+/// Other threads block as with a normal [Mutex](crate::mutex::Mutex) until the outermost guard is dropped.
+///
+/// The guard only gives out `&T`: the same thread can hold multiple guards at once,
+///  so a unique (`&mut T`) borrow can't be provided. Use a [Cell] or a [RefCell](core::cell::RefCell)
+///  inside for mutation.
+pub struct ReentrantMutex<T: ?Sized> {
+    inner: RawMutex,
+    owner: LockOwner,
+    /// How many times the owner locked the mutex, accessed only by the owner
+    lock_count: Cell<u32>,
+    data: T,
+}
+
+unsafe impl<T: ?Sized + Send> Send for ReentrantMutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for ReentrantMutex<T> {}
+
+pub struct ReentrantMutexGuard<'a, T: ?Sized> {
+    lock: &'a ReentrantMutex<T>,
+    // the lock is bound to the owning thread, so the guard must not be sent to another one
+    _not_send: PhantomData<*const ()>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for ReentrantMutexGuard<'_, T> {}
+
+fn current_thread_id() -> u64 {
+    horizon_svc::get_thread_id(CURRENT_THREAD_PSEUDO_HANDLE)
+        .expect("Getting current thread id failed")
+}
+
+impl<T> ReentrantMutex<T> {
+    pub const fn new(t: T) -> Self {
+        Self {
+            inner: RawMutex::new(),
+            owner: LockOwner::new(),
+            lock_count: Cell::new(0),
+            data: t,
+        }
+    }
+}
+
+impl<T: ?Sized> ReentrantMutex<T> {
+    pub fn lock(&self) -> ReentrantMutexGuard<'_, T> {
+        let this_thread = current_thread_id();
+        unsafe {
+            if self.owner.is(this_thread) {
+                self.increment_lock_count();
+            } else {
+                self.inner.lock();
+                self.owner.set(this_thread);
+                debug_assert_eq!(self.lock_count.get(), 0);
+                self.lock_count.set(1);
+            }
+            ReentrantMutexGuard::new(self)
+        }
+    }
+
+    pub fn try_lock(&self) -> Option<ReentrantMutexGuard<'_, T>> {
+        let this_thread = current_thread_id();
+        unsafe {
+            if self.owner.is(this_thread) {
+                self.increment_lock_count();
+                Some(ReentrantMutexGuard::new(self))
+            } else if self.inner.try_lock() {
+                self.owner.set(this_thread);
+                debug_assert_eq!(self.lock_count.get(), 0);
+                self.lock_count.set(1);
+                Some(ReentrantMutexGuard::new(self))
+            } else {
+                None
+            }
+        }
+    }
+
+    unsafe fn increment_lock_count(&self) {
+        self.lock_count.set(
+            self.lock_count
+                .get()
+                .checked_add(1)
+                .expect("lock count overflow in reentrant mutex"),
+        );
+    }
+
+    pub fn into_inner(self) -> T
+    where
+        T: Sized,
+    {
+        self.data
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ReentrantMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("ReentrantMutex");
+        if let Some(guard) = self.try_lock() {
+            d.field("data", &&*guard);
+        } else {
+            struct LockedPlaceholder;
+            impl fmt::Debug for LockedPlaceholder {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str("<locked>")
+                }
+            }
+            d.field("data", &LockedPlaceholder);
+        }
+        d.finish_non_exhaustive()
+    }
+}
+
+impl<'mutex, T: ?Sized> ReentrantMutexGuard<'mutex, T> {
+    unsafe fn new(lock: &'mutex ReentrantMutex<T>) -> ReentrantMutexGuard<'mutex, T> {
+        ReentrantMutexGuard {
+            lock,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for ReentrantMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.lock.data
+    }
+}
+
+impl<T: ?Sized> Drop for ReentrantMutexGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // only the owner can have a guard, so it's safe to touch the lock count
+        let lock_count = self.lock.lock_count.get() - 1;
+        self.lock.lock_count.set(lock_count);
+        if lock_count == 0 {
+            self.lock.owner.clear();
+            unsafe {
+                self.lock.inner.unlock();
+            }
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ReentrantMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for ReentrantMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::ReentrantMutex;
+    use core::cell::Cell;
+    use core::sync::atomic::{AtomicBool, Ordering::SeqCst};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn nested_lock() {
+        let mutex = Arc::new(ReentrantMutex::new(Cell::new(0)));
+        let released = Arc::new(AtomicBool::new(false));
+
+        let outer = mutex.lock();
+        {
+            let inner = mutex.lock();
+            let innermost = mutex.try_lock().unwrap();
+            inner.set(1);
+            innermost.set(innermost.get() + 1);
+        }
+        // the inner guards are gone, but the outer one still holds the lock
+        assert_eq!(outer.get(), 2);
+
+        let other = {
+            let mutex = mutex.clone();
+            let released = released.clone();
+            std::thread::spawn(move || {
+                assert!(mutex.try_lock().is_none());
+                let guard = mutex.lock();
+                assert!(released.load(SeqCst));
+                guard.get()
+            })
+        };
+
+        std::thread::sleep(Duration::from_millis(50));
+        outer.set(3);
+        released.store(true, SeqCst);
+        drop(outer);
+
+        assert_eq!(other.join().unwrap(), 3);
+    }
+}
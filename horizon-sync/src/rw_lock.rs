@@ -1,11 +1,24 @@
 //! This module implements an RW Lock wrapper type
 //! Most code is borrowed from libstd, but without the poisoning
+//!
+//! There are two backends for the lock:
+//! - [RawRwLock](crate::raw_rw_lock::RawRwLock) (the default): the state is a single futex word,
+//!   so an uncontended lock or unlock is one atomic operation. Suits read-heavy workloads,
+//!   where the readers rarely have to wait at all.
+//! - [RawArbiterRwLock](crate::arbiter_rw_lock::RawArbiterRwLock) (the `arbiter-rw-lock` feature):
+//!   the libnx design, with a mutex and separate condition variables for the readers and writers.
+//!   Every operation takes the mutex, but the writers are woken one by one and always go before
+//!   new readers. Suits write-heavy workloads, where the futex backend wakes up more threads than needed.
 
 ij_core_workaround!();
 
 use crate::core::cell::UnsafeCell;
 use crate::core::fmt;
 use crate::core::ops::{Deref, DerefMut};
+
+#[cfg(feature = "arbiter-rw-lock")]
+use crate::arbiter_rw_lock::RawArbiterRwLock as RawRwLock;
+#[cfg(not(feature = "arbiter-rw-lock"))]
 use crate::raw_rw_lock::RawRwLock;
 
 pub struct RwLock<T: ?Sized> {
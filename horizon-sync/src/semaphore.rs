@@ -0,0 +1,157 @@
+ij_core_workaround!();
+
+use crate::core::sync::atomic::{
+    AtomicI32, AtomicU32,
+    Ordering::{Acquire, Relaxed, SeqCst},
+};
+use crate::core::time::Duration;
+use horizon_error::KernelErrorCode;
+use horizon_svc::{ArbitrationType, SignalType};
+
+/// A counting semaphore, based on the address arbiter syscalls
+///
+/// Unlike [BoundedGate](crate::bounded_gate::BoundedGate), the counter never goes negative: the waiting
+///  threads don't take a permit in advance with [ArbitrationType::DecrementAndWaitIfLessThan], but wait with
+///  [ArbitrationType::WaitIfLessThan] and take it from userspace once woken. This is what makes
+///  [acquire_timeout](Self::acquire_timeout) possible, a timed out waiter has no decrement to undo.
+///  `SignalType::SignalAndModifyByWaitingCountIfEqual` doesn't help here either: it moves the value by at most one,
+///  depending on how many waiters are left, which fits the tri-state events it's meant for, not a counter.
+///
+/// The number of waiters is tracked separately, so [release](Self::release) only makes a syscall when
+///  there is someone to wake.
+///
+/// It makes this primitive `[4.0.0+]`, same as other futex-based ones.
+pub struct Semaphore {
+    /// Number of available permits, never negative
+    count: AtomicI32,
+    /// Number of threads in [acquire](Self::acquire) or [acquire_timeout](Self::acquire_timeout) that
+    ///  didn't get a permit right away
+    waiters: AtomicU32,
+}
+
+impl Semaphore {
+    #[inline]
+    pub const fn new(count: i32) -> Self {
+        assert!(count >= 0);
+
+        Self {
+            count: AtomicI32::new(count),
+            waiters: AtomicU32::new(0),
+        }
+    }
+
+    /// Takes a permit if one is available without blocking
+    #[inline]
+    pub fn try_acquire(&self) -> bool {
+        let mut count = self.count.load(Relaxed);
+        while count > 0 {
+            match self
+                .count
+                .compare_exchange_weak(count, count - 1, Acquire, Relaxed)
+            {
+                Ok(_) => return true,
+                Err(c) => count = c,
+            }
+        }
+        false
+    }
+
+    /// Takes a permit, blocking until one is released if there are none
+    #[inline]
+    pub fn acquire(&self) {
+        if !self.try_acquire() {
+            self.acquire_contended(None);
+        }
+    }
+
+    /// Takes a permit, blocking for at most `timeout` if there are none
+    ///
+    /// Returns false if the timeout expired. The timeout starts over when the thread is woken up,
+    ///  but another thread takes the released permit first.
+    #[inline]
+    pub fn acquire_timeout(&self, timeout: Duration) -> bool {
+        self.try_acquire() || self.acquire_contended(Some(timeout))
+    }
+
+    #[cold]
+    fn acquire_contended(&self, timeout: Option<Duration>) -> bool {
+        // the releasing thread either sees us here, or we see its permit in the kernel check below
+        self.waiters.fetch_add(1, SeqCst);
+
+        let acquired = loop {
+            if self.try_acquire() {
+                break true;
+            }
+
+            match unsafe {
+                horizon_svc::wait_for_address(
+                    &self.count,
+                    ArbitrationType::WaitIfLessThan,
+                    1,
+                    timeout,
+                )
+            }
+            .map_err(|e| unsafe { e.try_as::<KernelErrorCode>().unwrap_unchecked() })
+            {
+                // woken by a release, or a permit became available before we got to wait:
+                //  either way, try to take it (someone else might have been faster)
+                Ok(_) | Err(KernelErrorCode::InvalidState) => {}
+                Err(KernelErrorCode::TimedOut) => break self.try_acquire(),
+                // some unknown error, let's panic
+                Err(e) => panic!("Semaphore::acquire: {:?}", e),
+            }
+        };
+
+        self.waiters.fetch_sub(1, Relaxed);
+        acquired
+    }
+
+    /// Adds `n` permits, waking up to `n` waiting threads
+    #[inline]
+    pub fn release(&self, n: i32) {
+        assert!(n >= 0);
+
+        self.count.fetch_add(n, SeqCst);
+        if n > 0 && self.waiters.load(SeqCst) > 0 {
+            self.wake(n);
+        }
+    }
+
+    #[cold]
+    fn wake(&self, n: i32) {
+        unsafe {
+            horizon_svc::signal_to_address(&self.count, SignalType::Signal, 0, n).unwrap();
+        }
+    }
+
+    /// Number of permits available right now
+    #[inline]
+    pub fn available(&self) -> i32 {
+        self.count.load(Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Semaphore;
+    use core::time::Duration;
+
+    #[test]
+    fn counter_transitions() {
+        let semaphore = Semaphore::new(2);
+
+        assert!(semaphore.try_acquire());
+        semaphore.acquire();
+        assert_eq!(semaphore.available(), 0);
+        assert!(!semaphore.try_acquire());
+
+        // no waiters, so nothing to wake
+        semaphore.release(3);
+        assert_eq!(semaphore.available(), 3);
+        assert!(semaphore.acquire_timeout(Duration::from_millis(1)));
+        assert_eq!(semaphore.available(), 2);
+
+        semaphore.release(0);
+        assert_eq!(semaphore.available(), 2);
+    }
+}